@@ -1,5 +1,6 @@
 use serde::Serialize;
 use tauri::command;
+use tauri::ipc::Response;
 
 #[derive(Serialize)]
 struct CompileError {
@@ -64,6 +65,60 @@ fn compile_gen_with_mod_points(
     }
 }
 
+// The archive goes back as raw bytes (an ArrayBuffer in JS), not a JSON array of numbers;
+// a compile error rejects the call instead
+#[command]
+fn compile_gen_mxl(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+    include_source: bool,
+) -> Result<Response, CompileError> {
+    gen::compile_mxl(source, clef, octave_shift, instrument_group, transpose_key, include_source)
+        .map(Response::new)
+        .map_err(error_to_compile_error)
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status")]
+enum JsonResult {
+    #[serde(rename = "success")]
+    Success { json: String },
+    #[serde(rename = "error")]
+    Error { error: CompileError },
+}
+
+#[command]
+fn parse_to_json(source: &str) -> JsonResult {
+    match gen::parse_to_json(source) {
+        Ok(json) => JsonResult::Success { json },
+        Err(e) => JsonResult::Error {
+            error: error_to_compile_error(e),
+        },
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status")]
+enum SourceResult {
+    #[serde(rename = "success")]
+    Success { source: String },
+    #[serde(rename = "error")]
+    Error { error: CompileError },
+}
+
+#[command]
+fn json_to_source(json: &str) -> SourceResult {
+    match gen::json_to_source(json) {
+        Ok(source) => SourceResult::Success { source },
+        Err(e) => SourceResult::Error {
+            error: error_to_compile_error(e),
+        },
+    }
+}
+
 #[derive(Serialize)]
 #[serde(tag = "status")]
 enum PlaybackResult {
@@ -89,6 +144,23 @@ fn generate_playback_data(
     }
 }
 
+#[command]
+fn generate_playback_data_with_options(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+    options: gen::PlaybackOptions,
+) -> PlaybackResult {
+    match gen::generate_playback_data_with_options(source, clef, octave_shift, instrument_group, transpose_key, &options) {
+        Ok(data) => PlaybackResult::Success { data },
+        Err(e) => PlaybackResult::Error {
+            error: error_to_compile_error(e),
+        },
+    }
+}
+
 fn error_to_compile_error(e: gen::GenError) -> CompileError {
     match e {
         gen::GenError::ParseError { line, column, message } => CompileError {
@@ -124,7 +196,11 @@ pub fn run() {
             compile_gen_unchecked,
             compile_gen_with_options,
             compile_gen_with_mod_points,
+            compile_gen_mxl,
+            parse_to_json,
+            json_to_source,
             generate_playback_data,
+            generate_playback_data_with_options,
             open_external_url,
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,30 @@
+//! # ABC Notation Module
+//!
+//! Converts scores to and from [ABC notation](https://abcnotation.com/wiki/abc:standard:v2.1),
+//! the plain-text format folk musicians use to share tunes.
+//!
+//! ## Sub-modules
+//! - `writer` - Score → ABC tune (headers, notes, tuplets, ties, slurs, repeats, endings and
+//!   chord symbols), with the same transposition options as MusicXML output
+//! - `parser` - ABC tune → Score, with warnings for what Gen can't hold (decorations, grace
+//!   notes, lyrics, extra voices); print the result with [`crate::printer::to_gen_source()`]
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, abc::to_abc};
+//!
+//! let score = parse("---\ntitle: Scale\n---\n{C}:C D E F\nG A B ^C").unwrap();
+//! let abc = to_abc(&score);
+//! assert!(abc.contains("T:Scale"));
+//! assert!(abc.contains("\"C\"C2 D2 E2 F2 | G2 A2 B2 c2 |]"));
+//! ```
+//!
+//! ## Related Modules
+//! - `musicxml` - Transposition and beaming shared with the MusicXML writer
+//! - `printer` - Gen source for imported tunes
+
+mod parser;
+mod writer;
+
+pub use parser::{parse_abc, AbcImport};
+pub use writer::{to_abc, to_abc_with_options};
@@ -0,0 +1,1215 @@
+//! ABC tune parser
+//!
+//! Reads the first tune of an ABC file into a [`Score`]: the `T`, `C`, `M`, `L`, `Q` and `K`
+//! header fields, then the body's notes, rests, lengths, broken rhythms (`>`/`<`), tuplets,
+//! ties, slurs, bars, repeats, endings, chord symbols and inline `K:` changes.
+//!
+//! Anything Gen can't hold is dropped with a warning rather than an error: decorations, grace
+//! notes, lyrics, text annotations, meter changes, every voice but the first, and all but the
+//! top note of a chord.
+//!
+//! ABC spellings are resolved to sounding pitches (key signature plus the bar's accidentals),
+//! then written the Gen way: an accidental only where the pitch differs from the key.
+
+use crate::ast::*;
+use crate::error::GenError;
+use crate::rational::Rational;
+use std::collections::HashMap;
+
+/// A score read from an ABC tune, with what was left out
+#[derive(Debug, Clone)]
+pub struct AbcImport {
+    pub score: Score,
+    /// One message per kind of dropped or approximated content
+    pub warnings: Vec<String>,
+}
+
+/// Note values Gen can write, longest first
+const NOTE_VALUES: [(Duration, bool); 12] = [
+    (Duration::Whole, true),
+    (Duration::Whole, false),
+    (Duration::Half, true),
+    (Duration::Half, false),
+    (Duration::Quarter, true),
+    (Duration::Quarter, false),
+    (Duration::Eighth, true),
+    (Duration::Eighth, false),
+    (Duration::Sixteenth, true),
+    (Duration::Sixteenth, false),
+    (Duration::ThirtySecond, true),
+    (Duration::ThirtySecond, false),
+];
+
+/// Parse the first tune of an ABC file into a score
+///
+/// # Errors
+/// Returns [`GenError::ParseError`] for an unterminated chord symbol, decoration or grace
+/// note group, or a tune without any notes.
+///
+/// # Example
+/// ```rust
+/// use gen::abc::parse_abc;
+///
+/// let tune = "X:1\nT:Scale\nM:4/4\nL:1/8\nK:G\n\"G\"G2 A2 B2 c2 | !fermata!d8 |]\n";
+/// let import = parse_abc(tune).unwrap();
+/// assert_eq!(import.score.metadata.title.as_deref(), Some("Scale"));
+/// assert_eq!(import.score.measures.len(), 2);
+/// assert_eq!(import.warnings, vec!["Dropped decoration !fermata!"]);
+/// ```
+pub fn parse_abc(source: &str) -> Result<AbcImport, GenError> {
+    let mut parser = AbcParser::new();
+    for (index, raw_line) in source.lines().enumerate() {
+        if !parser.line(raw_line, index + 1)? {
+            break;
+        }
+    }
+    parser.finish()
+}
+
+/// Where the parser is in the file
+#[derive(PartialEq)]
+enum Stage {
+    Header,
+    Body,
+    Done,
+}
+
+/// A note or rest as written in ABC, before it becomes Gen elements
+struct Item {
+    pitch: Option<(NoteName, i8, i8)>, // Letter, sounding alteration and octave (None for a rest)
+    length: Rational,                  // Written length as a fraction of a whole note
+    tuplet: Option<TupletInfo>,
+    tie_start: bool,
+    tie_stop: bool,
+    slur_start: bool,
+    slur_stop: bool,
+    chord: Option<String>,
+}
+
+/// The bar being read
+#[derive(Default)]
+struct Bar {
+    items: Vec<Item>,
+    repeat_start: bool,
+    ending: Option<Ending>,
+    key_change: Option<KeySignature>,
+    /// Accidentals written earlier in the bar, by letter and octave
+    accidentals: HashMap<(NoteName, i8), i8>,
+}
+
+/// A tuplet waiting for its remaining notes
+struct OpenTuplet {
+    info: TupletInfo,
+    remaining: usize,
+    started: bool,
+}
+
+struct AbcParser {
+    stage: Stage,
+    metadata: Metadata,
+    unit: Option<Rational>,
+    key: KeySignature,
+    gen_key: KeySignature,
+    warnings: Vec<String>,
+    measures: Vec<Measure>,
+    bar: Bar,
+    next_key_change: Option<KeySignature>,
+    voice: Option<String>,
+    other_voice: bool,
+    tuplet: Option<OpenTuplet>,
+    chord: Option<String>,
+    slur_start: bool,
+    tie_stop: bool,
+    broken: Option<Rational>,
+    ending: Option<Ending>,
+    repeat_open: bool,
+    section_start: usize,
+}
+
+impl AbcParser {
+    fn new() -> Self {
+        Self {
+            stage: Stage::Header,
+            metadata: Metadata::default(),
+            unit: None,
+            key: KeySignature::default(),
+            gen_key: KeySignature::default(),
+            warnings: Vec::new(),
+            measures: Vec::new(),
+            bar: Bar::default(),
+            next_key_change: None,
+            voice: None,
+            other_voice: false,
+            tuplet: None,
+            chord: None,
+            slur_start: false,
+            tie_stop: false,
+            broken: None,
+            ending: None,
+            repeat_open: false,
+            section_start: 0,
+        }
+    }
+
+    fn warn(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        if !self.warnings.contains(&message) {
+            self.warnings.push(message);
+        }
+    }
+
+    /// Read one line; returns false once the tune is over
+    fn line(&mut self, raw_line: &str, line_number: usize) -> Result<bool, GenError> {
+        if raw_line.starts_with("%%") {
+            return Ok(true);
+        }
+        let line = strip_comment(raw_line);
+        let trimmed = line.trim();
+
+        if self.stage == Stage::Done {
+            if !trimmed.is_empty() {
+                self.warn("Only the first tune was imported");
+                return Ok(false);
+            }
+            return Ok(true);
+        }
+        if trimmed.is_empty() {
+            // A blank line ends the tune (but not the file header before it)
+            if self.stage == Stage::Body {
+                self.stage = Stage::Done;
+            }
+            return Ok(true);
+        }
+
+        if let Some((field, value)) = field_line(trimmed) {
+            self.field(field, value.trim());
+            return Ok(true);
+        }
+        if self.stage == Stage::Header {
+            // Music without a K: field: start the body with the defaults
+            self.start_body();
+        }
+        self.music(&line.chars().collect::<Vec<char>>(), line_number)?;
+        Ok(true)
+    }
+
+    /// An information field, on its own line or inline (`[K:D]`)
+    fn field(&mut self, field: char, value: &str) {
+        match field {
+            'X' if self.stage == Stage::Body => {
+                self.stage = Stage::Done;
+                self.warn("Only the first tune was imported");
+            }
+            'T' if self.metadata.title.is_none() => self.metadata.title = Some(value.to_string()),
+            'C' if self.metadata.composer.is_none() => self.metadata.composer = Some(value.to_string()),
+            'M' => match parse_meter(value) {
+                Some(meter) if self.stage == Stage::Header => self.metadata.time_signature = meter,
+                Some(meter) if meter != self.metadata.time_signature => {
+                    self.warn(format!("Dropped meter change to {}/{}", meter.beats, meter.beat_type));
+                }
+                Some(_) => {}
+                None => self.warn(format!("Dropped unsupported meter M:{}", value)),
+            },
+            'L' => match parse_fraction(value) {
+                Some(unit) if unit > Rational::ZERO => self.unit = Some(unit),
+                _ => self.warn(format!("Ignored invalid unit length L:{}", value)),
+            },
+            'Q' => match parse_tempo(value, self.unit_length()) {
+                Some(tempo) if self.stage == Stage::Header => self.metadata.tempo = Some(tempo),
+                Some(_) => self.warn("Dropped tempo change"),
+                None => self.warn(format!("Dropped unsupported tempo Q:{}", value)),
+            },
+            'K' => {
+                let (key, warnings) = parse_key(value);
+                for warning in warnings {
+                    self.warn(warning);
+                }
+                if self.stage == Stage::Header {
+                    self.metadata.key_signature = key.clone();
+                    self.gen_key = key.clone();
+                    self.key = key;
+                    self.start_body();
+                } else if key != self.key {
+                    self.key = key.clone();
+                    if self.bar.items.is_empty() {
+                        self.bar.key_change = Some(key);
+                    } else {
+                        self.next_key_change = Some(key);
+                    }
+                }
+            }
+            'V' => {
+                let id = value.split_whitespace().next().unwrap_or("").to_string();
+                match &self.voice {
+                    None => self.voice = Some(id),
+                    Some(first) if *first == id => self.other_voice = false,
+                    Some(_) if self.stage == Stage::Body => {
+                        self.other_voice = true;
+                        self.warn(format!("Dropped voice {} (only the first voice is imported)", id));
+                    }
+                    Some(_) => {}
+                }
+            }
+            'w' | 'W' => self.warn("Dropped lyrics"),
+            _ => {}
+        }
+    }
+
+    /// Default unit length: an eighth, or a sixteenth in meters shorter than 3/4
+    fn unit_length(&self) -> Rational {
+        self.unit.unwrap_or_else(|| {
+            let meter = &self.metadata.time_signature;
+            if Rational::new(meter.beats as i64, meter.beat_type as i64) < Rational::new(3, 4) {
+                Rational::new(1, 16)
+            } else {
+                Rational::new(1, 8)
+            }
+        })
+    }
+
+    fn start_body(&mut self) {
+        self.unit = Some(self.unit_length());
+        self.stage = Stage::Body;
+    }
+
+    /// Read one line of music
+    fn music(&mut self, chars: &[char], line_number: usize) -> Result<(), GenError> {
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            let column = i + 1;
+
+            // Inline field: [K:D], [V:2], ...
+            if c == '[' && next.is_some_and(|n| n.is_ascii_alphabetic()) && chars.get(i + 2) == Some(&':') {
+                let end = find(chars, i, ']').ok_or_else(|| unterminated(line_number, column, "inline field"))?;
+                let value: String = chars[i + 3..end].iter().collect();
+                self.field(chars[i + 1], value.trim());
+                i = end + 1;
+                continue;
+            }
+            if self.other_voice {
+                i += 1;
+                continue;
+            }
+
+            match c {
+                '|' | ':' if c == '|' || matches!(next, Some('|') | Some(':')) => i = self.barline(chars, i),
+                '[' if next == Some('|') => i = self.barline(chars, i),
+                '[' if next.is_some_and(|n| n.is_ascii_digit()) => {
+                    let (numbers, end) = ending_numbers(chars, i + 1);
+                    self.start_ending(&numbers);
+                    i = end;
+                }
+                '[' => i = self.chord_of_notes(chars, i, line_number)?,
+                '"' => {
+                    let end = find(chars, i + 1, '"').ok_or_else(|| unterminated(line_number, column, "chord symbol"))?;
+                    let text: String = chars[i + 1..end].iter().collect();
+                    match text.chars().next() {
+                        Some('^' | '_' | '<' | '>' | '@') => self.warn("Dropped text annotations"),
+                        Some(_) => self.chord = Some(text),
+                        None => {}
+                    }
+                    i = end + 1;
+                }
+                '!' | '+' => {
+                    let end = find(chars, i + 1, c).ok_or_else(|| unterminated(line_number, column, "decoration"))?;
+                    let name: String = chars[i..=end].iter().collect();
+                    self.warn(format!("Dropped decoration {}", name));
+                    i = end + 1;
+                }
+                '.' | '~' | 'H' | 'L' | 'M' | 'O' | 'P' | 'S' | 'T' | 'u' | 'v' => {
+                    self.warn(format!("Dropped decoration {}", c));
+                    i += 1;
+                }
+                '{' => {
+                    let end = find(chars, i + 1, '}').ok_or_else(|| unterminated(line_number, column, "grace note group"))?;
+                    self.warn("Dropped grace notes");
+                    i = end + 1;
+                }
+                '(' if next.is_some_and(|n| n.is_ascii_digit()) => i = self.tuplet_start(chars, i + 1),
+                '(' => {
+                    self.slur_start = true;
+                    i += 1;
+                }
+                ')' => {
+                    if let Some(item) = self.bar.items.iter_mut().rev().find(|item| item.pitch.is_some()) {
+                        item.slur_stop = true;
+                    }
+                    i += 1;
+                }
+                '-' => {
+                    if let Some(item) = self.bar.items.last_mut() {
+                        item.tie_start = true;
+                        self.tie_stop = true;
+                    }
+                    i += 1;
+                }
+                '>' | '<' => {
+                    let count = chars[i..].iter().take_while(|&&ch| ch == c).count();
+                    self.broken_rhythm(c == '>', count);
+                    i += count;
+                }
+                '^' | '_' | '=' | 'A'..='G' | 'a'..='g' => {
+                    let (pitch, end) = self.pitch(chars, i);
+                    let (length, end) = length_multiplier(chars, end);
+                    match pitch {
+                        Some(pitch) => self.push_item(Some(pitch), length),
+                        None => self.warn(format!("Skipped unrecognized symbol {}", c)),
+                    }
+                    i = end;
+                }
+                'z' | 'x' => {
+                    let (length, end) = length_multiplier(chars, i + 1);
+                    self.push_item(None, length);
+                    i = end;
+                }
+                'Z' | 'X' => {
+                    let (count, end) = number(chars, i + 1);
+                    self.multi_measure_rest(count.unwrap_or(1));
+                    i = end;
+                }
+                '&' => {
+                    self.warn("Dropped voice overlays (&)");
+                    i = chars[i..].iter().position(|&ch| ch == '|').map_or(chars.len(), |p| i + p);
+                }
+                ' ' | '\t' | '`' | 'y' | '\\' | '$' => i += 1,
+                _ => {
+                    self.warn(format!("Skipped unrecognized symbol {}", c));
+                    i += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a barline starting at `start` (with any ending number after it)
+    fn barline(&mut self, chars: &[char], start: usize) -> usize {
+        let mut i = start;
+        while i < chars.len() {
+            match chars[i] {
+                '|' | ':' => i += 1,
+                ']' if i > start && chars[i - 1] == '|' => i += 1,
+                '[' if chars.get(i + 1) == Some(&'|') => i += 1,
+                _ => break,
+            }
+        }
+        let token: String = chars[start..i].iter().collect();
+        let repeat_end = token.starts_with(':');
+        let repeat_start = token.ends_with(':');
+        let section_end = token.contains("||") || token.contains("|]") || token.contains("[|");
+        self.finish_bar(repeat_end, section_end);
+        if repeat_start {
+            self.repeat_start();
+        }
+
+        // Ending number right after the barline: |1, :|2, |[1
+        let number_start = if chars.get(i) == Some(&'[') && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+            i + 1
+        } else {
+            i
+        };
+        if chars.get(number_start).is_some_and(|c| c.is_ascii_digit()) {
+            let (numbers, end) = ending_numbers(chars, number_start);
+            self.start_ending(&numbers);
+            return end;
+        }
+        i
+    }
+
+    fn repeat_start(&mut self) {
+        if self.repeat_open {
+            self.drop_open_repeat();
+        }
+        self.bar.repeat_start = true;
+        self.repeat_open = true;
+        self.section_start = self.measures.len();
+    }
+
+    /// A repeat start that never got its repeat end (Gen needs them paired)
+    fn drop_open_repeat(&mut self) {
+        let start = self.section_start.min(self.measures.len());
+        if let Some(measure) = self.measures[start..]
+            .iter_mut()
+            .find(|measure| measure.repeat_start)
+        {
+            measure.repeat_start = false;
+        }
+        self.warn("Dropped a repeat start without a repeat end");
+    }
+
+    fn start_ending(&mut self, numbers: &str) {
+        self.ending = match numbers.chars().next() {
+            Some('1') => Some(Ending::First),
+            Some('2') => Some(Ending::Second),
+            _ => {
+                self.warn(format!("Dropped ending {} (Gen has first and second endings)", numbers));
+                None
+            }
+        };
+        self.bar.ending = self.ending;
+    }
+
+    /// Close the bar being read and start the next one
+    fn finish_bar(&mut self, repeat_end: bool, section_end: bool) {
+        if self.bar.items.is_empty() {
+            // A barline right after another (e.g. at the start of a line)
+            if repeat_end {
+                if let Some(last) = self.measures.last_mut() {
+                    if !last.repeat_end {
+                        last.repeat_end = true;
+                        self.close_repeat();
+                    }
+                }
+            }
+            if repeat_end || section_end {
+                self.ending = None;
+                self.bar.ending = None;
+                self.section_start = self.measures.len();
+            }
+            return;
+        }
+
+        let bar = std::mem::take(&mut self.bar);
+        if let Some(key) = &bar.key_change {
+            self.gen_key = key.clone();
+        }
+        let elements = self.elements(bar.items);
+        self.measures.push(Measure {
+            elements,
+            repeat_start: bar.repeat_start,
+            repeat_end,
+            ending: bar.ending,
+            key_change: bar.key_change,
+            is_pickup: false,
+        });
+
+        if repeat_end {
+            self.close_repeat();
+        }
+        // A second ending is one measure in Gen; other endings run to a repeat or double bar
+        if repeat_end || section_end || bar.ending == Some(Ending::Second) {
+            self.ending = None;
+        }
+        if repeat_end || section_end {
+            self.section_start = self.measures.len();
+        }
+        self.bar.ending = self.ending;
+        self.bar.key_change = self.next_key_change.take();
+    }
+
+    /// A repeat end: add the repeat start ABC leaves implicit at the start of the section
+    fn close_repeat(&mut self) {
+        if !self.repeat_open {
+            let start = self.section_start.min(self.measures.len() - 1);
+            self.measures[start].repeat_start = true;
+        }
+        self.repeat_open = false;
+    }
+
+    /// `(p:q:r`: p notes in the time of q, for the next r notes
+    fn tuplet_start(&mut self, chars: &[char], start: usize) -> usize {
+        let (actual, mut i) = number(chars, start);
+        let actual = actual.unwrap_or(3);
+        let mut normal = None;
+        let mut notes = None;
+        if chars.get(i) == Some(&':') {
+            let (value, end) = number(chars, i + 1);
+            normal = value;
+            i = end;
+            if chars.get(i) == Some(&':') {
+                let (value, end) = number(chars, i + 1);
+                notes = value;
+                i = end;
+            }
+        }
+
+        let meter = &self.metadata.time_signature;
+        let compound = meter.beats.is_multiple_of(3) && meter.beats > 3;
+        let normal = normal.unwrap_or(match actual {
+            2 | 4 | 8 => 3,
+            3 | 6 => 2,
+            _ if compound => 3,
+            _ => 2,
+        });
+        if self.tuplet.is_some() {
+            self.warn("Dropped nested tuplets");
+        } else if actual >= 2 && actual <= u8::MAX as usize && (1..=u8::MAX as usize).contains(&normal) {
+            self.tuplet = Some(OpenTuplet {
+                info: TupletInfo::with_ratio(actual as u8, normal as u8),
+                remaining: notes.unwrap_or(actual).max(1),
+                started: false,
+            });
+        } else {
+            self.warn(format!("Dropped invalid tuplet ({}:{}", actual, normal));
+        }
+        i
+    }
+
+    /// `>` dots the previous note and halves the next (`>>` and `>>>` go further); `<` the reverse
+    fn broken_rhythm(&mut self, dotted_first: bool, count: usize) {
+        let Some(previous) = self.bar.items.last_mut() else {
+            return;
+        };
+        let short = Rational::new(1, 1 << count.min(3));
+        let long = Rational::from_integer(2) - short;
+        let (first, second) = if dotted_first { (long, short) } else { (short, long) };
+        previous.length = previous.length * first;
+        self.broken = Some(second);
+    }
+
+    /// Accidental, letter and octave marks; returns (letter, sounding alteration, octave)
+    fn pitch(&mut self, chars: &[char], start: usize) -> (Option<(NoteName, i8, i8)>, usize) {
+        let mut i = start;
+        let mut explicit: Option<i8> = None;
+        while let Some(&c) = chars.get(i) {
+            let alter = match c {
+                '^' => 1,
+                '_' => -1,
+                '=' => 0,
+                _ => break,
+            };
+            explicit = Some(explicit.unwrap_or(0) + alter);
+            i += 1;
+        }
+        let Some(&letter) = chars.get(i) else {
+            return (None, i);
+        };
+        let Some(name) = NoteName::from_letter(letter.to_ascii_uppercase()) else {
+            return (None, i);
+        };
+        i += 1;
+        let mut octave: i8 = if letter.is_ascii_lowercase() { 5 } else { 4 };
+        while let Some(&c) = chars.get(i) {
+            match c {
+                '\'' => octave += 1,
+                ',' => octave -= 1,
+                _ => break,
+            }
+            i += 1;
+        }
+
+        let alter = match explicit {
+            Some(alter) => {
+                self.bar.accidentals.insert((name, octave), alter);
+                alter
+            }
+            None => self
+                .bar
+                .accidentals
+                .get(&(name, octave))
+                .copied()
+                .unwrap_or_else(|| self.key.accidental_for_note(name).alter()),
+        };
+        (Some((name, alter, octave)), i)
+    }
+
+    /// `[CEG]2`: Gen has one note at a time, so the top note stands for the chord
+    fn chord_of_notes(&mut self, chars: &[char], start: usize, line_number: usize) -> Result<usize, GenError> {
+        let end = find(chars, start + 1, ']').ok_or_else(|| unterminated(line_number, start + 1, "chord"))?;
+        let mut i = start + 1;
+        let mut top: Option<((NoteName, i8, i8), Rational)> = None;
+        let mut count = 0;
+        let mut tied = false;
+        while i < end {
+            match chars[i] {
+                '^' | '_' | '=' | 'A'..='G' | 'a'..='g' => {
+                    let (pitch, after_pitch) = self.pitch(chars, i);
+                    let (length, after_length) = length_multiplier(chars, after_pitch);
+                    i = after_length.max(i + 1);
+                    let Some(pitch) = pitch else { continue };
+                    count += 1;
+                    let height = |(name, alter, octave): (NoteName, i8, i8)| octave as i32 * 12 + name.pitch_class() as i32 + alter as i32;
+                    match top {
+                        Some((current, first_length)) if height(pitch) > height(current) => top = Some((pitch, first_length)),
+                        Some(_) => {}
+                        None => top = Some((pitch, length)),
+                    }
+                }
+                '-' => {
+                    tied = true;
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+        let (multiplier, after) = length_multiplier(chars, end + 1);
+        if let Some((pitch, length)) = top {
+            if count > 1 {
+                self.warn("Kept only the top note of chords");
+            }
+            self.push_item(Some(pitch), length * multiplier);
+            if tied || chars.get(after) == Some(&'-') {
+                if let Some(item) = self.bar.items.last_mut() {
+                    item.tie_start = true;
+                }
+                self.tie_stop = true;
+            }
+        }
+        Ok(if chars.get(after) == Some(&'-') { after + 1 } else { after })
+    }
+
+    /// Add a note (or rest, for `None`) of `multiplier` unit lengths
+    fn push_item(&mut self, pitch: Option<(NoteName, i8, i8)>, multiplier: Rational) {
+        let mut length = multiplier * self.unit_length();
+        if let Some(factor) = self.broken.take() {
+            length = length * factor;
+        }
+
+        let tuplet = self.tuplet.as_mut().map(|open| {
+            let mut info = open.info;
+            info.is_start = !open.started;
+            open.started = true;
+            open.remaining -= 1;
+            info.is_stop = open.remaining == 0;
+            info
+        });
+        if self.tuplet.as_ref().is_some_and(|open| open.remaining == 0) {
+            self.tuplet = None;
+        }
+
+        let is_note = pitch.is_some();
+        let item = Item {
+            pitch,
+            length,
+            tuplet,
+            tie_start: false,
+            tie_stop: is_note && std::mem::take(&mut self.tie_stop),
+            slur_start: is_note && std::mem::take(&mut self.slur_start),
+            slur_stop: false,
+            chord: self.chord.take(),
+        };
+        if !is_note {
+            self.tie_stop = false;
+        }
+        self.bar.items.push(item);
+    }
+
+    /// `Z4`: that many measures of rest
+    fn multi_measure_rest(&mut self, count: usize) {
+        let meter = &self.metadata.time_signature;
+        let measure = Rational::new(meter.beats as i64, meter.beat_type as i64);
+        for k in 0..count {
+            if k > 0 {
+                self.finish_bar(false, false);
+            }
+            let units = measure / self.unit_length();
+            self.push_item(None, units);
+        }
+    }
+
+    /// Gen elements for a finished bar's items
+    fn elements(&mut self, items: Vec<Item>) -> Vec<Element> {
+        let mut elements = Vec::new();
+        let mut rounded = false;
+        let mut out_of_range = false;
+        for item in items {
+            let (pieces, inexact) = note_values(item.length);
+            rounded |= inexact;
+            let last = pieces.len() - 1;
+
+            for (k, &(duration, dotted)) in pieces.iter().enumerate() {
+                let tuplets: Vec<TupletInfo> = item
+                    .tuplet
+                    .map(|mut info| {
+                        info.is_start &= k == 0;
+                        info.is_stop &= k == last;
+                        info
+                    })
+                    .into_iter()
+                    .collect();
+                let chord = item.chord.as_ref().filter(|_| k == 0).map(|symbol| ChordAnnotation::new(symbol.clone()));
+
+                let element = match item.pitch {
+                    Some((name, alter, octave)) => {
+                        let key_alter = self.gen_key.accidental_for_note(name).alter();
+                        let accidental = match alter {
+                            _ if alter == key_alter => Accidental::Natural,
+                            0 => Accidental::ForceNatural,
+                            _ => Accidental::from_alter(alter),
+                        };
+                        out_of_range |= !(2..=6).contains(&octave);
+                        Element::Note(Note {
+                            name,
+                            accidental,
+                            octave: match octave.clamp(2, 6) {
+                                2 => Octave::DoubleLow,
+                                3 => Octave::Low,
+                                4 => Octave::Middle,
+                                5 => Octave::High,
+                                _ => Octave::DoubleHigh,
+                            },
+                            duration,
+                            dotted,
+                            tuplets,
+                            tie_start: item.tie_start || k < last,
+                            tie_stop: item.tie_stop || k > 0,
+                            slur_start: item.slur_start && k == 0,
+                            slur_stop: item.slur_stop && k == last,
+                            chord,
+                        })
+                    }
+                    None => Element::Rest { duration, dotted, tuplets, chord },
+                };
+                elements.push(element);
+            }
+        }
+        if rounded {
+            self.warn("Rounded note lengths Gen can't write (shorter than a 32nd)");
+        }
+        if out_of_range {
+            self.warn("Moved notes outside Gen's range (two octaves either side) into it");
+        }
+        elements
+    }
+
+    /// Wrap up after the last line
+    fn finish(mut self) -> Result<AbcImport, GenError> {
+        self.finish_bar(false, true);
+        if self.repeat_open {
+            self.drop_open_repeat();
+        }
+        if self.measures.is_empty() {
+            return Err(GenError::ParseError {
+                line: 1,
+                column: 1,
+                message: "The ABC tune has no notes".to_string(),
+            });
+        }
+        self.mark_short_measures();
+        self.measure_chords();
+
+        Ok(AbcImport {
+            score: Score {
+                metadata: self.metadata,
+                measures: self.measures,
+                mod_points: ModPoints::default(),
+                line_to_measure: HashMap::new(),
+            },
+            warnings: self.warnings,
+        })
+    }
+
+    /// Mark measures that don't fill the meter as `@pickup`: expected for an anacrusis, the last
+    /// measure and measures split around a repeat; anything else gets a warning
+    fn mark_short_measures(&mut self) {
+        let meter = &self.metadata.time_signature;
+        let expected = Rational::new(meter.beats as i64, meter.beat_type as i64);
+        let last = self.measures.len() - 1;
+        let mut unexpected = Vec::new();
+        for (i, measure) in self.measures.iter_mut().enumerate() {
+            let length: Rational = measure.elements.iter().map(Element::total_fraction).sum();
+            if length == expected {
+                continue;
+            }
+            measure.is_pickup = true;
+            let split = measure.repeat_end || measure.repeat_start || measure.ending.is_some();
+            if !(i == 0 || i == last || split) {
+                unexpected.push((i + 1).to_string());
+            }
+        }
+        if !unexpected.is_empty() {
+            self.warn(format!("Measures {} don't match the meter; marked @pickup", unexpected.join(", ")));
+        }
+    }
+
+    /// Each chord symbol lasts until the next one in the measure (or the end of the measure)
+    fn measure_chords(&mut self) {
+        let mut moved = false;
+        for measure in &mut self.measures {
+            // Chords inside a tuplet move to its first note
+            let mut group_start = None;
+            for i in 0..measure.elements.len() {
+                match measure.elements[i].tuplets().first() {
+                    Some(tuplet) if tuplet.is_start => group_start = Some(i),
+                    Some(_) => {}
+                    None => group_start = None,
+                }
+                if let Some(start) = group_start.filter(|&start| start < i) {
+                    if let Some(chord) = take_chord(&mut measure.elements[i]) {
+                        moved = true;
+                        if measure.elements[start].chord().is_none() {
+                            set_chord(&mut measure.elements[start], chord);
+                        }
+                    }
+                }
+            }
+
+            let starts: Vec<Rational> = measure
+                .elements
+                .iter()
+                .scan(Rational::ZERO, |position, element| {
+                    let start = *position;
+                    *position += element.total_fraction();
+                    Some(start)
+                })
+                .collect();
+            let end: Rational = measure.elements.iter().map(Element::total_fraction).sum();
+            let chord_indices: Vec<usize> = (0..measure.elements.len()).filter(|&i| measure.elements[i].chord().is_some()).collect();
+            for (k, &i) in chord_indices.iter().enumerate() {
+                let until = chord_indices.get(k + 1).map_or(end, |&next| starts[next]);
+                let own = match &measure.elements[i] {
+                    Element::Note(note) => (note.duration, note.dotted),
+                    Element::Rest { duration, dotted, .. } => (*duration, *dotted),
+                };
+                let (duration, dotted) = note_value(until - starts[i]).unwrap_or(own);
+                if let Some(chord) = take_chord(&mut measure.elements[i]) {
+                    set_chord(&mut measure.elements[i], ChordAnnotation::with_duration(chord.symbol, duration, dotted));
+                }
+            }
+        }
+        if moved {
+            self.warn("Moved chord symbols inside tuplets to the tuplet's first note");
+        }
+    }
+}
+
+fn take_chord(element: &mut Element) -> Option<ChordAnnotation> {
+    match element {
+        Element::Note(note) => note.chord.take(),
+        Element::Rest { chord, .. } => chord.take(),
+    }
+}
+
+fn set_chord(element: &mut Element, annotation: ChordAnnotation) {
+    match element {
+        Element::Note(note) => note.chord = Some(annotation),
+        Element::Rest { chord, .. } => *chord = Some(annotation),
+    }
+}
+
+/// The Gen note value of exactly this length, if there is one
+fn note_value(length: Rational) -> Option<(Duration, bool)> {
+    NOTE_VALUES
+        .iter()
+        .find(|(duration, dotted)| duration.modified_fraction(*dotted, &[]) == length)
+        .copied()
+}
+
+/// Note values that add up to a length (to be tied), and whether it had to be rounded
+fn note_values(length: Rational) -> (Vec<(Duration, bool)>, bool) {
+    let mut pieces = Vec::new();
+    let mut remaining = length;
+    while let Some(&(duration, dotted)) = NOTE_VALUES
+        .iter()
+        .find(|(duration, dotted)| duration.modified_fraction(*dotted, &[]) <= remaining)
+    {
+        pieces.push((duration, dotted));
+        remaining -= duration.modified_fraction(dotted, &[]);
+    }
+    if pieces.is_empty() {
+        pieces.push((Duration::ThirtySecond, false));
+    }
+    (pieces, remaining != Rational::ZERO)
+}
+
+/// Text before a `%` comment (`\%` is a literal percent sign)
+fn strip_comment(line: &str) -> String {
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'%') => {
+                text.push('%');
+                chars.next();
+            }
+            '%' => break,
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+/// `K:G` style field line: the field letter and its value
+fn field_line(line: &str) -> Option<(char, &str)> {
+    let mut chars = line.chars();
+    let field = chars.next()?;
+    (field.is_ascii_alphabetic() && chars.next() == Some(':')).then(|| (field, &line[2..]))
+}
+
+/// Index of the next `target` at or after `start`
+fn find(chars: &[char], start: usize, target: char) -> Option<usize> {
+    chars.get(start..)?.iter().position(|&c| c == target).map(|p| start + p)
+}
+
+fn unterminated(line: usize, column: usize, what: &str) -> GenError {
+    GenError::ParseError { line, column, message: format!("Unterminated {}", what) }
+}
+
+/// A decimal number at `start`, if there is one
+fn number(chars: &[char], start: usize) -> (Option<usize>, usize) {
+    let digits = chars.get(start..).map_or(0, |rest| rest.iter().take_while(|c| c.is_ascii_digit()).count());
+    let text: String = chars[start..start + digits].iter().collect();
+    (text.parse().ok(), start + digits)
+}
+
+/// Ending numbers such as `1`, `1,3` or `1-2`
+fn ending_numbers(chars: &[char], start: usize) -> (String, usize) {
+    let count = chars[start..].iter().take_while(|c| c.is_ascii_digit() || matches!(c, ',' | '-')).count();
+    let end = start + count;
+    let numbers = chars[start..end].iter().collect();
+    // A bracketed ending closes with `]` in some files: [1]
+    (numbers, if chars.get(end) == Some(&']') { end + 1 } else { end })
+}
+
+/// Length multiplier after a note: `2`, `3/2`, `/`, `//`, `/4`
+fn length_multiplier(chars: &[char], start: usize) -> (Rational, usize) {
+    let (numer, mut i) = number(chars, start);
+    let mut denom = 1;
+    while chars.get(i) == Some(&'/') {
+        let (value, end) = number(chars, i + 1);
+        denom *= value.unwrap_or(2).max(1);
+        i = end;
+    }
+    (Rational::new(numer.unwrap_or(1).max(1) as i64, denom as i64), i)
+}
+
+/// `3/8` style fraction
+fn parse_fraction(value: &str) -> Option<Rational> {
+    let (numer, denom) = value.trim().split_once('/')?;
+    let numer: i64 = numer.trim().parse().ok()?;
+    let denom: i64 = denom.trim().parse().ok()?;
+    (denom > 0).then(|| Rational::new(numer, denom))
+}
+
+/// `M:` field: `6/8`, `C` (4/4), `C|` (2/2) or an additive meter like `2+3/8`
+fn parse_meter(value: &str) -> Option<TimeSignature> {
+    match value.trim() {
+        "C" => return Some(TimeSignature { beats: 4, beat_type: 4 }),
+        "C|" => return Some(TimeSignature { beats: 2, beat_type: 2 }),
+        _ => {}
+    }
+    let (beats, beat_type) = value.trim().split_once('/')?;
+    let beats: u32 = beats.split('+').map(|part| part.trim().parse::<u32>().ok()).sum::<Option<u32>>()?;
+    let beat_type: u8 = beat_type.trim().parse().ok()?;
+    let beats = u8::try_from(beats).ok()?;
+    (beats > 0 && beat_type > 0).then_some(TimeSignature { beats, beat_type })
+}
+
+/// `Q:` field: `1/4=120`, `3/8=116`, `"Allegro" 1/4=120`, or a bare `120` (unit notes per minute)
+fn parse_tempo(value: &str, unit: Rational) -> Option<Tempo> {
+    // Drop quoted tempo text
+    let text: String = value.split('"').step_by(2).collect::<Vec<_>>().join(" ");
+    let (beat, bpm) = match text.split_once('=') {
+        Some((beats, bpm)) => {
+            let beat = beats.split_whitespace().map(parse_fraction).sum::<Option<Rational>>()?;
+            (beat, bpm)
+        }
+        None => (unit, text.as_str()),
+    };
+    let bpm: u32 = bpm.trim().parse().ok()?;
+    if bpm == 0 || beat <= Rational::ZERO {
+        return None;
+    }
+    match note_value(beat) {
+        Some((duration, dotted)) => Some(Tempo { bpm: u16::try_from(bpm).ok()?, duration, dotted }),
+        None => {
+            let quarters = (Rational::from_integer(bpm as i64) * beat * Rational::from_integer(4)).to_f64().round();
+            Some(Tempo { bpm: quarters as u16, duration: Duration::Quarter, dotted: false })
+        }
+    }
+}
+
+/// `K:` field: tonic and mode (`G`, `Em`, `D dorian`, `Amix`), optional extra or explicit
+/// accidentals (`D ^g`, `C exp _b _e`) and clef settings (dropped with a warning)
+fn parse_key(value: &str) -> (KeySignature, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut words = value.split_whitespace().peekable();
+
+    let mut key = KeySignature::default();
+    let mut alterations: Vec<(NoteName, i8)> = Vec::new();
+    let mut explicit = false;
+
+    match words.peek().copied() {
+        Some("none") => {
+            words.next();
+        }
+        Some("HP" | "Hp") => {
+            words.next();
+            warnings.push("Dropped the Highland pipes key signature".to_string());
+        }
+        Some(word) if word.starts_with(|c: char| c.is_ascii_uppercase() && NoteName::from_letter(c).is_some()) => {
+            words.next();
+            let tonic_len = 1 + word[1..].chars().take_while(|&c| c == '#' || c == 'b').count().min(1);
+            let (tonic, mut mode_name) = word.split_at(tonic_len);
+            let mode_word = words.peek().copied();
+            if mode_name.is_empty() && mode_word.is_some_and(|w| abc_mode(w).is_some()) {
+                mode_name = mode_word.unwrap_or("");
+                words.next();
+            }
+            let mode = abc_mode(mode_name).unwrap_or_else(|| {
+                warnings.push(format!("Read unknown mode {} as major", mode_name));
+                Mode::Major
+            });
+            match KeySignature::from_tonic(tonic, mode) {
+                Some(keyed) => key = keyed,
+                None => warnings.push(format!("Dropped unsupported key {}", word)),
+            }
+        }
+        _ => {}
+    }
+
+    for word in words {
+        let mut chars = word.chars();
+        match word {
+            "exp" => explicit = true,
+            _ if word.starts_with(['^', '_', '=']) => {
+                let marks: String = chars.by_ref().take_while(|c| matches!(c, '^' | '_' | '=')).collect();
+                let letter = word[marks.len()..].chars().next().and_then(|c| NoteName::from_letter(c.to_ascii_uppercase()));
+                match letter {
+                    Some(name) => {
+                        let alter = marks.chars().map(|c| match c { '^' => 1, '_' => -1, _ => 0 }).sum();
+                        alterations.push((name, alter));
+                    }
+                    None => warnings.push(format!("Dropped key signature accidental {}", word)),
+                }
+            }
+            _ if word.starts_with("clef=") || matches!(word, "treble" | "bass" | "alto" | "tenor" | "perc") => {
+                warnings.push(format!("Dropped clef {} (choose the clef when compiling)", word.trim_start_matches("clef=")));
+            }
+            _ => warnings.push(format!("Dropped key setting {}", word)),
+        }
+    }
+
+    if alterations.is_empty() && !explicit {
+        return (key, warnings);
+    }
+    // Extra accidentals modify the key's own; `exp` replaces them
+    let mut alters: Vec<(NoteName, i8)> = NoteName::ALL
+        .iter()
+        .map(|&name| (name, if explicit { 0 } else { key.accidental_for_note(name).alter() }))
+        .collect();
+    for (name, alter) in alterations {
+        if let Some(entry) = alters.iter_mut().find(|(step, _)| *step == name) {
+            entry.1 = alter;
+        }
+    }
+    if alters.iter().all(|&(name, alter)| key.accidental_for_note(name).alter() == alter) {
+        return (key, warnings);
+    }
+    let notes: Vec<String> = alters.iter().filter(|(_, alter)| *alter != 0).map(|&(name, alter)| name.spell(alter)).collect();
+    let custom = match notes.len() {
+        0 => KeySignature::default(),
+        _ => KeySignature::from_str(&format!("{},", notes.join(","))).unwrap_or_default(),
+    };
+    (custom, warnings)
+}
+
+/// ABC mode name (only the first three letters count): "" is major, "m" minor, "dor", ...
+fn abc_mode(name: &str) -> Option<Mode> {
+    let name = name.to_lowercase();
+    if name == "m" {
+        return Some(Mode::Minor);
+    }
+    match name.get(..3).unwrap_or(&name) {
+        "" | "maj" | "ion" => Some(Mode::Major),
+        "min" | "aeo" => Some(Mode::Minor),
+        "dor" => Some(Mode::Dorian),
+        "phr" => Some(Mode::Phrygian),
+        "lyd" => Some(Mode::Lydian),
+        "mix" => Some(Mode::Mixolydian),
+        "loc" => Some(Mode::Locrian),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::to_gen_source;
+    use crate::semantic::validate;
+
+    fn import(tune: &str) -> AbcImport {
+        let import = parse_abc(tune).unwrap();
+        validate(&import.score).unwrap_or_else(|e| panic!("{}\n{}", e, to_gen_source(&import.score)));
+        import
+    }
+
+    #[test]
+    fn test_headers() {
+        let tune = "X:1\nT:The Kesh\nT:Kesh Jig\nC:Trad\nM:6/8\nL:1/8\nQ:3/8=116\nK:G\nGAG GAB|\n";
+        let score = import(tune).score;
+        assert_eq!(score.metadata.title.as_deref(), Some("The Kesh"));
+        assert_eq!(score.metadata.composer.as_deref(), Some("Trad"));
+        assert_eq!(score.metadata.time_signature, TimeSignature { beats: 6, beat_type: 8 });
+        assert_eq!(score.metadata.tempo, Some(Tempo { bpm: 116, duration: Duration::Quarter, dotted: true }));
+        assert_eq!(score.metadata.key_signature.to_string(), "G");
+    }
+
+    #[test]
+    fn test_notes_octaves_and_accidentals() {
+        let tune = "X:1\nM:4/4\nL:1/8\nK:C\nA,2 ^c2 c2 =c2 | f2 g'/a'/ z B,,,4 |]\n";
+        let import = import(tune);
+        let printed = to_gen_source(&import.score);
+        // The sharp carries through the bar in ABC; Gen writes it on each note
+        assert!(printed.ends_with("_A ^C# ^C# ^C\n^F ^^G// ^^A// $/ __Bp\n"), "{}", printed);
+        assert_eq!(import.warnings, vec!["Moved notes outside Gen's range (two octaves either side) into it"]);
+    }
+
+    #[test]
+    fn test_broken_rhythms_tuplets_ties_and_slurs() {
+        let tune = "X:1\nM:2/4\nL:1/8\nK:C\n(A>B) c<d | (3efg a2- | a4 |]\n";
+        let printed = to_gen_source(&import(tune).score);
+        assert!(printed.ends_with("(A/* B//) ^C// ^D/*\n[^E/ ^F/ ^G/]3 ^A-\n^Ap\n"), "{}", printed);
+    }
+
+    #[test]
+    fn test_lengths_split_into_tied_notes() {
+        let tune = "X:1\nM:4/4\nL:1/8\nK:C\nC5 D3 |]\n";
+        let printed = to_gen_source(&import(tune).score);
+        assert!(printed.ends_with("Cp- C/ D*\n"), "{}", printed);
+    }
+
+    #[test]
+    fn test_repeats_and_endings() {
+        let tune = "X:1\nM:3/4\nL:1/4\nK:G\nD | G A B |1 c B A :|2 c d e || g3 |]\n";
+        let printed = to_gen_source(&import(tune).score);
+        assert!(printed.ends_with("||: @pickup D\nG A B\n1. ^C B A :||\n2. ^C ^D ^E\n^Gp*\n"), "{}", printed);
+    }
+
+    #[test]
+    fn test_chord_symbols_last_until_the_next() {
+        let tune = "X:1\nM:4/4\nL:1/8\nK:C\n\"C\"cdef \"G7\"g2 \"C\"c2 | \"F\"A8 |]\n";
+        let printed = to_gen_source(&import(tune).score);
+        assert!(printed.ends_with("{C}p ^C/ ^D/ ^E/ ^F/ {G7}:^G {C}:^C\n{F}:Ao\n"), "{}", printed);
+    }
+
+    #[test]
+    fn test_key_changes_and_custom_keys() {
+        assert_eq!(parse_key("Ddor").0.to_string(), "D dorian");
+        assert_eq!(parse_key("E minor clef=bass").1, vec!["Dropped clef bass (choose the clef when compiling)"]);
+        assert_eq!(parse_key("D ^g").0.to_string(), "C# F# G#");
+        assert_eq!(parse_key("C exp _b").0.to_string(), "Bb,");
+
+        let tune = "X:1\nM:2/4\nL:1/8\nK:C\nFGAB | [K:F] FGAB |]\n";
+        let score = import(tune).score;
+        assert_eq!(score.measures[1].key_change.as_ref().map(|k| k.to_string()).as_deref(), Some("F"));
+        assert_eq!(to_gen_source(&score).lines().last(), Some("@key:F F/ G/ A/ B/"));
+    }
+
+    #[test]
+    fn test_dropped_content_warnings() {
+        let tune = "X:1\nM:4/4\nL:1/4\nK:C\nV:1\n~c {g}d !trill!e [CEG] | \"^text\"c4 |]\nw:la la la\nV:2\nC4 | C4 |]\n\nX:2\nK:D\nD4|]\n";
+        let import = import(tune);
+        assert_eq!(import.score.measures.len(), 2);
+        assert_eq!(
+            import.warnings,
+            vec![
+                "Dropped decoration ~",
+                "Dropped grace notes",
+                "Dropped decoration !trill!",
+                "Kept only the top note of chords",
+                "Dropped text annotations",
+                "Dropped lyrics",
+                "Dropped voice 2 (only the first voice is imported)",
+                "Only the first tune was imported",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_chord_symbol_is_an_error() {
+        let result = parse_abc("X:1\nK:C\nC D \"Am E |\n");
+        assert!(matches!(result, Err(GenError::ParseError { line: 3, column: 5, .. })));
+    }
+}
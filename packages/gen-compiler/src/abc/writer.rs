@@ -0,0 +1,333 @@
+//! ABC tune writer
+//!
+//! Headers come from the score's metadata (`X`, `T`, `C`, `M`, `L`, `Q`, `K`); the body is
+//! written four measures to a line with a unit note length of an eighth (`L:1/8`).
+//! Eighth notes and shorter are run together where the MusicXML writer would beam them.
+//!
+//! ABC carries an accidental to later notes on the same letter in the bar (in any octave by
+//! default, only the same octave in some readers), so an accidental is written whenever either
+//! reading would get the pitch wrong, and wherever the source spelled one out.
+
+use crate::accidentals::MeasureAccidentals;
+use crate::ast::*;
+use crate::musicxml::{
+    calculate_beam_states, transpose_chord_root, written_key_signature, BeamState, Clef, Transposition, WrittenMeasure,
+};
+use crate::rational::Rational;
+use std::collections::HashMap;
+
+/// Measures per line of the tune body
+const MEASURES_PER_LINE: usize = 4;
+
+/// Unit note length (`L:` header): an eighth of a whole note
+const UNIT_DENOMINATOR: i64 = 8;
+
+/// Convert a Score to an ABC tune
+pub fn to_abc(score: &Score) -> String {
+    to_abc_with_options(score, None, Clef::Treble, 0, None)
+}
+
+/// Convert a Score to an ABC tune with transposition, clef, octave shift and mod points
+/// (the same options as [`crate::to_musicxml_with_mod_points()`])
+pub fn to_abc_with_options(
+    score: &Score,
+    transposition: Option<Transposition>,
+    clef: Clef,
+    octave_shift: i8,
+    instrument_group: Option<InstrumentGroup>,
+) -> String {
+    let transposition = transposition.as_ref();
+    let metadata = &score.metadata;
+    let mut abc = String::from("X:1\n");
+
+    if let Some(title) = &metadata.title {
+        abc.push_str(&format!("T:{}\n", title));
+    }
+    if let Some(composer) = &metadata.composer {
+        abc.push_str(&format!("C:{}\n", composer));
+    }
+    abc.push_str(&format!("M:{}/{}\n", metadata.time_signature.beats, metadata.time_signature.beat_type));
+    abc.push_str(&format!("L:1/{}\n", UNIT_DENOMINATOR));
+    if let Some(tempo) = &metadata.tempo {
+        let beat = tempo.duration.modified_fraction(tempo.dotted, &[]);
+        abc.push_str(&format!("Q:{}={}\n", beat, tempo.bpm));
+    }
+    let mut key_signature = metadata.key_signature.clone();
+    let clef_name = match clef {
+        Clef::Treble => "",
+        Clef::Bass => " clef=bass",
+    };
+    abc.push_str(&format!("K:{}{}\n", key_field(&written_key_signature(&key_signature, transposition)), clef_name));
+
+    let octave_shifts = score.octave_shifts(octave_shift, instrument_group);
+    let mut accidentals = MeasureAccidentals::new(metadata.accidentals);
+    let mut line = String::new();
+    for (i, measure) in score.measures.iter().enumerate() {
+        if i == 0 && measure.repeat_start {
+            line.push_str("|:");
+        }
+
+        line.push(' ');
+        if let Some(new_key) = measure.key_change.as_ref().filter(|key| **key != key_signature) {
+            key_signature = new_key.clone();
+            line.push_str(&format!("[K:{}] ", key_field(&written_key_signature(&key_signature, transposition))));
+        }
+
+        let mut bar = AbcBar {
+            measure: WrittenMeasure::start(&key_signature, transposition, octave_shifts[i], &mut accidentals),
+            accidentals: &mut accidentals,
+            letters: HashMap::new(),
+        };
+        line.push_str(&bar.write(&measure.elements, &metadata.time_signature));
+        line.push(' ');
+
+        let next = score.measures.get(i + 1);
+        line.push_str(&barline(measure, next));
+        if next.is_none() || (i + 1) % MEASURES_PER_LINE == 0 {
+            abc.push_str(line.trim_start());
+            abc.push('\n');
+            line.clear();
+        }
+    }
+
+    abc
+}
+
+/// Barline after a measure, with the next measure's ending number if one starts there
+fn barline(measure: &Measure, next: Option<&Measure>) -> String {
+    let Some(next) = next else {
+        return if measure.repeat_end { ":|" } else { "|]" }.to_string();
+    };
+    let ending_stops = measure.ending.is_some() && next.ending != measure.ending;
+    let mut barline = match (measure.repeat_end, next.repeat_start) {
+        (false, false) if ending_stops => "||",
+        (false, false) => "|",
+        (true, false) => ":|",
+        (false, true) => "|:",
+        (true, true) => "::",
+    }
+    .to_string();
+
+    let ending_start = next.ending.is_some() && next.ending != measure.ending;
+    if ending_start {
+        let number = match next.ending {
+            Some(Ending::First) => "1",
+            _ => "2",
+        };
+        if !barline.ends_with('|') {
+            barline.push('[');
+        }
+        barline.push_str(number);
+    }
+    barline
+}
+
+/// `K:` field value: tonic and mode ("G", "Em", "Ddor"), or explicit accidentals for a
+/// custom signature ("C exp ^f _b")
+fn key_field(key_signature: &KeySignature) -> String {
+    let Some((step, alter)) = key_signature.tonic() else {
+        let accidentals: Vec<String> = key_signature
+            .alterations
+            .iter()
+            .map(|&(step, alter)| format!("{}{}", accidental_mark(alter), step.letter().to_ascii_lowercase()))
+            .collect();
+        return format!("C exp {}", accidentals.join(" "));
+    };
+    let mode = match key_signature.mode {
+        Mode::Major => "",
+        Mode::Minor => "m",
+        Mode::Dorian => "dor",
+        Mode::Phrygian => "phr",
+        Mode::Lydian => "lyd",
+        Mode::Mixolydian => "mix",
+        Mode::Locrian => "loc",
+    };
+    format!("{}{}", step.spell(alter), mode)
+}
+
+/// ABC accidental for an alteration in semitones
+fn accidental_mark(alter: i8) -> &'static str {
+    match alter {
+        2.. => "^^",
+        1 => "^",
+        0 => "=",
+        -1 => "_",
+        _ => "__",
+    }
+}
+
+/// Note letter with octave marks (C = middle C, c = the octave above, C, = the octave below)
+fn pitch_name(step: NoteName, octave: i8) -> String {
+    let letter = step.letter();
+    if octave >= 5 {
+        format!("{}{}", letter.to_ascii_lowercase(), "'".repeat((octave - 5) as usize))
+    } else {
+        format!("{}{}", letter, ",".repeat((4 - octave).max(0) as usize))
+    }
+}
+
+/// Length multiplier relative to the unit note length ("" for one unit, "3/2", "/", "/4")
+fn length_suffix(duration: Duration, dotted: bool) -> String {
+    let units = duration.modified_fraction(dotted, &[]) * Rational::from_integer(UNIT_DENOMINATOR);
+    match (units.numer(), units.denom()) {
+        (1, 1) => String::new(),
+        (numer, 1) => numer.to_string(),
+        (1, 2) => "/".to_string(),
+        (1, denom) => format!("/{}", denom),
+        (numer, denom) => format!("{}/{}", numer, denom),
+    }
+}
+
+/// Tuplet marker: `(3` for a plain triplet, `(p:q:r` otherwise
+fn tuplet_marker(tuplet: &TupletInfo, notes: usize) -> String {
+    let (actual, normal) = (tuplet.actual_notes as usize, tuplet.normal_notes as usize);
+    match (actual, normal) {
+        (2, 3) | (3, 2) | (4, 3) if notes == actual => format!("({}", actual),
+        _ => format!("({}:{}:{}", actual, normal, notes),
+    }
+}
+
+/// Writes the notes of one measure
+struct AbcBar<'a> {
+    measure: WrittenMeasure<'a>,
+    accidentals: &'a mut MeasureAccidentals,
+    /// Alteration carried on each letter by earlier accidentals in the bar (any octave)
+    letters: HashMap<NoteName, i8>,
+}
+
+impl AbcBar<'_> {
+    fn write(&mut self, elements: &[Element], time_signature: &TimeSignature) -> String {
+        let beam_states = calculate_beam_states(elements, time_signature);
+        let mut bar = String::new();
+        for (i, beam_state) in beam_states.into_iter().enumerate() {
+            bar.push_str(&self.element(elements, i));
+            // Beamed notes are run together
+            let beamed = matches!(beam_state, BeamState::Begin | BeamState::Continue);
+            if !beamed && i + 1 < elements.len() {
+                bar.push(' ');
+            }
+        }
+        bar
+    }
+
+    fn element(&mut self, elements: &[Element], index: usize) -> String {
+        let element = &elements[index];
+        let mut token = String::new();
+
+        if let Some(chord) = element.chord() {
+            let symbol = match self.measure.transposition {
+                Some(trans) => transpose_chord_root(&chord.symbol, trans),
+                None => chord.symbol.clone(),
+            };
+            token.push_str(&format!("\"{}\"", symbol));
+        }
+
+        for (depth, tuplet) in element.tuplets().iter().enumerate() {
+            if tuplet.is_start {
+                let notes = elements[index..]
+                    .iter()
+                    .position(|e| e.tuplets().get(depth).is_some_and(|t| t.is_stop))
+                    .map_or(elements.len() - index, |stop| stop + 1);
+                token.push_str(&tuplet_marker(tuplet, notes));
+            }
+        }
+
+        match element {
+            Element::Note(note) => {
+                if note.slur_start {
+                    token.push('(');
+                }
+                token.push_str(&self.pitch(note));
+                token.push_str(&length_suffix(note.duration, note.dotted));
+                if note.tie_start {
+                    token.push('-');
+                }
+                if note.slur_stop {
+                    token.push(')');
+                }
+            }
+            Element::Rest { duration, dotted, .. } => {
+                token.push('z');
+                token.push_str(&length_suffix(*duration, *dotted));
+            }
+        }
+        token
+    }
+
+    /// Written pitch with any accidental ABC needs to read it back correctly
+    fn pitch(&mut self, note: &Note) -> String {
+        let (step, alter, octave) = self.measure.pitch(note, self.accidentals);
+
+        // Same-octave reading (plus courtesy and source accidentals), then any-octave reading
+        let explicit = note.accidental != Accidental::Natural;
+        let by_position = self
+            .accidentals
+            .note(step, octave, alter, explicit)
+            .filter(|_| explicit || !note.tie_stop)
+            .is_some();
+        let by_letter = self
+            .letters
+            .get(&step)
+            .copied()
+            .unwrap_or_else(|| self.measure.written_key.accidental_for_note(step).alter());
+        let accidental = if by_position || by_letter != alter {
+            self.letters.insert(step, alter);
+            accidental_mark(alter)
+        } else {
+            ""
+        };
+        format!("{}{}", accidental, pitch_name(step, octave))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_headers_from_metadata() {
+        let source = "---\ntitle: The Kesh\ncomposer: Trad\ntime-signature: 6/8\nkey-signature: G\ntempo: \"116*\"\n---\nG/ G/ G/ G/ A/ B/\n";
+        let abc = to_abc(&parse(source).unwrap());
+        assert!(abc.starts_with("X:1\nT:The Kesh\nC:Trad\nM:6/8\nL:1/8\nQ:3/8=116\nK:G\n"), "{}", abc);
+        assert!(abc.ends_with("GGG GAB |]\n"), "{}", abc);
+    }
+
+    #[test]
+    fn test_octaves_lengths_and_accidentals() {
+        let source = "---\nkey-signature: F\n---\n_Gp ^^C/ ^^D/ F# F\n";
+        let abc = to_abc(&parse(source).unwrap());
+        // Bb from the key needs no mark; F# stays sharp for the following F in ABC but not in Gen
+        assert!(abc.contains("G,4 c'd' ^F2 =F2 |]"), "{}", abc);
+    }
+
+    #[test]
+    fn test_tuplets_ties_slurs_and_chords() {
+        let source = "{Am}:[(A B ^C)]3 ^C/-^C/ {E7}:E-\nE E/ E/ Ep";
+        let abc = to_abc(&parse(source).unwrap());
+        assert!(abc.contains("\"Am\"(3(A2 B2 c2) c-c \"E7\"E2- | E2 EE E4 |]"), "{}", abc);
+    }
+
+    #[test]
+    fn test_repeats_and_endings() {
+        let source = "||: C D E F\n1. G A B ^C :||\n2. ^Co\nC D E F";
+        let abc = to_abc(&parse(source).unwrap());
+        assert!(abc.contains("|: C2 D2 E2 F2 |1 G2 A2 B2 c2 :|2 c8 || C2 D2 E2 F2 |]"), "{}", abc);
+    }
+
+    #[test]
+    fn test_transposed_for_bb_instrument() {
+        let source = "---\nkey-signature: Eb\n---\n{Eb}:E G B ^E\n@key:C {G7}:G B ^D ^F";
+        let abc = to_abc_with_options(&parse(source).unwrap(), Transposition::for_key("Bb"), Clef::Treble, 0, None);
+        assert!(abc.contains("K:F\n"), "{}", abc);
+        assert!(abc.contains("\"F\"F2 A2 c2 f2 | [K:D] \"A7\"A2 ^c2 e2 g2 |]"), "{}", abc);
+    }
+
+    #[test]
+    fn test_custom_key_and_mode() {
+        assert_eq!(key_field(&KeySignature::from_tonic("D", Mode::Dorian).unwrap()), "Ddor");
+        assert_eq!(key_field(&KeySignature::from_tonic("F#", Mode::Minor).unwrap()), "F#m");
+        let custom = KeySignature::from_str("F# Bb").unwrap();
+        assert_eq!(key_field(&custom), "C exp ^f _b");
+    }
+}
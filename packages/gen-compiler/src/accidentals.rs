@@ -0,0 +1,230 @@
+//! Measure-scoped accidental state
+//!
+//! In standard notation an accidental applies to later notes on the same line or space
+//! (step and octave) until the next barline. [`MeasureAccidentals`] tracks which alteration
+//! is in effect for each position so the MusicXML writer can decide which accidentals to
+//! print:
+//! - An alteration that differs from what is in effect is printed (including a natural that
+//!   cancels an earlier sharp or flat in the same bar)
+//! - The first note after a bar that altered the same position gets a courtesy
+//!   (cautionary) accidental
+//! - Accidentals written explicitly in the source are always printed
+//!
+//! Positions are tracked in written pitch (after transposition and octave shifts).
+//!
+//! [`AccidentalCarry`] resolves what each note *sounds* like under the score's
+//! [`AccidentalScope`]: with `accidentals: measure` a plain note takes the last accidental
+//! written on the same note name and octave earlier in the bar. MusicXML output and playback
+//! both use it so the rendered and played pitches agree.
+
+use crate::ast::{Accidental, AccidentalScope, KeySignature, Note, NoteName, Octave};
+use std::collections::HashMap;
+
+/// A staff position: step and octave
+type Position = (NoteName, i8);
+
+/// An accidental to print on a note
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct AccidentalDisplay {
+    /// Alteration in semitones (-2 to +2; 0 prints a natural sign)
+    pub alter: i8,
+    /// Courtesy accidental, not strictly required by the notation
+    pub cautionary: bool,
+}
+
+impl AccidentalDisplay {
+    /// MusicXML `<accidental>` value for this alteration
+    pub fn musicxml_value(&self) -> &'static str {
+        match self.alter {
+            2 => "double-sharp",
+            1 => "sharp",
+            -1 => "flat",
+            -2 => "flat-flat",
+            _ => "natural",
+        }
+    }
+}
+
+/// Alterations in effect within the current measure
+#[derive(Debug, Default)]
+pub(crate) struct MeasureAccidentals {
+    key: KeySignature,
+    current: HashMap<Position, i8>,
+    previous: HashMap<Position, i8>,
+    /// Sounding pitches of the source notes
+    sounding: AccidentalCarry,
+}
+
+impl MeasureAccidentals {
+    pub fn new(scope: AccidentalScope) -> Self {
+        Self { sounding: AccidentalCarry::new(scope), ..Self::default() }
+    }
+
+    /// Start a new measure in the given (written) key; the previous bar's alterations are
+    /// kept only for courtesy accidentals
+    pub fn start_measure(&mut self, key: &KeySignature) {
+        self.key = key.clone();
+        self.previous = std::mem::take(&mut self.current);
+        self.sounding.start_measure();
+    }
+
+    /// Sounding alteration of a source note in the (concert) key, see [`AccidentalCarry::resolve()`]
+    pub fn sounding_alter(&mut self, note: &Note, key: &KeySignature) -> i8 {
+        self.sounding.resolve(note, key)
+    }
+
+    /// Alteration a plain note at this position would carry (earlier accidental in the bar,
+    /// otherwise the key signature)
+    pub fn in_effect(&self, step: NoteName, octave: i8) -> i8 {
+        self.current
+            .get(&(step, octave))
+            .copied()
+            .unwrap_or_else(|| self.key.accidental_for_note(step).alter())
+    }
+
+    /// Record a note with the given alteration and return the accidental to print, if any
+    ///
+    /// `explicit` is true when the source spelled out an accidental on this note.
+    pub fn note(&mut self, step: NoteName, octave: i8, alter: i8, explicit: bool) -> Option<AccidentalDisplay> {
+        let position = (step, octave);
+        let in_effect = self.in_effect(step, octave);
+        let first_in_bar = !self.current.contains_key(&position);
+        self.current.insert(position, alter);
+
+        if alter != in_effect {
+            Some(AccidentalDisplay { alter, cautionary: false })
+        } else if first_in_bar && self.previous.get(&position).is_some_and(|&previous| previous != alter) {
+            Some(AccidentalDisplay { alter, cautionary: true })
+        } else if explicit {
+            Some(AccidentalDisplay { alter, cautionary: false })
+        } else {
+            None
+        }
+    }
+}
+
+/// Sounding alterations under the score's accidental scope
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AccidentalCarry {
+    scope: AccidentalScope,
+    current: HashMap<(NoteName, Octave), i8>,
+    /// Note name, octave and alteration of a note whose tie continues into the next note
+    tied: Option<(NoteName, Octave, i8)>,
+}
+
+impl AccidentalCarry {
+    pub fn new(scope: AccidentalScope) -> Self {
+        Self { scope, ..Self::default() }
+    }
+
+    /// Forget the previous measure's accidentals (a pending tie still carries over)
+    pub fn start_measure(&mut self) {
+        self.current.clear();
+    }
+
+    /// Sounding alteration of the next note in the measure, in semitones
+    pub fn resolve(&mut self, note: &Note, key: &KeySignature) -> i8 {
+        let position = (note.name, note.octave);
+        let carries = self.scope == AccidentalScope::Measure;
+
+        let alter = match note.accidental {
+            Accidental::Natural if carries => match self.tied {
+                Some((name, octave, alter)) if note.tie_stop && (name, octave) == position => alter,
+                _ => self
+                    .current
+                    .get(&position)
+                    .copied()
+                    .unwrap_or_else(|| key.accidental_for_note(note.name).alter()),
+            },
+            Accidental::Natural => key.accidental_for_note(note.name).alter(),
+            explicit => explicit.alter(),
+        };
+
+        if carries && note.accidental != Accidental::Natural {
+            self.current.insert(position, alter);
+        }
+        self.tied = note.tie_start.then_some((note.name, note.octave, alter));
+        alter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn c_major() -> MeasureAccidentals {
+        let mut accidentals = MeasureAccidentals::default();
+        accidentals.start_measure(&KeySignature::default());
+        accidentals
+    }
+
+    #[test]
+    fn test_natural_cancels_earlier_sharp() {
+        let mut accidentals = c_major();
+        assert_eq!(accidentals.note(NoteName::F, 4, 1, true).map(|a| a.alter), Some(1));
+        // A plain F later in the bar needs a natural sign
+        assert_eq!(accidentals.note(NoteName::F, 4, 0, false), Some(AccidentalDisplay { alter: 0, cautionary: false }));
+        // Once cancelled, further plain Fs print nothing
+        assert_eq!(accidentals.note(NoteName::F, 4, 0, false), None);
+    }
+
+    #[test]
+    fn test_other_octave_is_independent() {
+        let mut accidentals = c_major();
+        accidentals.note(NoteName::F, 4, 1, true);
+        assert_eq!(accidentals.note(NoteName::F, 5, 0, false), None);
+    }
+
+    #[test]
+    fn test_courtesy_accidental_in_next_bar() {
+        let mut accidentals = c_major();
+        accidentals.note(NoteName::F, 4, 1, true);
+        accidentals.start_measure(&KeySignature::default());
+
+        assert_eq!(accidentals.note(NoteName::F, 4, 0, false), Some(AccidentalDisplay { alter: 0, cautionary: true }));
+        assert_eq!(accidentals.note(NoteName::F, 4, 0, false), None);
+    }
+
+    #[test]
+    fn test_key_signature_alterations_are_silent() {
+        let mut accidentals = MeasureAccidentals::default();
+        accidentals.start_measure(&KeySignature::from_str("D").unwrap());
+        assert_eq!(accidentals.note(NoteName::F, 4, 1, false), None);
+        assert_eq!(accidentals.note(NoteName::C, 4, 2, true).map(|a| a.musicxml_value()), Some("double-sharp"));
+    }
+
+    fn resolved(source: &str, scope: AccidentalScope) -> Vec<i8> {
+        let score = parse(source).unwrap();
+        let mut carry = AccidentalCarry::new(scope);
+        let mut alters = Vec::new();
+        for measure in &score.measures {
+            carry.start_measure();
+            for element in &measure.elements {
+                if let crate::ast::Element::Note(note) = element {
+                    alters.push(carry.resolve(note, &score.metadata.key_signature));
+                }
+            }
+        }
+        alters
+    }
+
+    #[test]
+    fn test_measure_scope_carries_within_bar_and_octave() {
+        let alters = resolved("F# G F ^F\nF G A B", AccidentalScope::Measure);
+        assert_eq!(alters, vec![1, 0, 1, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_note_scope_follows_key() {
+        let alters = resolved("F# G F ^F", AccidentalScope::Note);
+        assert_eq!(alters, vec![1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_measure_scope_natural_and_tie_across_barline() {
+        // F% cancels the sharp; the tie carries Bb into the next bar, but the next B is natural
+        let alters = resolved("F# F% F Bb-\nB B G A", AccidentalScope::Measure);
+        assert_eq!(alters, vec![1, 0, 0, -1, -1, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,370 @@
+//! Roman numeral and Nashville number analysis of chord symbols
+//!
+//! Each chord symbol is numbered against the key in effect where it appears (the score's
+//! `key-signature`, then any `@key:` changes):
+//! - **Roman numerals** are relative to the key's own scale. Case shows the chord quality
+//!   (`ii`, `V`), `°`/`ø`/`+` mark diminished, half-diminished and augmented chords, and
+//!   inversion figures (`6`, `64`, `65`, `43`, `42`) show a chord tone in the bass
+//! - **Nashville numbers** are relative to the tonic's major scale (C minor's Ab chord is
+//!   `b6`), with `m`, `°`, `ø`, `+` for quality, superscript sevenths and `/` for the bass
+//!
+//! Chords are classified as (first match wins):
+//! - Diatonic: every chord tone is in the key's scale (in minor, the harmonic minor's
+//!   leading tone also counts, for `V7` and `vii°7`)
+//! - Borrowed: diatonic in the parallel major or minor (`iv` or `bVII` in a major key)
+//! - Secondary dominant: a major or dominant seventh chord a fifth above a major or minor
+//!   diatonic chord other than the tonic (`V7/ii`)
+//! - Chromatic: anything else (`bII7`)
+//!
+//! Custom key signatures have no tonic, so their chords are reported without numerals.
+
+use crate::ast::{Element, KeySignature, Mode, NoteName, Score};
+use crate::playback::{parse_chord_structure, ChordStructure};
+use crate::rational::Rational;
+use serde::Serialize;
+
+const ROMAN_NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+/// How a chord relates to the key
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChordFunction {
+    Diatonic,
+    SecondaryDominant,
+    Borrowed,
+    Chromatic,
+}
+
+/// Roman numeral and Nashville number for a chord symbol
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChordNumerals {
+    pub roman: String,     // e.g. "V7/V", "bVII", "viiø7", "I6"
+    pub nashville: String, // e.g. "2⁷", "b7", "7ø⁷", "1/3"
+    pub degree: u8,        // Scale degree of the root (1-7)
+    pub alter: i8,         // Root alteration against the key's scale (-1 for bVII in major)
+    pub function: ChordFunction,
+}
+
+/// A chord symbol in the score and its numerals
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChordAnalysis {
+    pub measure: usize, // 1-based measure number
+    pub beat: f64,      // 1-based beat within the measure
+    pub symbol: String,
+    pub key: String, // Key in effect, as `key-signature` spells it
+    #[serde(flatten)]
+    pub numerals: Option<ChordNumerals>, // None for unrecognised symbols and custom key signatures
+}
+
+/// Number every chord symbol in the score, in order
+pub fn analyze_harmony(score: &Score) -> Vec<ChordAnalysis> {
+    let mut analyses = Vec::new();
+    let mut key = score.metadata.key_signature.clone();
+
+    for (index, measure) in score.measures.iter().enumerate() {
+        if let Some(key_change) = &measure.key_change {
+            key = key_change.clone();
+        }
+
+        let mut position = Rational::ZERO;
+        for element in &measure.elements {
+            let chord = match element {
+                Element::Note(note) => &note.chord,
+                Element::Rest { chord, .. } => chord,
+            };
+            if let Some(chord) = chord {
+                analyses.push(ChordAnalysis {
+                    measure: index + 1,
+                    beat: 1.0 + position.to_f64(),
+                    symbol: chord.symbol.clone(),
+                    key: key.to_string(),
+                    numerals: chord_numerals(&chord.symbol, &key),
+                });
+            }
+            position += element.total_beats(&score.metadata.time_signature);
+        }
+    }
+    analyses
+}
+
+/// Roman numeral and Nashville number for a chord symbol in a key
+///
+/// Returns None if the symbol has no recognisable root or the key is a custom signature.
+pub fn chord_numerals(symbol: &str, key: &KeySignature) -> Option<ChordNumerals> {
+    let (tonic_step, tonic_alter) = key.tonic()?;
+    let structure = parse_chord_structure(symbol)?;
+    let (root_step, root_alter) = spelled_note(symbol)?;
+    let chord = Quality::of(&structure);
+
+    let scale = key.mode.scale();
+    let major_scale = Mode::Major.scale();
+    let degree = steps_between(tonic_step, root_step);
+    let interval = semitones_between((tonic_step, tonic_alter), (root_step, root_alter));
+    let mut alter = wrap(interval as i8 - scale[degree] as i8);
+    // The raised leading tone is part of minor keys (vii°7, not #vii°7)
+    if key.mode == Mode::Minor && degree == 6 && alter == 1 {
+        alter = 0;
+    }
+
+    // Chord tones as semitones above the tonic (extensions don't affect the function)
+    let tones: Vec<u8> = [Some(0), structure.third, structure.fifth, structure.seventh]
+        .into_iter()
+        .flatten()
+        .map(|tone| (interval + tone) % 12)
+        .collect();
+    let parallel = if scale[2] == 4 { Mode::Minor } else { Mode::Major };
+    let (function, roman) = if is_diatonic(&tones, key.mode) {
+        (ChordFunction::Diatonic, roman_numeral(alter, degree, &chord))
+    } else if is_diatonic(&tones, parallel) {
+        (ChordFunction::Borrowed, roman_numeral(alter, degree, &chord))
+    } else if let Some(target) = secondary_target(degree, interval, &chord, &scale, key.mode) {
+        (ChordFunction::SecondaryDominant, format!("{}/{}", roman_numeral(0, 4, &chord), target))
+    } else {
+        (ChordFunction::Chromatic, roman_numeral(alter, degree, &chord))
+    };
+
+    let mut nashville = format!(
+        "{}{}{}",
+        accidental_prefix(wrap(interval as i8 - major_scale[degree] as i8)),
+        degree + 1,
+        chord.nashville_suffix()
+    );
+    if let Some((bass_step, bass_alter)) = symbol.split_once('/').and_then(|(_, bass)| spelled_note(bass)) {
+        let bass_degree = steps_between(tonic_step, bass_step);
+        let bass_interval = semitones_between((tonic_step, tonic_alter), (bass_step, bass_alter));
+        let bass_alter = wrap(bass_interval as i8 - major_scale[bass_degree] as i8);
+        nashville.push_str(&format!("/{}{}", accidental_prefix(bass_alter), bass_degree + 1));
+    }
+
+    Some(ChordNumerals { roman, nashville, degree: degree as u8 + 1, alter, function })
+}
+
+/// Chord quality as intervals above the root
+struct Quality {
+    third: Option<u8>,
+    fifth: Option<u8>,
+    seventh: Option<u8>,
+    /// Inversion shown by the bass note: 0 = root position, 1 = third, 2 = fifth, 3 = seventh
+    inversion: usize,
+}
+
+impl Quality {
+    fn of(structure: &ChordStructure) -> Self {
+        let bass = structure.bass.map(|bass| (bass + 12 - structure.root) % 12);
+        let inversion = match bass {
+            Some(bass) if Some(bass) == structure.third => 1,
+            Some(bass) if Some(bass) == structure.fifth => 2,
+            Some(bass) if Some(bass) == structure.seventh => 3,
+            _ => 0,
+        };
+        Quality { third: structure.third, fifth: structure.fifth, seventh: structure.seventh, inversion }
+    }
+
+    fn is_minor(&self) -> bool {
+        self.third == Some(3)
+    }
+
+    fn is_diminished(&self) -> bool {
+        self.is_minor() && self.fifth == Some(6)
+    }
+
+    fn is_augmented(&self) -> bool {
+        self.third == Some(4) && self.fifth == Some(8)
+    }
+
+    /// Suspension replacing the third ("sus4", "sus2"), if any
+    fn suspension(&self) -> &'static str {
+        match self.third {
+            Some(5) => "sus4",
+            Some(2) => "sus2",
+            _ => "",
+        }
+    }
+
+    /// Major triad or dominant seventh with a perfect (or omitted) fifth
+    fn is_dominant(&self) -> bool {
+        self.third == Some(4) && matches!(self.fifth, Some(7) | None) && matches!(self.seventh, Some(10) | None)
+    }
+
+    /// Quality mark after a Roman numeral or Nashville number: °, ø, + or maj
+    fn mark(&self) -> &'static str {
+        match (self.seventh, self.is_diminished(), self.is_augmented()) {
+            (Some(10), true, _) => "ø",
+            (_, true, _) => "°",
+            (_, _, true) => "+",
+            (Some(11), ..) => "maj",
+            _ => "",
+        }
+    }
+
+    fn nashville_suffix(&self) -> String {
+        let minor = if self.is_minor() && !self.is_diminished() { "m" } else { "" };
+        let seventh = if self.seventh.is_some() { "⁷" } else { "" };
+        format!("{}{}{}{}", minor, self.mark(), seventh, self.suspension())
+    }
+}
+
+/// Roman numeral for a chord on a scale degree (0-based) with a root alteration
+fn roman_numeral(alter: i8, degree: usize, chord: &Quality) -> String {
+    let numeral = if chord.is_minor() {
+        ROMAN_NUMERALS[degree].to_lowercase()
+    } else {
+        ROMAN_NUMERALS[degree].to_string()
+    };
+    let figure = match (chord.seventh.is_some(), chord.inversion) {
+        (true, 1) => "65",
+        (true, 2) => "43",
+        (true, 3) => "42",
+        (true, _) => "7",
+        (false, 1) => "6",
+        (false, 2) => "64",
+        (false, _) => "",
+    };
+    format!("{}{}{}{}{}", accidental_prefix(alter), numeral, chord.mark(), figure, chord.suspension())
+}
+
+/// Numeral of the diatonic chord a dominant chord resolves to, if it is a secondary dominant
+fn secondary_target(degree: usize, interval: u8, chord: &Quality, scale: &[u8; 7], mode: Mode) -> Option<String> {
+    if !chord.is_dominant() {
+        return None;
+    }
+    let target = (degree + 3) % 7;
+    if target == 0 || scale[target] != (interval + 5) % 12 {
+        return None;
+    }
+    let third = (scale[(target + 2) % 7] + 12 - scale[target]) % 12;
+    let fifth = (scale[(target + 4) % 7] + 12 - scale[target]) % 12;
+    match (third, fifth) {
+        // Minor keys tonicise the (harmonic minor) major V, not the natural minor v
+        (3, 7) if mode == Mode::Minor && target == 4 => Some(ROMAN_NUMERALS[target].to_string()),
+        (3, 7) => Some(ROMAN_NUMERALS[target].to_lowercase()),
+        (4, 7) => Some(ROMAN_NUMERALS[target].to_string()),
+        _ => None,
+    }
+}
+
+/// Whether every chord tone (semitones above the tonic) is in the mode's scale
+fn is_diatonic(tones: &[u8], mode: Mode) -> bool {
+    let fits = |scale: [u8; 7]| tones.iter().all(|tone| scale.contains(tone));
+    let mut harmonic_minor = mode.scale();
+    harmonic_minor[6] = 11;
+    fits(mode.scale()) || (mode == Mode::Minor && fits(harmonic_minor))
+}
+
+/// Root step and alteration at the start of a chord symbol or bass note ("Bb7" -> (B, -1))
+fn spelled_note(symbol: &str) -> Option<(NoteName, i8)> {
+    let mut chars = symbol.chars();
+    let step = NoteName::from_letter(chars.next()?)?;
+    let alter = match chars.next() {
+        Some('#') => 1,
+        Some('b') => -1,
+        _ => 0,
+    };
+    Some((step, alter))
+}
+
+/// Letter names from one step up to another (0-6)
+fn steps_between(from: NoteName, to: NoteName) -> usize {
+    let index = |step: NoteName| NoteName::ALL.iter().position(|&s| s == step).unwrap_or(0);
+    (index(to) + 7 - index(from)) % 7
+}
+
+/// Semitones from one spelled note up to another (0-11)
+fn semitones_between(from: (NoteName, i8), to: (NoteName, i8)) -> u8 {
+    let pitch_class = |(step, alter): (NoteName, i8)| step.pitch_class() as i8 + alter;
+    (pitch_class(to) - pitch_class(from)).rem_euclid(12) as u8
+}
+
+/// Wrap a semitone difference into -6..=5
+fn wrap(semitones: i8) -> i8 {
+    (semitones + 6).rem_euclid(12) - 6
+}
+
+fn accidental_prefix(alter: i8) -> &'static str {
+    match alter {
+        2.. => "##",
+        1 => "#",
+        0 => "",
+        -1 => "b",
+        _ => "bb",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn numerals(symbol: &str, key: &str) -> (String, String, ChordFunction) {
+        let numerals = chord_numerals(symbol, &KeySignature::from_str(key).unwrap()).unwrap();
+        (numerals.roman, numerals.nashville, numerals.function)
+    }
+
+    #[test]
+    fn test_diatonic_chords_in_major() {
+        assert_eq!(numerals("C", "C"), ("I".to_string(), "1".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("Dm7", "C"), ("ii7".to_string(), "2m⁷".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("G7", "C"), ("V7".to_string(), "5⁷".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("Fmaj7", "C"), ("IVmaj7".to_string(), "4maj⁷".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("Bm7b5", "C"), ("viiø7".to_string(), "7ø⁷".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("F#m7", "D").0, "iii7");
+    }
+
+    #[test]
+    fn test_minor_key() {
+        assert_eq!(numerals("Cm", "Cm"), ("i".to_string(), "1m".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("Ab", "Cm"), ("VI".to_string(), "b6".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("G7", "Cm"), ("V7".to_string(), "5⁷".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("Bdim7", "Cm"), ("vii°7".to_string(), "7°⁷".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("D7", "Cm").0, "V7/V");
+    }
+
+    #[test]
+    fn test_secondary_dominants() {
+        assert_eq!(numerals("D7", "C"), ("V7/V".to_string(), "2⁷".to_string(), ChordFunction::SecondaryDominant));
+        assert_eq!(numerals("A7", "C").0, "V7/ii");
+        assert_eq!(numerals("E", "C").0, "V/vi");
+        assert_eq!(numerals("C7", "C").0, "V7/IV");
+        // F#7 points at the diminished vii°, which can't be tonicised
+        assert_eq!(numerals("B7", "C").0, "V7/iii");
+        assert_eq!(numerals("F#7", "C").2, ChordFunction::Chromatic);
+    }
+
+    #[test]
+    fn test_borrowed_and_chromatic_chords() {
+        assert_eq!(numerals("Fm", "C"), ("iv".to_string(), "4m".to_string(), ChordFunction::Borrowed));
+        assert_eq!(numerals("Bb", "C"), ("bVII".to_string(), "b7".to_string(), ChordFunction::Borrowed));
+        assert_eq!(numerals("Ab", "C").0, "bVI");
+        assert_eq!(numerals("F", "Am"), ("VI".to_string(), "b6".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("D", "Am"), ("IV".to_string(), "4".to_string(), ChordFunction::Borrowed));
+        assert_eq!(numerals("Db7", "C"), ("bII7".to_string(), "b2⁷".to_string(), ChordFunction::Chromatic));
+    }
+
+    #[test]
+    fn test_inversions_and_slash_chords() {
+        assert_eq!(numerals("C/E", "C"), ("I6".to_string(), "1/3".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("G7/B", "C").0, "V65");
+        assert_eq!(numerals("G7/F", "C").0, "V42");
+        assert_eq!(numerals("C/Bb", "C").1, "1/b7");
+    }
+
+    #[test]
+    fn test_modal_keys_and_custom_signatures() {
+        assert_eq!(numerals("C", "D dorian"), ("VII".to_string(), "b7".to_string(), ChordFunction::Diatonic));
+        assert_eq!(numerals("G", "D dorian").0, "IV");
+        assert!(chord_numerals("C", &KeySignature::from_str("Bb Eb F#").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_analysis_follows_key_changes() {
+        let score = parse("{C}:C D {G7}:E F\n@key:F {C7}:C D E F\n{F}:Fo").unwrap();
+        let analyses = analyze_harmony(&score);
+        let summary: Vec<(usize, f64, &str, &str)> = analyses
+            .iter()
+            .map(|a| (a.measure, a.beat, a.key.as_str(), a.numerals.as_ref().unwrap().roman.as_str()))
+            .collect();
+        assert_eq!(summary, vec![(1, 1.0, "C", "I"), (1, 3.0, "C", "V7"), (2, 1.0, "F", "V7"), (3, 1.0, "F", "I")]);
+    }
+}
@@ -0,0 +1,398 @@
+//! Key estimation (Krumhansl-Schmuckler) and the re-key transform
+//!
+//! The score is reduced to a 12-bin pitch-class distribution: every note adds its duration to
+//! its sounding pitch class, and every chord symbol adds half its duration to each chord tone
+//! (the root counts double). The distribution is correlated with a profile for each of the 84 keys
+//! (12 tonics x 7 modes):
+//! - Major and minor use the Krumhansl-Kessler probe-tone profiles
+//! - Church modes weight each scale degree like the major profile does (tonic, then fifth,
+//!   then third...) over the mode's own scale
+//!
+//! Church modes share their pitch set with a major and a minor key and are told apart only by
+//! which notes are emphasised, so they must beat major/minor by [`MODAL_MARGIN`] to win.
+
+use crate::accidentals::AccidentalCarry;
+use crate::ast::{Accidental, Element, KeySignature, Mode, Note, Score};
+use crate::lexer::{Lexer, Token};
+use crate::playback::parse_chord_structure;
+use crate::printer::accidental_text;
+use crate::{parse, GenError};
+
+/// Krumhansl-Kessler major key profile, from the tonic up by semitone
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+
+/// Krumhansl-Kessler minor key profile, from the tonic up by semitone
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Major profile weights by scale degree (1 to 7), used to build the church mode profiles
+const DEGREE_WEIGHTS: [f64; 7] = [6.35, 3.48, 4.38, 4.09, 5.19, 3.66, 2.88];
+
+/// Major profile weight of the notes outside the scale (average)
+const CHROMATIC_WEIGHT: f64 = 2.35;
+
+/// Correlation a church mode must gain over major/minor before it is preferred
+const MODAL_MARGIN: f64 = 0.05;
+
+/// Chord tones count for this share of the chord's duration (the root counts double)
+const CHORD_TONE_WEIGHT: f64 = 0.5;
+
+const MODES: [Mode; 7] = [Mode::Major, Mode::Minor, Mode::Dorian, Mode::Phrygian, Mode::Lydian, Mode::Mixolydian, Mode::Locrian];
+
+/// An estimated key
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyEstimate {
+    /// Key signature with tonic and mode (never a custom signature)
+    pub key: KeySignature,
+    /// Correlation between the score's pitch distribution and the key's profile (0.0 to 1.0)
+    pub confidence: f64,
+}
+
+/// Estimate the key and mode of a score
+///
+/// Returns None if the score has no notes or chord symbols to analyse.
+pub fn estimate_key(score: &Score) -> Option<KeyEstimate> {
+    rank_keys(score).into_iter().next()
+}
+
+/// Score every tonic and mode against the score's pitch content, best match first
+///
+/// Returns an empty list if the score has no notes or chord symbols to analyse.
+pub fn rank_keys(score: &Score) -> Vec<KeyEstimate> {
+    let content = PitchContent::of(score);
+    if content.distribution.iter().all(|&weight| weight == 0.0) {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(f64, KeyEstimate)> = Vec::new();
+    for tonic in 0..12 {
+        for mode in MODES {
+            let profile = profile(mode);
+            let rotated: Vec<f64> = (0..12).map(|pc| profile[(pc + 12 - tonic) % 12]).collect();
+            let correlation = correlation(&content.distribution, &rotated);
+            let margin = if matches!(mode, Mode::Major | Mode::Minor) { 0.0 } else { MODAL_MARGIN };
+            let key = spell_key(tonic, mode, content.prefers_sharps());
+            ranked.push((correlation - margin, KeyEstimate { key, confidence: correlation.clamp(0.0, 1.0) }));
+        }
+    }
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranked.into_iter().map(|(_, estimate)| estimate).collect()
+}
+
+/// Re-key a source in its estimated key
+///
+/// Sets `key-signature` in the frontmatter and rewrites accidentals so every note keeps its
+/// pitch, see [`rekey_to()`]. A source with nothing to analyse is returned unchanged.
+pub fn rekey(source: &str) -> Result<String, GenError> {
+    let score = parse(source)?;
+    match estimate_key(&score) {
+        Some(estimate) => rekey_to(source, &estimate.key),
+        None => Ok(source.to_string()),
+    }
+}
+
+/// Re-key a source in the given key
+///
+/// The `key-signature` metadata is set (or added) and each note's accidental is rewritten
+/// against the new key: accidentals the key now supplies are stripped, and notes the key
+/// would alter get an explicit accidental (`%` for a natural). Measures after an `@key:`
+/// change keep that key. The `accidentals` scope is respected, so under `accidentals: measure`
+/// an accidental that carries to later notes in the bar is kept.
+pub fn rekey_to(source: &str, key: &KeySignature) -> Result<String, GenError> {
+    let score = parse(source)?;
+    let notes = note_tokens(source)?;
+    let scope = score.metadata.accidentals;
+
+    let mut written_notes = 0;
+    let mut edits: Vec<(usize, usize, &'static str)> = Vec::new(); // (byte offset, length to replace, new text)
+    let mut old_key = score.metadata.key_signature.clone();
+    let mut new_key = key.clone();
+    let mut old_carry = AccidentalCarry::new(scope);
+    let mut new_carry = AccidentalCarry::new(scope);
+
+    for measure in &score.measures {
+        if let Some(key_change) = &measure.key_change {
+            old_key = key_change.clone();
+            new_key = key_change.clone();
+        }
+        old_carry.start_measure();
+        new_carry.start_measure();
+
+        for element in &measure.elements {
+            let Element::Note(note) = element else { continue };
+            let &(offset, accidental_len) = notes.get(written_notes).ok_or_else(unmatched_notes)?;
+            written_notes += 1;
+
+            let alter = old_carry.resolve(note, &old_key);
+            let plain = Note { accidental: Accidental::Natural, ..note.clone() };
+            let mut trial = new_carry.clone();
+            let accidental = if trial.resolve(&plain, &new_key) == alter {
+                new_carry = trial;
+                ""
+            } else {
+                let explicit = match Accidental::from_alter(alter) {
+                    Accidental::Natural => Accidental::ForceNatural,
+                    accidental => accidental,
+                };
+                new_carry.resolve(&Note { accidental: explicit, ..note.clone() }, &new_key);
+                accidental_text(explicit)
+            };
+
+            if accidental != &source[offset..offset + accidental_len] {
+                edits.push((offset, accidental_len, accidental));
+            }
+        }
+    }
+    if written_notes != notes.len() {
+        return Err(unmatched_notes());
+    }
+
+    let mut rewritten = source.to_string();
+    for &(offset, len, text) in edits.iter().rev() {
+        rewritten.replace_range(offset..offset + len, text);
+    }
+    Ok(set_key_signature(&rewritten, key))
+}
+
+/// Pitch content of a score
+struct PitchContent {
+    /// Duration-weighted pitch-class distribution
+    distribution: [f64; 12],
+    /// Sharps minus flats written in notes and chord roots
+    sharps: i32,
+}
+
+impl PitchContent {
+    fn of(score: &Score) -> Self {
+        let mut content = PitchContent { distribution: [0.0; 12], sharps: 0 };
+        let mut key = score.metadata.key_signature.clone();
+        let mut carry = AccidentalCarry::new(score.metadata.accidentals);
+
+        for measure in &score.measures {
+            if let Some(key_change) = &measure.key_change {
+                key = key_change.clone();
+            }
+            carry.start_measure();
+
+            for element in &measure.elements {
+                let chord = match element {
+                    Element::Note(note) => {
+                        let alter = carry.resolve(note, &key);
+                        let pitch_class = (note.name.pitch_class() as i32 + alter as i32).rem_euclid(12) as usize;
+                        content.distribution[pitch_class] += element.total_fraction().to_f64();
+                        content.sharps += note.accidental.alter().signum() as i32;
+                        &note.chord
+                    }
+                    Element::Rest { chord, .. } => chord,
+                };
+
+                let Some(chord) = chord else { continue };
+                let Some(structure) = parse_chord_structure(&chord.symbol) else { continue };
+                let weight = chord.duration.modified_fraction(chord.dotted, &[]).to_f64() * CHORD_TONE_WEIGHT;
+                content.distribution[structure.root as usize % 12] += 2.0 * weight;
+                for interval in structure.upper_intervals() {
+                    content.distribution[(structure.root + interval) as usize % 12] += weight;
+                }
+                content.sharps += match chord.symbol.chars().nth(1) {
+                    Some('#') => 1,
+                    Some('b') => -1,
+                    _ => 0,
+                };
+            }
+        }
+        content
+    }
+
+    fn prefers_sharps(&self) -> bool {
+        self.sharps >= 0
+    }
+}
+
+/// Profile of a mode, from the tonic up by semitone
+fn profile(mode: Mode) -> [f64; 12] {
+    match mode {
+        Mode::Major => MAJOR_PROFILE,
+        Mode::Minor => MINOR_PROFILE,
+        _ => {
+            let mut profile = [CHROMATIC_WEIGHT; 12];
+            for (semitone, weight) in mode.scale().into_iter().zip(DEGREE_WEIGHTS) {
+                profile[semitone as usize] = weight;
+            }
+            profile
+        }
+    }
+}
+
+/// Pearson correlation of two distributions
+fn correlation(x: &[f64], y: &[f64]) -> f64 {
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let (mean_x, mean_y) = (mean(x), mean(y));
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    let mut variance_y = 0.0;
+    for (a, b) in x.iter().zip(y) {
+        covariance += (a - mean_x) * (b - mean_y);
+        variance_x += (a - mean_x).powi(2);
+        variance_y += (b - mean_y).powi(2);
+    }
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return 0.0;
+    }
+    covariance / (variance_x * variance_y).sqrt()
+}
+
+/// Key signature for a tonic pitch class and mode, spelled with the fewest sharps or flats
+/// (enharmonic ties such as F#/Gb major follow the source's spelling)
+fn spell_key(tonic: usize, mode: Mode, prefers_sharps: bool) -> KeySignature {
+    let fifths = (-7..=7i8)
+        .filter(|fifths| {
+            // The major tonic for `fifths - offset` is that many perfect fifths (7 semitones) above C
+            let tonic_fifths = (fifths - mode.fifths_offset()) as i32;
+            (tonic_fifths * 7).rem_euclid(12) as usize == tonic
+        })
+        .min_by_key(|fifths| (fifths.abs(), if prefers_sharps { -fifths } else { *fifths }))
+        .unwrap_or(0);
+    KeySignature::from_fifths(fifths, mode)
+}
+
+/// Byte offset just after each note letter and the length of its written accidental, in order
+fn note_tokens(source: &str) -> Result<Vec<(usize, usize)>, GenError> {
+    let tokens = Lexer::new(source).tokenize()?;
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let byte_offset = |line: usize, column: usize| {
+        let start = line_starts[line - 1];
+        start + source[start..].chars().take(column - 1).map(char::len_utf8).sum::<usize>()
+    };
+
+    let mut notes = Vec::new();
+    for (i, located) in tokens.iter().enumerate() {
+        if !matches!(
+            located.token,
+            Token::NoteA | Token::NoteB | Token::NoteC | Token::NoteD | Token::NoteE | Token::NoteF | Token::NoteG
+        ) {
+            continue;
+        }
+        let accidental_len = match tokens.get(i + 1).map(|next| &next.token) {
+            Some(Token::Sharp | Token::Flat | Token::Natural) => 1,
+            Some(Token::DoubleSharp | Token::DoubleFlat) => 2,
+            _ => 0,
+        };
+        notes.push((byte_offset(located.line, located.column) + 1, accidental_len));
+    }
+    Ok(notes)
+}
+
+fn unmatched_notes() -> GenError {
+    GenError::ParseError { line: 1, column: 1, message: "Could not match the parsed notes to the source".to_string() }
+}
+
+/// Set `key-signature` in the frontmatter, adding the field (or the frontmatter) if missing
+fn set_key_signature(source: &str, key: &KeySignature) -> String {
+    let field = format!("key-signature: {}", key);
+    let mut lines: Vec<&str> = source.split('\n').collect();
+    let markers: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == "---")
+        .map(|(i, _)| i)
+        .take(2)
+        .collect();
+
+    let [start, end] = markers[..] else {
+        return format!("---\n{}\n---\n{}", field, source);
+    };
+    match (start + 1..end).find(|&i| lines[i].trim_start().starts_with("key-signature:")) {
+        Some(i) => lines[i] = &field,
+        None => lines.insert(end, &field),
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate(source: &str) -> KeyEstimate {
+        estimate_key(&parse(source).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_major_and_minor_keys() {
+        let g_major = estimate("G A B ^C\n^D ^E ^F# ^G\n^D B G $");
+        assert_eq!(g_major.key, KeySignature::from_str("G").unwrap());
+        assert!(g_major.confidence > 0.7);
+
+        let c_minor = estimate("C D Eb F\nG Ab B ^C\nG Eb C $");
+        assert_eq!(c_minor.key.to_string(), "Cm");
+    }
+
+    #[test]
+    fn test_modal_key() {
+        // D dorian: D minor with B natural, centred on D and A
+        let dorian = estimate("D E F G\nA B ^C ^D\nA B A F\nD A Dp");
+        assert_eq!(dorian.key.to_string(), "D dorian");
+    }
+
+    #[test]
+    fn test_chord_symbols_inform_estimate() {
+        // The same melody fits Eb major and C minor; the chords decide
+        let major = estimate("{Eb}:G Bb G Eb\n{Ab}:C Eb Ab C\n{Bb7}:D F Bb D\n{Eb}:Eb G Eb G");
+        let minor = estimate("{Cm}:G Bb G Eb\n{Fm}:C Eb Ab C\n{G7}:D F Bb D\n{Cm}:Eb G Eb G");
+        assert_eq!(major.key.to_string(), "Eb");
+        assert_eq!(minor.key.to_string(), "Cm");
+    }
+
+    #[test]
+    fn test_enharmonic_spelling_follows_source() {
+        let flats = estimate("Gb Ab Bb Cb\nDb Eb F Gb\nDb Bb Gbp");
+        assert_eq!(flats.key.to_string(), "Gb");
+        let sharps = estimate("F# G# A# B\nC# D# E# F#\nC# A# F#p");
+        assert_eq!(sharps.key.to_string(), "F#");
+    }
+
+    #[test]
+    fn test_empty_score_has_no_estimate() {
+        assert!(estimate_key(&parse("$ $ $ $").unwrap()).is_none());
+        assert!(rank_keys(&parse("$ $ $ $").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_rekey_strips_redundant_accidentals() {
+        let source = "---\ntitle: Tune\n---\nG A B ^C\n^D ^E ^F# ^G\nF ^D B G";
+        let rekeyed = rekey(source).unwrap();
+        assert_eq!(rekeyed, "---\ntitle: Tune\nkey-signature: G\n---\nG A B ^C\n^D ^E ^F ^G\nF% ^D B G");
+    }
+
+    #[test]
+    fn test_rekey_keeps_every_pitch() {
+        let source = "Bb/ C/ D/ Eb/ F/ G/ A/ Bb/\n[Bb A Ab]3 G F% Eb\nBbo";
+        let rekeyed = rekey_to(source, &KeySignature::from_str("Bb").unwrap()).unwrap();
+        assert_eq!(rekeyed, "---\nkey-signature: Bb\n---\nB/ C/ D/ E/ F/ G/ A/ B/\n[B A Ab]3 G F E\nBo");
+
+        let pitches = |source: &str| {
+            let score = parse(source).unwrap();
+            let key = score.metadata.key_signature.clone();
+            score.measures.iter().flat_map(|m| m.elements.clone()).filter_map(|e| match e {
+                Element::Note(note) => Some(note.to_midi_note(&key, 0)),
+                _ => None,
+            }).collect::<Vec<_>>()
+        };
+        assert_eq!(pitches(source), pitches(&rekeyed));
+    }
+
+    #[test]
+    fn test_rekey_replaces_existing_key_and_respects_measure_scope() {
+        // Under measure scope the second F carries the first F#'s sharp
+        let source = "---\nkey-signature: C\naccidentals: measure\n---\nF# F G A\nF G A B";
+        let rekeyed = rekey_to(source, &KeySignature::from_str("G").unwrap()).unwrap();
+        assert_eq!(rekeyed, "---\nkey-signature: G\naccidentals: measure\n---\nF F G A\nF% G A B");
+    }
+
+    #[test]
+    fn test_rekey_leaves_key_changes_alone() {
+        let source = "G A B ^C\n@key:F B A G F";
+        let rekeyed = rekey_to(source, &KeySignature::from_str("G").unwrap()).unwrap();
+        assert_eq!(rekeyed, "---\nkey-signature: G\n---\nG A B ^C\n@key:F B A G F");
+    }
+}
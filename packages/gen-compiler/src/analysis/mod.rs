@@ -0,0 +1,36 @@
+//! # Analysis Module
+//!
+//! Musical analysis of parsed Gen scores.
+//!
+//! ## Sub-modules
+//! - `key` - Key and mode estimation from pitch content and chord symbols, and the
+//!   re-key transform that rewrites a source in the estimated key
+//! - `harmony` - Roman numeral and Nashville number analysis of chord symbols
+//!
+//! ## Entry Points
+//! - [`estimate_key()`] - Most likely key of a score, with a confidence score
+//! - [`rank_keys()`] - Every candidate key, best first
+//! - [`rekey()`] - Set `key-signature` from the estimate and strip redundant accidentals
+//! - [`rekey_to()`] - Same, for a given key
+//! - [`analyze_harmony()`] - Roman numeral and Nashville number for every chord symbol
+//!   (serializes to the JSON harmony report)
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, analysis::estimate_key};
+//!
+//! let score = parse("G A B ^C\n^D ^E ^F# ^G\n^D B G $").unwrap();
+//! let estimate = estimate_key(&score).unwrap();
+//! assert_eq!(estimate.key.to_string(), "G");
+//! ```
+//!
+//! ## Related Modules
+//! - `ast` - Score, KeySignature and Mode types
+//! - `musicxml` - Writes the numerals into `<harmony>` on request
+//! - `playback` - Chord symbol parsing shared with accompaniment
+
+mod harmony;
+mod key;
+
+pub use harmony::{analyze_harmony, chord_numerals, ChordAnalysis, ChordFunction, ChordNumerals};
+pub use key::{estimate_key, rank_keys, rekey, rekey_to, KeyEstimate};
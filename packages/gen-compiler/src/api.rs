@@ -8,6 +8,24 @@
 //! - [`compile_unchecked()`] - Skip validation (useful for partial/incomplete scores)
 //! - [`compile_with_options()`] - Custom clef, octave shift, and transposition
 //! - [`compile_with_mod_points()`] - Instrument-specific rendering with mod points
+//! - [`compile_with_numerals()`] - Chord symbols as Roman numerals or Nashville numbers
+//! - [`compile_chart()`] - Comping chart with slashes in place of the melody
+//! - [`compile_text_chart()`] - Plain-text chord chart
+//! - [`compile_abc()`] - ABC notation
+//! - [`compile_lilypond()`] - LilyPond `.ly` file
+//! - [`compile_mei()`] - MEI 5
+//! - [`compile_kern()`] - Humdrum **kern
+//! - [`compile_mxl()`] - Compressed MusicXML (`.mxl` bytes)
+//! - [`compile_svg()`] - Engraved SVG pages or systems
+//!
+//! ## Import Functions
+//!
+//! - [`import_abc()`] - ABC tune → Gen source
+//!
+//! ## AST JSON Functions
+//!
+//! - [`parse_to_json()`] - Gen source → versioned AST JSON
+//! - [`json_to_source()`] - AST JSON → Gen source
 //!
 //! ## Typical Usage
 //!
@@ -47,8 +65,9 @@
 //! ```
 
 use crate::{
-    parse, to_musicxml, to_musicxml_with_mod_points, to_musicxml_with_options, validate, Clef,
-    GenError, InstrumentGroup, Transposition,
+    abc::parse_abc, json::{score_from_json, score_to_json}, parse, printer::{check_printable, to_gen_source}, to_abc_with_options, to_kern, to_lilypond_with_options, to_mxl, to_mei_with_options, to_musicxml, to_musicxml_chart, to_musicxml_with_mod_points, to_musicxml_with_numerals,
+    to_musicxml_with_options, to_svg_with_options, to_text_chart, validate, Clef, GenError, HarmonyNumerals, InstrumentGroup, SlashStyle,
+    SvgLayout, Transposition,
 };
 
 /// Compile a Gen source string to MusicXML.
@@ -130,10 +149,7 @@ pub fn compile_with_options(
     transposition: Option<Transposition>,
 ) -> Result<String, GenError> {
     let score = parse(source)?;
-    let clef = match clef {
-        "bass" => Clef::Bass,
-        _ => Clef::Treble,
-    };
+    let (clef, _, _) = instrument_options(clef, None, None);
     Ok(to_musicxml_with_options(
         &score,
         transposition,
@@ -180,12 +196,7 @@ pub fn compile_with_mod_points(
     transpose_key: Option<&str>,
 ) -> Result<String, GenError> {
     let score = parse(source)?;
-    let clef = match clef {
-        "bass" => Clef::Bass,
-        _ => Clef::Treble,
-    };
-    let group = instrument_group.and_then(InstrumentGroup::from_str);
-    let transposition = transpose_key.and_then(Transposition::for_key);
+    let (clef, group, transposition) = instrument_options(clef, instrument_group, transpose_key);
     Ok(to_musicxml_with_mod_points(
         &score,
         transposition,
@@ -194,3 +205,308 @@ pub fn compile_with_mod_points(
         group,
     ))
 }
+
+/// Compile to compressed MusicXML: the bytes of an `.mxl` file (see [`crate::mxl`]).
+///
+/// Takes the same options as [`compile_with_mod_points()`]; with `include_source` the Gen
+/// source is stored in the archive as well.
+///
+/// # Example
+/// ```rust
+/// use gen::compile_mxl;
+///
+/// let mxl = compile_mxl("C D E F", "treble", 0, None, Some("Bb"), true)?;
+/// assert!(mxl.starts_with(b"PK\x03\x04"));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_mxl(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+    include_source: bool,
+) -> Result<Vec<u8>, GenError> {
+    let musicxml = compile_with_mod_points(source, clef, octave_shift, instrument_group, transpose_key)?;
+    Ok(to_mxl(&musicxml, include_source.then_some(source)))
+}
+
+/// Compile Gen source to SVG images (see [`crate::svg`]): one per page, or one per system
+/// with `per_system` for thumbnails.
+///
+/// Takes the same clef, octave shift, mod point and transposition options as
+/// [`compile_with_mod_points()`].
+///
+/// # Example
+/// ```rust
+/// use gen::compile_svg;
+///
+/// let systems = compile_svg("{Bb7}:Bb D F Ab", "treble", 0, None, Some("Eb"), true)?;
+/// assert!(systems[0].contains(">G7</text>"));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_svg(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+    per_system: bool,
+) -> Result<Vec<String>, GenError> {
+    let score = parse(source)?;
+    let (clef, group, transposition) = instrument_options(clef, instrument_group, transpose_key);
+    let layout = if per_system { SvgLayout::Systems } else { SvgLayout::Pages };
+    Ok(to_svg_with_options(&score, transposition, clef, octave_shift, group, layout))
+}
+
+/// Compile with chord symbols numbered relative to the key.
+///
+/// Same parameters as [`compile_with_mod_points()`], plus the numeral display settings.
+/// Numerals follow `@key:` changes in the score.
+///
+/// # Example
+/// ```rust
+/// use gen::{compile_with_numerals, HarmonyNumerals, NumeralSystem};
+///
+/// let numerals = HarmonyNumerals { system: NumeralSystem::Roman, above_symbols: true };
+/// let musicxml = compile_with_numerals("{Dm7}:D E {G7}:F G", "treble", 0, None, None, numerals)?;
+/// assert!(musicxml.contains(r#"text="ii7""#));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_with_numerals(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+    numerals: HarmonyNumerals,
+) -> Result<String, GenError> {
+    let score = parse(source)?;
+    let (clef, group, transposition) = instrument_options(clef, instrument_group, transpose_key);
+    Ok(to_musicxml_with_numerals(
+        &score,
+        transposition,
+        clef,
+        octave_shift,
+        group,
+        numerals,
+    ))
+}
+
+/// Compile a comping chart: chord symbols over slashes instead of the melody.
+///
+/// Repeats, endings, and key and time signatures are kept from the score, so the
+/// same source gives both the lead sheet and the rhythm section's chart.
+///
+/// # Parameters
+/// - `source` - Gen source code
+/// - `clef` - "treble" or "bass"
+/// - `transpose_key` - "C" (concert pitch), "Bb", "Eb", or "F"
+/// - `style` - One slash per beat, or slashes in the melody's rhythm
+/// - `numerals` - Optionally label the chords with Roman numerals or Nashville numbers
+///
+/// # Example
+/// ```rust
+/// use gen::{compile_chart, SlashStyle};
+///
+/// let musicxml = compile_chart("{C}:C D E F", "treble", None, SlashStyle::Beat, None)?;
+/// assert!(musicxml.contains("<notehead>slash</notehead>"));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_chart(
+    source: &str,
+    clef: &str,
+    transpose_key: Option<&str>,
+    style: SlashStyle,
+    numerals: Option<HarmonyNumerals>,
+) -> Result<String, GenError> {
+    let score = parse(source)?;
+    let (clef, _, transposition) = instrument_options(clef, None, transpose_key);
+    Ok(to_musicxml_chart(&score, transposition, clef, style, numerals))
+}
+
+/// Compile a plain-text chord chart (see [`crate::text_chart`]).
+///
+/// # Parameters
+/// - `source` - Gen source code
+/// - `transpose_key` - "C" (concert pitch), "Bb", "Eb", or "F"
+///
+/// # Example
+/// ```rust
+/// use gen::compile_text_chart;
+///
+/// let chart = compile_text_chart("{C}:C D {G7}:E F", Some("Bb"))?;
+/// assert!(chart.ends_with("| D A7 |\n"));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_text_chart(source: &str, transpose_key: Option<&str>) -> Result<String, GenError> {
+    let score = parse(source)?;
+    let transposition = transpose_key.and_then(Transposition::for_key);
+    Ok(to_text_chart(&score, transposition))
+}
+
+/// Compile to an ABC tune (see [`crate::abc`]).
+///
+/// Takes the same options as [`compile_with_mod_points()`].
+///
+/// # Example
+/// ```rust
+/// use gen::compile_abc;
+///
+/// let abc = compile_abc("{C}:C D E F", "treble", 0, None, Some("Bb"))?;
+/// assert!(abc.contains("K:D\n\"D\"D2 E2 F2 G2 |]"));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_abc(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+) -> Result<String, GenError> {
+    let score = parse(source)?;
+    let (clef, group, transposition) = instrument_options(clef, instrument_group, transpose_key);
+    Ok(to_abc_with_options(&score, transposition, clef, octave_shift, group))
+}
+
+/// Compile Gen source to a LilyPond `.ly` file (see [`crate::lilypond`]).
+///
+/// Takes the same clef, octave shift, mod point and transposition options as
+/// [`compile_with_mod_points()`].
+///
+/// # Example
+/// ```rust
+/// use gen::compile_lilypond;
+///
+/// let ly = compile_lilypond("{C}:C D E F", "treble", 0, None, Some("Bb"))?;
+/// assert!(ly.contains("\\key d \\major"));
+/// assert!(ly.contains("d'4 e'4 fis'4 g'4 |"));
+/// assert!(ly.contains("d1 |"));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_lilypond(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+) -> Result<String, GenError> {
+    let score = parse(source)?;
+    let (clef, group, transposition) = instrument_options(clef, instrument_group, transpose_key);
+    Ok(to_lilypond_with_options(&score, transposition, clef, octave_shift, group))
+}
+
+/// Compile Gen source to MEI 5 (see [`crate::mei`]).
+///
+/// Takes the same clef, octave shift, mod point and transposition options as
+/// [`compile_with_mod_points()`]. Note ids match [`crate::PlaybackNote::element_id`].
+///
+/// # Example
+/// ```rust
+/// use gen::compile_mei;
+///
+/// let mei = compile_mei("C D E F", "treble", 0, None, Some("Eb"))?;
+/// assert!(mei.contains(r#"<keySig sig="3s" mode="major"/>"#));
+/// assert!(mei.contains(r#"<note xml:id="m1e1" dur="4" pname="a" oct="4"/>"#));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_mei(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+) -> Result<String, GenError> {
+    let score = parse(source)?;
+    let (clef, group, transposition) = instrument_options(clef, instrument_group, transpose_key);
+    Ok(to_mei_with_options(&score, transposition, clef, octave_shift, group))
+}
+
+/// Compile Gen source to a Humdrum file with `**kern` and `**mxhm` spines (see [`crate::kern`]).
+///
+/// Pitches are always concert pitch, as analysis tools expect.
+///
+/// # Example
+/// ```rust
+/// use gen::compile_kern;
+///
+/// let kern = compile_kern("{C}:C D E F")?;
+/// assert!(kern.contains("=1-\t=1-\n4c\tC major\n4d\t.\n"));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn compile_kern(source: &str) -> Result<String, GenError> {
+    let score = parse(source)?;
+    Ok(to_kern(&score))
+}
+
+/// Import an ABC tune as Gen source (see [`crate::abc::parse_abc()`]).
+///
+/// Returns the source with one warning per kind of content Gen can't hold (decorations,
+/// grace notes, lyrics, extra voices, ...).
+///
+/// # Example
+/// ```rust
+/// use gen::import_abc;
+///
+/// let (source, warnings) = import_abc("X:1\nT:Reel\nM:4/4\nL:1/8\nK:D\n\"D\"d2 ~B2 A2 F2 | E8 |]\n")?;
+/// assert_eq!(source, "---\ntitle: Reel\nkey-signature: D\n---\n{D} ^D B A F\nEo\n");
+/// assert_eq!(warnings, vec!["Dropped decoration ~"]);
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn import_abc(source: &str) -> Result<(String, Vec<String>), GenError> {
+    let import = parse_abc(source)?;
+    Ok((to_gen_source(&import.score), import.warnings))
+}
+
+/// Parse Gen source into the versioned AST JSON document (see [`crate::json`]).
+///
+/// The score is not validated, so unfinished sources can still be inspected.
+///
+/// # Example
+/// ```rust
+/// use gen::parse_to_json;
+///
+/// let json = parse_to_json("C D E F")?;
+/// assert!(json.contains("\"type\": \"note\""));
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn parse_to_json(source: &str) -> Result<String, GenError> {
+    let score = parse(source)?;
+    Ok(score_to_json(&score))
+}
+
+/// Print an AST JSON document as Gen source, for scores built or edited as JSON.
+///
+/// Scores that wouldn't print as Gen source reading back the same (see
+/// [`crate::printer::check_printable()`]) are rejected rather than printed lossily.
+///
+/// # Example
+/// ```rust
+/// use gen::{json_to_source, parse_to_json};
+///
+/// let source = "---\ntitle: Scale\n---\nC D E F\n";
+/// assert_eq!(json_to_source(&parse_to_json(source)?)?, source);
+/// # Ok::<(), gen::GenError>(())
+/// ```
+pub fn json_to_source(json: &str) -> Result<String, GenError> {
+    let score = score_from_json(json)?;
+    check_printable(&score)?;
+    Ok(to_gen_source(&score))
+}
+
+/// Read the clef, instrument group and transposition options shared by the compile functions
+///
+/// Unknown clefs fall back to treble; unknown groups and keys to none.
+fn instrument_options(
+    clef: &str,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+) -> (Clef, Option<InstrumentGroup>, Option<Transposition>) {
+    let clef = match clef {
+        "bass" => Clef::Bass,
+        _ => Clef::Treble,
+    };
+    let group = instrument_group.and_then(InstrumentGroup::from_str);
+    let transposition = transpose_key.and_then(Transposition::for_key);
+    (clef, group, transposition)
+}
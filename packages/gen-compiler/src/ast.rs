@@ -17,18 +17,18 @@
 //! Element (enum)
 //!   ├── Note
 //!   │     ├── name: NoteName (A-G)
-//!   │     ├── accidental: Accidental (#, b, natural)
+//!   │     ├── accidental: Accidental (#, b, ##, bb, natural)
 //!   │     ├── octave: Octave (^, ^^, _, __)
 //!   │     ├── duration: Duration (whole, half, quarter, eighth, sixteenth, 32nd)
 //!   │     ├── dotted: bool
-//!   │     ├── tuplet: Option<TupletInfo>
+//!   │     ├── tuplets: Vec<TupletInfo> (outermost first)
 //!   │     ├── tie_start/stop: bool
 //!   │     ├── slur_start/stop: bool
 //!   │     └── chord: Option<String>
 //!   └── Rest
 //!         ├── duration: Duration
 //!         ├── dotted: bool
-//!         ├── tuplet: Option<TupletInfo>
+//!         ├── tuplets: Vec<TupletInfo>
 //!         └── chord: Option<String>
 //! ```
 //!
@@ -56,6 +56,9 @@
 //! - Common: triplet = 3 notes in time of 2 (3:2 ratio)
 //! - Quintuplet = 5 notes in time of 4 (5:4 ratio)
 //! - Duration per note = `(normal_notes / actual_notes) * base_duration`
+//! - Explicit ratios override the default: `[...]7:8` is 7 notes in the time of 8
+//! - Tuplets nest: a note carries one `TupletInfo` per enclosing group, outermost first,
+//!   and its duration is scaled by every ratio (3:2 inside 5:4 = 8/15)
 //!
 //! ### Ties
 //! - `tie_start = true` and `tie_stop = false`: First note of a tied group
@@ -67,18 +70,29 @@
 //! - `parser` - Creates these types from Gen source
 //! - `semantic` - Validates these types (measure durations, repeats)
 //! - `musicxml` - Generates MusicXML from these types
+//! - `json` - Serializes these types as versioned JSON
 //! - `lib` - Uses these types for playback data generation
 
-use serde::Deserialize;
+use crate::rational::Rational;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Time signature (e.g., 4/4, 3/4, 6/8)
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TimeSignature {
     pub beats: u8,
     pub beat_type: u8,
 }
 
+impl TimeSignature {
+    /// Whether the signature can be counted: at least one beat, and a beat type that is a
+    /// power of two (1, 2, 4, 8, ...)
+    pub fn is_valid(&self) -> bool {
+        self.beats > 0 && self.beat_type.is_power_of_two()
+    }
+}
+
 impl Default for TimeSignature {
     fn default() -> Self {
         Self {
@@ -89,32 +103,107 @@ impl Default for TimeSignature {
 }
 
 /// Pitch class for written-pitch transposition
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Pitch {
     pub note: NoteName,
     pub octave_offset: i8, // ^ = +1, ^^ = +2, _ = -1, __ = -2
 }
 
-/// Mode for key signature
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Mode for key signature (major/minor plus the church modes)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Mode {
     #[default]
-    Major,
-    Minor,
+    Major, // Ionian
+    Minor, // Aeolian
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+}
+
+impl Mode {
+    /// Parse from string (case-insensitive): "major", "minor", "dorian", "phrygian",
+    /// "lydian", "mixolydian", "locrian", "ionian", "aeolian", or a three-letter abbreviation
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "major" | "maj" | "ionian" | "ion" => Some(Mode::Major),
+            "minor" | "min" | "aeolian" | "aeo" => Some(Mode::Minor),
+            "dorian" | "dor" => Some(Mode::Dorian),
+            "phrygian" | "phr" => Some(Mode::Phrygian),
+            "lydian" | "lyd" => Some(Mode::Lydian),
+            "mixolydian" | "mix" => Some(Mode::Mixolydian),
+            "locrian" | "loc" => Some(Mode::Locrian),
+            _ => None,
+        }
+    }
+
+    /// Circle-of-fifths offset from the major key on the same tonic
+    /// (e.g. D dorian has the signature of C major: D major's 2 sharps - 2)
+    pub fn fifths_offset(&self) -> i8 {
+        match self {
+            Mode::Lydian => 1,
+            Mode::Major => 0,
+            Mode::Mixolydian => -1,
+            Mode::Dorian => -2,
+            Mode::Minor => -3,
+            Mode::Phrygian => -4,
+            Mode::Locrian => -5,
+        }
+    }
+
+    /// Semitones of each scale degree above the tonic (the major scale rotated to the mode)
+    pub fn scale(&self) -> [u8; 7] {
+        const MAJOR_SCALE: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+        let degree = match self {
+            Mode::Major => 0,
+            Mode::Dorian => 1,
+            Mode::Phrygian => 2,
+            Mode::Lydian => 3,
+            Mode::Mixolydian => 4,
+            Mode::Minor => 5,
+            Mode::Locrian => 6,
+        };
+        std::array::from_fn(|step| (MAJOR_SCALE[(degree + step) % 7] + 12 - MAJOR_SCALE[degree]) % 12)
+    }
+
+    /// MusicXML `<mode>` value
+    pub fn musicxml_name(&self) -> &'static str {
+        match self {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+            Mode::Dorian => "dorian",
+            Mode::Phrygian => "phrygian",
+            Mode::Lydian => "lydian",
+            Mode::Mixolydian => "mixolydian",
+            Mode::Locrian => "locrian",
+        }
+    }
 }
 
 /// Key signature (number of sharps/flats)
 /// Positive = sharps, Negative = flats, Zero = C major / A minor
-#[derive(Debug, Clone, PartialEq, Default)]
+///
+/// A custom (non-traditional) signature lists its altered steps instead, e.g. `Bb Eb F#`
+/// for D freygish; `fifths` is then unused.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct KeySignature {
     pub fifths: i8, // -7 to +7 (flats to sharps)
     pub mode: Mode,
+    #[serde(default)]
+    pub alterations: Vec<(NoteName, i8)>, // Custom signature: altered steps in written order (empty = standard)
 }
 
 impl KeySignature {
     /// Parse a key signature string like "G", "D", "F", "Bb", "Eb", etc.
     /// Also supports minor keys: "Am", "Dm", "Ebm", etc.
-    /// Also supports sharp/flat count notation: "#", "##", "###", etc. or "b", "bb", "bbb", etc.
+    /// Also supports modes: "D dorian", "G mixolydian", "Ephr", "F lydian", etc.
+    /// Also supports sharp/flat count notation: "#", "##", "###", etc. or "bb", "bbb", etc.
+    /// Also supports custom signatures listing the altered notes: "Bb Eb F#" or "Bb,Eb,F#"
+    /// (a single altered note needs a trailing comma: "F#,")
     pub fn from_str(s: &str) -> Option<Self> {
         let trimmed = s.trim();
 
@@ -122,7 +211,7 @@ impl KeySignature {
         if !trimmed.is_empty() && trimmed.chars().all(|c| c == '#') {
             let count = trimmed.len() as i8;
             if count >= 1 && count <= 7 {
-                return Some(Self { fifths: count, mode: Mode::Major });
+                return Some(Self::from_fifths(count, Mode::Major));
             }
             return None;
         }
@@ -133,56 +222,113 @@ impl KeySignature {
         if trimmed.len() >= 2 && trimmed.chars().all(|c| c == 'b') {
             let count = trimmed.len() as i8;
             if count >= 2 && count <= 7 {
-                return Some(Self { fifths: -count, mode: Mode::Major });
+                return Some(Self::from_fifths(-count, Mode::Major));
             }
             return None;
         }
 
-        // Check if it's a minor key (ends with 'm')
-        if trimmed.ends_with('m') && trimmed.len() > 1 {
-            let key_name = &trimmed[..trimmed.len()-1];
-            let fifths = match key_name {
-                // Minor keys (using their natural key signature)
-                "A" => 0,        // A minor (no sharps/flats, same as C major)
-                "E" => 1,        // E minor (1 sharp, same as G major)
-                "B" => 2,        // B minor (2 sharps, same as D major)
-                "F#" | "Fs" => 3, // F# minor (3 sharps, same as A major)
-                "C#" | "Cs" => 4, // C# minor (4 sharps, same as E major)
-                "G#" | "Gs" => 5, // G# minor (5 sharps, same as B major)
-                "D#" | "Ds" => 6, // D# minor (6 sharps, same as F# major)
-                "A#" | "As" => 7, // A# minor (7 sharps, same as C# major)
-                "D" => -1,       // D minor (1 flat, same as F major)
-                "G" => -2,       // G minor (2 flats, same as Bb major)
-                "C" => -3,       // C minor (3 flats, same as Eb major)
-                "F" => -4,       // F minor (4 flats, same as Ab major)
-                "Bb" | "Bf" => -5, // Bb minor (5 flats, same as Db major)
-                "Eb" | "Ef" => -6, // Eb minor (6 flats, same as Gb major)
-                "Ab" | "Af" => -7, // Ab minor (7 flats, same as Cb major)
-                _ => return None,
+        // Tonic followed by a mode name: "D dorian", "Ddorian", "Am", "Bb"
+        let words: Vec<&str> = trimmed.split_whitespace().collect();
+        if !trimmed.contains(',') {
+            let keyed = match words.as_slice() {
+                [tonic, mode] => Mode::from_name(mode).and_then(|mode| Self::from_tonic(tonic, mode)),
+                [key] => Self::parse_tonic(key).and_then(|(tonic_fifths, mode_str)| {
+                    let mode = match mode_str {
+                        "" => Mode::Major,
+                        "m" => Mode::Minor,
+                        other => Mode::from_name(other)?,
+                    };
+                    Self::from_fifths_checked(tonic_fifths + mode.fifths_offset(), mode)
+                }),
+                _ => None,
             };
-            return Some(Self { fifths, mode: Mode::Minor });
+            if keyed.is_some() || words.len() < 2 {
+                return keyed;
+            }
+        }
+
+        // Custom signature: a list of altered notes
+        Self::custom(trimmed)
+    }
+
+    /// Create a standard key signature from its position on the circle of fifths
+    pub fn from_fifths(fifths: i8, mode: Mode) -> Self {
+        Self { fifths, mode, alterations: Vec::new() }
+    }
+
+    /// Create a key signature from a tonic name ("D", "Bb", "F#") and a mode
+    /// Returns None if the key would need more than 7 sharps or flats
+    pub fn from_tonic(tonic: &str, mode: Mode) -> Option<Self> {
+        match Self::parse_tonic(tonic)? {
+            (tonic_fifths, "") => Self::from_fifths_checked(tonic_fifths + mode.fifths_offset(), mode),
+            _ => None,
         }
+    }
+
+    /// Whether this is a custom signature with explicitly listed alterations
+    pub fn is_custom(&self) -> bool {
+        !self.alterations.is_empty()
+    }
+
+    /// Tonic step and alteration of a standard key signature (None for custom signatures)
+    /// e.g. 1 sharp major -> (G, 0), 2 flats minor -> (G, 0), 0 dorian -> (D, 0)
+    pub fn tonic(&self) -> Option<(NoteName, i8)> {
+        if self.is_custom() {
+            return None;
+        }
+        // Major-key tonics run F C G D A E B around the circle of fifths, starting at -1
+        const LETTERS: [NoteName; 7] = [NoteName::F, NoteName::C, NoteName::G, NoteName::D, NoteName::A, NoteName::E, NoteName::B];
+        let position = self.fifths - self.mode.fifths_offset() + 1;
+        Some((LETTERS[position.rem_euclid(7) as usize], position.div_euclid(7)))
+    }
+
+    fn from_fifths_checked(fifths: i8, mode: Mode) -> Option<Self> {
+        (-7..=7).contains(&fifths).then(|| Self::from_fifths(fifths, mode))
+    }
 
-        // Major keys
-        let fifths = match trimmed {
+    /// Split a tonic off the start of a key name, returning its major-key fifths and the rest
+    /// ("Bbm" -> (-2, "m"), "Ddorian" -> (2, "dorian"))
+    fn parse_tonic(s: &str) -> Option<(i8, &str)> {
+        let (name, rest) = (s.get(..1)?, &s[1..]);
+        let natural_fifths = match name {
             "C" => 0,
             "G" => 1,
             "D" => 2,
             "A" => 3,
             "E" => 4,
             "B" => 5,
-            "F#" | "Fs" => 6,
-            "C#" | "Cs" => 7,
             "F" => -1,
-            "Bb" | "Bf" => -2,
-            "Eb" | "Ef" => -3,
-            "Ab" | "Af" => -4,
-            "Db" | "Df" => -5,
-            "Gb" | "Gf" => -6,
-            "Cb" | "Cf" => -7,
             _ => return None,
         };
-        Some(Self { fifths, mode: Mode::Major })
+        // Sharp/flat (also "s"/"f" spellings, e.g. "Fs", "Bf") moves the tonic 7 fifths
+        match rest.chars().next() {
+            Some('#') | Some('s') => Some((natural_fifths + 7, &rest[1..])),
+            Some('b') | Some('f') => Some((natural_fifths - 7, &rest[1..])),
+            _ => Some((natural_fifths, rest)),
+        }
+    }
+
+    /// Parse a custom signature: altered notes separated by spaces or commas ("Bb Eb F#")
+    fn custom(s: &str) -> Option<Self> {
+        let mut alterations: Vec<(NoteName, i8)> = Vec::new();
+        for token in s.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+            let name = NoteName::from_letter(token.chars().next()?)?;
+            let alter = match &token[1..] {
+                "#" => 1,
+                "##" | "x" => 2,
+                "b" => -1,
+                "bb" => -2,
+                _ => return None,
+            };
+            if alterations.iter().any(|&(step, _)| step == name) {
+                return None; // Each step may only be altered once
+            }
+            alterations.push((name, alter));
+        }
+        if alterations.is_empty() {
+            return None;
+        }
+        Some(Self { fifths: 0, mode: Mode::Major, alterations })
     }
 
     /// Returns the accidental for a note based on this key signature.
@@ -190,6 +336,14 @@ impl KeySignature {
     /// Order of sharps: F C G D A E B
     /// Order of flats: B E A D G C F
     pub fn accidental_for_note(&self, note: NoteName) -> Accidental {
+        if self.is_custom() {
+            return self
+                .alterations
+                .iter()
+                .find(|&&(step, _)| step == note)
+                .map_or(Accidental::Natural, |&(_, alter)| Accidental::from_alter(alter));
+        }
+
         if self.fifths > 0 {
             // Sharp keys - sharps are added in order: F C G D A E B
             let sharped_notes = match self.fifths {
@@ -231,8 +385,27 @@ impl KeySignature {
     }
 }
 
+/// Formats the key the way `key-signature` accepts it ("G", "Bbm", "D dorian", "Bb Eb F#")
+impl std::fmt::Display for KeySignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((step, alter)) = self.tonic() {
+            let tonic = step.spell(alter);
+            return match self.mode {
+                Mode::Major => write!(f, "{}", tonic),
+                Mode::Minor => write!(f, "{}m", tonic),
+                mode => write!(f, "{} {}", tonic, mode.musicxml_name()),
+            };
+        }
+        let notes: Vec<String> = self.alterations.iter().map(|&(step, alter)| step.spell(alter)).collect();
+        // A single altered note needs a trailing comma so it isn't read as a key name
+        let separator = if notes.len() == 1 { "," } else { "" };
+        write!(f, "{}{}", notes.join(" "), separator)
+    }
+}
+
 /// Tempo specification with optional rhythm modifier
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Tempo {
     pub bpm: u16,              // Beats per minute at the specified duration
     pub duration: Duration,    // Which note duration gets the beat (default: Quarter)
@@ -240,11 +413,74 @@ pub struct Tempo {
 }
 
 /// Swing feel specification
-/// Specifies which note duration should be played with swing feel
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Swing {
-    Eighth,     // Swing eighth notes (standard jazz swing)
-    Sixteenth,  // Swing sixteenth notes (sometimes used in funk/fusion)
+/// Specifies which note duration should be played with swing feel, and how much
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Swing {
+    pub duration: Duration, // Swung note value: Eighth (standard jazz swing) or Sixteenth (funk/fusion)
+    pub ratio: f64,         // Share of each pair given to the first note (0.5 = straight, 2/3 = triplet)
+}
+
+impl Swing {
+    /// Triplet swing (2:1), the default when no ratio is given
+    pub const TRIPLET: f64 = 2.0 / 3.0;
+    /// Dotted swing (3:1), as in a dotted eighth and sixteenth
+    pub const DOTTED: f64 = 0.75;
+}
+
+/// Accompaniment rhythm pattern (comping style) for chord playback
+/// Specifies how each chord symbol is rhythmically played by the backing track
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompingPattern {
+    #[default]
+    Pads,           // One sustained chord per symbol
+    HalfNotes,      // Chord on beats 1 and 3
+    Charleston,     // Dotted quarter on 1, then the "and" of 2
+    Bossa,          // Two-bar bossa nova syncopation
+    Waltz,          // Bass on 1, chord on 2 and 3
+    BalladArpeggio, // Chord tones rolled upward in eighth notes
+    Stride,         // Low bass on 1 and 3, chord on 2 and 4
+}
+
+impl CompingPattern {
+    /// Parse from string (case-insensitive): "pads", "half-notes", "charleston", "bossa",
+    /// "waltz", "ballad-arpeggio", "stride"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "pads" | "whole" => Some(CompingPattern::Pads),
+            "half-notes" | "half" => Some(CompingPattern::HalfNotes),
+            "charleston" => Some(CompingPattern::Charleston),
+            "bossa" | "bossa-nova" => Some(CompingPattern::Bossa),
+            "waltz" => Some(CompingPattern::Waltz),
+            "ballad-arpeggio" | "ballad" | "arpeggio" => Some(CompingPattern::BalladArpeggio),
+            "stride" => Some(CompingPattern::Stride),
+            _ => None,
+        }
+    }
+}
+
+/// How far an explicit accidental reaches
+/// Note: each accidental applies only to its own note (plain notes always follow the key)
+/// Measure: standard notation, an accidental carries to later notes of the same name and
+/// octave until the barline (and through a tie into the next bar)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccidentalScope {
+    #[default]
+    Note,
+    Measure,
+}
+
+impl AccidentalScope {
+    /// Parse from string (case-insensitive): "note" or "measure"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "note" => Some(AccidentalScope::Note),
+            "measure" | "bar" => Some(AccidentalScope::Measure),
+            _ => None,
+        }
+    }
 }
 
 impl Default for Tempo {
@@ -284,7 +520,8 @@ impl Tempo {
 }
 
 /// Document metadata from YAML header
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct Metadata {
     pub title: Option<String>,
     pub composer: Option<String>,
@@ -292,7 +529,9 @@ pub struct Metadata {
     pub key_signature: KeySignature,
     pub written_pitch: Pitch,
     pub tempo: Option<Tempo>, // Tempo with optional rhythm modifier (default 120 quarter notes if not specified)
-    pub swing: Option<Swing>, // Optional swing feel (eighth or sixteenth notes)
+    pub swing: Option<Swing>, // Optional swing feel (eighth or sixteenth notes, with ratio)
+    pub comping: Option<CompingPattern>, // Optional accompaniment rhythm for chord playback
+    pub accidentals: AccidentalScope, // Whether accidentals carry through the measure (default: note only)
 }
 
 /// Raw metadata for YAML deserialization
@@ -305,11 +544,13 @@ pub struct RawMetadata {
     pub key_signature: Option<String>,
     pub written_pitch: Option<String>,
     pub tempo: Option<String>, // Can be just "120" or with rhythm "d160" or "*120"
-    pub swing: Option<String>, // "/" for eighth note swing, "//" for sixteenth note swing
+    pub swing: Option<String>, // "/" for eighth note swing, "//" for sixteenth, optional ratio: "/60%", "/dotted"
+    pub comping: Option<String>, // Comping pattern name, e.g. "charleston" or "bossa"
+    pub accidentals: Option<String>, // Accidental scope: "note" (default) or "measure"
 }
 
 /// Note names A through G
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub enum NoteName {
     #[default]
     C,
@@ -321,18 +562,104 @@ pub enum NoteName {
     B,
 }
 
-/// Accidentals: sharp, flat, natural (default/unspecified), or force natural (explicit %)
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+impl NoteName {
+    /// All note names in scale order from C
+    pub const ALL: [NoteName; 7] = [NoteName::C, NoteName::D, NoteName::E, NoteName::F, NoteName::G, NoteName::A, NoteName::B];
+
+    /// Parse an uppercase note letter ('A' to 'G')
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'C' => Some(NoteName::C),
+            'D' => Some(NoteName::D),
+            'E' => Some(NoteName::E),
+            'F' => Some(NoteName::F),
+            'G' => Some(NoteName::G),
+            'A' => Some(NoteName::A),
+            'B' => Some(NoteName::B),
+            _ => None,
+        }
+    }
+
+    /// Uppercase note letter
+    pub fn letter(&self) -> char {
+        match self {
+            NoteName::C => 'C',
+            NoteName::D => 'D',
+            NoteName::E => 'E',
+            NoteName::F => 'F',
+            NoteName::G => 'G',
+            NoteName::A => 'A',
+            NoteName::B => 'B',
+        }
+    }
+
+    /// Pitch class of the unaltered note (C = 0, D = 2, ... B = 11)
+    pub fn pitch_class(&self) -> u8 {
+        match self {
+            NoteName::C => 0,
+            NoteName::D => 2,
+            NoteName::E => 4,
+            NoteName::F => 5,
+            NoteName::G => 7,
+            NoteName::A => 9,
+            NoteName::B => 11,
+        }
+    }
+
+    /// Spell the note with an alteration in semitones ("F#", "Bb", "C##")
+    pub fn spell(&self, alter: i8) -> String {
+        let accidental = match alter {
+            2.. => "##",
+            1 => "#",
+            0 => "",
+            -1 => "b",
+            _ => "bb",
+        };
+        format!("{}{}", self.letter(), accidental)
+    }
+}
+
+/// Accidentals: sharp, flat, double sharp/flat, natural (default/unspecified), or force natural (explicit %)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Accidental {
     #[default]
     Natural,      // No accidental specified - follows key signature
     Sharp,        // #
     Flat,         // b
+    DoubleSharp,  // ##
+    DoubleFlat,   // bb
     ForceNatural, // % - explicitly show natural sign
 }
 
+impl Accidental {
+    /// Chromatic alteration in semitones (MusicXML `<alter>`)
+    /// Natural (unspecified) is 0 here; resolve it against the key signature first.
+    pub fn alter(&self) -> i8 {
+        match self {
+            Accidental::DoubleSharp => 2,
+            Accidental::Sharp => 1,
+            Accidental::Natural | Accidental::ForceNatural => 0,
+            Accidental::Flat => -1,
+            Accidental::DoubleFlat => -2,
+        }
+    }
+
+    /// Accidental for an alteration in semitones (0 = Natural, i.e. no accidental)
+    pub fn from_alter(alter: i8) -> Self {
+        match alter {
+            2.. => Accidental::DoubleSharp,
+            1 => Accidental::Sharp,
+            0 => Accidental::Natural,
+            -1 => Accidental::Flat,
+            _ => Accidental::DoubleFlat,
+        }
+    }
+}
+
 /// Octave relative to middle octave
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Octave {
     DoubleLow,  // __
     Low,        // _
@@ -342,8 +669,22 @@ pub enum Octave {
     DoubleHigh, // ^^
 }
 
+impl Octave {
+    /// Scientific pitch octave number, before any clef or instrument shift (middle C is C4)
+    pub fn number(self) -> i8 {
+        match self {
+            Octave::DoubleLow => 2,
+            Octave::Low => 3,
+            Octave::Middle => 4,
+            Octave::High => 5,
+            Octave::DoubleHigh => 6,
+        }
+    }
+}
+
 /// Note duration
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Duration {
     Whole,       // o
     Half,        // d
@@ -355,33 +696,39 @@ pub enum Duration {
 }
 
 impl Duration {
-    /// Returns the duration as a fraction of a whole note
-    pub fn as_fraction(&self) -> f64 {
+    /// Returns the duration as an exact fraction of a whole note
+    pub fn as_fraction(&self) -> Rational {
         match self {
-            Duration::Whole => 1.0,
-            Duration::Half => 0.5,
-            Duration::Quarter => 0.25,
-            Duration::Eighth => 0.125,
-            Duration::Sixteenth => 0.0625,
-            Duration::ThirtySecond => 0.03125,
+            Duration::Whole => Rational::ONE,
+            Duration::Half => Rational::new(1, 2),
+            Duration::Quarter => Rational::new(1, 4),
+            Duration::Eighth => Rational::new(1, 8),
+            Duration::Sixteenth => Rational::new(1, 16),
+            Duration::ThirtySecond => Rational::new(1, 32),
         }
     }
 
     /// Returns duration in beats based on time signature
-    /// In 4/4 time: quarter = 1 beat, eighth = 0.5 beats, etc.
+    /// In 4/4 time: quarter = 1 beat, eighth = 1/2 beat, etc.
     /// In 6/8 time: eighth = 1 beat, quarter = 2 beats, etc.
-    pub fn as_beats(&self, time_sig: &TimeSignature) -> f64 {
-        // Calculate based on what note type gets the beat
+    pub fn as_beats(&self, time_sig: &TimeSignature) -> Rational {
         // beat_type of 4 means quarter note gets the beat (1/4 of whole note)
         // beat_type of 8 means eighth note gets the beat (1/8 of whole note)
+        // Example in 4/4: quarter note (1/4) * 4 = 1 beat
+        // Example in 6/8: quarter note (1/4) * 8 = 2 beats
+        self.as_fraction() * Rational::from_integer(time_sig.beat_type as i64)
+    }
 
-        // Fraction of a whole note that gets one beat
-        let beat_value = 1.0 / time_sig.beat_type as f64;
+    /// Apply dot and tuplet modifiers to this duration's whole-note fraction
+    pub fn modified_fraction(&self, dotted: bool, tuplets: &[TupletInfo]) -> Rational {
+        let base = self.as_fraction();
+        let with_dot = if dotted { base * Rational::new(3, 2) } else { base };
+        with_dot * TupletInfo::combined_ratio(tuplets)
+    }
 
-        // This note's fraction of a whole note divided by the beat value
-        // Example in 4/4: quarter note (0.25) / beat_value (0.25) = 1.0 beat
-        // Example in 4/4: eighth note (0.125) / beat_value (0.25) = 0.5 beats
-        self.as_fraction() / beat_value
+    /// Apply dot and tuplet modifiers to this duration's beats
+    pub fn modified_beats(&self, time_sig: &TimeSignature, dotted: bool, tuplets: &[TupletInfo]) -> Rational {
+        self.modified_fraction(dotted, tuplets) * Rational::from_integer(time_sig.beat_type as i64)
     }
 
     /// MusicXML type name
@@ -398,7 +745,8 @@ impl Duration {
 }
 
 /// Tuplet information for a note (e.g., triplet = 3 notes in the time of 2)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TupletInfo {
     pub actual_notes: u8,   // Number of notes played (e.g., 3 for triplet)
     pub normal_notes: u8,   // Number of notes in normal time (e.g., 2 for triplet)
@@ -408,6 +756,8 @@ pub struct TupletInfo {
 
 impl TupletInfo {
     /// Create tuplet info for a standard tuplet (N notes in the time of the next lower power of 2)
+    ///
+    /// `actual_notes` must be at least 2.
     pub fn new(actual_notes: u8) -> Self {
         // Standard tuplet: N notes in the time of (N-1) for odd, or N in N-1 for even
         // But the common convention is:
@@ -421,6 +771,11 @@ impl TupletInfo {
             4 // For 5, 6, 7, etc., they're typically in the time of 4
         };
 
+        Self::with_ratio(actual_notes, normal_notes)
+    }
+
+    /// Create tuplet info with an explicit ratio (e.g. 7 notes in the time of 8)
+    pub fn with_ratio(actual_notes: u8, normal_notes: u8) -> Self {
         Self {
             actual_notes,
             normal_notes,
@@ -428,10 +783,26 @@ impl TupletInfo {
             is_stop: false,
         }
     }
+
+    /// Whether the ratio is the one `TupletInfo::new` would infer from `actual_notes`
+    pub fn is_standard(&self) -> bool {
+        self.actual_notes >= 2 && Self::new(self.actual_notes).normal_notes == self.normal_notes
+    }
+
+    /// Factor applied to each note's written duration (e.g. 2/3 for a triplet)
+    pub fn ratio(&self) -> Rational {
+        Rational::new(self.normal_notes as i64, self.actual_notes as i64)
+    }
+
+    /// Combined factor of nested tuplets (e.g. 8/15 for a triplet inside a quintuplet)
+    pub fn combined_ratio(tuplets: &[TupletInfo]) -> Rational {
+        tuplets.iter().fold(Rational::ONE, |acc, tuplet| acc * tuplet.ratio())
+    }
 }
 
 /// Chord annotation with its own duration (independent from the melody)
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ChordAnnotation {
     pub symbol: String,       // Chord symbol (e.g., "Cmaj7", "Dm", "G7")
     pub duration: Duration,   // Duration for playback (default: Whole)
@@ -458,43 +829,59 @@ impl ChordAnnotation {
     }
 
     /// Returns duration in beats based on time signature
-    pub fn duration_beats(&self, time_sig: &TimeSignature) -> f64 {
-        let base = self.duration.as_beats(time_sig);
-        if self.dotted { base * 1.5 } else { base }
+    pub fn duration_beats(&self, time_sig: &TimeSignature) -> Rational {
+        self.duration.modified_beats(time_sig, self.dotted, &[])
     }
 }
 
 /// A musical note
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Note {
     pub name: NoteName,
+    #[serde(default)]
     pub accidental: Accidental,
+    #[serde(default)]
     pub octave: Octave,
     pub duration: Duration,
+    #[serde(default)]
     pub dotted: bool,
-    pub tuplet: Option<TupletInfo>,
+    #[serde(default)]
+    pub tuplets: Vec<TupletInfo>,  // Enclosing tuplet groups, outermost first
+    #[serde(default)]
     pub tie_start: bool,   // This note starts a tie (to the next note)
+    #[serde(default)]
     pub tie_stop: bool,    // This note ends a tie (from the previous note)
+    #[serde(default)]
     pub slur_start: bool,  // This note starts a slur
+    #[serde(default)]
     pub slur_stop: bool,   // This note ends a slur
+    #[serde(default)]
     pub chord: Option<ChordAnnotation>,  // Optional chord symbol with independent duration
 }
 
 impl Note {
     /// Returns total duration in beats including dotted and tuplet modifiers
-    pub fn total_beats(&self, time_sig: &TimeSignature) -> f64 {
-        let base = self.duration.as_beats(time_sig);
-        let with_dot = if self.dotted { base * 1.5 } else { base };
-
-        match &self.tuplet {
-            Some(tuplet) => with_dot * (tuplet.normal_notes as f64 / tuplet.actual_notes as f64),
-            None => with_dot,
-        }
+    pub fn total_beats(&self, time_sig: &TimeSignature) -> Rational {
+        self.duration.modified_beats(time_sig, self.dotted, &self.tuplets)
     }
 
     /// Returns MIDI note number (C4 = 60, middle C)
     /// Takes into account: note name, explicit accidental, octave offset, key signature
+    ///
+    /// Only the note's own accidental is considered; use [`Note::to_midi_note_with_alter()`]
+    /// when an earlier accidental in the measure carries over.
     pub fn to_midi_note(&self, key_sig: &KeySignature, clef_offset: i8) -> u8 {
+        // Apply key signature accidentals if note doesn't have explicit accidental
+        let alter = match self.accidental {
+            Accidental::Natural => key_sig.accidental_for_note(self.name).alter(), // Follow key signature
+            explicit => explicit.alter(),
+        };
+        self.to_midi_note_with_alter(alter, clef_offset)
+    }
+
+    /// Returns MIDI note number for an already-resolved alteration in semitones
+    pub fn to_midi_note_with_alter(&self, accidental_offset: i8, clef_offset: i8) -> u8 {
         // Base MIDI numbers for each note (C4=60)
         let base_midi = match self.name {
             NoteName::C => 60,
@@ -506,21 +893,6 @@ impl Note {
             NoteName::B => 71,
         };
 
-        // Apply key signature accidentals if note doesn't have explicit accidental
-        let accidental_offset = match self.accidental {
-            Accidental::Sharp => 1,
-            Accidental::Flat => -1,
-            Accidental::ForceNatural => 0,
-            Accidental::Natural => {
-                // Follow key signature
-                match key_sig.accidental_for_note(self.name) {
-                    Accidental::Sharp => 1,
-                    Accidental::Flat => -1,
-                    _ => 0,
-                }
-            }
-        };
-
         // Apply octave offset (^ = +12, _ = -12, etc.)
         let octave_offset = match self.octave {
             Octave::DoubleLow => -24,
@@ -539,50 +911,83 @@ impl Note {
 }
 
 /// An element in a measure: either a note or a rest
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
 pub enum Element {
     Note(Note),
-    Rest { duration: Duration, dotted: bool, tuplet: Option<TupletInfo>, chord: Option<ChordAnnotation> },
+    Rest {
+        duration: Duration,
+        #[serde(default)]
+        dotted: bool,
+        #[serde(default)]
+        tuplets: Vec<TupletInfo>,
+        #[serde(default)]
+        chord: Option<ChordAnnotation>,
+    },
 }
 
 impl Element {
     /// Returns total duration in beats
-    pub fn total_beats(&self, time_sig: &TimeSignature) -> f64 {
+    pub fn total_beats(&self, time_sig: &TimeSignature) -> Rational {
         match self {
             Element::Note(note) => note.total_beats(time_sig),
-            Element::Rest { duration, dotted, tuplet, .. } => {
-                let base = duration.as_beats(time_sig);
-                let with_dot = if *dotted { base * 1.5 } else { base };
-
-                match tuplet {
-                    Some(t) => with_dot * (t.normal_notes as f64 / t.actual_notes as f64),
-                    None => with_dot,
-                }
-            }
+            Element::Rest { duration, dotted, tuplets, .. } => duration.modified_beats(time_sig, *dotted, tuplets),
+        }
+    }
+
+    /// Returns total duration as an exact fraction of a whole note (independent of time signature)
+    pub fn total_fraction(&self) -> Rational {
+        match self {
+            Element::Note(note) => note.duration.modified_fraction(note.dotted, &note.tuplets),
+            Element::Rest { duration, dotted, tuplets, .. } => duration.modified_fraction(*dotted, tuplets),
+        }
+    }
+
+    /// Enclosing tuplet groups, outermost first (empty if not in a tuplet)
+    pub fn tuplets(&self) -> &[TupletInfo] {
+        match self {
+            Element::Note(note) => &note.tuplets,
+            Element::Rest { tuplets, .. } => tuplets,
+        }
+    }
+
+    /// Chord symbol attached to this note or rest, if any
+    pub fn chord(&self) -> Option<&ChordAnnotation> {
+        match self {
+            Element::Note(note) => note.chord.as_ref(),
+            Element::Rest { chord, .. } => chord.as_ref(),
         }
     }
 }
 
 /// Ending type for volta brackets (1st/2nd endings)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Ending {
     First,   // 1.
     Second,  // 2.
 }
 
 /// A single measure containing musical elements
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Measure {
     pub elements: Vec<Element>,
+    #[serde(default)]
     pub repeat_start: bool,   // ||: at the beginning of the measure
+    #[serde(default)]
     pub repeat_end: bool,     // :|| at the end of the measure
+    #[serde(default)]
     pub ending: Option<Ending>, // 1. or 2. volta bracket
+    #[serde(default)]
     pub key_change: Option<KeySignature>, // @key: annotation - changes key signature from this point forward
+    #[serde(default)]
     pub is_pickup: bool, // @pickup annotation - skip duration validation for this measure
 }
 
 /// Instrument groups for mod points
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum InstrumentGroup {
     Eb,  // Alto sax, Baritone sax
     Bb,  // Trumpet, Tenor sax, Clarinet
@@ -600,10 +1005,11 @@ impl InstrumentGroup {
 }
 
 /// Mod points - per-line octave shifts for instrument groups
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ModPoints {
     /// Maps line number -> (instrument group -> octave shift)
     /// Line numbers are 1-indexed (matching editor display)
+    #[serde(serialize_with = "crate::json::sorted_mod_points")]
     pub points: HashMap<usize, HashMap<InstrumentGroup, i8>>,
 }
 
@@ -633,11 +1039,38 @@ impl ModPoints {
 }
 
 /// A complete musical score
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Score {
+    #[serde(default)]
     pub metadata: Metadata,
     pub measures: Vec<Measure>,
+    #[serde(default)]
     pub mod_points: ModPoints,
     /// Maps source line number (1-indexed) to measure index
+    #[serde(default, serialize_with = "crate::json::sorted_map")]
     pub line_to_measure: HashMap<usize, usize>,
 }
+
+impl Score {
+    /// Octave shift from the mod point on each measure's source line for an instrument group
+    ///
+    /// Indexed by measure; 0 where the measure's line has no mod point for the group.
+    pub fn mod_point_shifts(&self, group: InstrumentGroup) -> Vec<i8> {
+        let mut shifts = vec![0; self.measures.len()];
+        for (&line, &measure_index) in &self.line_to_measure {
+            if let (Some(shift), Some(slot)) = (self.mod_points.get_shift(line, group), shifts.get_mut(measure_index)) {
+                *slot = shift;
+            }
+        }
+        shifts
+    }
+
+    /// Octave shift of each measure: the base shift plus the instrument group's mod point, if any
+    pub fn octave_shifts(&self, octave_shift: i8, group: Option<InstrumentGroup>) -> Vec<i8> {
+        match group {
+            Some(group) => self.mod_point_shifts(group).into_iter().map(|shift| octave_shift + shift).collect(),
+            None => vec![octave_shift; self.measures.len()],
+        }
+    }
+}
@@ -0,0 +1,678 @@
+//! # Score Builder
+//!
+//! Typed builders for putting a [`Score`] together in code, for scripts that generate
+//! exercises (scales, arpeggios in every key) instead of concatenating Gen source.
+//!
+//! - [`NoteBuilder`] - a note with its duration, accidental, octave, tie, slur and chord symbol
+//! - [`TupletBuilder`] - a tuplet group with a standard or explicit ratio, which may hold
+//!   further groups
+//! - [`MeasureBuilder`] - the notes, rests and tuplets of one measure, plus its repeat,
+//!   ending, `@key:` and `@pickup` markings
+//! - [`ScoreBuilder`] - the metadata and the measures
+//!
+//! Durations are checked as you go: adding an element that doesn't fit in what is left of
+//! the measure fails, and so does pushing a measure that isn't full (unless it is a pickup)
+//! or is empty. Chord symbols follow the lexer's rules for `{...}`.
+//! [`ScoreBuilder::build()`] links ties, runs [`crate::validate()`] for repeats and endings
+//! and [`crate::printer::check_printable()`], so the result compiles as is and
+//! [`crate::to_gen_source()`] saves it as an editable `.gen` file that reads back the same.
+//!
+//! ## Example
+//! ```rust
+//! use gen::builder::{NoteBuilder, ScoreBuilder};
+//! use gen::{to_gen_source, Duration, KeySignature, Mode, NoteName, Octave};
+//!
+//! let mut score = ScoreBuilder::new()
+//!     .title("G major scale")
+//!     .key_signature(KeySignature::from_fifths(1, Mode::Major));
+//!
+//! let mut bar = score.measure();
+//! for name in [NoteName::G, NoteName::A, NoteName::B] {
+//!     bar = bar.note(NoteBuilder::new(name))?;
+//! }
+//! bar = bar.note(NoteBuilder::new(NoteName::C).octave(Octave::High))?;
+//! score = score.push(bar)?;
+//!
+//! let bar = score.measure().note(NoteBuilder::new(NoteName::G).duration(Duration::Whole).chord("G"))?;
+//! let score = score.push(bar)?.build()?;
+//!
+//! assert_eq!(to_gen_source(&score), "---\ntitle: G major scale\nkey-signature: G\n---\nG A B ^C\n{G}:Go\n");
+//! # Ok::<(), gen::GenError>(())
+//! ```
+
+use crate::ast::*;
+use crate::error::GenError;
+use crate::lexer::check_chord_symbol;
+use crate::printer::check_printable;
+use crate::rational::Rational;
+use crate::semantic::{validate, validate_measure};
+use std::collections::HashMap;
+
+/// Builds a [`Note`]: a quarter note in the middle octave that follows the key signature
+#[derive(Debug, Clone)]
+pub struct NoteBuilder {
+    note: Note,
+    chord: Option<String>,
+}
+
+impl NoteBuilder {
+    /// Start a quarter note
+    pub fn new(name: NoteName) -> Self {
+        Self {
+            note: Note {
+                name,
+                accidental: Accidental::Natural,
+                octave: Octave::Middle,
+                duration: Duration::Quarter,
+                dotted: false,
+                tuplets: Vec::new(),
+                tie_start: false,
+                tie_stop: false,
+                slur_start: false,
+                slur_stop: false,
+                chord: None,
+            },
+            chord: None,
+        }
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.note.duration = duration;
+        self
+    }
+
+    pub fn dotted(mut self) -> Self {
+        self.note.dotted = true;
+        self
+    }
+
+    /// Explicit accidental (`Accidental::Natural` follows the key, `ForceNatural` writes `%`)
+    pub fn accidental(mut self, accidental: Accidental) -> Self {
+        self.note.accidental = accidental;
+        self
+    }
+
+    pub fn octave(mut self, octave: Octave) -> Self {
+        self.note.octave = octave;
+        self
+    }
+
+    /// Tie this note to the next one (which may be in the next measure)
+    pub fn tie(mut self) -> Self {
+        self.note.tie_start = true;
+        self
+    }
+
+    pub fn slur_start(mut self) -> Self {
+        self.note.slur_start = true;
+        self
+    }
+
+    pub fn slur_stop(mut self) -> Self {
+        self.note.slur_stop = true;
+        self
+    }
+
+    /// Chord symbol lasting as long as the note
+    pub fn chord(mut self, symbol: &str) -> Self {
+        self.chord = Some(symbol.to_string());
+        self
+    }
+
+    pub fn build(self) -> Note {
+        let mut note = self.note;
+        note.chord = self
+            .chord
+            .map(|symbol| ChordAnnotation::with_duration(symbol, note.duration, note.dotted));
+        note
+    }
+}
+
+impl From<NoteBuilder> for Note {
+    fn from(builder: NoteBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Builds a tuplet group for [`MeasureBuilder::tuplet_group()`]
+///
+/// Members are notes, rests or nested groups; each element ends up with one
+/// [`TupletInfo`] per enclosing group, outermost first.
+#[derive(Debug, Clone)]
+pub struct TupletBuilder {
+    actual_notes: u8,
+    normal_notes: Option<u8>,
+    members: Vec<TupletMember>,
+}
+
+#[derive(Debug, Clone)]
+enum TupletMember {
+    Element(Element),
+    Group(TupletBuilder),
+}
+
+impl TupletBuilder {
+    /// Start a group with the standard ratio for `actual_notes` (3:2, 5:4, ...)
+    pub fn new(actual_notes: u8) -> Self {
+        Self {
+            actual_notes,
+            normal_notes: None,
+            members: Vec::new(),
+        }
+    }
+
+    /// Start a group of `actual_notes` in the time of `normal_notes`
+    pub fn with_ratio(actual_notes: u8, normal_notes: u8) -> Self {
+        Self {
+            actual_notes,
+            normal_notes: Some(normal_notes),
+            members: Vec::new(),
+        }
+    }
+
+    pub fn note(mut self, note: impl Into<Note>) -> Self {
+        self.members.push(TupletMember::Element(Element::Note(note.into())));
+        self
+    }
+
+    pub fn rest(mut self, duration: Duration, dotted: bool) -> Self {
+        self.members.push(TupletMember::Element(Element::Rest {
+            duration,
+            dotted,
+            tuplets: Vec::new(),
+            chord: None,
+        }));
+        self
+    }
+
+    /// Nest another group inside this one
+    pub fn tuplet(mut self, group: TupletBuilder) -> Self {
+        self.members.push(TupletMember::Group(group));
+        self
+    }
+
+    /// Flatten the group into elements carrying its tuplet info (and any nested groups')
+    fn into_elements(self) -> Result<Vec<Element>, String> {
+        let tuplet = match self.normal_notes {
+            Some(normal_notes) if self.actual_notes >= 1 && normal_notes >= 1 => {
+                TupletInfo::with_ratio(self.actual_notes, normal_notes)
+            }
+            None if self.actual_notes >= 2 => TupletInfo::new(self.actual_notes),
+            _ => {
+                let ratio = match self.normal_notes {
+                    Some(normal_notes) => format!("{}:{}", self.actual_notes, normal_notes),
+                    None => self.actual_notes.to_string(),
+                };
+                return Err(format!("Invalid tuplet ratio {}", ratio));
+            }
+        };
+        if self.members.len() < 2 {
+            return Err("A tuplet needs at least two notes".to_string());
+        }
+
+        let mut elements = Vec::new();
+        for member in self.members {
+            match member {
+                TupletMember::Element(element) => elements.push(element),
+                TupletMember::Group(group) => elements.extend(group.into_elements()?),
+            }
+        }
+        let last = elements.len() - 1;
+        for (i, element) in elements.iter_mut().enumerate() {
+            let tuplets = match element {
+                Element::Note(note) => &mut note.tuplets,
+                Element::Rest { tuplets, .. } => tuplets,
+            };
+            let mut info = tuplet;
+            info.is_start = i == 0;
+            info.is_stop = i == last;
+            tuplets.insert(0, info);
+        }
+        Ok(elements)
+    }
+}
+
+/// Builds one [`Measure`], rejecting elements that overflow the time signature
+///
+/// Created by [`ScoreBuilder::measure()`], which knows the time signature and measure number.
+#[derive(Debug, Clone)]
+pub struct MeasureBuilder {
+    measure: Measure,
+    time_signature: TimeSignature,
+    number: usize,
+    filled: Rational,
+}
+
+impl MeasureBuilder {
+    fn new(time_signature: TimeSignature, number: usize) -> Self {
+        Self {
+            measure: Measure {
+                elements: Vec::new(),
+                repeat_start: false,
+                repeat_end: false,
+                ending: None,
+                key_change: None,
+                is_pickup: false,
+            },
+            time_signature,
+            number,
+            filled: Rational::ZERO,
+        }
+    }
+
+    /// Add a note
+    ///
+    /// # Errors
+    /// Returns [`GenError::SemanticError`] if the note doesn't fit in the rest of the measure,
+    /// or its chord symbol is empty or contains a `}` or line break.
+    pub fn note(self, note: impl Into<Note>) -> Result<Self, GenError> {
+        self.push_elements(vec![Element::Note(note.into())])
+    }
+
+    /// Add a rest
+    ///
+    /// # Errors
+    /// Returns [`GenError::SemanticError`] if the rest doesn't fit in the rest of the measure.
+    pub fn rest(self, duration: Duration, dotted: bool) -> Result<Self, GenError> {
+        self.push_elements(vec![Element::Rest {
+            duration,
+            dotted,
+            tuplets: Vec::new(),
+            chord: None,
+        }])
+    }
+
+    /// Add a standard tuplet of `actual_notes` (3 = triplet, 5 = quintuplet, ...), as `[C D E]3`
+    ///
+    /// # Errors
+    /// Returns [`GenError::SemanticError`] for a tuplet of fewer than two notes, or one that
+    /// doesn't fit in the rest of the measure.
+    pub fn tuplet<N: Into<Note>>(self, actual_notes: u8, notes: impl IntoIterator<Item = N>) -> Result<Self, GenError> {
+        let group = notes.into_iter().fold(TupletBuilder::new(actual_notes), TupletBuilder::note);
+        self.tuplet_group(group)
+    }
+
+    /// Add a tuplet of `actual_notes` in the time of `normal_notes`, as `[C D E F G]5:8`
+    ///
+    /// # Errors
+    /// Returns [`GenError::SemanticError`] for a count of 0, a tuplet of fewer than two
+    /// notes, or one that doesn't fit in the rest of the measure.
+    pub fn tuplet_ratio<N: Into<Note>>(
+        self,
+        actual_notes: u8,
+        normal_notes: u8,
+        notes: impl IntoIterator<Item = N>,
+    ) -> Result<Self, GenError> {
+        let group = notes
+            .into_iter()
+            .fold(TupletBuilder::with_ratio(actual_notes, normal_notes), TupletBuilder::note);
+        self.tuplet_group(group)
+    }
+
+    /// Add a tuplet group, which may mix notes, rests and nested groups
+    ///
+    /// # Errors
+    /// Returns [`GenError::SemanticError`] for a group (or nested group) with an invalid ratio
+    /// or fewer than two members, or one that doesn't fit in the rest of the measure.
+    pub fn tuplet_group(self, group: TupletBuilder) -> Result<Self, GenError> {
+        let elements = group.into_elements().map_err(|message| GenError::SemanticError {
+            measure: self.number,
+            message,
+        })?;
+        self.push_elements(elements)
+    }
+
+    /// Start a repeat (`||:`) at this measure
+    pub fn repeat_start(mut self) -> Self {
+        self.measure.repeat_start = true;
+        self
+    }
+
+    /// End a repeat (`:||`) at this measure
+    pub fn repeat_end(mut self) -> Self {
+        self.measure.repeat_end = true;
+        self
+    }
+
+    /// Put this measure under a first or second ending bracket
+    pub fn ending(mut self, ending: Ending) -> Self {
+        self.measure.ending = Some(ending);
+        self
+    }
+
+    /// Change the key from this measure on (`@key:`)
+    pub fn key_change(mut self, key: KeySignature) -> Self {
+        self.measure.key_change = Some(key);
+        self
+    }
+
+    /// Mark this measure as a pickup (`@pickup`), so it may be shorter than the time signature
+    pub fn pickup(mut self) -> Self {
+        self.measure.is_pickup = true;
+        self
+    }
+
+    /// Time left in the measure, in beats of the time signature
+    pub fn remaining_beats(&self) -> Rational {
+        (self.capacity() - self.filled) * Rational::from_integer(self.time_signature.beat_type as i64)
+    }
+
+    /// Whether the measure is full
+    pub fn is_full(&self) -> bool {
+        self.filled == self.capacity()
+    }
+
+    fn capacity(&self) -> Rational {
+        Rational::new(self.time_signature.beats as i64, self.time_signature.beat_type as i64)
+    }
+
+    fn push_elements(mut self, elements: Vec<Element>) -> Result<Self, GenError> {
+        for chord in elements.iter().filter_map(Element::chord) {
+            check_chord_symbol(&chord.symbol).map_err(|message| GenError::SemanticError {
+                measure: self.number,
+                message,
+            })?;
+        }
+
+        let length: Rational = elements.iter().map(Element::total_fraction).sum();
+        if self.filled + length > self.capacity() {
+            let beat_type = Rational::from_integer(self.time_signature.beat_type as i64);
+            return Err(GenError::SemanticError {
+                measure: self.number,
+                message: format!(
+                    "{} beats don't fit in the measure: {} beats left",
+                    length * beat_type,
+                    self.remaining_beats()
+                ),
+            });
+        }
+        self.filled += length;
+        self.measure.elements.extend(elements);
+        Ok(self)
+    }
+}
+
+/// Builds a [`Score`] from its metadata and complete measures
+#[derive(Debug, Clone, Default)]
+pub struct ScoreBuilder {
+    metadata: Metadata,
+    measures: Vec<Measure>,
+}
+
+impl ScoreBuilder {
+    /// Start an empty score in 4/4, C major
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.metadata.title = Some(title.to_string());
+        self
+    }
+
+    pub fn composer(mut self, composer: &str) -> Self {
+        self.metadata.composer = Some(composer.to_string());
+        self
+    }
+
+    /// Time signature for the whole score (set it before adding measures)
+    ///
+    /// # Errors
+    /// Returns [`GenError::MetadataError`] for no beats or a beat type that isn't a power
+    /// of two.
+    pub fn time_signature(mut self, beats: u8, beat_type: u8) -> Result<Self, GenError> {
+        let time_signature = TimeSignature { beats, beat_type };
+        if !time_signature.is_valid() {
+            return Err(GenError::MetadataError(format!(
+                "Invalid time signature: {}/{} (needs at least one beat and a beat type of 1, 2, 4, 8, ...)",
+                beats, beat_type
+            )));
+        }
+        self.metadata.time_signature = time_signature;
+        Ok(self)
+    }
+
+    pub fn key_signature(mut self, key: KeySignature) -> Self {
+        self.metadata.key_signature = key;
+        self
+    }
+
+    pub fn tempo(mut self, tempo: Tempo) -> Self {
+        self.metadata.tempo = Some(tempo);
+        self
+    }
+
+    pub fn swing(mut self, swing: Swing) -> Self {
+        self.metadata.swing = Some(swing);
+        self
+    }
+
+    pub fn comping(mut self, comping: CompingPattern) -> Self {
+        self.metadata.comping = Some(comping);
+        self
+    }
+
+    /// Start the next measure, in the score's time signature
+    pub fn measure(&self) -> MeasureBuilder {
+        MeasureBuilder::new(self.metadata.time_signature.clone(), self.measures.len() + 1)
+    }
+
+    /// Append a finished measure
+    ///
+    /// # Errors
+    /// Returns [`GenError::SemanticError`] if the measure is empty, or isn't full and isn't
+    /// a pickup.
+    pub fn push(mut self, measure: MeasureBuilder) -> Result<Self, GenError> {
+        if measure.measure.elements.is_empty() {
+            return Err(GenError::SemanticError {
+                measure: measure.number,
+                message: "Empty measure".to_string(),
+            });
+        }
+        if !measure.measure.is_pickup {
+            validate_measure(&measure.measure, &self.metadata.time_signature, measure.number)?;
+        }
+        self.measures.push(measure.measure);
+        Ok(self)
+    }
+
+    /// Finish the score: mark the notes that close ties, check repeats and endings, and
+    /// check that the score prints as Gen source
+    ///
+    /// # Errors
+    /// Returns [`GenError::SemanticError`] from [`crate::validate()`], or an error from
+    /// [`crate::printer::check_printable()`] (swing on quarter notes, a tempo of 0 BPM).
+    pub fn build(mut self) -> Result<Score, GenError> {
+        let mut tied = false;
+        for element in self.measures.iter_mut().flat_map(|measure| measure.elements.iter_mut()) {
+            match element {
+                Element::Note(note) => {
+                    note.tie_stop = tied;
+                    tied = note.tie_start;
+                }
+                Element::Rest { .. } => tied = false,
+            }
+        }
+
+        let score = Score {
+            metadata: self.metadata,
+            measures: self.measures,
+            mod_points: ModPoints::default(),
+            line_to_measure: HashMap::new(),
+        };
+        validate(&score)?;
+        check_printable(&score)?;
+        Ok(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::printer::to_gen_source;
+
+    fn note(name: NoteName, duration: Duration) -> NoteBuilder {
+        NoteBuilder::new(name).duration(duration)
+    }
+
+    #[test]
+    fn test_builds_what_the_parser_reads() {
+        let mut score = ScoreBuilder::new()
+            .title("Arpeggio")
+            .time_signature(3, 4)
+            .unwrap()
+            .key_signature(KeySignature::from_fifths(-2, Mode::Major))
+            .tempo(Tempo { bpm: 96, duration: Duration::Quarter, dotted: false });
+
+        let bar = score
+            .measure()
+            .pickup()
+            .note(note(NoteName::F, Duration::Quarter).octave(Octave::Low))
+            .unwrap();
+        score = score.push(bar).unwrap();
+        let bar = score
+            .measure()
+            .repeat_start()
+            .note(note(NoteName::B, Duration::Half).dotted().chord("Bb").slur_start())
+            .unwrap();
+        score = score.push(bar).unwrap();
+        let bar = score
+            .measure()
+            .tuplet(3, [NoteName::D, NoteName::F, NoteName::A].map(|name| note(name, Duration::Eighth)))
+            .unwrap()
+            .note(note(NoteName::B, Duration::Quarter).accidental(Accidental::ForceNatural).tie())
+            .unwrap()
+            .note(NoteBuilder::new(NoteName::B).accidental(Accidental::ForceNatural).slur_stop())
+            .unwrap()
+            .repeat_end();
+        let score = score.push(bar).unwrap().build().unwrap();
+
+        let source = to_gen_source(&score);
+        assert_eq!(
+            source,
+            "---\ntitle: Arpeggio\ntime-signature: 3/4\nkey-signature: Bb\ntempo: '96'\n---\n\
+             @pickup _F\n||: {Bb}:(Bp*\n[D/ F/ A/]3 B%- B%) :||\n"
+        );
+        let reparsed = parse(&source).unwrap();
+        for (built, parsed) in score.measures.iter().zip(&reparsed.measures) {
+            assert_eq!(built.elements, parsed.elements);
+        }
+    }
+
+    #[test]
+    fn test_rejects_elements_past_the_barline() {
+        let score = ScoreBuilder::new().time_signature(6, 8).unwrap();
+        let bar = score.measure().note(note(NoteName::C, Duration::Half)).unwrap();
+        assert_eq!(bar.remaining_beats(), Rational::from_integer(2));
+
+        let error = bar.clone().note(note(NoteName::D, Duration::Quarter).dotted()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Semantic error at measure 1: 3 beats don't fit in the measure: 2 beats left"
+        );
+        assert!(bar.clone().rest(Duration::Quarter, false).unwrap().is_full());
+        assert!(bar.tuplet(3, [note(NoteName::E, Duration::Quarter)]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unprintable_chords_and_empty_measures() {
+        let score = ScoreBuilder::new();
+        for symbol in ["", "C}x"] {
+            let result = score.measure().note(note(NoteName::C, Duration::Whole).chord(symbol));
+            assert!(matches!(result, Err(GenError::SemanticError { measure: 1, .. })), "{:?}", symbol);
+        }
+
+        let error = score.clone().push(score.measure().pickup()).unwrap_err();
+        assert_eq!(error.to_string(), "Semantic error at measure 1: Empty measure");
+
+        let swung = score.swing(Swing { duration: Duration::Quarter, ratio: Swing::TRIPLET });
+        let bar = swung.measure().note(note(NoteName::C, Duration::Whole)).unwrap();
+        assert!(matches!(swung.push(bar).unwrap().build(), Err(GenError::MetadataError(_))));
+    }
+
+    #[test]
+    fn test_explicit_and_nested_tuplets() {
+        let eighths = |count: usize| vec![note(NoteName::C, Duration::Eighth); count];
+        let score = ScoreBuilder::new();
+
+        // Twelve eighths in the time of eight fill a 4/4 bar
+        let bar = score.measure().tuplet_ratio(12, 8, eighths(12)).unwrap();
+        assert!(bar.is_full());
+        // The standard ratio for 12 is 12:4, half the bar
+        let bar = score.measure().tuplet(12, eighths(12)).unwrap();
+        assert_eq!(bar.remaining_beats(), Rational::from_integer(2));
+
+        // A triplet inside a quintuplet: 3:2 within 5:4
+        let inner = [NoteName::E, NoteName::F, NoteName::G]
+            .into_iter()
+            .fold(TupletBuilder::new(3), |group, name| group.note(note(name, Duration::Sixteenth)));
+        let group = TupletBuilder::new(5)
+            .note(note(NoteName::C, Duration::Eighth))
+            .note(note(NoteName::D, Duration::Eighth))
+            .tuplet(inner)
+            .rest(Duration::Eighth, false)
+            .note(note(NoteName::A, Duration::Eighth));
+        let bar = score.measure().tuplet_group(group).unwrap().note(note(NoteName::B, Duration::Half)).unwrap();
+        let built = score.push(bar).unwrap().build().unwrap();
+        let source = to_gen_source(&built);
+        let reparsed = parse(&source).unwrap();
+        assert_eq!(reparsed.measures[0].elements, built.measures[0].elements, "{}", source);
+        assert_eq!(built.measures[0].elements[2].tuplets().len(), 2);
+
+        let pair = |group: TupletBuilder| {
+            group.note(note(NoteName::C, Duration::Eighth)).note(note(NoteName::D, Duration::Eighth))
+        };
+        let invalid = [
+            pair(TupletBuilder::with_ratio(3, 0)),
+            pair(TupletBuilder::new(1)),
+            TupletBuilder::new(3).note(note(NoteName::C, Duration::Eighth)),
+        ];
+        for group in invalid {
+            let nested = TupletBuilder::new(3).note(note(NoteName::E, Duration::Eighth)).tuplet(group);
+            let result = ScoreBuilder::new().measure().tuplet_group(nested);
+            assert!(matches!(result, Err(GenError::SemanticError { measure: 1, .. })), "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn test_rejects_uncountable_time_signatures() {
+        for (beats, beat_type) in [(4, 0), (0, 4), (4, 6)] {
+            let result = ScoreBuilder::new().time_signature(beats, beat_type);
+            assert!(matches!(result, Err(GenError::MetadataError(_))), "{}/{}", beats, beat_type);
+        }
+    }
+
+    #[test]
+    fn test_rejects_short_measures_unless_pickup() {
+        let score = ScoreBuilder::new();
+        let bar = score.measure().note(note(NoteName::C, Duration::Half)).unwrap();
+        let error = score.clone().push(bar.clone()).unwrap_err();
+        assert!(matches!(error, GenError::SemanticError { measure: 1, .. }), "{}", error);
+
+        let score = score.push(bar.pickup()).unwrap();
+        assert_eq!(score.measure().number, 2);
+    }
+
+    #[test]
+    fn test_ties_across_barlines_and_repeat_check() {
+        let mut score = ScoreBuilder::new();
+        let bar = score.measure().note(note(NoteName::G, Duration::Whole).tie()).unwrap();
+        score = score.push(bar).unwrap();
+        let bar = score.measure().note(note(NoteName::G, Duration::Whole)).unwrap().repeat_end();
+        score = score.push(bar).unwrap();
+
+        let Element::Note(last) = &score.clone().measures[1].elements[0] else { unreachable!() };
+        assert!(!last.tie_stop);
+        assert!(score.build().is_err());
+
+        let mut score = ScoreBuilder::new();
+        let bar = score.measure().note(note(NoteName::G, Duration::Whole).tie()).unwrap();
+        score = score.push(bar).unwrap();
+        let bar = score.measure().note(note(NoteName::G, Duration::Whole)).unwrap();
+        let score = score.push(bar).unwrap().build().unwrap();
+        let Element::Note(last) = &score.measures[1].elements[0] else { unreachable!() };
+        assert!(last.tie_stop);
+        assert_eq!(to_gen_source(&score), "Go-\nGo\n");
+    }
+}
@@ -0,0 +1,328 @@
+//! # AST JSON
+//!
+//! Serializes the full [`Score`] AST to JSON and reads it back, so editors and scripts can
+//! inspect a parsed score or build one without writing Gen source.
+//!
+//! ## Schema
+//! Every document is an envelope carrying the schema version next to the score:
+//!
+//! ```text
+//! { "schemaVersion": 1, "score": Score }
+//!
+//! Score     { metadata, measures: [Measure], modPoints, lineToMeasure: { "<line>": index } }
+//! Metadata  { title, composer, timeSignature: { beats, beatType },
+//!             keySignature: { fifths, mode, alterations: [["Bb", -1], ...] },
+//!             writtenPitch: { note, octaveOffset }, tempo: { bpm, duration, dotted } | null,
+//!             swing: { duration, ratio } | null, comping, accidentals: "note" | "measure" }
+//! Measure   { elements: [Element], repeatStart, repeatEnd, ending: "first" | "second" | null,
+//!             keyChange: KeySignature | null, isPickup }
+//! Element   { "type": "note", name, accidental, octave, duration, dotted, tuplets,
+//!             tieStart, tieStop, slurStart, slurStop, chord }
+//!         | { "type": "rest", duration, dotted, tuplets, chord }
+//! Tuplet    { actualNotes, normalNotes, isStart, isStop }
+//! Chord     { symbol, duration, dotted }
+//! ModPoints { points: { "<line>": { "eb" | "bb": octaveShift } } }
+//! ```
+//!
+//! - Field names are camelCase, enum values are camelCase strings (`"doubleSharp"`,
+//!   `"sixteenth"`, `"mixolydian"`), except note names which stay `"C"` to `"B"` and
+//!   comping patterns which use their frontmatter names (`"half-notes"`)
+//! - Lines are 1-indexed source lines; measure indices are 0-indexed
+//! - When reading, optional fields can be left out: flags default to `false`, lists to
+//!   empty, options to `null`, accidental to `"natural"` (follow the key) and octave to
+//!   `"middle"`; missing metadata falls back to 4/4, C major
+//! - Maps are written with sorted keys, so the same score always gives the same text
+//!
+//! `schemaVersion` is bumped whenever a change would stop an older reader from understanding
+//! the document; [`score_from_json()`] rejects any other version.
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, json::{score_from_json, score_to_json}};
+//!
+//! let json = score_to_json(&parse("C D E F")?);
+//! assert!(json.contains("\"schemaVersion\": 1"));
+//!
+//! let score = score_from_json(r#"{
+//!     "schemaVersion": 1,
+//!     "score": { "measures": [{ "elements": [
+//!         { "type": "note", "name": "G", "duration": "whole" }
+//!     ] }] }
+//! }"#)?;
+//! assert_eq!(gen::to_gen_source(&score), "Go\n");
+//! # Ok::<(), gen::GenError>(())
+//! ```
+
+use crate::ast::{Element, InstrumentGroup, KeySignature, Score};
+use crate::GenError;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+
+/// Version of the AST JSON schema written by [`score_to_json()`]
+pub const AST_SCHEMA_VERSION: u32 = 1;
+
+/// Envelope written around the score
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Document<'a> {
+    schema_version: u32,
+    score: &'a Score,
+}
+
+/// Just the version, read before the score so a newer document gets a clear error
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    schema_version: u32,
+}
+
+#[derive(Deserialize)]
+struct OwnedDocument {
+    score: Score,
+}
+
+/// Serialize a score as a pretty-printed, versioned JSON document
+pub fn score_to_json(score: &Score) -> String {
+    let document = Document {
+        schema_version: AST_SCHEMA_VERSION,
+        score,
+    };
+    serde_json::to_string_pretty(&document).expect("AST serialization cannot fail")
+}
+
+/// Read a score back from a JSON document written by [`score_to_json()`] (or by hand)
+///
+/// Values no Gen source could produce (a beat type of 0, a 0:2 tuplet, 9 sharps) are
+/// rejected, so the score is safe to validate and export. Measure durations are not
+/// checked; run [`crate::validate()`] on it before compiling if it may not add up.
+///
+/// # Errors
+/// Returns [`GenError::ParseError`] for malformed JSON, a document that doesn't match the
+/// schema, an out-of-range value, or an unsupported `schemaVersion`.
+pub fn score_from_json(json: &str) -> Result<Score, GenError> {
+    let header: Header = serde_json::from_str(json).map_err(json_error)?;
+    if header.schema_version != AST_SCHEMA_VERSION {
+        return Err(document_error(format!(
+            "Unsupported AST schema version {} (expected {})",
+            header.schema_version, AST_SCHEMA_VERSION
+        )));
+    }
+    let document: OwnedDocument = serde_json::from_str(json).map_err(json_error)?;
+    check_ranges(&document.score)?;
+    Ok(document.score)
+}
+
+/// Reject values the rest of the compiler assumes can't happen
+fn check_ranges(score: &Score) -> Result<(), GenError> {
+    let time = &score.metadata.time_signature;
+    if !time.is_valid() {
+        return Err(document_error(format!(
+            "Invalid time signature {}/{} (needs at least one beat and a beat type of 1, 2, 4, 8, ...)",
+            time.beats, time.beat_type
+        )));
+    }
+    check_key(&score.metadata.key_signature, "the key signature")?;
+
+    for (i, measure) in score.measures.iter().enumerate() {
+        if let Some(key) = &measure.key_change {
+            check_key(key, &format!("the key change in measure {}", i + 1))?;
+        }
+        for tuplet in measure.elements.iter().flat_map(Element::tuplets) {
+            if tuplet.actual_notes == 0 || tuplet.normal_notes == 0 {
+                return Err(document_error(format!(
+                    "Invalid tuplet {}:{} in measure {}",
+                    tuplet.actual_notes,
+                    tuplet.normal_notes,
+                    i + 1
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_key(key: &KeySignature, what: &str) -> Result<(), GenError> {
+    if !(-7..=7).contains(&key.fifths) {
+        return Err(document_error(format!(
+            "Invalid fifths {} in {} (expected -7 to 7)",
+            key.fifths, what
+        )));
+    }
+    if let Some((name, alter)) = key.alterations.iter().find(|(_, alter)| !(-2..=2).contains(alter)) {
+        return Err(document_error(format!(
+            "Invalid alteration {} for {} in {} (expected -2 to 2)",
+            alter,
+            name.letter(),
+            what
+        )));
+    }
+    Ok(())
+}
+
+/// An error about the document as a whole, which has no position of its own
+fn document_error(message: String) -> GenError {
+    GenError::ParseError {
+        line: 1,
+        column: 1,
+        message,
+    }
+}
+
+fn json_error(error: serde_json::Error) -> GenError {
+    // serde_json appends the location to its message; it goes in the error fields instead
+    let message = error.to_string();
+    let message = match message.rsplit_once(" at line ") {
+        Some((text, _)) => text.to_string(),
+        None => message,
+    };
+    GenError::ParseError {
+        line: error.line(),
+        column: error.column(),
+        message: format!("Invalid AST JSON: {}", message),
+    }
+}
+
+/// Serialize a map with its keys in order
+pub(crate) fn sorted_map<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Serialize mod points with both the lines and the instrument groups in order
+pub(crate) fn sorted_mod_points<S>(
+    points: &HashMap<usize, HashMap<InstrumentGroup, i8>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    points
+        .iter()
+        .map(|(line, groups)| (line, groups.iter().collect::<BTreeMap<_, _>>()))
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::printer::to_gen_source;
+
+    #[test]
+    fn test_round_trip_every_construct() {
+        let source = "---\ntitle: Tour\ncomposer: Someone\ntime-signature: 3/4\nkey-signature: D dorian\n\
+                      tempo: \"90*\"\nswing: /\ncomping: bossa\naccidentals: measure\n---\n\
+                      @pickup {Dm7}:D\n||: {G7}:[A/ B/ ^C#/]3 (Bb- Bb) @Eb:^\n\
+                      1. @key:F# $ _E// F##// G// Abb// Gp :||\n2. ^^Co*\n";
+        let score = parse(source).unwrap();
+        let json = score_to_json(&score);
+        let back = score_from_json(&json).unwrap();
+
+        assert_eq!(to_gen_source(&back), to_gen_source(&score));
+        assert_eq!(score_to_json(&back), json);
+        assert_eq!(back.line_to_measure, score.line_to_measure);
+        assert_eq!(back.mod_points.points, score.mod_points.points);
+    }
+
+    #[test]
+    fn test_documented_names() {
+        let json = score_to_json(&parse("---\ntempo: 120\n---\n{C7}:^F#/ $/ [C D E]3\n").unwrap());
+
+        assert!(json.starts_with("{\n  \"schemaVersion\": 1,\n  \"score\": {"));
+        for fragment in [
+            "\"timeSignature\": {\n        \"beats\": 4,\n        \"beatType\": 4",
+            "\"type\": \"note\"",
+            "\"name\": \"F\"",
+            "\"accidental\": \"sharp\"",
+            "\"octave\": \"high\"",
+            "\"duration\": \"eighth\"",
+            "\"symbol\": \"C7\"",
+            "\"type\": \"rest\"",
+            "\"actualNotes\": 3",
+            "\"lineToMeasure\": {\n      \"4\": 0",
+        ] {
+            assert!(json.contains(fragment), "missing {} in {}", fragment, json);
+        }
+    }
+
+    #[test]
+    fn test_left_out_fields_default() {
+        let score = score_from_json(
+            r#"{"schemaVersion": 1, "score": {
+                "metadata": {"keySignature": {"fifths": -1, "mode": "minor"}},
+                "measures": [
+                    {"repeatStart": true, "elements": [{"type": "rest", "duration": "half"}, {"type": "note", "name": "A", "duration": "half", "dotted": false}]},
+                    {"repeatEnd": true, "elements": [{"type": "note", "name": "D", "accidental": "flat", "octave": "low", "duration": "whole"}]}
+                ]
+            }}"#,
+        )
+        .unwrap();
+
+        assert_eq!(score.metadata.time_signature.beats, 4);
+        assert_eq!(to_gen_source(&score), "---\nkey-signature: Dm\n---\n||: $p Ap\n_Dbo :||\n");
+        crate::validate(&score).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_other_versions_and_bad_documents() {
+        let error = score_from_json(r#"{"schemaVersion": 2, "score": {"measures": []}}"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parse error at line 1, column 1: Unsupported AST schema version 2 (expected 1)"
+        );
+
+        let error = score_from_json("{\"schemaVersion\": 1,\n \"score\": {\"measures\": [{\"elements\": [{\"type\": \"chord\"}]}]}}")
+            .unwrap_err();
+        assert!(matches!(error, GenError::ParseError { line: 2, .. }), "{}", error);
+        assert!(error.to_string().contains("unknown variant `chord`"), "{}", error);
+
+        assert!(score_from_json(r#"{"score": {"measures": []}}"#).is_err());
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_values() {
+        let document = |metadata: &str, element: &str| {
+            format!(
+                r#"{{"schemaVersion": 1, "score": {{"metadata": {{{}}}, "measures": [{{"elements": [{}]}}]}}}}"#,
+                metadata, element
+            )
+        };
+        let note = r#"{"type": "note", "name": "C", "duration": "whole"}"#;
+        let tuplet = |actual: u8, normal: u8| {
+            format!(
+                r#"{{"type": "rest", "duration": "half", "tuplets": [{{"actualNotes": {}, "normalNotes": {}, "isStart": true, "isStop": true}}]}}"#,
+                actual, normal
+            )
+        };
+
+        let cases = [
+            (document(r#""timeSignature": {"beats": 4, "beatType": 0}"#, note), "Invalid time signature 4/0"),
+            (document(r#""timeSignature": {"beats": 0, "beatType": 4}"#, note), "Invalid time signature 0/4"),
+            (document(r#""timeSignature": {"beats": 4, "beatType": 6}"#, note), "Invalid time signature 4/6"),
+            (document("", &tuplet(0, 2)), "Invalid tuplet 0:2 in measure 1"),
+            (document("", &tuplet(3, 0)), "Invalid tuplet 3:0 in measure 1"),
+            (document(r#""keySignature": {"fifths": 9, "mode": "major"}"#, note), "Invalid fifths 9 in the key signature"),
+            (
+                document(r#""keySignature": {"fifths": 0, "mode": "major", "alterations": [["B", -3]]}"#, note),
+                "Invalid alteration -3 for B in the key signature",
+            ),
+        ];
+        for (json, message) in cases {
+            let error = score_from_json(&json).unwrap_err();
+            assert!(matches!(error, GenError::ParseError { .. }), "{}", error);
+            assert!(error.to_string().contains(message), "{}", error);
+        }
+
+        let key_change = r#"{"schemaVersion": 1, "score": {"measures": [{"keyChange": {"fifths": -8, "mode": "minor"}, "elements": []}]}}"#;
+        let error = score_from_json(key_change).unwrap_err();
+        assert!(error.to_string().contains("Invalid fifths -8 in the key change in measure 1"), "{}", error);
+
+        let tuplet = document("", &tuplet(3, 2));
+        assert!(crate::validate(&score_from_json(&tuplet).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! # Humdrum **kern Export
+//!
+//! Writes a score as a [Humdrum](https://www.humdrum.org) file for corpus analysis tools
+//! (the Humdrum Toolkit, humlib, music21): the melody in a `**kern` spine and the chord
+//! symbols in a parallel `**mxhm` spine. Spines are separated by tabs:
+//!
+//! ```text
+//! !!!OTL: Scale
+//! **kern  **mxhm
+//! *clefG2 *
+//! *k[]    *
+//! *C:     *
+//! *M4/4   *
+//! =1-     =1-
+//! 4c      C major
+//! 4d      .
+//! 8eL     G dominant
+//! 8fJ     .
+//! 4g      .
+//! ==      ==
+//! *-      *-
+//! ```
+//!
+//! ## Notes
+//! - Pitches are concert pitch in kern octave notation (`c` is middle C, `cc` the octave above,
+//!   `C` the octave below), spelled with `#`/`-` whatever the key; `n` marks a natural that
+//!   cancels an earlier accidental or the key signature
+//! - Durations are reciprocals, so tuplets need no markup (a triplet eighth is `12`, a
+//!   quintuplet sixteenth `20`); lengths with no reciprocal use rational rhythms (`16%3`)
+//! - Ties are `[`, `_`, `]`, slurs `(` `)`, and beams `L`/`J` follow the MusicXML grouping
+//! - Barlines are numbered from the first full measure, with `!|:` and `:|!` repeats and `==`
+//!   at the end
+//! - Key signature (`*k[f#]`), key (`*G:`, `*d:dor`), meter (`*M6/8`) and tempo (`*MM`, in
+//!   quarter notes) interpretations start the spine; `@key:` changes repeat the key lines
+//! - Each chord symbol sits on the line of the note it starts on, as a MusicXML root and kind
+//!   (`D minor-seventh`, `C major/E`); a symbol with no MusicXML kind is written as it is
+//! - A score with endings is split into labelled sections (`*>A`, and `*>A1`/`*>A2` for the
+//!   endings after it), and an expansion list (`*>[A,A1,A,A2]`) gives the order they are
+//!   played in
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, to_kern};
+//!
+//! let score = parse("{Dm7}:D F A ^C").unwrap();
+//! let kern = to_kern(&score);
+//! assert!(kern.contains("4d\tD minor-seventh\n4f\t.\n4a\t.\n4cc\t.\n"));
+//! ```
+
+use crate::accidentals::MeasureAccidentals;
+use crate::ast::*;
+use crate::musicxml::{calculate_beam_states, harmony_kind, BeamState};
+use crate::rational::Rational;
+
+/// Order in which sharps are added to a key signature (flats go in reverse)
+const SHARP_ORDER: [NoteName; 7] = [NoteName::F, NoteName::C, NoteName::G, NoteName::D, NoteName::A, NoteName::E, NoteName::B];
+
+/// Convert a Score to a Humdrum file with `**kern` and `**mxhm` spines
+pub fn to_kern(score: &Score) -> String {
+    let metadata = &score.metadata;
+    let mut lines: Vec<String> = Vec::new();
+    if let Some(composer) = &metadata.composer {
+        lines.push(format!("!!!COM: {}", composer));
+    }
+    if let Some(title) = &metadata.title {
+        lines.push(format!("!!!OTL: {}", title));
+    }
+
+    let mut interpretations = vec!["**kern".to_string(), "*clefG2".to_string()];
+    interpretations.extend(key_interpretations(&metadata.key_signature));
+    let time = &metadata.time_signature;
+    interpretations.push(format!("*M{}/{}", time.beats, time.beat_type));
+    if let Some(tempo) = &metadata.tempo {
+        interpretations.push(format!("*MM{}", tempo.to_quarter_note_bpm()));
+    }
+    for (k, token) in interpretations.iter().enumerate() {
+        let harmony = if k == 0 { "**mxhm" } else { "*" };
+        lines.push(format!("{}\t{}", token, harmony));
+    }
+
+    let sections = if score.measures.iter().any(|m| m.ending.is_some()) { sections(score) } else { Vec::new() };
+    if !sections.is_empty() {
+        let expansion = format!("*>[{}]", expansion(&sections).join(","));
+        lines.push(format!("{}\t{}", expansion, expansion));
+    }
+
+    let mut key_signature = metadata.key_signature.clone();
+    let mut accidentals = MeasureAccidentals::new(metadata.accidentals);
+    let mut bar_number = 0;
+    for (i, measure) in score.measures.iter().enumerate() {
+        // Barline before the measure: a pickup at the start has none, unless it opens a repeat
+        let previous = i.checked_sub(1).map(|p| &score.measures[p]);
+        let leading_pickup = i == 0 && measure.is_pickup;
+        if !leading_pickup {
+            bar_number += 1;
+        }
+        let style = match (previous.is_some_and(|p| p.repeat_end), measure.repeat_start) {
+            (true, true) => ":|!|:",
+            (true, false) => ":|!",
+            (false, true) => "!|:",
+            (false, false) => "",
+        };
+        let barline = match (i, leading_pickup) {
+            (0, true) if style.is_empty() => None,
+            (0, true) => Some(format!("={}", style)),
+            (0, false) if style.is_empty() => Some("=1-".to_string()),
+            _ => Some(format!("={}{}", bar_number, style)),
+        };
+        if let Some(barline) = barline {
+            lines.push(format!("{}\t{}", barline, barline));
+        }
+        if let Some(section) = sections.iter().find(|section| section.start == i) {
+            lines.push(format!("*>{}\t*>{}", section.label, section.label));
+        }
+
+        if let Some(new_key) = measure.key_change.as_ref().filter(|key| **key != key_signature) {
+            key_signature = new_key.clone();
+            for token in key_interpretations(&key_signature) {
+                lines.push(format!("{}\t*", token));
+            }
+        }
+
+        accidentals.start_measure(&key_signature);
+        let beams = calculate_beam_states(&measure.elements, time);
+        for (element, beam) in measure.elements.iter().zip(beams) {
+            let token = match element {
+                Element::Note(note) => note_token(note, beam, &key_signature, &mut accidentals),
+                Element::Rest { duration, dotted, tuplets, .. } => format!("{}r", recip(*duration, *dotted, tuplets)),
+            };
+            let harmony = element.chord().map_or(".".to_string(), |chord| harmony_token(&chord.symbol));
+            lines.push(format!("{}\t{}", token, harmony));
+        }
+    }
+    let last = if score.measures.last().is_some_and(|m| m.repeat_end) { "=:|!" } else { "==" };
+    lines.push(format!("{}\t{}", last, last));
+    lines.push("*-\t*-".to_string());
+
+    let mut kern = lines.join("\n");
+    kern.push('\n');
+    kern
+}
+
+/// Measures labelled as one section of the expansion list
+struct Section {
+    label: String,
+    start: usize,
+    ending: Option<Ending>,
+    repeat_start: bool,
+    repeat_end: bool,
+}
+
+/// Split the measures into sections: one starts at each repeat start, after each repeat end,
+/// and wherever an ending starts or stops. Sections are lettered in order; an ending is named
+/// after the section before it (`A1`, `A2`)
+fn sections(score: &Score) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut body = String::new();
+    let mut letters = 0;
+    for (i, measure) in score.measures.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|p| &score.measures[p]);
+        if previous.is_none_or(|p| measure.repeat_start || p.repeat_end || p.ending != measure.ending) {
+            let label = match measure.ending {
+                Some(Ending::First) => format!("{}1", body),
+                Some(Ending::Second) => format!("{}2", body),
+                None => {
+                    let letter = (b'A' + letters % 26) as char;
+                    body = letter.to_string().repeat(letters as usize / 26 + 1);
+                    letters += 1;
+                    body.clone()
+                }
+            };
+            sections.push(Section { label, start: i, ending: measure.ending, repeat_start: measure.repeat_start, repeat_end: false });
+        }
+        if let Some(section) = sections.last_mut() {
+            section.repeat_end = measure.repeat_end;
+        }
+    }
+    sections
+}
+
+/// Labels in the order the sections are played: each repeat end goes back once to the last
+/// repeat start, taking the first ending the first time through and the second after that
+fn expansion(sections: &[Section]) -> Vec<&str> {
+    let mut order = Vec::new();
+    let mut repeat_from = 0;
+    // Section whose repeat end was taken, until the music moves on past the endings
+    let mut repeated: Option<usize> = None;
+    let mut k = 0;
+    while let Some(section) = sections.get(k) {
+        if section.ending.is_none() && repeated.is_some_and(|jump| k > jump) {
+            repeated = None;
+        }
+        if section.repeat_start && repeated.is_none() {
+            repeat_from = k;
+        }
+        let played = match section.ending {
+            Some(Ending::First) => repeated.is_none(),
+            Some(Ending::Second) => repeated.is_some(),
+            None => true,
+        };
+        if played {
+            order.push(section.label.as_str());
+        }
+        if played && section.repeat_end && repeated.is_none() {
+            repeated = Some(k);
+            k = repeat_from;
+        } else {
+            k += 1;
+        }
+    }
+    order
+}
+
+/// Key signature (`*k[f#c#]`) and key (`*D:`, `*e:`, `*g:dor`) interpretations
+fn key_interpretations(key_signature: &KeySignature) -> Vec<String> {
+    let alterations: Vec<(NoteName, i8)> = if key_signature.is_custom() {
+        key_signature.alterations.clone()
+    } else if key_signature.fifths >= 0 {
+        SHARP_ORDER.iter().take(key_signature.fifths as usize).map(|&step| (step, 1)).collect()
+    } else {
+        SHARP_ORDER.iter().rev().take(key_signature.fifths.unsigned_abs() as usize).map(|&step| (step, -1)).collect()
+    };
+    let signature: String = alterations
+        .iter()
+        .map(|&(step, alter)| format!("{}{}", step.letter().to_ascii_lowercase(), accidental(alter)))
+        .collect();
+    let mut tokens = vec![format!("*k[{}]", signature)];
+    if let Some((step, alter)) = key_signature.tonic() {
+        let (minor_third, mode) = match key_signature.mode {
+            Mode::Major => (false, ""),
+            Mode::Minor => (true, ""),
+            Mode::Dorian => (true, ":dor"),
+            Mode::Phrygian => (true, ":phr"),
+            Mode::Lydian => (false, ":lyd"),
+            Mode::Mixolydian => (false, ":mix"),
+            Mode::Locrian => (true, ":loc"),
+        };
+        let letter = if minor_third { step.letter().to_ascii_lowercase() } else { step.letter() };
+        tokens.push(format!("*{}{}:{}", letter, accidental(alter), mode.trim_start_matches(':')));
+    }
+    tokens
+}
+
+/// Note token: slur/tie openings, duration, pitch, accidental, tie continuation, slur end, beam
+fn note_token(note: &Note, beam: BeamState, key_signature: &KeySignature, accidentals: &mut MeasureAccidentals) -> String {
+    let alter = accidentals.sounding_alter(note, key_signature);
+    let octave = note.octave.number();
+    let explicit = note.accidental != Accidental::Natural;
+    let natural_sign = accidentals
+        .note(note.name, octave, alter, explicit)
+        .is_some_and(|display| display.alter == 0 && !display.cautionary);
+
+    let mut token = String::new();
+    if note.slur_start {
+        token.push('(');
+    }
+    if note.tie_start && !note.tie_stop {
+        token.push('[');
+    }
+    token.push_str(&recip(note.duration, note.dotted, &note.tuplets));
+    token.push_str(&pitch(note.name, octave));
+    token.push_str(&accidental(alter));
+    if natural_sign {
+        token.push('n');
+    }
+    match (note.tie_start, note.tie_stop) {
+        (true, true) => token.push('_'),
+        (false, true) => token.push(']'),
+        _ => {}
+    }
+    if note.slur_stop {
+        token.push(')');
+    }
+    match beam {
+        BeamState::Begin => token.push('L'),
+        BeamState::End => token.push('J'),
+        BeamState::Continue | BeamState::None => {}
+    }
+    token
+}
+
+/// Kern duration: the reciprocal of the undotted length in whole notes, then dots
+fn recip(duration: Duration, dotted: bool, tuplets: &[TupletInfo]) -> String {
+    let length: Rational = duration.modified_fraction(false, tuplets);
+    let value = if length.numer() == 1 {
+        length.denom().to_string()
+    } else {
+        format!("{}%{}", length.denom(), length.numer())
+    };
+    format!("{}{}", value, if dotted { "." } else { "" })
+}
+
+/// Kern pitch letters: `c` is middle C, `cc` an octave above, `C` and `CC` below
+fn pitch(step: NoteName, octave: i8) -> String {
+    if octave >= 4 {
+        step.letter().to_ascii_lowercase().to_string().repeat((octave - 3) as usize)
+    } else {
+        step.letter().to_string().repeat((4 - octave).max(1) as usize)
+    }
+}
+
+/// Kern accidental for an alteration (`#`, `##`, `-`, `--`)
+fn accidental(alter: i8) -> String {
+    if alter >= 0 {
+        "#".repeat(alter as usize)
+    } else {
+        "-".repeat(alter.unsigned_abs() as usize)
+    }
+}
+
+/// `**mxhm` token: MusicXML root and kind, with the bass after a slash
+fn harmony_token(symbol: &str) -> String {
+    let (main, bass) = match symbol.split_once('/') {
+        Some((main, bass)) => (main, Some(bass)),
+        None => (symbol, None),
+    };
+    let Some((root, quality)) = split_root(main) else {
+        return symbol.to_string();
+    };
+    let kind = harmony_kind(quality);
+    if kind == "other" {
+        return symbol.to_string();
+    }
+    let mut token = format!("{} {}", root, kind);
+    if let Some((bass, _)) = bass.and_then(split_root) {
+        token.push_str(&format!("/{}", bass));
+    }
+    token
+}
+
+/// Root of a chord symbol in Humdrum spelling (`B-`, `F#`) and the quality after it
+fn split_root(symbol: &str) -> Option<(String, &str)> {
+    let letter = symbol.chars().next().filter(|c| NoteName::from_letter(*c).is_some())?;
+    let accidentals = symbol[1..].chars().take_while(|&c| c == '#' || c == 'b').count().min(2);
+    let alter: i8 = symbol[1..1 + accidentals].chars().map(|c| if c == '#' { 1 } else { -1 }).sum();
+    Some((format!("{}{}", letter, accidental(alter)), &symbol[1 + accidentals..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_header_and_interpretations() {
+        let source = "---\ntitle: Jig\ncomposer: Trad\ntime-signature: 6/8\nkey-signature: E minor\ntempo: \"116*\"\n---\nE/ F/ G/ A/ B/ ^C/\n";
+        let kern = to_kern(&parse(source).unwrap());
+        assert_eq!(
+            kern,
+            "!!!COM: Trad\n!!!OTL: Jig\n**kern\t**mxhm\n*clefG2\t*\n*k[f#]\t*\n*e:\t*\n*M6/8\t*\n*MM174\t*\n=1-\t=1-\n8eL\t.\n8f#\t.\n8gJ\t.\n8aL\t.\n8b\t.\n8ccJ\t.\n==\t==\n*-\t*-\n"
+        );
+    }
+
+    #[test]
+    fn test_durations_pitches_ties_and_slurs() {
+        let source = "---\nkey-signature: F\n---\n(__Bp* _E#/) B%/\n[C D E]3/ ^^F-^^F-^^F/ $/\n[C/ D/ E/ F/ G/]5 Fp";
+        let kern = to_kern(&parse(source).unwrap());
+        assert!(kern.contains("=1-\t=1-\n(2.BB-\t.\n8E#)L\t.\n8bnJ\t.\n=2\t=2\n"), "{}", kern);
+        assert!(kern.contains("12cL\t.\n12d\t.\n12eJ\t.\n[4fff\t.\n4fff_\t.\n8fff]\t.\n8r\t.\n"), "{}", kern);
+        assert!(kern.contains("=3\t=3\n10cL\t.\n10dJ\t.\n10e\t.\n10fL\t.\n10gJ\t.\n2f\t.\n"), "{}", kern);
+    }
+
+    #[test]
+    fn test_barlines_and_repeats() {
+        let source = "@pickup G\n||: C D E F\n1. G A B ^C :||\n2. ^Co\n||: C D E F\n@key:Bb B A G F :||";
+        let kern = to_kern(&parse(source).unwrap());
+        assert!(kern.starts_with("**kern\t**mxhm\n*clefG2\t*\n*k[]\t*\n*C:\t*\n*M4/4\t*\n*>[A,B,B1,B,B2,C,C]\t*>[A,B,B1,B,B2,C,C]\n"), "{}", kern);
+        assert!(kern.contains("*>A\t*>A\n4g\t.\n=1!|:\t=1!|:\n*>B\t*>B\n4c\t.\n"), "{}", kern);
+        assert!(kern.contains("=3:|!\t=3:|!\n*>B2\t*>B2\n1cc\t.\n=4!|:\t=4!|:\n*>C\t*>C\n"), "{}", kern);
+        assert!(kern.contains("=5\t=5\n*k[b-e-]\t*\n*B-:\t*\n4b-\t.\n"), "{}", kern);
+        assert!(kern.ends_with("4f\t.\n=:|!\t=:|!\n*-\t*-\n"), "{}", kern);
+    }
+
+    #[test]
+    fn test_endings_as_sections() {
+        let source = "C D E F\n||: G A B ^C\n1. ^C B A G :||\n2. Co\nF E D C";
+        let kern = to_kern(&parse(source).unwrap());
+        assert!(kern.contains("*M4/4\t*\n*>[A,B,B1,B,B2,C]\t*>[A,B,B1,B,B2,C]\n=1-\t=1-\n*>A\t*>A\n4c\t.\n"), "{}", kern);
+        assert!(kern.contains("=2!|:\t=2!|:\n*>B\t*>B\n4g\t.\n"), "{}", kern);
+        assert!(kern.contains("=3\t=3\n*>B1\t*>B1\n4cc\t.\n"), "{}", kern);
+        assert!(kern.contains("=4:|!\t=4:|!\n*>B2\t*>B2\n1c\t.\n=5\t=5\n*>C\t*>C\n4f\t.\n"), "{}", kern);
+
+        // Without endings the output has no sections
+        assert!(!to_kern(&parse("||: C D E F :||").unwrap()).contains("*>"));
+    }
+
+    #[test]
+    fn test_harmony_spine() {
+        let source = "{Bbmaj7}:C {F#m7b5}p D E {C/E}:E\n{G7b9}:Go";
+        let kern = to_kern(&parse(source).unwrap());
+        assert!(kern.contains("4c\tB- major-seventh\n4d\tF# half-diminished\n4e\t.\n4e\tC major/E\n"), "{}", kern);
+        assert!(kern.contains("1g\tG7b9\n"), "{}", kern);
+    }
+
+    #[test]
+    fn test_modal_and_custom_keys() {
+        assert_eq!(key_interpretations(&KeySignature::from_str("D dorian").unwrap()), ["*k[]", "*d:dor"]);
+        assert_eq!(key_interpretations(&KeySignature::from_str("Eb").unwrap()), ["*k[b-e-a-]", "*E-:"]);
+        assert_eq!(key_interpretations(&KeySignature::from_str("F# Bb").unwrap()), ["*k[f#b-]"]);
+    }
+}
@@ -10,8 +10,9 @@
 //! ## Token Types
 //! - **Rhythm modifiers**: `/` (eighth), `p` (half), `o` (whole), `*` (dotted)
 //! - **Note names**: A-G, `$` (rest)
-//! - **Pitch modifiers**: `#` (sharp), `b` (flat), `%` (natural), `^` (up octave), `_` (down octave)
-//! - **Tuplets**: `[`, `]`, numbers (2-9)
+//! - **Pitch modifiers**: `#` (sharp), `b` (flat), `##` (double sharp), `bb` (double flat), `%` (natural),
+//!   `^` (up octave), `_` (down octave)
+//! - **Tuplets**: `[`, `]`, numbers, `:` (explicit ratio, e.g. `]7:8`)
 //! - **Ties**: `-` (hyphen)
 //! - **Slurs**: `(`, `)`
 //! - **Repeats**: `||:` (start), `:||` (end)
@@ -66,6 +67,8 @@ pub enum Token {
     // Pitch modifiers
     Sharp,          // #
     Flat,           // b
+    DoubleSharp,    // ##
+    DoubleFlat,     // bb
     Natural,        // %
     Underscore,     // _
     Caret,          // ^
@@ -73,7 +76,8 @@ pub enum Token {
     // Tuplet/grouping
     LeftBracket,    // [
     RightBracket,   // ]
-    Number(u8),     // 2, 3, 4, 5, 6, etc.
+    Number(u8),     // 2, 3, 4, 5, 6, ..., 12, etc.
+    Colon,          // : (tuplet ratio, e.g. ]7:8)
 
     // Ties
     Hyphen,         // -
@@ -274,6 +278,9 @@ impl<'a> Lexer<'a> {
                                 message: "Unexpected ':' followed by single '|'. Did you mean ':||'?".to_string(),
                             });
                         }
+                    } else if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        // Tuplet ratio separator, e.g. [C D E F G F E]7:8
+                        Token::Colon
                     } else {
                         // Standalone : is not valid in Gen syntax
                         return Err(GenError::ParseError {
@@ -329,11 +336,21 @@ impl<'a> Lexer<'a> {
                 }
                 '#' => {
                     self.advance();
-                    Token::Sharp
+                    if let Some(&'#') = self.peek() {
+                        self.advance();
+                        Token::DoubleSharp
+                    } else {
+                        Token::Sharp
+                    }
                 }
                 'b' => {
                     self.advance();
-                    Token::Flat
+                    if let Some(&'b') = self.peek() {
+                        self.advance();
+                        Token::DoubleFlat
+                    } else {
+                        Token::Flat
+                    }
                 }
                 '%' => {
                     self.advance();
@@ -356,8 +373,19 @@ impl<'a> Lexer<'a> {
                     Token::RightBracket
                 }
                 '0'..='9' => {
-                    self.advance();
-                    Token::Number(c.to_digit(10).unwrap() as u8)
+                    let mut value = 0u32;
+                    while let Some(&digit) = self.peek().filter(|d| d.is_ascii_digit()) {
+                        self.advance();
+                        value = value * 10 + digit.to_digit(10).unwrap();
+                        if value > u8::MAX as u32 {
+                            return Err(GenError::ParseError {
+                                line,
+                                column,
+                                message: format!("Number too large (maximum {})", u8::MAX),
+                            });
+                        }
+                    }
+                    Token::Number(value as u8)
                 }
                 '-' => {
                     self.advance();
@@ -405,12 +433,8 @@ impl<'a> Lexer<'a> {
                     }
 
                     let chord_symbol = &self.input[start_pos..self.position];
-                    if chord_symbol.is_empty() {
-                        return Err(GenError::ParseError {
-                            line,
-                            column,
-                            message: "Empty chord annotation '{}'".to_string(),
-                        });
+                    if let Err(message) = check_chord_symbol(chord_symbol) {
+                        return Err(GenError::ParseError { line, column, message });
                     }
 
                     // Skip the closing brace
@@ -538,6 +562,20 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Check that a chord symbol can be written as `{symbol}`: not empty, and without a `}` or
+/// line break that would end the annotation early
+pub(crate) fn check_chord_symbol(symbol: &str) -> Result<(), String> {
+    if symbol.is_empty() {
+        Err("Empty chord annotation '{}'".to_string())
+    } else if symbol.contains('}') {
+        Err(format!("Chord symbol '{}' can't contain '}}'", symbol))
+    } else if symbol.contains('\n') {
+        Err(format!("Chord symbol {:?} can't contain a line break", symbol))
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -577,6 +615,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_double_accidentals() {
+        let mut lexer = Lexer::new("F## Bbb Eb");
+        let tokens = lexer.tokenize().unwrap();
+        let token_types: Vec<_> = tokens.iter().map(|t| &t.token).collect();
+        assert_eq!(
+            token_types,
+            vec![
+                &Token::NoteF,
+                &Token::DoubleSharp,
+                &Token::Whitespace,
+                &Token::NoteB,
+                &Token::DoubleFlat,
+                &Token::Whitespace,
+                &Token::NoteE,
+                &Token::Flat,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tuplet_ratio() {
+        let mut lexer = Lexer::new("[C]12:8/");
+        let tokens = lexer.tokenize().unwrap();
+        let token_types: Vec<_> = tokens.iter().map(|t| &t.token).collect();
+        assert_eq!(
+            token_types,
+            vec![
+                &Token::LeftBracket,
+                &Token::NoteC,
+                &Token::RightBracket,
+                &Token::Number(12),
+                &Token::Colon,
+                &Token::Number(8),
+                &Token::Slash,
+            ]
+        );
+    }
+
     #[test]
     fn test_repeat_start() {
         let mut lexer = Lexer::new("||: C D");
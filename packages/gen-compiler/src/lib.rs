@@ -44,14 +44,33 @@
 //! - [`compile_unchecked()`] - Skip validation (for partial/incomplete scores)
 //! - [`compile_with_options()`] - Custom clef, octave shift, transposition
 //! - [`compile_with_mod_points()`] - Instrument-specific rendering with mod points
+//! - [`compile_with_numerals()`] - Chord symbols as Roman numerals or Nashville numbers
+//! - [`compile_chart()`] - Comping chart with slashes in place of the melody
+//! - [`compile_text_chart()`] - Plain-text chord chart
+//! - [`compile_abc()`] - ABC notation
+//! - [`compile_lilypond()`] - LilyPond `.ly` file
+//! - [`compile_mei()`] - MEI 5
+//! - [`compile_kern()`] - Humdrum **kern
+//! - [`compile_mxl()`] - Compressed MusicXML (`.mxl` bytes)
+//! - [`compile_svg()`] - Engraved SVG pages or systems
+//!
+//! ### Import Functions
+//! - [`import_abc()`] - ABC tune → Gen source, with warnings for dropped content
+//!
+//! ### AST JSON Functions
+//! - [`parse_to_json()`] - Gen source → versioned AST JSON
+//! - [`json_to_source()`] - AST JSON → Gen source
 //!
 //! ### Playback Functions
 //! - [`generate_playback_data()`] - Generate MIDI playback data with timing info
+//! - [`generate_playback_data_with_options()`] - Playback data with accompaniment options
+//! - [`render_wav()`] - Render playback data to a WAV practice track
 //!
 //! ### Low-Level API
 //! - [`parse()`] - Parse Gen source into AST
 //! - [`validate()`] - Validate AST semantic correctness
 //! - [`to_musicxml()`] - Generate MusicXML from AST
+//! - [`ScoreBuilder`] - Build a Score in code, checking durations as you go
 //!
 //! ## Gen Language Syntax Overview
 //!
@@ -60,7 +79,7 @@
 //!
 //! - **Rhythm modifiers**: `/` (eighth), `//` (sixteenth), `p` (half), `o` (whole), `*` (dotted)
 //! - **Notes**: A-G or `$` (rest)
-//! - **Pitch modifiers**: `#` (sharp), `b` (flat), `^` (octave up), `_` (octave down)
+//! - **Pitch modifiers**: `#` (sharp), `b` (flat), `##` (double sharp), `bb` (double flat), `^` (octave up), `_` (octave down)
 //!
 //! ### Examples
 //! - `C` - C quarter note
@@ -87,11 +106,23 @@
 //! ## Module Structure
 //!
 //! - [`ast`] - Abstract Syntax Tree type definitions (Score, Measure, Note, etc.)
+//! - [`rational`] - Exact fractions for durations and positions
 //! - [`error`] - Error types (GenError variants)
 //! - [`lexer`] - Tokenization (String → Vec<Token>)
 //! - [`parser`] - Parsing (Vec<Token> → Score AST)
 //! - [`semantic`] - Validation (measure durations, repeats)
 //! - [`musicxml`] - MusicXML generation (Score → MusicXML string)
+//! - [`analysis`] - Key estimation, re-keying, and Roman numeral / Nashville number analysis
+//! - [`text_chart`] - Plain-text chord charts (Score → text)
+//! - [`abc`] - ABC notation (Score ↔ ABC tune)
+//! - [`lilypond`] - LilyPond export (Score → .ly file)
+//! - [`mei`] - MEI export (Score → MEI 5 XML)
+//! - [`kern`] - Humdrum **kern export (Score → .krn file)
+//! - [`mxl`] - Compressed MusicXML container (MusicXML → .mxl zip)
+//! - [`svg`] - Minimal SVG engraving (Score → SVG pages or systems)
+//! - [`builder`] - Typed Score/Measure/Note builders
+//! - [`printer`] - Gen source printing (Score → .gen source)
+//! - [`json`] - Versioned AST JSON (Score ↔ JSON)
 //!
 //! ## Additional Resources
 //!
@@ -114,12 +145,25 @@
 
 // Core modules
 pub mod ast;
+pub mod rational;
 pub mod error;
 pub mod lexer;
 pub mod parser;
 pub mod semantic;
 pub mod musicxml;
 pub mod playback;
+pub mod analysis;
+pub mod text_chart;
+pub mod abc;
+pub mod lilypond;
+pub mod mei;
+pub mod kern;
+pub mod mxl;
+pub mod svg;
+pub mod builder;
+pub mod printer;
+pub mod json;
+mod accidentals;
 
 // Public API
 pub mod api;
@@ -127,15 +171,36 @@ pub mod api;
 // Re-export core types
 pub use ast::*;
 pub use error::*;
+pub use rational::Rational;
 
 // Re-export pipeline functions
 pub use parser::parse;
 pub use semantic::validate;
-pub use musicxml::{to_musicxml, to_musicxml_with_options, to_musicxml_with_mod_points, Clef, Transposition};
+pub use musicxml::{
+    to_musicxml, to_musicxml_chart, to_musicxml_with_options, to_musicxml_with_mod_points, to_musicxml_with_numerals, Clef,
+    HarmonyNumerals, NumeralSystem, SlashStyle, Transposition,
+};
+pub use text_chart::to_text_chart;
+pub use abc::{to_abc, to_abc_with_options};
+pub use lilypond::{to_lilypond, to_lilypond_with_options};
+pub use mei::{to_mei, to_mei_with_options};
+pub use kern::to_kern;
+pub use mxl::to_mxl;
+pub use svg::{to_svg, to_svg_with_options, SvgLayout};
+pub use builder::{MeasureBuilder, NoteBuilder, ScoreBuilder, TupletBuilder};
+pub use printer::to_gen_source;
+pub use json::{score_from_json, score_to_json, AST_SCHEMA_VERSION};
 
 // Re-export playback functions
-pub use playback::{generate_playback_data, PlaybackData, PlaybackNote, PlaybackChord, TieType};
+pub use playback::{
+    generate_playback_data, generate_playback_data_with_options, BassStyle, GroovePattern, PlaybackBassNote,
+    PlaybackData, PlaybackDrumHit, PlaybackNote, PlaybackChord, PlaybackOptions, TieType, VoicingStyle,
+    render_wav, SynthOptions, Waveform,
+};
 
 // Re-export API functions for convenience
-pub use api::{compile, compile_unchecked, compile_with_options, compile_with_mod_points};
+pub use api::{
+    compile, compile_abc, compile_chart, compile_kern, compile_lilypond, compile_mei, compile_mxl, compile_svg, compile_text_chart, compile_unchecked, compile_with_options,
+    compile_with_mod_points, compile_with_numerals, import_abc, json_to_source, parse_to_json,
+};
 
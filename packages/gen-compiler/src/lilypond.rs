@@ -0,0 +1,517 @@
+//! # LilyPond Export
+//!
+//! Writes a score as a [LilyPond](https://lilypond.org) `.ly` file for engraved parts:
+//!
+//! ```text
+//! \version "2.24.0"
+//! \header { title = "Scale" }
+//!
+//! melody = {
+//!   \clef treble \key c \major \time 4/4
+//!   \repeat volta 2 {
+//!     c'4 d'4 e'4 f'4 |
+//!   }
+//!   \alternative {
+//!     { g'4 a'4 b'4 c''4 | }
+//!     { c''1 | }
+//!   }
+//!   \bar "|."
+//! }
+//!
+//! harmonies = \chordmode { c1 | g1:7 | c1 | }
+//!
+//! \score { << \new ChordNames \harmonies \new Staff \melody >> \layout { } \midi { } }
+//! ```
+//!
+//! ## Notes
+//! - Pitches are absolute (no `\relative`): `c'` is middle C
+//! - `\key`, `\time`, `\tempo` and `\clef` come from the metadata; `@key:` changes and custom
+//!   key signatures (`keyAlterations`) are kept
+//! - Tuplets use `\tuplet 3/2 { ... }`; ties `~` and slurs `( )` follow the notes
+//! - Repeats become `\repeat volta 2 { }`, with first and second endings in `\alternative`
+//! - A first measure marked `@pickup` gets `\partial`
+//! - Chord symbols go to a `\chordmode` staff of `ChordNames`, each lasting until the next
+//!   chord in its measure
+//!
+//! Transposition, clef, octave shift and mod points work as in the MusicXML writer.
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, to_lilypond};
+//!
+//! let score = parse("{Am7}:A ^C ^E ^G").unwrap();
+//! let ly = to_lilypond(&score);
+//! assert!(ly.contains("a'4 c''4 e''4 g''4 |"));
+//! assert!(ly.contains("a1:m7 |"));
+//! ```
+
+use crate::accidentals::MeasureAccidentals;
+use crate::ast::*;
+use crate::musicxml::{transpose_chord_root, written_key_signature, Clef, Transposition, WrittenMeasure};
+use crate::playback::parse_chord_structure;
+use crate::rational::Rational;
+
+/// Convert a Score to a LilyPond file
+pub fn to_lilypond(score: &Score) -> String {
+    to_lilypond_with_options(score, None, Clef::Treble, 0, None)
+}
+
+/// Convert a Score to a LilyPond file with transposition, clef, octave shift and mod points
+/// (the same options as [`crate::to_musicxml_with_mod_points()`])
+pub fn to_lilypond_with_options(
+    score: &Score,
+    transposition: Option<Transposition>,
+    clef: Clef,
+    octave_shift: i8,
+    instrument_group: Option<InstrumentGroup>,
+) -> String {
+    let transposition = transposition.as_ref();
+    let metadata = &score.metadata;
+    let mut ly = String::from("\\version \"2.24.0\"\n\n");
+
+    let mut header = Vec::new();
+    if let Some(title) = &metadata.title {
+        header.push(format!("  title = {}\n", quoted(title)));
+    }
+    if let Some(composer) = &metadata.composer {
+        header.push(format!("  composer = {}\n", quoted(composer)));
+    }
+    if !header.is_empty() {
+        ly.push_str(&format!("\\header {{\n{}}}\n\n", header.concat()));
+    }
+
+    // Melody
+    let time = &metadata.time_signature;
+    let clef_name = match clef {
+        Clef::Treble => "treble",
+        Clef::Bass => "bass",
+    };
+    let mut key_signature = metadata.key_signature.clone();
+    ly.push_str("melody = {\n");
+    ly.push_str(&format!("  \\clef {}\n", clef_name));
+    ly.push_str(&format!("  {}\n", key_command(&written_key_signature(&key_signature, transposition))));
+    ly.push_str(&format!("  \\time {}/{}\n", time.beats, time.beat_type));
+    if let Some(tempo) = &metadata.tempo {
+        ly.push_str(&format!("  \\tempo {} = {}\n", duration_text(tempo.duration, tempo.dotted), tempo.bpm));
+    }
+
+    let measure_length = Rational::new(time.beats as i64, time.beat_type as i64);
+    let octave_shifts = score.octave_shifts(octave_shift, instrument_group);
+    let mut accidentals = MeasureAccidentals::new(metadata.accidentals);
+    let mut structure = RepeatStructure::default();
+    for (i, measure) in score.measures.iter().enumerate() {
+        let length: Rational = measure.elements.iter().map(Element::total_fraction).sum();
+        let mut line = String::new();
+
+        if let Some(new_key) = measure.key_change.as_ref().filter(|key| **key != key_signature) {
+            key_signature = new_key.clone();
+            line.push_str(&key_command(&written_key_signature(&key_signature, transposition)));
+            line.push(' ');
+        }
+        if i == 0 && measure.is_pickup && length < measure_length {
+            line.push_str(&format!("\\partial {} ", length_text(length)));
+        }
+
+        let mut bar = LilyBar {
+            measure: WrittenMeasure::start(&key_signature, transposition, octave_shifts[i], &mut accidentals),
+            accidentals: &mut accidentals,
+        };
+        line.push_str(&bar.write(&measure.elements));
+        line.push_str(" |");
+
+        structure.measure(&mut ly, score, i, &line);
+    }
+    structure.finish(&mut ly, score.measures.last());
+    ly.push_str("}\n");
+
+    // Chord symbols
+    let has_chords = score.measures.iter().any(|m| m.elements.iter().any(|e| e.chord().is_some()));
+    if has_chords {
+        ly.push_str("\nharmonies = \\chordmode {\n");
+        for (i, measure) in score.measures.iter().enumerate() {
+            let length: Rational = measure.elements.iter().map(Element::total_fraction).sum();
+            ly.push_str("  ");
+            if i == 0 && measure.is_pickup && length < measure_length {
+                ly.push_str(&format!("\\partial {} ", length_text(length)));
+            }
+            ly.push_str(&chord_measure(measure, transposition));
+            ly.push_str(" |\n");
+        }
+        ly.push_str("}\n");
+    }
+
+    ly.push_str("\n\\score {\n  <<\n");
+    if has_chords {
+        ly.push_str("    \\new ChordNames \\harmonies\n");
+    }
+    ly.push_str("    \\new Staff \\melody\n  >>\n  \\layout { }\n  \\midi { }\n}\n");
+    ly
+}
+
+/// Opens and closes `\repeat volta` and `\alternative` blocks around the measures
+#[derive(Default)]
+struct RepeatStructure {
+    in_repeat: bool,
+    in_alternative: bool,
+}
+
+impl RepeatStructure {
+    fn indent(&self) -> &'static str {
+        match (self.in_repeat, self.in_alternative) {
+            (_, true) => "      ",
+            (true, false) => "    ",
+            _ => "  ",
+        }
+    }
+
+    fn measure(&mut self, ly: &mut String, score: &Score, index: usize, line: &str) {
+        let measure = &score.measures[index];
+        let previous = index.checked_sub(1).map(|i| &score.measures[i]);
+
+        if measure.repeat_start {
+            self.close(ly);
+            ly.push_str("  \\repeat volta 2 {\n");
+            self.in_repeat = true;
+        }
+        let ending_start = measure.ending.is_some() && previous.is_none_or(|p| p.ending != measure.ending);
+        match measure.ending {
+            Some(Ending::First) if ending_start && self.in_repeat => {
+                ly.push_str("  }\n  \\alternative {\n    {\n");
+                self.in_alternative = true;
+            }
+            Some(Ending::Second) if ending_start && self.in_alternative => ly.push_str("    {\n"),
+            _ => {}
+        }
+
+        ly.push_str(self.indent());
+        ly.push_str(line);
+        ly.push('\n');
+
+        let next = score.measures.get(index + 1);
+        let ending_stops = measure.ending.is_some() && next.is_none_or(|n| n.ending != measure.ending);
+        if self.in_alternative && ending_stops {
+            ly.push_str("    }\n");
+            if measure.ending == Some(Ending::Second) || !next.is_some_and(|n| n.ending == Some(Ending::Second)) {
+                ly.push_str("  }\n");
+                self.in_alternative = false;
+                self.in_repeat = false;
+            }
+        } else if measure.repeat_end && self.in_repeat && !self.in_alternative {
+            ly.push_str("  }\n");
+            self.in_repeat = false;
+        } else if measure.repeat_end && !self.in_repeat {
+            // A repeat end without a start (unvalidated score)
+            ly.push_str("  \\bar \":|.\"\n");
+        }
+    }
+
+    /// Close any open block
+    fn close(&mut self, ly: &mut String) {
+        if self.in_alternative {
+            ly.push_str("    }\n  }\n");
+        } else if self.in_repeat {
+            ly.push_str("  }\n");
+        }
+        self.in_repeat = false;
+        self.in_alternative = false;
+    }
+
+    fn finish(&mut self, ly: &mut String, last: Option<&Measure>) {
+        let open = self.in_repeat || self.in_alternative;
+        self.close(ly);
+        if !open && !last.is_some_and(|m| m.repeat_end) {
+            ly.push_str("  \\bar \"|.\"\n");
+        }
+    }
+}
+
+/// Writes the notes of one measure
+struct LilyBar<'a> {
+    measure: WrittenMeasure<'a>,
+    accidentals: &'a mut MeasureAccidentals,
+}
+
+impl LilyBar<'_> {
+    fn write(&mut self, elements: &[Element]) -> String {
+        let mut tokens: Vec<String> = Vec::new();
+        for element in elements {
+            let mut token = String::new();
+            for tuplet in element.tuplets() {
+                if tuplet.is_start {
+                    token.push_str(&format!("\\tuplet {}/{} {{ ", tuplet.actual_notes, tuplet.normal_notes));
+                }
+            }
+            match element {
+                Element::Note(note) => {
+                    token.push_str(&self.pitch(note));
+                    token.push_str(&duration_text(note.duration, note.dotted));
+                    if note.tie_start {
+                        token.push('~');
+                    }
+                    if note.slur_start {
+                        token.push('(');
+                    }
+                    if note.slur_stop {
+                        token.push(')');
+                    }
+                }
+                Element::Rest { duration, dotted, .. } => {
+                    token.push('r');
+                    token.push_str(&duration_text(*duration, *dotted));
+                }
+            }
+            for tuplet in element.tuplets().iter().rev() {
+                if tuplet.is_stop {
+                    token.push_str(" }");
+                }
+            }
+            tokens.push(token);
+        }
+        tokens.join(" ")
+    }
+
+    /// Absolute written pitch; a source accidental the key already implies is forced (`!`)
+    fn pitch(&mut self, note: &Note) -> String {
+        let (step, alter, octave) = self.measure.pitch(note, self.accidentals);
+        let courtesy = note.accidental != Accidental::Natural && self.measure.written_key.accidental_for_note(step).alter() == alter;
+        format!("{}{}{}", pitch_name(step, alter), octave_marks(octave), if courtesy { "!" } else { "" })
+    }
+}
+
+/// Dutch (default) LilyPond pitch name: `c`, `fis`, `bes`, `es`, `asas`
+fn pitch_name(step: NoteName, alter: i8) -> String {
+    let letter = step.letter().to_ascii_lowercase();
+    let suffix = match (alter, letter) {
+        (0, _) => "",
+        (-1, 'e' | 'a') => "s",
+        (-2, 'e' | 'a') => "ses",
+        (-1, _) => "es",
+        (..=-2, _) => "eses",
+        (1, _) => "is",
+        _ => "isis",
+    };
+    format!("{}{}", letter, suffix)
+}
+
+/// Absolute octave marks: `c` is C3, `c'` middle C, `c,` C2
+fn octave_marks(octave: i8) -> String {
+    if octave >= 3 {
+        "'".repeat((octave - 3) as usize)
+    } else {
+        ",".repeat((3 - octave) as usize)
+    }
+}
+
+/// Duration as `4`, `8.`, `1`
+fn duration_text(duration: Duration, dotted: bool) -> String {
+    let value = match duration {
+        Duration::Whole => "1",
+        Duration::Half => "2",
+        Duration::Quarter => "4",
+        Duration::Eighth => "8",
+        Duration::Sixteenth => "16",
+        Duration::ThirtySecond => "32",
+    };
+    format!("{}{}", value, if dotted { "." } else { "" })
+}
+
+/// Any length: a plain or dotted value when there is one, otherwise a scaled whole (`1*5/8`)
+fn length_text(length: Rational) -> String {
+    for duration in [Duration::Whole, Duration::Half, Duration::Quarter, Duration::Eighth, Duration::Sixteenth, Duration::ThirtySecond] {
+        for dotted in [false, true] {
+            if duration.modified_fraction(dotted, &[]) == length {
+                return duration_text(duration, dotted);
+            }
+        }
+    }
+    format!("1*{}", length)
+}
+
+/// `\key` command: `\key g \major`, `\key d \dorian`, or explicit `keyAlterations` for a custom
+/// signature
+fn key_command(key_signature: &KeySignature) -> String {
+    let Some((step, alter)) = key_signature.tonic() else {
+        let alterations: Vec<String> = key_signature
+            .alterations
+            .iter()
+            .map(|&(step, alter)| {
+                let index = NoteName::ALL.iter().position(|&s| s == step).unwrap_or(0);
+                let name = match alter {
+                    2.. => "DOUBLE-SHARP",
+                    1 => "SHARP",
+                    -1 => "FLAT",
+                    _ => "DOUBLE-FLAT",
+                };
+                format!("({} . ,{})", index, name)
+            })
+            .collect();
+        return format!("\\set Staff.keyAlterations = #`({})", alterations.join(" "));
+    };
+    let mode = match key_signature.mode {
+        Mode::Major => "major",
+        Mode::Minor => "minor",
+        Mode::Dorian => "dorian",
+        Mode::Phrygian => "phrygian",
+        Mode::Lydian => "lydian",
+        Mode::Mixolydian => "mixolydian",
+        Mode::Locrian => "locrian",
+    };
+    format!("\\key {} \\{}", pitch_name(step, alter), mode)
+}
+
+/// Chord track for one measure: each chord lasts until the next, with spacers before the first
+fn chord_measure(measure: &Measure, transposition: Option<&Transposition>) -> String {
+    let mut events: Vec<(Rational, Option<String>)> = Vec::new();
+    let mut position = Rational::ZERO;
+    for element in &measure.elements {
+        if let Some(chord) = element.chord() {
+            let symbol = match transposition {
+                Some(trans) => transpose_chord_root(&chord.symbol, trans),
+                None => chord.symbol.clone(),
+            };
+            events.push((position, Some(symbol)));
+        }
+        position += element.total_fraction();
+    }
+    let end = position;
+    if events.first().is_none_or(|(start, _)| *start > Rational::ZERO) {
+        events.insert(0, (Rational::ZERO, None));
+    }
+
+    let mut tokens = Vec::new();
+    for (k, (start, symbol)) in events.iter().enumerate() {
+        let until = events.get(k + 1).map_or(end, |(next, _)| *next);
+        if until <= *start {
+            continue;
+        }
+        let length = length_text(until - *start);
+        tokens.push(match symbol {
+            Some(symbol) => chord_text(symbol, &length),
+            None => format!("s{}", length),
+        });
+    }
+    tokens.join(" ")
+}
+
+/// A chord symbol in `\chordmode` (`g2:7`, `d4:m7.5-`, `c1/e`); unreadable symbols are rests,
+/// which ChordNames shows as N.C.
+fn chord_text(symbol: &str, length: &str) -> String {
+    let (Some(structure), Some((root, rest))) = (parse_chord_structure(symbol), spelled_note(symbol)) else {
+        return format!("r{}", length);
+    };
+    let mut text = format!("{}{}", root, length);
+
+    let (prefix, suffix) = match structure.third {
+        Some(3) => ("m", ""),
+        Some(5) => ("", "sus4"),
+        Some(2) => ("", "sus2"),
+        _ => ("", ""),
+    };
+    let mut steps: Vec<&str> = Vec::new();
+    match (prefix, structure.fifth, structure.seventh) {
+        ("m", Some(6), Some(9)) => steps.push("dim7"),
+        ("m", Some(6), None) => steps.push("dim"),
+        (_, fifth, seventh) => {
+            match seventh {
+                Some(9) => steps.push("7-"),
+                Some(10) => steps.push("7"),
+                Some(11) => steps.push("7+"),
+                _ => {}
+            }
+            match fifth {
+                Some(6) => steps.push("5-"),
+                Some(8) => steps.push("5+"),
+                _ => {}
+            }
+        }
+    }
+    for &extension in &structure.extensions {
+        steps.push(match extension {
+            9 if structure.seventh.is_none() => "6",
+            9 | 21 => "13",
+            13 => "9-",
+            14 => "9",
+            15 => "9+",
+            17 => "11",
+            18 => "11+",
+            20 => "13-",
+            _ => continue,
+        });
+    }
+    let prefix = if steps.first().is_some_and(|s| s.starts_with("dim")) { "" } else { prefix };
+    if !prefix.is_empty() || !steps.is_empty() || !suffix.is_empty() {
+        text.push_str(&format!(":{}{}{}", prefix, steps.join("."), suffix));
+    }
+    if let Some((bass, _)) = rest.split_once('/').and_then(|(_, bass)| spelled_note(bass)) {
+        text.push_str(&format!("/{}", bass));
+    }
+    text
+}
+
+/// LilyPond name of the note a chord symbol starts with, and the rest of the symbol
+fn spelled_note(symbol: &str) -> Option<(String, &str)> {
+    let step = NoteName::from_letter(symbol.chars().next()?)?;
+    let accidentals = symbol[1..].chars().take_while(|&c| c == '#' || c == 'b').count().min(2);
+    let alter: i8 = symbol[1..1 + accidentals].chars().map(|c| if c == '#' { 1 } else { -1 }).sum();
+    Some((pitch_name(step, alter), &symbol[1 + accidentals..]))
+}
+
+/// A LilyPond string literal
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_header_and_global_settings() {
+        let source = "---\ntitle: Jig \"One\"\ncomposer: Trad\ntime-signature: 6/8\nkey-signature: E minor\ntempo: \"116*\"\n---\nE/ F/ G/ A/ B/ ^C/\n";
+        let ly = to_lilypond(&parse(source).unwrap());
+        assert!(ly.starts_with("\\version \"2.24.0\"\n\n\\header {\n  title = \"Jig \\\"One\\\"\"\n  composer = \"Trad\"\n}\n"), "{}", ly);
+        assert!(ly.contains("  \\clef treble\n  \\key e \\minor\n  \\time 6/8\n  \\tempo 4. = 116\n"), "{}", ly);
+        assert!(ly.contains("  e'8 fis'8 g'8 a'8 b'8 c''8 |\n  \\bar \"|.\"\n}\n"), "{}", ly);
+        assert!(!ly.contains("ChordNames"));
+    }
+
+    #[test]
+    fn test_pitches_durations_tuplets_ties_and_slurs() {
+        let source = "---\nkey-signature: F\n---\n(__Bp* _E#/) Bb/\n[C D E]3/ ^^F-^^F/ $/ $p";
+        let ly = to_lilypond(&parse(source).unwrap());
+        assert!(ly.contains("  bes,2.( eis8) bes'!8 |\n"), "{}", ly);
+        assert!(ly.contains("  \\tuplet 3/2 { c'8 d'8 e'8 } f'''4~ f'''8 r8 r2 |\n"), "{}", ly);
+    }
+
+    #[test]
+    fn test_repeats_and_alternatives() {
+        let source = "@pickup G\n||: C D E F\n1. G A B ^C :||\n2. ^Co\n||: C Dp* :||";
+        let ly = to_lilypond(&parse(source).unwrap());
+        let expected = "  \\partial 4 g'4 |\n  \\repeat volta 2 {\n    c'4 d'4 e'4 f'4 |\n  }\n  \\alternative {\n    {\n      g'4 a'4 b'4 c''4 |\n    }\n    {\n      c''1 |\n    }\n  }\n  \\repeat volta 2 {\n    c'4 d'2. |\n  }\n}\n";
+        assert!(ly.contains(expected), "{}", ly);
+    }
+
+    #[test]
+    fn test_chord_track() {
+        let source = "C {Dm7b5}:D {G7b9}p E F\n{C/E}:E E E E\nC D E F";
+        let ly = to_lilypond(&parse(source).unwrap());
+        assert!(ly.contains("harmonies = \\chordmode {\n  s4 d4:m7.5- g2:7.9- |\n  c1/e |\n  s1 |\n}\n"), "{}", ly);
+        assert!(ly.contains("\\new ChordNames \\harmonies\n    \\new Staff \\melody"), "{}", ly);
+    }
+
+    #[test]
+    fn test_transposition_clef_and_key_changes() {
+        let source = "---\nkey-signature: Eb\n---\n{Eb}:E G B ^E\n@key:C {G7}:G B ^D ^F";
+        let ly = to_lilypond_with_options(&parse(source).unwrap(), Transposition::for_key("Bb"), Clef::Bass, -1, None);
+        assert!(ly.contains("  \\clef bass\n  \\key f \\major\n"), "{}", ly);
+        assert!(ly.contains("  f4 a4 c'4 f'4 |\n  \\key d \\major a4 cis'4 e'4 g'4 |\n"), "{}", ly);
+        assert!(ly.contains("harmonies = \\chordmode {\n  f1 |\n  a1:7 |\n}\n"), "{}", ly);
+    }
+
+    #[test]
+    fn test_custom_key_signature() {
+        let key = KeySignature::from_str("F# Bb").unwrap();
+        assert_eq!(key_command(&key), "\\set Staff.keyAlterations = #`((3 . ,SHARP) (6 . ,FLAT))");
+    }
+}
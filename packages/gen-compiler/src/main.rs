@@ -2,65 +2,275 @@ use std::env;
 use std::fs;
 use std::process;
 
+/// Output formats for a compiled score
+enum Format {
+    MusicXml,
+    TextChart,
+    Abc,
+    LilyPond,
+    Mei,
+    Kern,
+    Mxl,
+    Svg(gen::SvgLayout),
+    Wav,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: gen [options] <input.gen> [output]");
+    eprintln!("       gen --rekey <input.gen> [output.gen]");
+    eprintln!("       gen --from-abc <input.abc> [output.gen]");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --no-validate        Skip validation (for partial/incomplete scores)");
+    eprintln!("  --chords             Write a plain-text chord chart instead of MusicXML");
+    eprintln!("  --abc                Write ABC notation instead of MusicXML");
+    eprintln!("  --lilypond           Write a LilyPond file instead of MusicXML");
+    eprintln!("  --mei                Write MEI instead of MusicXML");
+    eprintln!("  --kern               Write Humdrum **kern (always concert pitch) instead of MusicXML");
+    eprintln!("  --mxl                Write compressed MusicXML (.mxl, needs an output file)");
+    eprintln!("  --embed-source       Include the Gen source in the .mxl");
+    eprintln!("  --svg                Engrave SVG pages (output.svg, output-2.svg, ...)");
+    eprintln!("  --svg-systems        Engrave one SVG per system");
+    eprintln!("  --wav                Render a WAV practice track (needs an output file)");
+    eprintln!("  --wave <name>        WAV oscillator: sine, saw or fm (default fm)");
+    eprintln!("  --melody-gain <g>    WAV melody volume (default 1.0)");
+    eprintln!("  --comp-gain <g>      WAV chord, bass and drum volume (default 0.6)");
+    eprintln!("  --bass <style>       Add a bass line to the WAV: walking, two-feel or root-fifth");
+    eprintln!("  --groove <pattern>   Add drums to the WAV: swing-ride, rock or bossa");
+    eprintln!("  --count-in <bars>    Click this many bars before the WAV starts");
+    eprintln!("  --transpose <key>    Transpose for a Bb, Eb or F instrument");
+    process::exit(1);
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
-        eprintln!("Usage: gen <input.gen> [output.xml]");
-        eprintln!("       gen --no-validate <input.gen> [output.xml]");
-        process::exit(1);
+        usage();
     }
 
-    let mut no_validate = false;
-    let mut input_path = &args[1];
-    let mut output_path: Option<&String> = args.get(2);
+    if args[1] == "--rekey" {
+        rekey(args.get(2), args.get(3));
+        return;
+    }
+    if args[1] == "--from-abc" {
+        from_abc(args.get(2), args.get(3));
+        return;
+    }
 
     // Parse flags
-    if args[1] == "--no-validate" {
-        no_validate = true;
-        if args.len() < 3 {
-            eprintln!("Usage: gen --no-validate <input.gen> [output.xml]");
-            process::exit(1);
+    let mut no_validate = false;
+    let mut embed_source = false;
+    let mut format = Format::MusicXml;
+    let mut synth = gen::SynthOptions::default();
+    let mut playback = gen::PlaybackOptions::default();
+    let mut transpose_key: Option<&String> = None;
+    let mut paths: Vec<&String> = Vec::new();
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--no-validate" => no_validate = true,
+            "--chords" => format = Format::TextChart,
+            "--abc" => format = Format::Abc,
+            "--lilypond" => format = Format::LilyPond,
+            "--mei" => format = Format::Mei,
+            "--kern" => format = Format::Kern,
+            "--mxl" => format = Format::Mxl,
+            "--embed-source" => embed_source = true,
+            "--svg" => format = Format::Svg(gen::SvgLayout::Pages),
+            "--svg-systems" => format = Format::Svg(gen::SvgLayout::Systems),
+            "--wav" => format = Format::Wav,
+            "--wave" => match rest.next().and_then(|name| gen::Waveform::from_name(name)) {
+                Some(waveform) => {
+                    synth.melody_waveform = waveform;
+                    synth.accompaniment_waveform = waveform;
+                }
+                None => usage(),
+            },
+            "--melody-gain" => synth.melody_gain = parsed_value(rest.next()),
+            "--comp-gain" => synth.accompaniment_gain = parsed_value(rest.next()),
+            "--bass" => playback.bass = Some(rest.next().and_then(|name| gen::BassStyle::from_name(name)).unwrap_or_else(|| usage())),
+            "--groove" => playback.groove = Some(rest.next().and_then(|name| gen::GroovePattern::from_name(name)).unwrap_or_else(|| usage())),
+            "--count-in" => playback.count_in_bars = parsed_value(rest.next()),
+            "--transpose" => match rest.next() {
+                Some(key) => transpose_key = Some(key),
+                None => usage(),
+            },
+            _ => paths.push(arg),
         }
-        input_path = &args[2];
-        output_path = args.get(3);
     }
+    let (input_path, output_path) = match paths[..] {
+        [input] => (input, None),
+        [input, output] => (input, Some(output)),
+        _ => usage(),
+    };
+    if matches!(format, Format::Mxl | Format::Wav) && output_path.is_none() {
+        eprintln!("--mxl and --wav write binary files; give an output path (e.g. score.wav)");
+        process::exit(1);
+    }
+    if embed_source && !matches!(format, Format::Mxl) {
+        eprintln!("--embed-source only applies to --mxl output");
+        process::exit(1);
+    }
+    let transposition = match transpose_key {
+        Some(key) => match gen::Transposition::for_key(key) {
+            Some(transposition) => Some(transposition),
+            None => {
+                eprintln!("Unknown transposition key '{}' (expected C, Bb, Eb or F)", key);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
 
     // Read input file
-    let source = match fs::read_to_string(input_path) {
-        Ok(content) => content,
+    let source = read_input(input_path);
+
+    // Compile
+    let result = gen::parse(&source).and_then(|score| {
+        if !no_validate {
+            gen::validate(&score)?;
+        }
+        // One file, or several for SVG pages and systems
+        let output = match format {
+            Format::MusicXml => gen::musicxml::to_musicxml_transposed(&score, transposition),
+            Format::TextChart => gen::to_text_chart(&score, transposition),
+            Format::Abc => gen::to_abc_with_options(&score, transposition, gen::Clef::Treble, 0, None),
+            Format::LilyPond => gen::to_lilypond_with_options(&score, transposition, gen::Clef::Treble, 0, None),
+            Format::Mei => gen::to_mei_with_options(&score, transposition, gen::Clef::Treble, 0, None),
+            Format::Kern => gen::to_kern(&score),
+            Format::Mxl => {
+                let musicxml = gen::musicxml::to_musicxml_transposed(&score, transposition);
+                return Ok(vec![gen::to_mxl(&musicxml, embed_source.then_some(source.as_str()))]);
+            }
+            Format::Svg(layout) => {
+                let images = gen::to_svg_with_options(&score, transposition, gen::Clef::Treble, 0, None, layout);
+                return Ok(images.into_iter().map(String::into_bytes).collect());
+            }
+            Format::Wav => {
+                let data = gen::generate_playback_data_with_options(&source, "treble", 0, None, None, &playback)?;
+                return Ok(vec![gen::render_wav(&data, &synth)]);
+            }
+        };
+        Ok(vec![output.into_bytes()])
+    });
+
+    let outputs: Vec<Vec<u8>> = match result {
+        Ok(outputs) => outputs,
         Err(e) => {
-            eprintln!("Error reading file '{}': {}", input_path, e);
+            eprintln!("Compilation error: {}", e);
             process::exit(1);
         }
     };
 
-    // Compile
-    let result = if no_validate {
-        gen::compile_unchecked(&source)
-    } else {
-        gen::compile(&source)
+    // Output
+    match output_path {
+        Some(path) => {
+            let mut paths = Vec::new();
+            for (i, output) in outputs.iter().enumerate() {
+                let path = if i == 0 { path.to_string() } else { numbered_path(path, i + 1) };
+                if let Err(e) = fs::write(&path, output) {
+                    eprintln!("Error writing to '{}': {}", path, e);
+                    process::exit(1);
+                }
+                paths.push(path);
+            }
+            let kind = match format {
+                Format::MusicXml => "MusicXML",
+                Format::TextChart => "chord chart",
+                Format::Abc => "ABC",
+                Format::LilyPond => "LilyPond",
+                Format::Mei => "MEI",
+                Format::Kern => "Humdrum",
+                Format::Mxl => "compressed MusicXML",
+                Format::Svg(_) => "SVG",
+                Format::Wav => "WAV audio",
+            };
+            eprintln!("Wrote {} to {}", kind, paths.join(", "));
+        }
+        None => {
+            for output in &outputs {
+                match format {
+                    Format::MusicXml | Format::Mei => println!("{}", String::from_utf8_lossy(output)),
+                    _ => print!("{}", String::from_utf8_lossy(output)),
+                }
+            }
+        }
+    }
+}
+
+/// Value of a numeric option, or the usage message if it is missing or malformed
+fn parsed_value<T: std::str::FromStr>(value: Option<&String>) -> T {
+    value.and_then(|value| value.parse().ok()).unwrap_or_else(|| usage())
+}
+
+/// Path for the n-th of several output files: `score.svg` becomes `score-2.svg`
+fn numbered_path(path: &str, n: usize) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !extension.contains('/') => format!("{}-{}.{}", stem, n, extension),
+        _ => format!("{}-{}", path, n),
+    }
+}
+
+/// Rewrite a source in its estimated key (see `gen::analysis::rekey`)
+fn rekey(input_path: Option<&String>, output_path: Option<&String>) {
+    let Some(input_path) = input_path else {
+        eprintln!("Usage: gen --rekey <input.gen> [output.gen]");
+        process::exit(1);
     };
+    let source = read_input(input_path);
 
-    let xml = match result {
-        Ok(xml) => xml,
+    let rekeyed = match gen::analysis::rekey(&source) {
+        Ok(rekeyed) => rekeyed,
         Err(e) => {
             eprintln!("Compilation error: {}", e);
             process::exit(1);
         }
     };
 
-    // Output
-    match output_path {
+    write_output(output_path, &rekeyed, "re-keyed source");
+}
+
+/// Convert an ABC tune to Gen source (see `gen::abc::parse_abc`)
+fn from_abc(input_path: Option<&String>, output_path: Option<&String>) {
+    let Some(input_path) = input_path else {
+        eprintln!("Usage: gen --from-abc <input.abc> [output.gen]");
+        process::exit(1);
+    };
+    let abc = read_input(input_path);
+
+    let (source, warnings) = match gen::import_abc(&abc) {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("Import error: {}", e);
+            process::exit(1);
+        }
+    };
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    write_output(output_path, &source, "Gen source");
+}
+
+/// Contents of an input file, exiting with an error if it can't be read
+fn read_input(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Error reading file '{}': {}", path, e);
+        process::exit(1);
+    })
+}
+
+/// Write text output to a file (reporting what was written) or to stdout
+fn write_output(path: Option<&String>, contents: &str, kind: &str) {
+    match path {
         Some(path) => {
-            if let Err(e) = fs::write(path, &xml) {
+            if let Err(e) = fs::write(path, contents) {
                 eprintln!("Error writing to '{}': {}", path, e);
                 process::exit(1);
             }
-            eprintln!("Wrote MusicXML to {}", path);
-        }
-        None => {
-            println!("{}", xml);
+            eprintln!("Wrote {} to {}", kind, path);
         }
+        None => print!("{}", contents),
     }
 }
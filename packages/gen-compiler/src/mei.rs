@@ -0,0 +1,539 @@
+//! # MEI Export
+//!
+//! Writes a score as [MEI 5](https://music-encoding.org) (Music Encoding Initiative), the
+//! encoding used for critical editions and musicology tools such as Verovio.
+//!
+//! ## Structure
+//! - `<meiHead>` with the title and composer
+//! - A `<scoreDef>` with one `<staffDef>`: clef, key signature (`<keySig>`, with `<keyAccid>`s
+//!   for a custom signature) and meter (`<meterSig>`), plus the transposition for transposing
+//!   instruments
+//! - One `<section>` of `<measure>`s, each with a single staff and layer of `<note>`s and
+//!   `<rest>`s (with `dots`), nested in `<tuplet>`s
+//! - Ties, slurs, beams and chord symbols (`<harm>`) as control events that point at the
+//!   notes by `startid`/`endid`
+//! - Repeats as measure barlines (`rptstart`/`rptend`), endings as `<ending>` around their
+//!   measures, and `@key:` changes as a `<scoreDef>` before the measure
+//! - The tempo as `<tempo>` in the first measure
+//!
+//! ## Identifiers
+//! Every measure, note, rest and control event has an `xml:id`. Note and rest ids come from
+//! their position in the source ([`crate::playback::element_id()`]: `m3e2` is the second element of the third
+//! measure), so they are the same whatever the transposition or clef, and
+//! [`crate::PlaybackNote::element_id`] names the note to highlight.
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, to_mei};
+//!
+//! let score = parse("{C}:C D E F").unwrap();
+//! let mei = to_mei(&score);
+//! assert!(mei.contains(r#"<note xml:id="m1e1" dur="4" pname="c" oct="4"/>"#));
+//! assert!(mei.contains(r##"<harm xml:id="m1h1" staff="1" startid="#m1e1">C</harm>"##));
+//! ```
+
+use crate::accidentals::{AccidentalDisplay, MeasureAccidentals};
+use crate::ast::*;
+use crate::playback::element_id;
+use crate::musicxml::{
+    calculate_beam_states, transpose_chord_root, written_key_signature, BeamState, Clef, Transposition, WrittenMeasure,
+};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+use std::io::Cursor;
+
+/// MEI namespace
+const MEI_NS: &str = "http://www.music-encoding.org/ns/mei";
+
+/// Convert a Score to MEI
+pub fn to_mei(score: &Score) -> String {
+    to_mei_with_options(score, None, Clef::Treble, 0, None)
+}
+
+/// Convert a Score to MEI with transposition, clef, octave shift and mod points
+/// (the same options as [`crate::to_musicxml_with_mod_points()`])
+pub fn to_mei_with_options(
+    score: &Score,
+    transposition: Option<Transposition>,
+    clef: Clef,
+    octave_shift: i8,
+    instrument_group: Option<InstrumentGroup>,
+) -> String {
+    let transposition = transposition.as_ref();
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None))).unwrap();
+    std::io::Write::write_all(
+        writer.get_mut(),
+        b"<?xml-model href=\"https://music-encoding.org/schema/5.0/mei-CMN.rng\" type=\"application/xml\" schematypens=\"http://relaxng.org/ns/structure/1.0\"?>",
+    )
+    .unwrap();
+
+    start(&mut writer, "mei", &[("xmlns", MEI_NS), ("meiversion", "5.0")]);
+    write_head(&mut writer, &score.metadata);
+    for name in ["music", "body", "mdiv", "score"] {
+        start(&mut writer, name, &[]);
+    }
+
+    // Score definition
+    let metadata = &score.metadata;
+    let mut key_signature = metadata.key_signature.clone();
+    start(&mut writer, "scoreDef", &[("xml:id", "scoredef")]);
+    start(&mut writer, "staffGrp", &[]);
+    let mut staff_def = vec![("n", "1".to_string()), ("lines", "5".to_string())];
+    if let Some(trans) = transposition {
+        // Written to sounding: a Bb instrument sounds a major 2nd below the written note
+        staff_def.push(("trans.diat", (-trans.diatonic).to_string()));
+        staff_def.push(("trans.semi", (-trans.chromatic).to_string()));
+    }
+    start_owned(&mut writer, "staffDef", &staff_def);
+    let (shape, line) = match clef {
+        Clef::Treble => ("G", "2"),
+        Clef::Bass => ("F", "4"),
+    };
+    empty(&mut writer, "clef", &[("shape", shape), ("line", line)]);
+    write_key_sig(&mut writer, &written_key_signature(&key_signature, transposition));
+    let time = &metadata.time_signature;
+    empty(&mut writer, "meterSig", &[("count", &time.beats.to_string()), ("unit", &time.beat_type.to_string())]);
+    end(&mut writer, "staffDef");
+    end(&mut writer, "staffGrp");
+    end(&mut writer, "scoreDef");
+
+    start(&mut writer, "section", &[]);
+    let spans = ControlSpans::new(score);
+    let octave_shifts = score.octave_shifts(octave_shift, instrument_group);
+    let mut accidentals = MeasureAccidentals::new(metadata.accidentals);
+    let last = score.measures.len().saturating_sub(1);
+    for (i, measure) in score.measures.iter().enumerate() {
+        let previous_ending = i.checked_sub(1).and_then(|p| score.measures[p].ending);
+        if previous_ending.is_some() && previous_ending != measure.ending {
+            end(&mut writer, "ending");
+        }
+        if let Some(new_key) = measure.key_change.as_ref().filter(|key| **key != key_signature) {
+            key_signature = new_key.clone();
+            start_owned(&mut writer, "scoreDef", &[("xml:id", format!("m{}sd", i + 1))]);
+            write_key_sig(&mut writer, &written_key_signature(&key_signature, transposition));
+            end(&mut writer, "scoreDef");
+        }
+        if let Some(ending) = measure.ending.filter(|ending| previous_ending != Some(*ending)) {
+            let n = match ending {
+                Ending::First => "1",
+                Ending::Second => "2",
+            };
+            start_owned(
+                &mut writer,
+                "ending",
+                &[("xml:id", format!("m{}v{}", i + 1, n)), ("n", n.to_string()), ("label", format!("{}.", n))],
+            );
+        }
+
+        let mut attributes = vec![("xml:id", format!("m{}", i + 1)), ("n", (i + 1).to_string())];
+        if measure.is_pickup {
+            attributes.push(("metcon", "false".to_string()));
+        }
+        if measure.repeat_start {
+            attributes.push(("left", "rptstart".to_string()));
+        }
+        if measure.repeat_end {
+            attributes.push(("right", "rptend".to_string()));
+        } else if i == last {
+            attributes.push(("right", "end".to_string()));
+        }
+        start_owned(&mut writer, "measure", &attributes);
+
+        let mut bar = MeiBar {
+            index: i,
+            measure: WrittenMeasure::start(&key_signature, transposition, octave_shifts[i], &mut accidentals),
+            accidentals: &mut accidentals,
+        };
+        bar.write(&mut writer, &measure.elements);
+
+        if i == 0 {
+            if let Some(tempo) = &metadata.tempo {
+                write_tempo(&mut writer, tempo);
+            }
+        }
+        spans.write(&mut writer, score, i, transposition);
+        end(&mut writer, "measure");
+    }
+    if score.measures.last().is_some_and(|m| m.ending.is_some()) {
+        end(&mut writer, "ending");
+    }
+    end(&mut writer, "section");
+
+    for name in ["score", "mdiv", "body", "music", "mei"] {
+        end(&mut writer, name);
+    }
+    String::from_utf8(writer.into_inner().into_inner()).unwrap()
+}
+
+/// `<meiHead>`: title, composer and the encoding application
+fn write_head<W: std::io::Write>(writer: &mut Writer<W>, metadata: &Metadata) {
+    start(writer, "meiHead", &[]);
+    start(writer, "fileDesc", &[]);
+    start(writer, "titleStmt", &[]);
+    text_element(writer, "title", &[], metadata.title.as_deref().unwrap_or("Untitled"));
+    if let Some(composer) = &metadata.composer {
+        text_element(writer, "composer", &[], composer);
+    }
+    end(writer, "titleStmt");
+    empty(writer, "pubStmt", &[]);
+    end(writer, "fileDesc");
+    start(writer, "encodingDesc", &[]);
+    start(writer, "appInfo", &[]);
+    start(writer, "application", &[("xml:id", "gen")]);
+    text_element(writer, "name", &[], "Gen");
+    end(writer, "application");
+    end(writer, "appInfo");
+    end(writer, "encodingDesc");
+    end(writer, "meiHead");
+}
+
+/// `<keySig>`: `sig` and `mode`, or `<keyAccid>`s for a custom signature
+fn write_key_sig<W: std::io::Write>(writer: &mut Writer<W>, key_signature: &KeySignature) {
+    if key_signature.is_custom() {
+        start(writer, "keySig", &[]);
+        for &(step, alter) in &key_signature.alterations {
+            empty(writer, "keyAccid", &[("pname", &pname(step)), ("accid", accid_value(alter))]);
+        }
+        end(writer, "keySig");
+        return;
+    }
+    let sig = match key_signature.fifths {
+        0 => "0".to_string(),
+        fifths if fifths > 0 => format!("{}s", fifths),
+        fifths => format!("{}f", -fifths),
+    };
+    empty(writer, "keySig", &[("sig", &sig), ("mode", key_signature.mode.musicxml_name())]);
+}
+
+/// `<tempo>` at the start of the first measure
+fn write_tempo<W: std::io::Write>(writer: &mut Writer<W>, tempo: &Tempo) {
+    let mut attributes = vec![
+        ("xml:id", "m1tempo".to_string()),
+        ("tstamp", "1".to_string()),
+        ("staff", "1".to_string()),
+        ("mm", tempo.bpm.to_string()),
+        ("mm.unit", dur_value(tempo.duration).to_string()),
+    ];
+    if tempo.dotted {
+        attributes.push(("mm.dots", "1".to_string()));
+    }
+    attributes.push(("midi.bpm", tempo.to_quarter_note_bpm().to_string()));
+    empty_owned(writer, "tempo", &attributes);
+}
+
+/// Writes the layer of one measure
+struct MeiBar<'a> {
+    index: usize,
+    measure: WrittenMeasure<'a>,
+    accidentals: &'a mut MeasureAccidentals,
+}
+
+impl MeiBar<'_> {
+    fn write<W: std::io::Write>(&mut self, writer: &mut Writer<W>, elements: &[Element]) {
+        start(writer, "staff", &[("n", "1")]);
+        start(writer, "layer", &[("n", "1")]);
+        let mut tuplet_count = 0;
+        for (k, element) in elements.iter().enumerate() {
+            for tuplet in element.tuplets() {
+                if tuplet.is_start {
+                    tuplet_count += 1;
+                    start_owned(
+                        writer,
+                        "tuplet",
+                        &[
+                            ("xml:id", format!("m{}tp{}", self.index + 1, tuplet_count)),
+                            ("num", tuplet.actual_notes.to_string()),
+                            ("numbase", tuplet.normal_notes.to_string()),
+                        ],
+                    );
+                }
+            }
+            let id = element_id(self.index, k);
+            match element {
+                Element::Note(note) => self.write_note(writer, note, id),
+                Element::Rest { duration, dotted, .. } => {
+                    let mut attributes = vec![("xml:id", id), ("dur", dur_value(*duration).to_string())];
+                    if *dotted {
+                        attributes.push(("dots", "1".to_string()));
+                    }
+                    empty_owned(writer, "rest", &attributes);
+                }
+            }
+            for tuplet in element.tuplets() {
+                if tuplet.is_stop {
+                    end(writer, "tuplet");
+                }
+            }
+        }
+        end(writer, "layer");
+        end(writer, "staff");
+    }
+
+    /// `<note>` at its written pitch; a courtesy accidental is an `<accid func="caution">`
+    fn write_note<W: std::io::Write>(&mut self, writer: &mut Writer<W>, note: &Note, id: String) {
+        let (step, alter, octave) = self.measure.pitch(note, self.accidentals);
+        let explicit = note.accidental != Accidental::Natural;
+        let display = self
+            .accidentals
+            .note(step, octave, alter, explicit)
+            .filter(|_| explicit || !note.tie_stop);
+
+        let mut attributes = vec![("xml:id", id), ("dur", dur_value(note.duration).to_string())];
+        if note.dotted {
+            attributes.push(("dots", "1".to_string()));
+        }
+        attributes.push(("pname", pname(step)));
+        attributes.push(("oct", octave.to_string()));
+        match display {
+            Some(AccidentalDisplay { alter: shown, cautionary: false }) => {
+                attributes.push(("accid", accid_value(shown).to_string()));
+            }
+            _ if alter != 0 => attributes.push(("accid.ges", accid_value(alter).to_string())),
+            _ => {}
+        }
+        match display {
+            Some(AccidentalDisplay { alter: shown, cautionary: true }) => {
+                start_owned(writer, "note", &attributes);
+                empty(writer, "accid", &[("accid", accid_value(shown)), ("func", "caution")]);
+                end(writer, "note");
+            }
+            _ => empty_owned(writer, "note", &attributes),
+        }
+    }
+}
+
+/// Control events that start in each measure: ties, slurs, beams and chord symbols
+struct ControlSpans {
+    /// (measure index, start id, end id) per tie
+    ties: Vec<(usize, String, String)>,
+    /// (measure index, start id, end id) per slur
+    slurs: Vec<(usize, String, String)>,
+}
+
+impl ControlSpans {
+    /// Pair up tie and slur starts with the notes that end them, across barlines
+    fn new(score: &Score) -> Self {
+        let mut ties = Vec::new();
+        let mut slurs = Vec::new();
+        let mut open_tie: Option<(usize, String)> = None;
+        let mut open_slurs: Vec<(usize, String)> = Vec::new();
+        for (i, measure) in score.measures.iter().enumerate() {
+            for (k, element) in measure.elements.iter().enumerate() {
+                let Element::Note(note) = element else {
+                    continue;
+                };
+                let id = element_id(i, k);
+                if note.tie_stop {
+                    if let Some((start_measure, start_id)) = open_tie.take() {
+                        ties.push((start_measure, start_id, id.clone()));
+                    }
+                }
+                if note.tie_start {
+                    open_tie = Some((i, id.clone()));
+                }
+                if note.slur_start {
+                    open_slurs.push((i, id.clone()));
+                }
+                if note.slur_stop {
+                    if let Some((start_measure, start_id)) = open_slurs.pop() {
+                        slurs.push((start_measure, start_id, id));
+                    }
+                }
+            }
+        }
+        Self { ties, slurs }
+    }
+
+    fn write<W: std::io::Write>(&self, writer: &mut Writer<W>, score: &Score, index: usize, transposition: Option<&Transposition>) {
+        let number = index + 1;
+        for (name, prefix, spans) in [("tie", "t", &self.ties), ("slur", "s", &self.slurs)] {
+            let starting = spans.iter().filter(|(measure, _, _)| *measure == index);
+            for (count, (_, start_id, end_id)) in starting.enumerate() {
+                empty_owned(
+                    writer,
+                    name,
+                    &[
+                        ("xml:id", format!("m{}{}{}", number, prefix, count + 1)),
+                        ("staff", "1".to_string()),
+                        ("startid", format!("#{}", start_id)),
+                        ("endid", format!("#{}", end_id)),
+                    ],
+                );
+            }
+        }
+
+        // Beams reuse the MusicXML grouping; as spans they can cross tuplet boundaries
+        let elements = &score.measures[index].elements;
+        let mut group: Vec<String> = Vec::new();
+        let mut beam_count = 0;
+        for (k, state) in calculate_beam_states(elements, &score.metadata.time_signature).into_iter().enumerate() {
+            match state {
+                BeamState::None => continue,
+                BeamState::Begin => group.clear(),
+                BeamState::Continue | BeamState::End => {}
+            }
+            group.push(format!("#{}", element_id(index, k)));
+            if state == BeamState::End {
+                beam_count += 1;
+                empty_owned(
+                    writer,
+                    "beamSpan",
+                    &[
+                        ("xml:id", format!("m{}b{}", number, beam_count)),
+                        ("staff", "1".to_string()),
+                        ("startid", group[0].clone()),
+                        ("endid", group[group.len() - 1].clone()),
+                        ("plist", group.join(" ")),
+                    ],
+                );
+            }
+        }
+
+        let chords = elements.iter().enumerate().filter_map(|(k, e)| e.chord().map(|chord| (k, chord)));
+        for (count, (k, chord)) in chords.enumerate() {
+            let symbol = match transposition {
+                Some(trans) => transpose_chord_root(&chord.symbol, trans),
+                None => chord.symbol.clone(),
+            };
+            text_element(
+                writer,
+                "harm",
+                &[
+                    ("xml:id", &format!("m{}h{}", number, count + 1)),
+                    ("staff", "1"),
+                    ("startid", &format!("#{}", element_id(index, k))),
+                ],
+                &symbol,
+            );
+        }
+    }
+}
+
+/// MEI `pname` (lowercase step)
+fn pname(step: NoteName) -> String {
+    step.letter().to_ascii_lowercase().to_string()
+}
+
+/// MEI `accid` value for an alteration
+fn accid_value(alter: i8) -> &'static str {
+    match alter {
+        2.. => "x",
+        1 => "s",
+        -1 => "f",
+        ..=-2 => "ff",
+        _ => "n",
+    }
+}
+
+/// MEI `dur` value
+fn dur_value(duration: Duration) -> &'static str {
+    match duration {
+        Duration::Whole => "1",
+        Duration::Half => "2",
+        Duration::Quarter => "4",
+        Duration::Eighth => "8",
+        Duration::Sixteenth => "16",
+        Duration::ThirtySecond => "32",
+    }
+}
+
+fn element_start<'a>(name: &'a str, attributes: &[(&str, &str)]) -> BytesStart<'a> {
+    let mut element = BytesStart::new(name);
+    for &attribute in attributes {
+        element.push_attribute(attribute);
+    }
+    element
+}
+
+fn start<W: std::io::Write>(writer: &mut Writer<W>, name: &str, attributes: &[(&str, &str)]) {
+    writer.write_event(Event::Start(element_start(name, attributes))).unwrap();
+}
+
+fn start_owned<W: std::io::Write>(writer: &mut Writer<W>, name: &str, attributes: &[(&str, String)]) {
+    let attributes: Vec<(&str, &str)> = attributes.iter().map(|(key, value)| (*key, value.as_str())).collect();
+    start(writer, name, &attributes);
+}
+
+fn empty<W: std::io::Write>(writer: &mut Writer<W>, name: &str, attributes: &[(&str, &str)]) {
+    writer.write_event(Event::Empty(element_start(name, attributes))).unwrap();
+}
+
+fn empty_owned<W: std::io::Write>(writer: &mut Writer<W>, name: &str, attributes: &[(&str, String)]) {
+    let attributes: Vec<(&str, &str)> = attributes.iter().map(|(key, value)| (*key, value.as_str())).collect();
+    empty(writer, name, &attributes);
+}
+
+fn end<W: std::io::Write>(writer: &mut Writer<W>, name: &str) {
+    writer.write_event(Event::End(BytesEnd::new(name))).unwrap();
+}
+
+fn text_element<W: std::io::Write>(writer: &mut Writer<W>, name: &str, attributes: &[(&str, &str)], text: &str) {
+    start(writer, name, attributes);
+    writer.write_event(Event::Text(BytesText::new(text))).unwrap();
+    end(writer, name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_head_and_score_def() {
+        let source = "---\ntitle: Jig & Reel\ncomposer: Trad\ntime-signature: 6/8\nkey-signature: E minor\ntempo: \"116*\"\n---\nE/ F/ G/ A/ B/ ^C/\n";
+        let mei = to_mei(&parse(source).unwrap());
+        assert!(mei.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?><?xml-model"), "{}", mei);
+        assert!(mei.contains(r#"<mei xmlns="http://www.music-encoding.org/ns/mei" meiversion="5.0">"#));
+        assert!(mei.contains("<titleStmt><title>Jig &amp; Reel</title><composer>Trad</composer></titleStmt>"), "{}", mei);
+        assert!(mei.contains(r#"<staffDef n="1" lines="5"><clef shape="G" line="2"/><keySig sig="1s" mode="minor"/><meterSig count="6" unit="8"/></staffDef>"#), "{}", mei);
+        assert!(mei.contains(r#"<tempo xml:id="m1tempo" tstamp="1" staff="1" mm="116" mm.unit="4" mm.dots="1" midi.bpm="174"/>"#), "{}", mei);
+        assert!(mei.contains(r##"<beamSpan xml:id="m1b1" staff="1" startid="#m1e1" endid="#m1e3" plist="#m1e1 #m1e2 #m1e3"/>"##), "{}", mei);
+        assert!(mei.contains(r#"<measure xml:id="m1" n="1" right="end">"#));
+    }
+
+    #[test]
+    fn test_notes_rests_tuplets_ties_and_slurs() {
+        let source = "---\nkey-signature: F\n---\n(_Bp* E#/) B%/ $/\n[C D E]3/ F- F/ $p\n";
+        let mei = to_mei(&parse(source).unwrap());
+        assert!(mei.contains(r#"<note xml:id="m1e1" dur="2" dots="1" pname="b" oct="3" accid.ges="f"/>"#), "{}", mei);
+        assert!(mei.contains(r#"<note xml:id="m1e2" dur="8" pname="e" oct="4" accid="s"/>"#), "{}", mei);
+        assert!(mei.contains(r#"<note xml:id="m1e3" dur="8" pname="b" oct="4" accid="n"/><rest xml:id="m1e4" dur="8"/>"#), "{}", mei);
+        assert!(mei.contains(r#"<tuplet xml:id="m2tp1" num="3" numbase="2"><note xml:id="m2e1" dur="8" pname="c" oct="4"/>"#), "{}", mei);
+        assert!(mei.contains(r#"<note xml:id="m2e3" dur="8" pname="e" oct="4"><accid accid="n" func="caution"/></note></tuplet>"#), "{}", mei);
+        assert!(mei.contains(r##"<slur xml:id="m1s1" staff="1" startid="#m1e1" endid="#m1e2"/>"##), "{}", mei);
+        assert!(mei.contains(r##"<tie xml:id="m2t1" staff="1" startid="#m2e4" endid="#m2e5"/>"##), "{}", mei);
+    }
+
+    #[test]
+    fn test_repeats_endings_and_key_changes() {
+        let source = "@pickup G\n||: C D E F\n1. G A B ^C :||\n2. @key:G ^Co";
+        let mei = to_mei(&parse(source).unwrap());
+        assert!(mei.contains(r#"<measure xml:id="m1" n="1" metcon="false">"#), "{}", mei);
+        assert!(mei.contains(r#"<measure xml:id="m2" n="2" left="rptstart">"#));
+        assert!(mei.contains(r#"<ending xml:id="m3v1" n="1" label="1."><measure xml:id="m3" n="3" right="rptend">"#), "{}", mei);
+        assert!(mei.contains(r#"</measure></ending><scoreDef xml:id="m4sd"><keySig sig="1s" mode="major"/></scoreDef><ending xml:id="m4v2" n="2" label="2."><measure xml:id="m4" n="4" right="end">"#), "{}", mei);
+        assert!(mei.ends_with("</measure></ending></section></score></mdiv></body></music></mei>"), "{}", mei);
+    }
+
+    #[test]
+    fn test_transposition_and_harmony() {
+        let source = "{Cmaj7}:C D {G7/B}p E F";
+        let mei = to_mei_with_options(&parse(source).unwrap(), Transposition::for_key("Bb"), Clef::Bass, -2, None);
+        assert!(mei.contains(r#"<staffDef n="1" lines="5" trans.diat="-1" trans.semi="-2"><clef shape="F" line="4"/><keySig sig="2s" mode="major"/>"#), "{}", mei);
+        assert!(mei.contains(r#"<note xml:id="m1e1" dur="4" pname="d" oct="2"/>"#), "{}", mei);
+        assert!(mei.contains(r#"<note xml:id="m1e3" dur="4" pname="f" oct="2" accid.ges="s"/>"#), "{}", mei);
+        assert!(mei.contains(r##"<harm xml:id="m1h1" staff="1" startid="#m1e1">Dmaj7</harm><harm xml:id="m1h2" staff="1" startid="#m1e3">A7/C#</harm>"##), "{}", mei);
+    }
+
+    #[test]
+    fn test_ids_are_unique() {
+        let source = "||: {C}:(C/ D/ E/ F/) [G A B]3 ^C-\n1. ^C D {F}:E F :||\n2. [C D E]3/ Cp*";
+        let mei = to_mei(&parse(source).unwrap());
+        let ids: Vec<&str> = mei.split("xml:id=\"").skip(1).map(|rest| &rest[..rest.find('"').unwrap()]).collect();
+        let unique: HashSet<&str> = ids.iter().copied().collect();
+        assert_eq!(ids.len(), unique.len(), "{:?}", ids);
+        assert!(ids.len() > 25);
+    }
+}
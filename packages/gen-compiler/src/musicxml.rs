@@ -14,7 +14,8 @@
 //! - Dotted rhythms
 //! - Tuplets (triplets, quintuplets, sextuplets, etc.)
 //! - Ties and slurs
-//! - Accidentals (sharp, flat, natural)
+//! - Accidentals (sharp, flat, double sharp/flat, natural), with cancelling and courtesy
+//!   accidentals tracked per measure
 //! - Octave modifiers
 //!
 //! ### Score Structure
@@ -29,7 +30,11 @@
 //! - **Clef Support**: Treble and bass clefs with automatic octave adjustment
 //! - **Mod Points**: Instrument-specific octave shifts per line
 //! - **Chord Symbols**: Lead sheet chord notation
+//! - **Chord Numerals**: Roman numerals or Nashville numbers instead of, or above, the chord
+//!   symbols (MusicXML 4.0 `<numeral>`)
 //! - **Automatic Beaming**: Intelligent beam grouping based on time signature
+//! - **Slash Notation**: Comping charts with the melody replaced by beat slashes or rhythm
+//!   slashes, keeping chord symbols, repeats, endings and key/time signatures
 //!
 //! ## Entry Points
 //!
@@ -59,6 +64,25 @@
 //! let musicxml = to_musicxml_with_mod_points(&score, None, Clef::Treble, 0, Some(InstrumentGroup::Eb));
 //! ```
 //!
+//! ### With Nashville Numbers
+//! ```rust
+//! use gen::{parse, to_musicxml_with_numerals, Clef, HarmonyNumerals, NumeralSystem};
+//!
+//! let score = parse("{C}:C D {G7}:E F").unwrap();
+//! let numerals = HarmonyNumerals { system: NumeralSystem::Nashville, above_symbols: false };
+//! let musicxml = to_musicxml_with_numerals(&score, None, Clef::Treble, 0, None, numerals);
+//! assert!(musicxml.contains(r#"<numeral-root text="5⁷">5</numeral-root>"#));
+//! ```
+//!
+//! ### As a Comping Chart
+//! ```rust
+//! use gen::{parse, to_musicxml_chart, Clef, SlashStyle};
+//!
+//! let score = parse("{Cmaj7}:C D E F\n{Dm7}:D/ E/ F {G7}:G A").unwrap();
+//! let musicxml = to_musicxml_chart(&score, None, Clef::Treble, SlashStyle::Beat, None);
+//! assert_eq!(musicxml.matches("<notehead>slash</notehead>").count(), 8);
+//! ```
+//!
 //! ## MusicXML Compatibility
 //! Generates MusicXML 3.1 compatible output tested with:
 //! - MuseScore 3/4
@@ -84,6 +108,9 @@
 //! - `semantic` - Validates AST before generation
 
 use crate::ast::*;
+use crate::accidentals::MeasureAccidentals;
+use crate::analysis::{analyze_harmony, ChordAnalysis, ChordNumerals};
+use crate::rational::{lcm, Rational};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 use std::io::Cursor;
@@ -121,19 +148,83 @@ impl Transposition {
     }
 }
 
+/// Numbering system for chord numerals
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumeralSystem {
+    Roman,     // ii7 V7 I
+    Nashville, // 2m⁷ 5⁷ 1
+}
+
+impl NumeralSystem {
+    /// Parse from string (case-insensitive): "roman" or "nashville"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "roman" => Some(NumeralSystem::Roman),
+            "nashville" => Some(NumeralSystem::Nashville),
+            _ => None,
+        }
+    }
+}
+
+/// Chord numerals to write in `<harmony>` (see [`crate::analysis::analyze_harmony()`])
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HarmonyNumerals {
+    pub system: NumeralSystem,
+    pub above_symbols: bool, // true = numerals above the chord symbols, false = instead of them
+}
+
+/// Slash notation for comping charts, written in place of the melody
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SlashStyle {
+    Beat,   // One stemless slash per beat
+    Rhythm, // Stemmed slashes in the melody's rhythm (rests stay rests)
+}
+
+impl SlashStyle {
+    /// Parse from string (case-insensitive): "beat" (or "slash") or "rhythm"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "beat" | "slash" => Some(SlashStyle::Beat),
+            "rhythm" => Some(SlashStyle::Rhythm),
+            _ => None,
+        }
+    }
+}
+
+/// How a score is rendered to MusicXML: pitch, clef and chord-symbol display
+#[derive(Clone, Copy, Default)]
+struct RenderOptions {
+    transposition: Option<Transposition>,
+    clef: Clef,
+    octave_shift: i8,
+    instrument_group: Option<InstrumentGroup>,
+    numerals: Option<HarmonyNumerals>,
+    slashes: Option<SlashStyle>,
+}
+
+/// How one measure is written: the render options resolved for the measure, with the score's divisions
+#[derive(Clone, Copy)]
+struct MeasureOptions {
+    transposition: Option<Transposition>,
+    clef: Clef,
+    octave_shift: i8, // Base shift plus the measure's mod point
+    slashes: Option<SlashStyle>,
+    divisions: u32,
+}
+
 /// Convert a Score to MusicXML format
 pub fn to_musicxml(score: &Score) -> String {
-    to_musicxml_full(score, None, Clef::Treble, 0, None)
+    to_musicxml_full(score, RenderOptions::default())
 }
 
 /// Convert a Score to MusicXML format with optional transposition
 pub fn to_musicxml_transposed(score: &Score, transposition: Option<Transposition>) -> String {
-    to_musicxml_full(score, transposition, Clef::Treble, 0, None)
+    to_musicxml_full(score, RenderOptions { transposition, ..RenderOptions::default() })
 }
 
 /// Convert a Score to MusicXML format with clef and octave shift options
 pub fn to_musicxml_with_options(score: &Score, transposition: Option<Transposition>, clef: Clef, octave_shift: i8) -> String {
-    to_musicxml_full(score, transposition, clef, octave_shift, None)
+    to_musicxml_full(score, RenderOptions { transposition, clef, octave_shift, ..RenderOptions::default() })
 }
 
 /// Convert a Score to MusicXML format with mod points support
@@ -145,17 +236,39 @@ pub fn to_musicxml_with_mod_points(
     octave_shift: i8,
     instrument_group: Option<InstrumentGroup>,
 ) -> String {
-    to_musicxml_full(score, transposition, clef, octave_shift, instrument_group)
+    to_musicxml_full(score, RenderOptions { transposition, clef, octave_shift, instrument_group, ..RenderOptions::default() })
 }
 
-/// Convert a Score to MusicXML format with all options
-fn to_musicxml_full(
+/// Convert a Score to MusicXML format with chord symbols numbered relative to the key
+/// Numerals follow `@key:` changes and are unaffected by transposition
+pub fn to_musicxml_with_numerals(
     score: &Score,
     transposition: Option<Transposition>,
     clef: Clef,
     octave_shift: i8,
     instrument_group: Option<InstrumentGroup>,
+    numerals: HarmonyNumerals,
+) -> String {
+    to_musicxml_full(
+        score,
+        RenderOptions { transposition, clef, octave_shift, instrument_group, numerals: Some(numerals), slashes: None },
+    )
+}
+
+/// Convert a Score to a comping chart: chord symbols over slashes instead of the melody
+/// Repeats, endings, key and time signatures are kept; numerals optionally label the chords
+pub fn to_musicxml_chart(
+    score: &Score,
+    transposition: Option<Transposition>,
+    clef: Clef,
+    style: SlashStyle,
+    numerals: Option<HarmonyNumerals>,
 ) -> String {
+    to_musicxml_full(score, RenderOptions { transposition, clef, numerals, slashes: Some(style), ..RenderOptions::default() })
+}
+
+/// Convert a Score to MusicXML format with all options
+fn to_musicxml_full(score: &Score, options: RenderOptions) -> String {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
 
     // XML declaration
@@ -231,36 +344,32 @@ fn to_musicxml_full(
 
     // Track current key signature as it changes through the score
     let mut current_key_signature = score.metadata.key_signature.clone();
+    let mut accidentals = MeasureAccidentals::new(score.metadata.accidentals);
+    let mut numeral_labels = NumeralLabels::new(score, options.numerals);
+    let divisions = match options.slashes {
+        // Beat slashes need a division for the beat itself (e.g. a dotted quarter in 6/8)
+        Some(SlashStyle::Beat) => {
+            let beat = beat_length(&score.metadata.time_signature) * Rational::from_integer(4);
+            lcm(score_divisions(score) as i64, beat.denom()) as u32
+        }
+        _ => score_divisions(score),
+    };
 
+    // Apply the instrument group's mod point on each measure's source line
+    let octave_shifts = score.octave_shifts(options.octave_shift, options.instrument_group);
+    let measure_options = MeasureOptions {
+        transposition: options.transposition,
+        clef: options.clef,
+        octave_shift: options.octave_shift,
+        slashes: options.slashes,
+        divisions,
+    };
     for (i, measure) in score.measures.iter().enumerate() {
         // Update key signature if this measure has a key change
         if let Some(ref new_key) = measure.key_change {
             current_key_signature = new_key.clone();
         }
 
-        // Calculate effective octave shift for this measure
-        // If we have an instrument group, check if there's a mod point for this measure's source line
-        let effective_octave_shift = if let Some(group) = instrument_group {
-            // Find the source line for this measure
-            let source_line = score.line_to_measure
-                .iter()
-                .find(|(_, &measure_idx)| measure_idx == i)
-                .map(|(&line, _)| line);
-
-            if let Some(line) = source_line {
-                // Check for mod point on this line
-                if let Some(mod_shift) = score.mod_points.get_shift(line, group) {
-                    octave_shift + mod_shift
-                } else {
-                    octave_shift
-                }
-            } else {
-                octave_shift
-            }
-        } else {
-            octave_shift
-        };
-
         // Determine if this is the first measure with the current ending
         // (we need to open the ending bracket if the previous measure had a different ending or no ending)
         let is_ending_start = if measure.ending.is_some() {
@@ -296,12 +405,12 @@ fn to_musicxml_full(
             &score.metadata.time_signature,
             &current_key_signature,
             i == 0,
-            transposition,
-            clef,
-            effective_octave_shift,
             is_ending_start,
             is_ending_stop,
             score.metadata.tempo.as_ref(),
+            MeasureOptions { octave_shift: octave_shifts[i], ..measure_options },
+            &mut accidentals,
+            &mut numeral_labels,
         );
     }
 
@@ -316,6 +425,26 @@ fn to_musicxml_full(
     String::from_utf8(result).unwrap()
 }
 
+/// Numerals for the score's chord symbols, handed out in the order the harmonies are written
+struct NumeralLabels {
+    display: Option<HarmonyNumerals>,
+    analyses: std::vec::IntoIter<ChordAnalysis>,
+}
+
+impl NumeralLabels {
+    fn new(score: &Score, display: Option<HarmonyNumerals>) -> Self {
+        let analyses = if display.is_some() { analyze_harmony(score) } else { Vec::new() };
+        Self { display, analyses: analyses.into_iter() }
+    }
+
+    /// Display settings and numerals for the next chord symbol (None to write the symbol only)
+    fn next(&mut self) -> Option<(HarmonyNumerals, ChordNumerals)> {
+        let display = self.display?;
+        let numerals = self.analyses.next()?.numerals?;
+        Some((display, numerals))
+    }
+}
+
 /// Helper to write a simple text element
 fn write_text_element<W: std::io::Write>(writer: &mut Writer<W>, name: &str, text: &str) {
     writer
@@ -331,7 +460,7 @@ fn write_text_element<W: std::io::Write>(writer: &mut Writer<W>, name: &str, tex
 
 /// Beam state for a note
 #[derive(Clone, Copy, PartialEq)]
-enum BeamState {
+pub(crate) enum BeamState {
     None,
     Begin,
     Continue,
@@ -346,49 +475,34 @@ fn is_beamable(duration: Duration) -> bool {
     )
 }
 
-/// Get the duration of an element in divisions (12 per quarter note for internal beam calculations)
-/// Using 12 divisions allows clean representation of triplets (divisible by 3)
-fn element_divisions_for_beaming(element: &Element) -> u32 {
-    match element {
-        Element::Note(note) => {
-            duration_to_divisions_high_res(note.duration, note.dotted, note.tuplet)
-        }
-        Element::Rest {
-            duration,
-            dotted,
-            tuplet,
-            ..
-        } => duration_to_divisions_high_res(*duration, *dotted, *tuplet),
+/// Beat size as an exact fraction of a whole note
+/// For 4/4: beat = 1/4 (quarter note)
+/// For 6/8, 9/8, 12/8: beat = 3/8 (dotted quarter note, 3 eighth notes)
+/// For 2/4: beat = 1/4 (quarter note)
+pub(crate) fn beat_length(time_signature: &TimeSignature) -> Rational {
+    if time_signature.beat_type == 8 && time_signature.beats % 3 == 0 {
+        // Compound meter (6/8, 9/8, 12/8): beam in groups of 3 eighth notes (dotted quarter)
+        Rational::new(3, 8)
+    } else {
+        // Simple meter: use beat_type to determine beat size
+        Rational::new(1, time_signature.beat_type as i64)
     }
 }
 
 /// Calculate beam states for all elements in a measure, respecting beat boundaries
-fn calculate_beam_states(elements: &[Element], time_signature: &TimeSignature) -> Vec<BeamState> {
+pub(crate) fn calculate_beam_states(elements: &[Element], time_signature: &TimeSignature) -> Vec<BeamState> {
     let mut states = vec![BeamState::None; elements.len()];
-
-    // Calculate beat size in high-res divisions (12 divisions = 1 quarter note)
-    // This allows clean representation of triplets (12 is divisible by 3)
-    // For 4/4: beat = 12 divisions (quarter note)
-    // For 6/8, 9/8, 12/8: beat = 18 divisions (dotted quarter note, 3 eighth notes)
-    // For 2/4: beat = 12 divisions (quarter note)
-    let beat_divisions = if time_signature.beat_type == 8 && time_signature.beats % 3 == 0 {
-        // Compound meter (6/8, 9/8, 12/8): beam in groups of 3 eighth notes (dotted quarter)
-        // 3 eighth notes = 18 divisions (each eighth = 6 divisions)
-        18
-    } else {
-        // Simple meter: use beat_type to determine beat size
-        48 / time_signature.beat_type as u32
-    };
+    let beat_length = beat_length(time_signature);
 
     // Track position in measure and group notes by beat
-    let mut position: u32 = 0;
+    let mut position = Rational::ZERO;
     let mut i = 0;
 
     while i < elements.len() {
         // Find which beat we're currently in
-        let current_beat = position / beat_divisions;
-        let beat_start_pos = current_beat * beat_divisions;
-        let beat_end_pos = beat_start_pos + beat_divisions;
+        let current_beat = (position / beat_length).floor();
+        let beat_start_pos = Rational::from_integer(current_beat) * beat_length;
+        let beat_end_pos = beat_start_pos + beat_length;
 
         // Check if current element is a beamable note
         let is_current_beamable = match &elements[i] {
@@ -404,7 +518,7 @@ fn calculate_beam_states(elements: &[Element], time_signature: &TimeSignature) -
             while i < elements.len() && group_position < beat_end_pos {
                 match &elements[i] {
                     Element::Note(note) if is_beamable(note.duration) => {
-                        let note_divs = element_divisions_for_beaming(&elements[i]);
+                        let note_divs = elements[i].total_fraction();
                         // Check if adding this note would cross the beat boundary
                         if group_position + note_divs > beat_end_pos && i > start {
                             // Don't include this note, it would cross the beat
@@ -430,7 +544,7 @@ fn calculate_beam_states(elements: &[Element], time_signature: &TimeSignature) -
             // Update position to where we ended up
             position = group_position;
         } else {
-            position += element_divisions_for_beaming(&elements[i]);
+            position += elements[i].total_fraction();
             i += 1;
         }
     }
@@ -488,6 +602,7 @@ fn write_tempo_direction<W: std::io::Write>(
         .unwrap();
 }
 
+#[allow(clippy::too_many_arguments)]
 fn write_measure<W: std::io::Write>(
     writer: &mut Writer<W>,
     measure: &Measure,
@@ -495,13 +610,15 @@ fn write_measure<W: std::io::Write>(
     time_signature: &TimeSignature,
     key_signature: &KeySignature,
     include_attributes: bool,
-    transposition: Option<Transposition>,
-    clef: Clef,
-    octave_shift: i8,
     is_ending_start: bool,
     is_ending_stop: bool,
     tempo: Option<&crate::ast::Tempo>,
+    options: MeasureOptions,
+    accidentals: &mut MeasureAccidentals,
+    numerals: &mut NumeralLabels,
 ) {
+    let MeasureOptions { transposition, clef, octave_shift, slashes, divisions } = options;
+
     let mut measure_elem = BytesStart::new("measure");
     measure_elem.push_attribute(("number", number.to_string().as_str()));
     writer.write_event(Event::Start(measure_elem)).unwrap();
@@ -512,6 +629,9 @@ fn write_measure<W: std::io::Write>(
         write_left_barline(writer, measure.repeat_start, if is_ending_start { measure.ending } else { None });
     }
 
+    // Key signature as written (transposed for transposing instruments)
+    let written_key = written_key_signature(key_signature, transposition.as_ref());
+
     // Write attributes for key changes in mid-score
     if measure.key_change.is_some() && !include_attributes {
         writer
@@ -519,32 +639,7 @@ fn write_measure<W: std::io::Write>(
             .unwrap();
 
         // Transpose key signature if transposition is specified
-        let transposed_fifths = if let Some(trans) = transposition {
-            let new_fifths = key_signature.fifths + trans.fifths;
-            // Wrap around: keep in range -7 to +7 (valid key signatures)
-            if new_fifths > 7 {
-                new_fifths - 12
-            } else if new_fifths < -7 {
-                new_fifths + 12
-            } else {
-                new_fifths
-            }
-        } else {
-            key_signature.fifths
-        };
-
-        writer
-            .write_event(Event::Start(BytesStart::new("key")))
-            .unwrap();
-        write_text_element(writer, "fifths", &transposed_fifths.to_string());
-        let mode_str = match key_signature.mode {
-            crate::ast::Mode::Major => "major",
-            crate::ast::Mode::Minor => "minor",
-        };
-        write_text_element(writer, "mode", mode_str);
-        writer
-            .write_event(Event::End(BytesEnd::new("key")))
-            .unwrap();
+        write_key(writer, &written_key);
 
         writer
             .write_event(Event::End(BytesEnd::new("attributes")))
@@ -557,35 +652,10 @@ fn write_measure<W: std::io::Write>(
             .write_event(Event::Start(BytesStart::new("attributes")))
             .unwrap();
 
-        write_text_element(writer, "divisions", "4");
+        write_text_element(writer, "divisions", &divisions.to_string());
 
         // Transpose key signature if transposition is specified
-        let transposed_fifths = if let Some(trans) = transposition {
-            let new_fifths = key_signature.fifths + trans.fifths;
-            // Wrap around: keep in range -7 to +7 (valid key signatures)
-            if new_fifths > 7 {
-                new_fifths - 12
-            } else if new_fifths < -7 {
-                new_fifths + 12
-            } else {
-                new_fifths
-            }
-        } else {
-            key_signature.fifths
-        };
-
-        writer
-            .write_event(Event::Start(BytesStart::new("key")))
-            .unwrap();
-        write_text_element(writer, "fifths", &transposed_fifths.to_string());
-        let mode_str = match key_signature.mode {
-            crate::ast::Mode::Major => "major",
-            crate::ast::Mode::Minor => "minor",
-        };
-        write_text_element(writer, "mode", mode_str);
-        writer
-            .write_event(Event::End(BytesEnd::new("key")))
-            .unwrap();
+        write_key(writer, &written_key);
 
         writer
             .write_event(Event::Start(BytesStart::new("time")))
@@ -635,11 +705,18 @@ fn write_measure<W: std::io::Write>(
         }
     }
 
-    // Calculate beam states for all elements
-    let beam_states = calculate_beam_states(&measure.elements, time_signature);
+    if let Some(style) = slashes {
+        write_slash_measure(writer, measure, time_signature, style, clef, transposition.as_ref(), divisions, numerals);
+    } else {
+        // Calculate beam states for all elements
+        let beam_states = calculate_beam_states(&measure.elements, time_signature);
+
+        // Accidentals are tracked against the written key
+        accidentals.start_measure(&written_key);
 
-    for (element, beam_state) in measure.elements.iter().zip(beam_states.iter()) {
-        write_element(writer, element, *beam_state, octave_shift, key_signature, transposition.as_ref());
+        for (element, beam_state) in measure.elements.iter().zip(beam_states.iter()) {
+            write_element(writer, element, *beam_state, octave_shift, key_signature, transposition.as_ref(), divisions, accidentals, numerals);
+        }
     }
 
     // Write right barline (repeat end and/or ending stop)
@@ -740,27 +817,122 @@ fn write_right_barline<W: std::io::Write>(
         .unwrap();
 }
 
-fn write_element<W: std::io::Write>(writer: &mut Writer<W>, element: &Element, beam_state: BeamState, octave_shift: i8, key_signature: &KeySignature, transposition: Option<&Transposition>) {
+/// Key signature as written for a transposing instrument
+pub(crate) fn written_key_signature(key_signature: &KeySignature, transposition: Option<&Transposition>) -> KeySignature {
+    let Some(trans) = transposition else {
+        return key_signature.clone();
+    };
+
+    if key_signature.is_custom() {
+        // Transpose every step of the scale, keeping the listed steps in their written order
+        let mut transposed: Vec<(NoteName, i8)> = Vec::new();
+        let listed = key_signature.alterations.iter().map(|&(step, _)| step);
+        let unlisted = NoteName::ALL.into_iter().filter(|step| !key_signature.alterations.iter().any(|&(s, _)| s == *step));
+        for step in listed.chain(unlisted) {
+            let alter = key_signature.accidental_for_note(step).alter();
+            let (new_step, new_alter, _) = transpose_pitch(step, alter, trans.diatonic, trans.chromatic);
+            if new_alter != 0 {
+                transposed.push((new_step, new_alter));
+            }
+        }
+        return KeySignature { alterations: transposed, ..key_signature.clone() };
+    }
+
+    let new_fifths = key_signature.fifths + trans.fifths;
+    // Wrap around: keep in range -7 to +7 (valid key signatures)
+    let fifths = if new_fifths > 7 {
+        new_fifths - 12
+    } else if new_fifths < -7 {
+        new_fifths + 12
+    } else {
+        new_fifths
+    };
+    KeySignature::from_fifths(fifths, key_signature.mode)
+}
+
+/// Written step, alteration and octave (0-9) of a note with the given sounding alteration
+pub(crate) fn written_pitch(note: &Note, sounding_alter: i8, transposition: Option<&Transposition>, octave_shift: i8) -> (NoteName, i8, i8) {
+    let (step, alter, octave_adjustment) = match transposition {
+        Some(trans) => transpose_pitch(note.name, sounding_alter, trans.diatonic, trans.chromatic),
+        None => (note.name, sounding_alter, 0),
+    };
+    (step, alter, (note.octave.number() + octave_shift + octave_adjustment).clamp(0, 9))
+}
+
+/// Sounding and written key and octave shift of one measure, for writers that spell written pitches
+pub(crate) struct WrittenMeasure<'a> {
+    pub key_signature: &'a KeySignature,
+    pub written_key: KeySignature,
+    pub transposition: Option<&'a Transposition>,
+    pub octave_shift: i8,
+}
+
+impl<'a> WrittenMeasure<'a> {
+    /// Measure in `key_signature` with its octave shift (see [`Score::octave_shifts`]); starts the bar's accidentals in the written key
+    pub(crate) fn start(
+        key_signature: &'a KeySignature,
+        transposition: Option<&'a Transposition>,
+        octave_shift: i8,
+        accidentals: &mut MeasureAccidentals,
+    ) -> Self {
+        let written_key = written_key_signature(key_signature, transposition);
+        accidentals.start_measure(&written_key);
+        WrittenMeasure {
+            key_signature,
+            written_key,
+            transposition,
+            octave_shift,
+        }
+    }
+
+    /// Written step, alteration and octave of a note, after the bar's earlier accidentals
+    pub(crate) fn pitch(&self, note: &Note, accidentals: &mut MeasureAccidentals) -> (NoteName, i8, i8) {
+        written_pitch(note, accidentals.sounding_alter(note, self.key_signature), self.transposition, self.octave_shift)
+    }
+}
+
+/// Write a <key> element: fifths and mode, or key-step/key-alter pairs for a custom signature
+fn write_key<W: std::io::Write>(writer: &mut Writer<W>, key_signature: &KeySignature) {
+    writer
+        .write_event(Event::Start(BytesStart::new("key")))
+        .unwrap();
+    if key_signature.is_custom() {
+        for &(step, alter) in &key_signature.alterations {
+            write_text_element(writer, "key-step", note_name_to_str(step));
+            write_text_element(writer, "key-alter", &alter.to_string());
+        }
+    } else {
+        write_text_element(writer, "fifths", &key_signature.fifths.to_string());
+        write_text_element(writer, "mode", key_signature.mode.musicxml_name());
+    }
+    writer
+        .write_event(Event::End(BytesEnd::new("key")))
+        .unwrap();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_element<W: std::io::Write>(writer: &mut Writer<W>, element: &Element, beam_state: BeamState, octave_shift: i8, key_signature: &KeySignature, transposition: Option<&Transposition>, divisions: u32, accidentals: &mut MeasureAccidentals, numerals: &mut NumeralLabels) {
     match element {
-        Element::Note(note) => write_note(writer, note, beam_state, octave_shift, key_signature, transposition),
+        Element::Note(note) => write_note(writer, note, beam_state, octave_shift, key_signature, transposition, divisions, accidentals, numerals),
         Element::Rest {
             duration,
             dotted,
-            tuplet,
+            tuplets,
             chord,
         } => {
             // Write harmony before rest if chord symbol exists
             if let Some(ref chord_ann) = chord {
-                write_harmony(writer, &chord_ann.symbol, transposition);
+                write_harmony(writer, &chord_ann.symbol, transposition, numerals, 0);
             }
-            write_rest(writer, *duration, *dotted, *tuplet);
+            write_rest(writer, *duration, *dotted, tuplets, divisions);
         }
     }
 }
 
 /// Transpose a note's pitch based on diatonic and chromatic intervals
-/// Returns (new_step, new_alter, octave_adjustment)
-fn transpose_pitch(note_name: NoteName, accidental: Accidental, diatonic: i8, chromatic: i8) -> (NoteName, i8, i8) {
+/// `alter` is the sounding alteration in semitones (-2 to +2)
+/// Returns (new_step, new_alter, octave_adjustment); the alter may be a double sharp/flat
+pub(crate) fn transpose_pitch(note_name: NoteName, alter: i8, diatonic: i8, chromatic: i8) -> (NoteName, i8, i8) {
     // Map note names to their position in the scale (C=0, D=1, E=2, F=3, G=4, A=5, B=6)
     let note_to_index = |n: NoteName| match n {
         NoteName::C => 0,
@@ -794,13 +966,7 @@ fn transpose_pitch(note_name: NoteName, accidental: Accidental, diatonic: i8, ch
         NoteName::B => 11,
     };
 
-    let alter_value = match accidental {
-        Accidental::Sharp => 1,
-        Accidental::Flat => -1,
-        Accidental::Natural | Accidental::ForceNatural => 0,
-    };
-
-    let current_semitone = note_to_semitone(note_name) + alter_value;
+    let current_semitone = note_to_semitone(note_name) + alter;
 
     // Apply transposition
     let new_note_index = note_to_index(note_name) + diatonic;
@@ -812,42 +978,35 @@ fn transpose_pitch(note_name: NoteName, accidental: Accidental, diatonic: i8, ch
     // Get the new note name
     let new_note = index_to_note(new_note_index);
 
-    // Calculate what alteration is needed
+    // Calculate what alteration is needed, taking the shortest way round the octave
+    // (e.g. B# is C plus one semitone on B, not eleven below)
     let expected_semitone = note_to_semitone(new_note);
-    let new_alter = new_semitone.rem_euclid(12) - expected_semitone;
+    let new_alter = match (new_semitone - expected_semitone).rem_euclid(12) {
+        offset if offset > 6 => offset - 12,
+        offset => offset,
+    };
 
     (new_note, new_alter, octave_adjustment)
 }
 
-fn write_note<W: std::io::Write>(writer: &mut Writer<W>, note: &Note, beam_state: BeamState, octave_shift: i8, key_signature: &KeySignature, transposition: Option<&Transposition>) {
+#[allow(clippy::too_many_arguments)]
+fn write_note<W: std::io::Write>(writer: &mut Writer<W>, note: &Note, beam_state: BeamState, octave_shift: i8, key_signature: &KeySignature, transposition: Option<&Transposition>, divisions: u32, accidentals: &mut MeasureAccidentals, numerals: &mut NumeralLabels) {
     // Write harmony BEFORE note element if chord symbol exists
     if let Some(ref chord_ann) = note.chord {
-        write_harmony(writer, &chord_ann.symbol, transposition);
+        write_harmony(writer, &chord_ann.symbol, transposition, numerals, 0);
     }
 
     writer
         .write_event(Event::Start(BytesStart::new("note")))
         .unwrap();
 
-    // Determine the effective accidental: if no explicit accidental, apply key signature
+    // Determine the effective alteration: if no explicit accidental, apply key signature
+    // (or an earlier accidental in the bar when accidentals carry through the measure)
     // ForceNatural (%) explicitly cancels key signature accidentals
-    let effective_accidental = match note.accidental {
-        Accidental::Natural => key_signature.accidental_for_note(note.name),
-        Accidental::ForceNatural => Accidental::Natural, // Force natural pitch (no alter)
-        other => other,
-    };
+    let effective_alter = accidentals.sounding_alter(note, key_signature);
 
-    // Apply transposition if specified
-    let (final_note_name, final_alter, transpose_octave_adj) = if let Some(trans) = transposition {
-        transpose_pitch(note.name, effective_accidental, trans.diatonic, trans.chromatic)
-    } else {
-        let alter_value = match effective_accidental {
-            Accidental::Sharp => 1,
-            Accidental::Flat => -1,
-            Accidental::Natural | Accidental::ForceNatural => 0,
-        };
-        (note.name, alter_value, 0)
-    };
+    // Apply transposition and octave shift (middle C = octave 4)
+    let (final_note_name, final_alter, octave) = written_pitch(note, effective_alter, transposition, octave_shift);
 
     // Pitch
     writer
@@ -860,23 +1019,19 @@ fn write_note<W: std::io::Write>(writer: &mut Writer<W>, note: &Note, beam_state
         write_text_element(writer, "alter", &final_alter.to_string());
     }
 
-    // Octave (middle C = octave 4, adjusted by octave_shift and transposition)
-    let base_octave: i8 = match note.octave {
-        Octave::DoubleLow => 2,
-        Octave::Low => 3,
-        Octave::Middle => 4,
-        Octave::High => 5,
-        Octave::DoubleHigh => 6,
-    };
-    let octave = (base_octave + octave_shift + transpose_octave_adj).max(0).min(9);
     write_text_element(writer, "octave", &octave.to_string());
+
+    let explicit_accidental = note.accidental != Accidental::Natural;
+    let accidental_display = accidentals
+        .note(final_note_name, octave, final_alter, explicit_accidental)
+        .filter(|_| explicit_accidental || !note.tie_stop);
     writer
         .write_event(Event::End(BytesEnd::new("pitch")))
         .unwrap();
 
-    // Duration (in divisions - 4 per quarter note)
-    let divisions = duration_to_divisions_with_tuplet(note.duration, note.dotted, note.tuplet);
-    write_text_element(writer, "duration", &divisions.to_string());
+    // Duration (in divisions per quarter note)
+    let note_divisions = duration_to_divisions(note.duration, note.dotted, &note.tuplets, divisions);
+    write_text_element(writer, "duration", &note_divisions.to_string());
 
     // Ties (for playback - must come before <type>)
     if note.tie_start {
@@ -901,16 +1056,7 @@ fn write_note<W: std::io::Write>(writer: &mut Writer<W>, note: &Note, beam_state
     }
 
     // Time modification for tuplets
-    if let Some(tuplet_info) = note.tuplet {
-        writer
-            .write_event(Event::Start(BytesStart::new("time-modification")))
-            .unwrap();
-        write_text_element(writer, "actual-notes", &tuplet_info.actual_notes.to_string());
-        write_text_element(writer, "normal-notes", &tuplet_info.normal_notes.to_string());
-        writer
-            .write_event(Event::End(BytesEnd::new("time-modification")))
-            .unwrap();
-    }
+    write_time_modification(writer, &note.tuplets);
 
     // Beam (for eighth notes and shorter)
     match beam_state {
@@ -921,10 +1067,7 @@ fn write_note<W: std::io::Write>(writer: &mut Writer<W>, note: &Note, beam_state
     }
 
     // Notations (tuplet markers, ties, slurs, and accidentals display)
-    let has_tuplet_notation = note
-        .tuplet
-        .map(|t| t.is_start || t.is_stop)
-        .unwrap_or(false);
+    let has_tuplet_notation = note.tuplets.iter().any(|t| t.is_start || t.is_stop);
     let has_tie_notation = note.tie_start || note.tie_stop;
     let has_slur_notation = note.slur_start || note.slur_stop;
     if has_tuplet_notation || has_tie_notation || has_slur_notation {
@@ -959,31 +1102,26 @@ fn write_note<W: std::io::Write>(writer: &mut Writer<W>, note: &Note, beam_state
         }
 
         // Tuplet notations
-        if let Some(tuplet_info) = note.tuplet {
-            if tuplet_info.is_start {
-                let mut tuplet = BytesStart::new("tuplet");
-                tuplet.push_attribute(("type", "start"));
-                tuplet.push_attribute(("bracket", "yes"));
-                writer.write_event(Event::Empty(tuplet)).unwrap();
-            }
-            if tuplet_info.is_stop {
-                let mut tuplet = BytesStart::new("tuplet");
-                tuplet.push_attribute(("type", "stop"));
-                writer.write_event(Event::Empty(tuplet)).unwrap();
-            }
-        }
+        write_tuplet_notations(writer, &note.tuplets);
 
         writer
             .write_event(Event::End(BytesEnd::new("notations")))
             .unwrap();
     }
 
-    // Accidental display
-    match note.accidental {
-        Accidental::Sharp => write_text_element(writer, "accidental", "sharp"),
-        Accidental::Flat => write_text_element(writer, "accidental", "flat"),
-        Accidental::ForceNatural => write_text_element(writer, "accidental", "natural"),
-        Accidental::Natural => {}
+    // Accidental display (a tie continuation keeps the accidental of the note it continues)
+    if let Some(display) = accidental_display {
+        let mut accidental = BytesStart::new("accidental");
+        if display.cautionary {
+            accidental.push_attribute(("cautionary", "yes"));
+        }
+        writer.write_event(Event::Start(accidental)).unwrap();
+        writer
+            .write_event(Event::Text(BytesText::new(display.musicxml_value())))
+            .unwrap();
+        writer
+            .write_event(Event::End(BytesEnd::new("accidental")))
+            .unwrap();
     }
 
     writer
@@ -1007,7 +1145,8 @@ fn write_rest<W: std::io::Write>(
     writer: &mut Writer<W>,
     duration: Duration,
     dotted: bool,
-    tuplet: Option<TupletInfo>,
+    tuplets: &[TupletInfo],
+    divisions: u32,
 ) {
     writer
         .write_event(Event::Start(BytesStart::new("note")))
@@ -1017,8 +1156,8 @@ fn write_rest<W: std::io::Write>(
         .write_event(Event::Empty(BytesStart::new("rest")))
         .unwrap();
 
-    let divisions = duration_to_divisions_with_tuplet(duration, dotted, tuplet);
-    write_text_element(writer, "duration", &divisions.to_string());
+    let rest_divisions = duration_to_divisions(duration, dotted, tuplets, divisions);
+    write_text_element(writer, "duration", &rest_divisions.to_string());
     write_text_element(writer, "type", duration.musicxml_type());
 
     if dotted {
@@ -1028,36 +1167,233 @@ fn write_rest<W: std::io::Write>(
     }
 
     // Time modification for tuplets
-    if let Some(tuplet_info) = tuplet {
+    write_time_modification(writer, tuplets);
+
+    // Notations (tuplet markers)
+    let has_tuplet_notation = tuplets.iter().any(|t| t.is_start || t.is_stop);
+    if has_tuplet_notation {
         writer
-            .write_event(Event::Start(BytesStart::new("time-modification")))
+            .write_event(Event::Start(BytesStart::new("notations")))
             .unwrap();
-        write_text_element(writer, "actual-notes", &tuplet_info.actual_notes.to_string());
-        write_text_element(writer, "normal-notes", &tuplet_info.normal_notes.to_string());
+        write_tuplet_notations(writer, tuplets);
         writer
-            .write_event(Event::End(BytesEnd::new("time-modification")))
+            .write_event(Event::End(BytesEnd::new("notations")))
             .unwrap();
     }
 
-    // Notations (tuplet markers)
-    let has_tuplet_notation = tuplet.map(|t| t.is_start || t.is_stop).unwrap_or(false);
-    if has_tuplet_notation {
+    writer
+        .write_event(Event::End(BytesEnd::new("note")))
+        .unwrap();
+}
+
+/// Write <time-modification> for a note inside (possibly nested) tuplets
+///
+/// Nested ratios multiply, so a triplet inside a quintuplet is 15 notes in the time of 8.
+fn write_time_modification<W: std::io::Write>(writer: &mut Writer<W>, tuplets: &[TupletInfo]) {
+    if tuplets.is_empty() {
+        return;
+    }
+    let actual: u32 = tuplets.iter().map(|t| t.actual_notes as u32).product();
+    let normal: u32 = tuplets.iter().map(|t| t.normal_notes as u32).product();
+
+    writer
+        .write_event(Event::Start(BytesStart::new("time-modification")))
+        .unwrap();
+    write_text_element(writer, "actual-notes", &actual.to_string());
+    write_text_element(writer, "normal-notes", &normal.to_string());
+    writer
+        .write_event(Event::End(BytesEnd::new("time-modification")))
+        .unwrap();
+}
+
+/// Write <tuplet> start/stop markers, numbered by nesting depth (1 = outermost)
+///
+/// Nested and non-standard ratios (e.g. 7:8) spell out their own actual/normal counts,
+/// since <time-modification> only carries the combined ratio.
+fn write_tuplet_notations<W: std::io::Write>(writer: &mut Writer<W>, tuplets: &[TupletInfo]) {
+    for (depth, tuplet_info) in tuplets.iter().enumerate() {
+        if !tuplet_info.is_start {
+            continue;
+        }
+        let number = (depth + 1).to_string();
+        let mut tuplet = BytesStart::new("tuplet");
+        tuplet.push_attribute(("type", "start"));
+        tuplet.push_attribute(("number", number.as_str()));
+        tuplet.push_attribute(("bracket", "yes"));
+        if !tuplet_info.is_standard() {
+            tuplet.push_attribute(("show-number", "both"));
+        }
+
+        if tuplets.len() == 1 && tuplet_info.is_standard() {
+            writer.write_event(Event::Empty(tuplet)).unwrap();
+            continue;
+        }
+        writer.write_event(Event::Start(tuplet)).unwrap();
+        for (portion, count) in [("tuplet-actual", tuplet_info.actual_notes), ("tuplet-normal", tuplet_info.normal_notes)] {
+            writer.write_event(Event::Start(BytesStart::new(portion))).unwrap();
+            write_text_element(writer, "tuplet-number", &count.to_string());
+            writer.write_event(Event::End(BytesEnd::new(portion))).unwrap();
+        }
+        writer.write_event(Event::End(BytesEnd::new("tuplet"))).unwrap();
+    }
+
+    // Close inner tuplets before the ones that enclose them
+    for (depth, tuplet_info) in tuplets.iter().enumerate().rev() {
+        if tuplet_info.is_stop {
+            let number = (depth + 1).to_string();
+            let mut tuplet = BytesStart::new("tuplet");
+            tuplet.push_attribute(("type", "stop"));
+            tuplet.push_attribute(("number", number.as_str()));
+            writer.write_event(Event::Empty(tuplet)).unwrap();
+        }
+    }
+}
+
+/// Write a measure of slash notation: the chord symbols over slashes instead of the melody
+///
+/// Beat slashes fill each beat with one stemless slash, placing chords that start mid-beat with
+/// a harmony `<offset>`. Measures that aren't a whole number of beats (e.g. a pickup of an
+/// eighth note) fall back to rhythm slashes.
+#[allow(clippy::too_many_arguments)]
+fn write_slash_measure<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    measure: &Measure,
+    time_signature: &TimeSignature,
+    style: SlashStyle,
+    clef: Clef,
+    transposition: Option<&Transposition>,
+    divisions: u32,
+    numerals: &mut NumeralLabels,
+) {
+    let beat = beat_length(time_signature);
+    let length: Rational = measure.elements.iter().map(Element::total_fraction).sum();
+    let beats = length / beat;
+    let beat_slash = beat_note(beat).filter(|_| style == SlashStyle::Beat && beats.is_integer());
+    let Some((duration, dotted)) = beat_slash else {
+        let beam_states = calculate_beam_states(&measure.elements, time_signature);
+        for (element, beam_state) in measure.elements.iter().zip(beam_states) {
+            if let Some(chord) = element.chord() {
+                write_harmony(writer, &chord.symbol, transposition, numerals, 0);
+            }
+            match element {
+                Element::Note(note) => write_slash(writer, note, beam_state, clef, true, divisions),
+                Element::Rest { duration, dotted, tuplets, .. } => {
+                    write_rest(writer, *duration, *dotted, tuplets, divisions)
+                }
+            }
+        }
+        return;
+    };
+
+    // Chord symbols with their position in the measure
+    let mut position = Rational::ZERO;
+    let mut chords = Vec::new();
+    for element in &measure.elements {
+        if let Some(chord) = element.chord() {
+            chords.push((position, chord.symbol.as_str()));
+        }
+        position += element.total_fraction();
+    }
+
+    let slash = Note {
+        name: NoteName::B,
+        accidental: Accidental::Natural,
+        octave: Octave::Middle,
+        duration,
+        dotted,
+        tuplets: Vec::new(),
+        tie_start: false,
+        tie_stop: false,
+        slur_start: false,
+        slur_stop: false,
+        chord: None,
+    };
+    for i in 0..beats.floor() {
+        let beat_start = beat * Rational::from_integer(i);
+        let beat_end = beat_start + beat;
+        for &(position, symbol) in chords.iter().filter(|(p, _)| *p >= beat_start && *p < beat_end) {
+            let offset = (position - beat_start) * Rational::from_integer(4 * divisions as i64);
+            write_harmony(writer, symbol, transposition, numerals, offset.floor() as u32);
+        }
+        write_slash(writer, &slash, BeamState::None, clef, false, divisions);
+    }
+}
+
+/// Note value of one beat (e.g. a quarter, or a dotted quarter in compound meters)
+fn beat_note(beat: Rational) -> Option<(Duration, bool)> {
+    [
+        Duration::Whole,
+        Duration::Half,
+        Duration::Quarter,
+        Duration::Eighth,
+        Duration::Sixteenth,
+        Duration::ThirtySecond,
+    ]
+    .into_iter()
+    .flat_map(|duration| [(duration, false), (duration, true)])
+    .find(|&(duration, dotted)| duration.modified_fraction(dotted, &[]) == beat)
+}
+
+/// Write a slash notehead on the middle staff line with the note's rhythm, ties and tuplets
+/// (beat slashes have no stem)
+fn write_slash<W: std::io::Write>(writer: &mut Writer<W>, note: &Note, beam_state: BeamState, clef: Clef, stem: bool, divisions: u32) {
+    writer
+        .write_event(Event::Start(BytesStart::new("note")))
+        .unwrap();
+
+    let (step, octave) = match clef {
+        Clef::Treble => ("B", "4"),
+        Clef::Bass => ("D", "3"),
+    };
+    writer
+        .write_event(Event::Start(BytesStart::new("pitch")))
+        .unwrap();
+    write_text_element(writer, "step", step);
+    write_text_element(writer, "octave", octave);
+    writer
+        .write_event(Event::End(BytesEnd::new("pitch")))
+        .unwrap();
+
+    let note_divisions = duration_to_divisions(note.duration, note.dotted, &note.tuplets, divisions);
+    write_text_element(writer, "duration", &note_divisions.to_string());
+    for (tied, tie_type) in [(note.tie_start, "start"), (note.tie_stop, "stop")] {
+        if tied {
+            let mut tie = BytesStart::new("tie");
+            tie.push_attribute(("type", tie_type));
+            writer.write_event(Event::Empty(tie)).unwrap();
+        }
+    }
+
+    write_text_element(writer, "type", note.duration.musicxml_type());
+    if note.dotted {
+        writer
+            .write_event(Event::Empty(BytesStart::new("dot")))
+            .unwrap();
+    }
+    write_time_modification(writer, &note.tuplets);
+    write_text_element(writer, "stem", if stem { "up" } else { "none" });
+    write_text_element(writer, "notehead", "slash");
+
+    match beam_state {
+        BeamState::Begin => write_beam(writer, "begin"),
+        BeamState::Continue => write_beam(writer, "continue"),
+        BeamState::End => write_beam(writer, "end"),
+        BeamState::None => {}
+    }
+
+    let has_tuplet_notation = note.tuplets.iter().any(|t| t.is_start || t.is_stop);
+    if has_tuplet_notation || note.tie_start || note.tie_stop {
         writer
             .write_event(Event::Start(BytesStart::new("notations")))
             .unwrap();
-        if let Some(tuplet_info) = tuplet {
-            if tuplet_info.is_start {
-                let mut tuplet_elem = BytesStart::new("tuplet");
-                tuplet_elem.push_attribute(("type", "start"));
-                tuplet_elem.push_attribute(("bracket", "yes"));
-                writer.write_event(Event::Empty(tuplet_elem)).unwrap();
-            }
-            if tuplet_info.is_stop {
-                let mut tuplet_elem = BytesStart::new("tuplet");
-                tuplet_elem.push_attribute(("type", "stop"));
-                writer.write_event(Event::Empty(tuplet_elem)).unwrap();
+        for (tied, tie_type) in [(note.tie_start, "start"), (note.tie_stop, "stop")] {
+            if tied {
+                let mut tied = BytesStart::new("tied");
+                tied.push_attribute(("type", tie_type));
+                writer.write_event(Event::Empty(tied)).unwrap();
             }
         }
+        write_tuplet_notations(writer, &note.tuplets);
         writer
             .write_event(Event::End(BytesEnd::new("notations")))
             .unwrap();
@@ -1071,7 +1407,7 @@ fn write_rest<W: std::io::Write>(
 /// Transpose a chord root note by the given transposition interval
 /// Only transposes the root note letter, preserves quality (maj7, m7, etc.)
 /// Also transposes bass note in slash chords (e.g., C/E -> D/F#)
-fn transpose_chord_root(chord_symbol: &str, transposition: &Transposition) -> String {
+pub(crate) fn transpose_chord_root(chord_symbol: &str, transposition: &Transposition) -> String {
     if chord_symbol.is_empty() {
         return chord_symbol.to_string();
     }
@@ -1208,7 +1544,14 @@ fn transpose_chord_root(chord_symbol: &str, transposition: &Transposition) -> St
 }
 
 /// Write a harmony (chord symbol) element
-fn write_harmony<W: std::io::Write>(writer: &mut Writer<W>, chord_symbol: &str, transposition: Option<&Transposition>) {
+fn write_harmony<W: std::io::Write>(writer: &mut Writer<W>, chord_symbol: &str, transposition: Option<&Transposition>, numerals: &mut NumeralLabels, offset: u32) {
+    if let Some((display, chord_numerals)) = numerals.next() {
+        write_numeral_harmony(writer, chord_symbol, display, &chord_numerals, offset);
+        if !display.above_symbols {
+            return;
+        }
+    }
+
     // Transpose the chord symbol if transposition is specified
     let transposed_symbol = if let Some(trans) = transposition {
         transpose_chord_root(chord_symbol, trans)
@@ -1266,36 +1609,7 @@ fn write_harmony<W: std::io::Write>(writer: &mut Writer<W>, chord_symbol: &str,
         ""
     };
 
-    // Map quality string to MusicXML kind
-    let kind_value = match quality {
-        "" => "major",
-        "m" | "min" | "-" => "minor",
-        "maj7" | "M7" | "Δ7" => "major-seventh",
-        "m7" | "min7" | "-7" => "minor-seventh",
-        "7" => "dominant",
-        "dim" | "o" | "°" => "diminished",
-        "dim7" | "o7" | "°7" => "diminished-seventh",
-        "m7b5" | "ø" | "half-dim" => "half-diminished",
-        "aug" | "+" => "augmented",
-        "sus4" | "sus" => "suspended-fourth",
-        "sus2" => "suspended-second",
-        "6" => "major-sixth",
-        "m6" => "minor-sixth",
-        "9" => "dominant-ninth",
-        "maj9" | "M9" => "major-ninth",
-        "m9" => "minor-ninth",
-        "11" => "dominant-11th",
-        "maj11" => "major-11th",
-        "m11" => "minor-11th",
-        "13" => "dominant-13th",
-        "maj13" => "major-13th",
-        "m13" => "minor-13th",
-        "add9" => "major-ninth", // add9 is a triad + 9th (close to major-ninth without 7th)
-        "madd9" => "minor-ninth",
-        "7#5" | "7+5" | "aug7" => "augmented-seventh",
-        "7b5" => "dominant", // dominant with altered 5th
-        _ => "other", // For complex/altered chords
-    };
+    let kind_value = harmony_kind(quality);
 
     // Kind element with text attribute for display and proper kind for parsing
     let mut kind = BytesStart::new("kind");
@@ -1331,11 +1645,114 @@ fn write_harmony<W: std::io::Write>(writer: &mut Writer<W>, chord_symbol: &str,
         }
     }
 
+    write_harmony_offset(writer, offset);
     writer
         .write_event(Event::End(BytesEnd::new("harmony")))
         .unwrap();
 }
 
+/// Write a `<harmony>` labelled with a Roman numeral or Nashville number (MusicXML 4.0 `<numeral>`)
+///
+/// The numeral's text carries the full label (e.g. "V7/V" or "2m⁷"); the root, alteration and
+/// kind are written for applications that rebuild the label themselves.
+fn write_numeral_harmony<W: std::io::Write>(writer: &mut Writer<W>, chord_symbol: &str, display: HarmonyNumerals, chord_numerals: &ChordNumerals, offset: u32) {
+    let label = match display.system {
+        NumeralSystem::Roman => &chord_numerals.roman,
+        NumeralSystem::Nashville => &chord_numerals.nashville,
+    };
+    let main_chord = chord_symbol.split('/').next().unwrap_or(chord_symbol);
+    let quality = main_chord
+        .get(1..)
+        .map_or("", |rest| rest.strip_prefix(['#', 'b']).unwrap_or(rest));
+
+    let mut harmony = BytesStart::new("harmony");
+    if display.above_symbols {
+        harmony.push_attribute(("placement", "above"));
+    }
+    writer.write_event(Event::Start(harmony)).unwrap();
+    writer
+        .write_event(Event::Start(BytesStart::new("numeral")))
+        .unwrap();
+    let mut numeral_root = BytesStart::new("numeral-root");
+    numeral_root.push_attribute(("text", label.as_str()));
+    writer.write_event(Event::Start(numeral_root)).unwrap();
+    writer
+        .write_event(Event::Text(BytesText::new(&chord_numerals.degree.to_string())))
+        .unwrap();
+    writer
+        .write_event(Event::End(BytesEnd::new("numeral-root")))
+        .unwrap();
+    if chord_numerals.alter != 0 {
+        let mut numeral_alter = BytesStart::new("numeral-alter");
+        numeral_alter.push_attribute(("print-object", "no"));
+        writer.write_event(Event::Start(numeral_alter)).unwrap();
+        writer
+            .write_event(Event::Text(BytesText::new(&chord_numerals.alter.to_string())))
+            .unwrap();
+        writer
+            .write_event(Event::End(BytesEnd::new("numeral-alter")))
+            .unwrap();
+    }
+    writer
+        .write_event(Event::End(BytesEnd::new("numeral")))
+        .unwrap();
+
+    // The label already shows the quality
+    let mut kind = BytesStart::new("kind");
+    kind.push_attribute(("text", ""));
+    writer.write_event(Event::Start(kind)).unwrap();
+    writer
+        .write_event(Event::Text(BytesText::new(harmony_kind(quality))))
+        .unwrap();
+    writer
+        .write_event(Event::End(BytesEnd::new("kind")))
+        .unwrap();
+    write_harmony_offset(writer, offset);
+    writer
+        .write_event(Event::End(BytesEnd::new("harmony")))
+        .unwrap();
+}
+
+/// Write a harmony's `<offset>` (in divisions after the following note's start), if any
+fn write_harmony_offset<W: std::io::Write>(writer: &mut Writer<W>, offset: u32) {
+    if offset > 0 {
+        write_text_element(writer, "offset", &offset.to_string());
+    }
+}
+
+/// MusicXML `<kind>` value for a chord quality (the symbol after its root, e.g. "m7")
+pub(crate) fn harmony_kind(quality: &str) -> &'static str {
+    match quality {
+        "" => "major",
+        "m" | "min" | "-" => "minor",
+        "maj7" | "M7" | "Δ7" => "major-seventh",
+        "m7" | "min7" | "-7" => "minor-seventh",
+        "7" => "dominant",
+        "dim" | "o" | "°" => "diminished",
+        "dim7" | "o7" | "°7" => "diminished-seventh",
+        "m7b5" | "ø" | "half-dim" => "half-diminished",
+        "aug" | "+" => "augmented",
+        "sus4" | "sus" => "suspended-fourth",
+        "sus2" => "suspended-second",
+        "6" => "major-sixth",
+        "m6" => "minor-sixth",
+        "9" => "dominant-ninth",
+        "maj9" | "M9" => "major-ninth",
+        "m9" => "minor-ninth",
+        "11" => "dominant-11th",
+        "maj11" => "major-11th",
+        "m11" => "minor-11th",
+        "13" => "dominant-13th",
+        "maj13" => "major-13th",
+        "m13" => "minor-13th",
+        "add9" => "major-ninth", // add9 is a triad + 9th (close to major-ninth without 7th)
+        "madd9" => "minor-ninth",
+        "7#5" | "7+5" | "aug7" => "augmented-seventh",
+        "7b5" => "dominant", // dominant with altered 5th
+        _ => "other", // For complex/altered chords
+    }
+}
+
 fn note_name_to_str(name: NoteName) -> &'static str {
     match name {
         NoteName::C => "C",
@@ -1348,67 +1765,26 @@ fn note_name_to_str(name: NoteName) -> &'static str {
     }
 }
 
-/// Convert duration to MusicXML divisions (4 per quarter note)
-fn duration_to_divisions(duration: Duration, dotted: bool) -> u32 {
-    let base = match duration {
-        Duration::Whole => 16,
-        Duration::Half => 8,
-        Duration::Quarter => 4,
-        Duration::Eighth => 2,
-        Duration::Sixteenth => 1,
-        Duration::ThirtySecond => 1, // MusicXML may need finer divisions for 32nd notes
-    };
-
-    if dotted {
-        base + (base / 2)
-    } else {
-        base
-    }
-}
-
-/// Convert duration to high-resolution divisions (12 per quarter note) for beam calculations
-/// Using 12 divisions allows clean representation of triplets (divisible by 2, 3, 4, 6)
-fn duration_to_divisions_high_res(
-    duration: Duration,
-    dotted: bool,
-    tuplet: Option<TupletInfo>,
-) -> u32 {
-    let base = match duration {
-        Duration::Whole => 48,
-        Duration::Half => 24,
-        Duration::Quarter => 12,
-        Duration::Eighth => 6,
-        Duration::Sixteenth => 3,
-        Duration::ThirtySecond => 2, // Approximate, but fine for beaming
-    };
-
-    let with_dot = if dotted { base + (base / 2) } else { base };
-
-    if let Some(tuplet_info) = tuplet {
-        // For tuplets, actual duration = base * (normal_notes / actual_notes)
-        // e.g., triplet = base * 2/3
-        (with_dot * tuplet_info.normal_notes as u32) / tuplet_info.actual_notes as u32
-    } else {
-        with_dot
-    }
+/// Smallest number of MusicXML divisions per quarter note that expresses every duration
+/// in the score exactly (e.g. 4 for sixteenths, 6 for eighth-note triplets, 5 for
+/// quintuplet sixteenths)
+fn score_divisions(score: &Score) -> u32 {
+    score
+        .measures
+        .iter()
+        .flat_map(|measure| &measure.elements)
+        .map(|element| (element.total_fraction() * Rational::from_integer(4)).denom())
+        .fold(1, lcm) as u32
 }
 
-/// Convert duration to MusicXML divisions, accounting for tuplets
-fn duration_to_divisions_with_tuplet(
-    duration: Duration,
-    dotted: bool,
-    tuplet: Option<TupletInfo>,
-) -> u32 {
-    let base = duration_to_divisions(duration, dotted);
-
-    if let Some(tuplet_info) = tuplet {
-        // For tuplets, actual duration = base * (normal_notes / actual_notes)
-        // e.g., triplet = base * 2/3
-        // We need to handle this carefully to avoid integer division issues
-        (base * tuplet_info.normal_notes as u32) / tuplet_info.actual_notes as u32
-    } else {
-        base
-    }
+/// Convert a duration to MusicXML divisions, accounting for dots and tuplets
+///
+/// `divisions` (per quarter note) must come from [`score_divisions()`] so the result is exact.
+fn duration_to_divisions(duration: Duration, dotted: bool, tuplets: &[TupletInfo], divisions: u32) -> u32 {
+    let fraction = duration.modified_fraction(dotted, tuplets);
+    let quarters = fraction * Rational::from_integer(4 * divisions as i64);
+    debug_assert!(quarters.is_integer(), "divisions {} can't express {}", divisions, fraction);
+    quarters.floor() as u32
 }
 
 #[cfg(test)]
@@ -1454,6 +1830,27 @@ C"#;
         assert!(xml.contains("<tuplet type=\"stop\""));
     }
 
+    #[test]
+    fn test_musicxml_divisions_are_exact() {
+        // Plain sixteenths and 32nds: 8 divisions per quarter
+        let xml = to_musicxml(&parse("C/// D/// E// F/ G Ap").unwrap());
+        assert!(xml.contains("<divisions>8</divisions>"));
+        assert!(xml.contains("<duration>1</duration>"));
+        assert!(xml.contains("<duration>16</duration>"));
+
+        // Quintuplet sixteenths: each is exactly 1/5 of a quarter
+        let xml = to_musicxml(&parse("[C D E F G]5// Gp*").unwrap());
+        assert!(xml.contains("<divisions>5</divisions>"));
+        assert_eq!(xml.matches("<duration>1</duration>").count(), 5);
+        assert!(xml.contains("<duration>15</duration>"));
+
+        // Triplet eighths mixed with sixteenths: lcm of 3 and 4
+        let xml = to_musicxml(&parse("[C D E]3/ F// G// Ap").unwrap());
+        assert!(xml.contains("<divisions>12</divisions>"));
+        assert_eq!(xml.matches("<duration>4</duration>").count(), 3);
+        assert_eq!(xml.matches("<duration>3</duration>").count(), 2);
+    }
+
     #[test]
     fn test_musicxml_tie_output() {
         let score = parse("C-D").unwrap();
@@ -1468,6 +1865,38 @@ C"#;
         assert!(xml.contains("<tied type=\"stop\"/>"));
     }
 
+    #[test]
+    fn test_musicxml_nested_tuplets() {
+        let score = parse("[C D [E F G]3 A]5/ Gp").unwrap();
+        let xml = to_musicxml(&score);
+
+        // Inner notes carry the combined ratio, outer-only notes the quintuplet
+        assert!(xml.contains("<actual-notes>15</actual-notes>"));
+        assert!(xml.contains("<normal-notes>8</normal-notes>"));
+        assert_eq!(xml.matches("<actual-notes>5</actual-notes>").count(), 3);
+
+        // Brackets are numbered by nesting depth
+        assert!(xml.contains("<tuplet type=\"start\" number=\"1\" bracket=\"yes\"/>"));
+        assert!(xml.contains("<tuplet type=\"start\" number=\"2\" bracket=\"yes\">"));
+        assert!(xml.contains("<tuplet-actual><tuplet-number>3</tuplet-number></tuplet-actual>"));
+        assert!(xml.contains("<tuplet type=\"stop\" number=\"2\"/>"));
+        assert!(xml.contains("<tuplet type=\"stop\" number=\"1\"/>"));
+
+        // 1/15 of a whole note needs 15 divisions per quarter
+        assert!(xml.contains("<divisions>15</divisions>"));
+    }
+
+    #[test]
+    fn test_musicxml_explicit_tuplet_ratio() {
+        let score = parse("[C D E F G A B]7:8/").unwrap();
+        let xml = to_musicxml(&score);
+
+        assert!(xml.contains("<actual-notes>7</actual-notes>"));
+        assert!(xml.contains("<normal-notes>8</normal-notes>"));
+        assert!(xml.contains("show-number=\"both\""));
+        assert!(xml.contains("<tuplet-normal><tuplet-number>8</tuplet-number></tuplet-normal>"));
+    }
+
     #[test]
     fn test_musicxml_chained_ties() {
         let score = parse("C-D-E").unwrap();
@@ -1765,6 +2194,47 @@ F F%"#;
             "F% should display natural accidental sign");
     }
 
+    #[test]
+    fn test_double_accidentals_output() {
+        let score = parse("F## Bbb C D").unwrap();
+        let xml = to_musicxml(&score);
+
+        assert!(xml.contains("<step>F</step><alter>2</alter>"));
+        assert!(xml.contains("<accidental>double-sharp</accidental>"));
+        assert!(xml.contains("<step>B</step><alter>-2</alter>"));
+        assert!(xml.contains("<accidental>flat-flat</accidental>"));
+    }
+
+    #[test]
+    fn test_natural_cancels_accidental_in_same_bar() {
+        // The plain F is F natural, so it needs a natural sign after the F#
+        let score = parse("F# F G F").unwrap();
+        let xml = to_musicxml(&score);
+
+        assert_eq!(xml.matches("<accidental>sharp</accidental>").count(), 1);
+        assert_eq!(xml.matches("<accidental>natural</accidental>").count(), 1);
+    }
+
+    #[test]
+    fn test_courtesy_accidental_after_barline() {
+        let score = parse("F# G A B\nF G A B").unwrap();
+        let xml = to_musicxml(&score);
+
+        assert!(xml.contains("<accidental cautionary=\"yes\">natural</accidental>"));
+    }
+
+    #[test]
+    fn test_transposition_spells_remote_keys() {
+        // B# up a major second (Bb instrument) is C## written, not D
+        let score = parse("B# E# C D").unwrap();
+        let trans = Transposition::for_key("Bb").unwrap();
+        let xml = to_musicxml_transposed(&score, Some(trans));
+
+        assert!(xml.contains("<step>C</step><alter>2</alter>"));
+        assert!(xml.contains("<step>F</step><alter>2</alter>"));
+        assert!(xml.contains("<accidental>double-sharp</accidental>"));
+    }
+
     #[test]
     fn test_mod_points_octave_shift() {
         // Test that mod points apply per-line octave shifts
@@ -1928,6 +2398,90 @@ F F%"#;
         assert!(xml.contains("text=\"G7\""), "Concert pitch should not transpose");
     }
 
+    #[test]
+    fn test_harmony_roman_numerals_instead_of_symbols() {
+        let score = parse("{Dm7} D {G7} G {Bb} B").unwrap();
+        let numerals = HarmonyNumerals { system: NumeralSystem::Roman, above_symbols: false };
+        let xml = to_musicxml_with_numerals(&score, None, Clef::Treble, 0, None, numerals);
+
+        assert_eq!(xml.matches("<harmony").count(), 3, "One harmony per chord symbol");
+        assert!(!xml.contains("<root-step>"), "Symbols should be replaced by numerals");
+        assert!(xml.contains(r#"<numeral-root text="ii7">2</numeral-root>"#));
+        assert!(xml.contains(r#"<numeral-root text="V7">5</numeral-root></numeral><kind text="">dominant</kind>"#));
+        assert!(xml.contains(r#"<numeral-root text="bVII">7</numeral-root><numeral-alter print-object="no">-1</numeral-alter>"#));
+    }
+
+    #[test]
+    fn test_harmony_nashville_numbers_above_symbols() {
+        let score = parse("{C} C {Am} A").unwrap();
+        let numerals = HarmonyNumerals { system: NumeralSystem::Nashville, above_symbols: true };
+        let xml = to_musicxml_with_numerals(&score, None, Clef::Treble, 0, None, numerals);
+
+        assert_eq!(xml.matches("<harmony").count(), 4, "Numeral and symbol for each chord");
+        assert!(xml.contains(r#"<harmony placement="above"><numeral><numeral-root text="6m">6</numeral-root>"#));
+        assert!(xml.contains("<root-step>A</root-step>"), "Chord symbols should still be written");
+    }
+
+    #[test]
+    fn test_beat_slashes_replace_melody() {
+        let score = parse("||: {Cmaj7}:C D E F\n1. {Dm7}:D/ E/ F {G7}:G A :||\n2. {C}:Cp Cp").unwrap();
+        let xml = to_musicxml_chart(&score, None, Clef::Treble, SlashStyle::Beat, None);
+
+        assert_eq!(xml.matches("<notehead>slash</notehead>").count(), 12, "Four slashes per 4/4 measure");
+        assert_eq!(xml.matches("<stem>none</stem>").count(), 12, "Beat slashes have no stems");
+        assert!(!xml.contains("<step>C</step>"), "Melody notes should be replaced");
+        assert_eq!(xml.matches("<harmony").count(), 4, "Chord symbols should be kept");
+        assert!(xml.contains(r#"<repeat direction="forward"/>"#));
+        assert!(xml.contains(r#"<ending number="2""#));
+    }
+
+    #[test]
+    fn test_beat_slashes_offset_mid_beat_chords() {
+        // G7 starts on the "and" of beat 2
+        let score = parse("{C}:C/ D/ E/ {G7}:F/ G A").unwrap();
+        let xml = to_musicxml_chart(&score, None, Clef::Treble, SlashStyle::Beat, None);
+
+        assert!(xml.contains(r#"<kind text="G7">dominant</kind><offset>1</offset></harmony>"#));
+        assert_eq!(xml.matches("<notehead>slash</notehead>").count(), 4);
+    }
+
+    #[test]
+    fn test_beat_slashes_compound_meter() {
+        let score = parse("---\ntime-signature: 6/8\n---\n{Am}:Ap*").unwrap();
+        let xml = to_musicxml_chart(&score, None, Clef::Treble, SlashStyle::Beat, None);
+
+        assert_eq!(xml.matches("<notehead>slash</notehead>").count(), 2, "Two dotted-quarter beats in 6/8");
+        assert!(xml.contains("<divisions>2</divisions>"));
+        assert!(xml.contains("<duration>3</duration><type>quarter</type><dot/>"));
+    }
+
+    #[test]
+    fn test_rhythm_slashes_follow_melody() {
+        let score = parse("{F}:F/ G/ A- B $").unwrap();
+        let xml = to_musicxml_chart(&score, None, Clef::Bass, SlashStyle::Rhythm, None);
+
+        assert_eq!(xml.matches("<notehead>slash</notehead>").count(), 4);
+        assert_eq!(xml.matches("<rest/>").count(), 1, "Rests stay rests");
+        assert_eq!(xml.matches("<stem>up</stem>").count(), 4);
+        assert!(xml.contains("<beam number=\"1\">begin</beam>"), "Eighth slashes are beamed");
+        assert!(xml.contains(r#"<tie type="start"/>"#), "Ties are kept");
+        assert!(xml.contains("<step>D</step><octave>3</octave>"), "Slashes sit on the bass staff's middle line");
+    }
+
+    #[test]
+    fn test_slash_style_from_name() {
+        assert_eq!(SlashStyle::from_name("Beat"), Some(SlashStyle::Beat));
+        assert_eq!(SlashStyle::from_name("rhythm"), Some(SlashStyle::Rhythm));
+        assert_eq!(SlashStyle::from_name("diamond"), None);
+    }
+
+    #[test]
+    fn test_numeral_system_from_name() {
+        assert_eq!(NumeralSystem::from_name("Roman"), Some(NumeralSystem::Roman));
+        assert_eq!(NumeralSystem::from_name("nashville"), Some(NumeralSystem::Nashville));
+        assert_eq!(NumeralSystem::from_name("figured"), None);
+    }
+
     #[test]
     fn test_key_signature_transposition_bb_instrument() {
         // Gb major (6 flats, fifths=-6) for Bb instrument (fifths=+2)
@@ -1976,6 +2530,57 @@ C"#;
         assert!(xml.contains("<fifths>-4</fifths>"), "B major (+5) + Eb transposition (+3) = Ab major (-4, wrapped from +8)");
     }
 
+    #[test]
+    fn test_key_signature_mode() {
+        let source = r#"---
+key-signature: D dorian
+---
+D E F G"#;
+        let score = parse(source).unwrap();
+        let xml = to_musicxml(&score);
+
+        assert!(xml.contains("<fifths>0</fifths>"));
+        assert!(xml.contains("<mode>dorian</mode>"));
+
+        // The mode survives transposition (E dorian, 2 sharps)
+        let transposition = Transposition { diatonic: 1, chromatic: 2, fifths: 2 };
+        let xml = to_musicxml_with_options(&score, Some(transposition), Clef::Treble, 0);
+        assert!(xml.contains("<fifths>2</fifths>"));
+        assert!(xml.contains("<mode>dorian</mode>"));
+    }
+
+    #[test]
+    fn test_custom_key_signature() {
+        let source = r#"---
+key-signature: Bb Eb F#
+---
+D E F G"#;
+        let score = parse(source).unwrap();
+        let xml = to_musicxml(&score);
+
+        assert!(xml.contains("<key-step>B</key-step><key-alter>-1</key-alter><key-step>E</key-step><key-alter>-1</key-alter><key-step>F</key-step><key-alter>1</key-alter>"));
+        assert!(!xml.contains("<fifths>"));
+        // E and F follow the signature without printed accidentals
+        assert!(xml.contains("<step>E</step><alter>-1</alter>"));
+        assert!(xml.contains("<step>F</step><alter>1</alter>"));
+        assert!(!xml.contains("<accidental>"));
+    }
+
+    #[test]
+    fn test_custom_key_signature_transposition() {
+        // D freygish for a Bb instrument is E freygish: only G# remains (Bb -> C, Eb -> F)
+        let source = r#"---
+key-signature: Bb Eb F#
+---
+D E F G"#;
+        let score = parse(source).unwrap();
+        let transposition = Transposition { diatonic: 1, chromatic: 2, fifths: 2 };
+        let xml = to_musicxml_with_options(&score, Some(transposition), Clef::Treble, 0);
+
+        assert!(xml.contains("<key><key-step>G</key-step><key-alter>1</key-alter></key>"));
+        assert!(!xml.contains("<accidental>"));
+    }
+
     #[test]
     fn test_note_transposition_bb_instrument() {
         // Bb instrument: transposes up a major 2nd (C -> D, E -> F#, G -> A)
@@ -0,0 +1,208 @@
+//! # Compressed MusicXML (.mxl)
+//!
+//! Packs MusicXML into the `.mxl` container that notation apps and sharing sites accept: a
+//! zip archive holding
+//! - `mimetype`, first and uncompressed: `application/vnd.recordare.musicxml`
+//! - `META-INF/container.xml`, listing the score as the first rootfile
+//! - `score.musicxml`
+//! - `score.gen` with the Gen source, when it is included (listed as a second rootfile)
+//!
+//! Entries are stored rather than deflated, which every zip reader accepts and keeps the
+//! crate free of compression dependencies (and small for wasm).
+//!
+//! ## Example
+//! ```rust
+//! use gen::{compile, to_mxl};
+//!
+//! let source = "C D E F";
+//! let mxl = to_mxl(&compile(source)?, Some(source));
+//! assert!(mxl.starts_with(b"PK\x03\x04"));
+//! # Ok::<(), gen::GenError>(())
+//! ```
+
+/// MIME type of a compressed MusicXML file
+pub const MXL_MIME_TYPE: &str = "application/vnd.recordare.musicxml";
+
+/// Name of the score inside the archive
+const SCORE_PATH: &str = "score.musicxml";
+
+/// Name of the embedded Gen source inside the archive
+const SOURCE_PATH: &str = "score.gen";
+
+/// Wrap MusicXML in an `.mxl` archive, optionally with the Gen source it was compiled from
+pub fn to_mxl(musicxml: &str, gen_source: Option<&str>) -> Vec<u8> {
+    let mut rootfiles = format!(
+        "<rootfile full-path=\"{}\" media-type=\"application/vnd.recordare.musicxml+xml\"/>",
+        SCORE_PATH
+    );
+    if gen_source.is_some() {
+        rootfiles.push_str(&format!("<rootfile full-path=\"{}\" media-type=\"text/plain\"/>", SOURCE_PATH));
+    }
+    let container = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<container><rootfiles>{}</rootfiles></container>\n",
+        rootfiles
+    );
+
+    let mut zip = ZipWriter::default();
+    zip.add("mimetype", MXL_MIME_TYPE.as_bytes());
+    zip.add("META-INF/container.xml", container.as_bytes());
+    zip.add(SCORE_PATH, musicxml.as_bytes());
+    if let Some(source) = gen_source {
+        zip.add(SOURCE_PATH, source.as_bytes());
+    }
+    zip.finish()
+}
+
+/// A zip archive of stored (uncompressed) entries
+#[derive(Default)]
+struct ZipWriter {
+    data: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: u16,
+}
+
+/// DOS date for 1980-01-01, the earliest a zip can record (entries carry no real timestamp)
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, contents: &[u8]) {
+        let offset = self.data.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+
+        // Local file header
+        push_u32(&mut self.data, 0x0403_4b50);
+        push_entry_fields(&mut self.data, name, crc, size, false);
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(contents);
+
+        // Central directory header
+        let directory = &mut self.central_directory;
+        push_u32(directory, 0x0201_4b50);
+        push_u16(directory, 20); // Made by: zip 2.0
+        push_entry_fields(directory, name, crc, size, true);
+        push_u16(directory, 0); // Disk number
+        push_u16(directory, 0); // Internal attributes
+        push_u32(directory, 0); // External attributes
+        push_u32(directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.data.len() as u32;
+        let directory_size = self.central_directory.len() as u32;
+        self.data.append(&mut self.central_directory);
+
+        // End of central directory record
+        let data = &mut self.data;
+        push_u32(data, 0x0605_4b50);
+        push_u16(data, 0); // This disk
+        push_u16(data, 0); // Disk with the central directory
+        push_u16(data, self.entries);
+        push_u16(data, self.entries);
+        push_u32(data, directory_size);
+        push_u32(data, directory_offset);
+        push_u16(data, 0); // Comment length
+        self.data
+    }
+}
+
+/// Fields shared by the local and central headers, from "version needed" to the lengths
+fn push_entry_fields(data: &mut Vec<u8>, name: &str, crc: u32, size: u32, central: bool) {
+    push_u16(data, 10); // Version needed: 1.0 (stored)
+    push_u16(data, 0x0800); // Flags: UTF-8 names
+    push_u16(data, 0); // Method: stored
+    push_u16(data, 0); // Time
+    push_u16(data, DOS_DATE);
+    push_u32(data, crc);
+    push_u32(data, size); // Compressed size
+    push_u32(data, size); // Uncompressed size
+    push_u16(data, name.len() as u16);
+    push_u16(data, 0); // Extra field length
+    if central {
+        push_u16(data, 0); // Comment length
+    }
+}
+
+fn push_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// CRC-32 (IEEE 802.3, as zip uses)
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (name, contents) of each entry, read back through the central directory
+    fn read_entries(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let u16_at = |i: usize| u16::from_le_bytes([zip[i], zip[i + 1]]) as usize;
+        let u32_at = |i: usize| u32::from_le_bytes([zip[i], zip[i + 1], zip[i + 2], zip[i + 3]]) as usize;
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(end), 0x0605_4b50);
+        let mut position = u32_at(end + 16);
+        let mut entries = Vec::new();
+        for _ in 0..u16_at(end + 10) {
+            assert_eq!(u32_at(position), 0x0201_4b50);
+            let (crc, size, name_length) = (u32_at(position + 16), u32_at(position + 24), u16_at(position + 28));
+            let offset = u32_at(position + 42);
+            let name = String::from_utf8(zip[position + 46..position + 46 + name_length].to_vec()).unwrap();
+
+            assert_eq!(u32_at(offset), 0x0403_4b50);
+            assert_eq!(u16_at(offset + 8), 0, "entries are stored");
+            let start = offset + 30 + u16_at(offset + 26) + u16_at(offset + 28);
+            let contents = zip[start..start + size].to_vec();
+            assert_eq!(crc32(&contents) as usize, crc);
+            entries.push((name, contents));
+            position += 46 + name_length;
+        }
+        entries
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_container_layout() {
+        let zip = to_mxl("<score-partwise/>", None);
+        // The mimetype comes first, uncompressed, so it can be read at a fixed offset
+        assert_eq!(&zip[30..38], b"mimetype");
+        assert_eq!(&zip[38..38 + MXL_MIME_TYPE.len()], MXL_MIME_TYPE.as_bytes());
+
+        let entries = read_entries(&zip);
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["mimetype", "META-INF/container.xml", "score.musicxml"]);
+        let container = String::from_utf8(entries[1].1.clone()).unwrap();
+        assert!(container.contains(r#"<rootfiles><rootfile full-path="score.musicxml" media-type="application/vnd.recordare.musicxml+xml"/></rootfiles>"#));
+        assert_eq!(entries[2].1, b"<score-partwise/>");
+    }
+
+    #[test]
+    fn test_embedded_source() {
+        let source = "---\ntitle: Café\n---\nC D E F\n";
+        let entries = read_entries(&to_mxl("<score-partwise/>", Some(source)));
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[3], ("score.gen".to_string(), source.as_bytes().to_vec()));
+        let container = String::from_utf8(entries[1].1.clone()).unwrap();
+        assert!(container.contains(r#"<rootfile full-path="score.gen" media-type="text/plain"/>"#));
+    }
+}
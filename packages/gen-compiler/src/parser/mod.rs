@@ -280,6 +280,25 @@ impl Parser {
             None
         };
 
+        let comping = if let Some(ref comping_str) = raw.comping {
+            Some(CompingPattern::from_name(comping_str).ok_or_else(|| {
+                GenError::MetadataError(format!(
+                    "Invalid comping pattern: '{}'. Use pads, half-notes, charleston, bossa, waltz, ballad-arpeggio or stride",
+                    comping_str
+                ))
+            })?)
+        } else {
+            None
+        };
+
+        let accidentals = if let Some(ref scope_str) = raw.accidentals {
+            AccidentalScope::from_name(scope_str).ok_or_else(|| {
+                GenError::MetadataError(format!("Invalid accidentals setting: '{}'. Use note or measure", scope_str))
+            })?
+        } else {
+            AccidentalScope::Note
+        };
+
         Ok(Metadata {
             title: raw.title,
             composer: raw.composer,
@@ -288,6 +307,8 @@ impl Parser {
             written_pitch,
             tempo,
             swing,
+            comping,
+            accidentals,
         })
     }
 
@@ -307,7 +328,14 @@ impl Parser {
             .parse()
             .map_err(|_| GenError::MetadataError(format!("Invalid time signature beat type: {}", s)))?;
 
-        Ok(TimeSignature { beats, beat_type })
+        let time_signature = TimeSignature { beats, beat_type };
+        if !time_signature.is_valid() {
+            return Err(GenError::MetadataError(format!(
+                "Invalid time signature: {} (needs at least one beat and a beat type of 1, 2, 4, 8, ...)",
+                s
+            )));
+        }
+        Ok(time_signature)
     }
 
     fn parse_pitch(&self, s: &str) -> Result<Pitch, GenError> {
@@ -407,14 +435,56 @@ impl Parser {
 
     fn parse_swing(&self, s: &str) -> Result<Swing, GenError> {
         let s = s.trim();
-        match s {
-            "/" => Ok(Swing::Eighth),
-            "//" => Ok(Swing::Sixteenth),
-            _ => Err(GenError::MetadataError(format!(
-                "Invalid swing value: '{}'. Use '/' for eighth note swing or '//' for sixteenth note swing",
-                s
-            ))),
+
+        // Swung note value first, then an optional ratio
+        // Examples: / (triplet eighths), //60% (sixteenths at 60:40), /dotted (3:1), /3:2
+        let slashes = s.chars().take_while(|&c| c == '/').count();
+        let duration = match slashes {
+            1 => Duration::Eighth,
+            2 => Duration::Sixteenth,
+            _ => {
+                return Err(GenError::MetadataError(format!(
+                    "Invalid swing value: '{}'. Use '/' for eighth note swing or '//' for sixteenth note swing",
+                    s
+                )))
+            }
+        };
+
+        let ratio_str = s[slashes..].trim().to_lowercase();
+        let invalid_ratio = || {
+            GenError::MetadataError(format!(
+                "Invalid swing ratio: '{}'. Use a percentage (e.g. '60%'), a ratio (e.g. '3:2'), 'triplet' or 'dotted'",
+                ratio_str
+            ))
+        };
+        let ratio = match ratio_str.as_str() {
+            "" | "triplet" => Swing::TRIPLET,
+            "dotted" => Swing::DOTTED,
+            "straight" => 0.5,
+            r if r.ends_with('%') => {
+                let percent: f64 = r[..r.len() - 1].trim().parse().map_err(|_| invalid_ratio())?;
+                percent / 100.0
+            }
+            r if r.contains(':') => {
+                let (long, short) = r.split_once(':').unwrap();
+                let long: f64 = long.trim().parse().map_err(|_| invalid_ratio())?;
+                let short: f64 = short.trim().parse().map_err(|_| invalid_ratio())?;
+                if long + short <= 0.0 {
+                    return Err(invalid_ratio());
+                }
+                long / (long + short)
+            }
+            _ => return Err(invalid_ratio()),
+        };
+
+        if !(0.5..1.0).contains(&ratio) {
+            return Err(GenError::MetadataError(format!(
+                "Swing ratio must be at least 50% and below 100%: '{}'",
+                ratio_str
+            )));
         }
+
+        Ok(Swing { duration, ratio })
     }
 
     /// Parse a single measure (one line)
@@ -526,20 +596,7 @@ impl Parser {
             let saved_position = self.position;
 
             // Parse optional octave modifiers BEFORE the bracket (^, _)
-            let mut group_octave_offset = 0i8;
-            while let Some(current_t) = self.current() {
-                match &current_t.token {
-                    Token::Underscore => {
-                        group_octave_offset -= 1;
-                        self.advance();
-                    }
-                    Token::Caret => {
-                        group_octave_offset += 1;
-                        self.advance();
-                    }
-                    _ => break,
-                }
-            }
+            let group_octave_offset = self.parse_group_octave_offset();
 
             // Now check if we have a bracket
             if let Some(current_t) = self.current() {
@@ -630,7 +687,7 @@ impl Parser {
             self.position = saved_position;
 
             {
-                let mut element = self.parse_element(None)?;
+                let mut element = self.parse_element()?;
 
                 // Apply chord annotation to notes or rests
                 match &mut element {
@@ -716,8 +773,23 @@ impl Parser {
         }
     }
 
+    /// Parse octave modifiers before a bracket group (^[...] or _[...]) as an octave offset
+    fn parse_group_octave_offset(&mut self) -> i8 {
+        let mut offset = 0i8;
+        while let Some(t) = self.current() {
+            match &t.token {
+                Token::Underscore => offset -= 1,
+                Token::Caret => offset += 1,
+                _ => break,
+            }
+            self.advance();
+        }
+        offset
+    }
+
     /// Parse a bracket group with new syntax: ^[C D E]3/ (octave before, tuplet+rhythm after)
     /// group_octave_offset: octave modifier parsed before the bracket
+    /// Groups may nest, e.g. [C [D E F]3 G]5/ (a triplet inside a quintuplet)
     /// Returns: (elements, has_pending_tie_stop)
     fn parse_bracket_group(&mut self, group_octave_offset: i8) -> Result<(Vec<Element>, bool), GenError> {
         let (line, column) = self
@@ -770,8 +842,41 @@ impl Parser {
                 continue;
             }
 
+            // Nested bracket group (with optional octave modifiers before it)
+            let saved_position = self.position;
+            let nested_octave_offset = self.parse_group_octave_offset();
+            if self.current().is_some_and(|t| t.token == Token::LeftBracket) {
+                let (mut nested, nested_pending_tie) = self.parse_bracket_group(nested_octave_offset)?;
+
+                if pending_tie_stop {
+                    if let Some(Element::Note(note)) = nested.first_mut() {
+                        note.tie_stop = true;
+                    }
+                }
+                if in_slur && !slur_start_marked {
+                    if let Some(Element::Note(note)) = nested.first_mut() {
+                        note.slur_start = true;
+                        slur_start_marked = true;
+                    }
+                }
+                pending_tie_stop = nested_pending_tie;
+
+                // Check for tie (hyphen) after the nested group
+                if self.current().is_some_and(|t| t.token == Token::Hyphen) {
+                    self.advance();
+                    if let Some(Element::Note(note)) = nested.last_mut() {
+                        note.tie_start = true;
+                    }
+                    pending_tie_stop = true;
+                }
+
+                raw_elements.extend(nested);
+                continue;
+            }
+            self.position = saved_position;
+
             // Parse element without tuplet context for now
-            let mut element = self.parse_element(None)?;
+            let mut element = self.parse_element()?;
 
             // Apply tie_stop if there was a tie from the previous element
             if pending_tie_stop {
@@ -824,23 +929,14 @@ impl Parser {
             });
         }
 
-        // Parse optional tuplet number AFTER the closing bracket
-        let tuplet_number = if let Some(t) = self.current() {
-            if let Token::Number(n) = t.token {
-                self.advance();
-                Some(n)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        // Parse optional tuplet number or ratio AFTER the closing bracket
+        let tuplet = self.parse_tuplet_ratio()?;
 
         // Parse rhythm modifiers AFTER the bracket (and after tuplet number if present)
         let (group_duration, group_dotted) = self.parse_rhythm()?;
 
         // If this is a tuplet (has a number), apply tuplet info to all elements
-        if let Some(actual_notes) = tuplet_number {
+        if let Some(tuplet) = tuplet {
             let tuplet_context = TupletContext {
                 default_duration: group_duration,
             };
@@ -849,7 +945,7 @@ impl Parser {
             let last_idx = raw_elements.len() - 1;
 
             for (i, element) in raw_elements.into_iter().enumerate() {
-                let mut tuplet_info = TupletInfo::new(actual_notes);
+                let mut tuplet_info = tuplet;
                 tuplet_info.is_start = i == 0;
                 tuplet_info.is_stop = i == last_idx;
 
@@ -859,7 +955,8 @@ impl Parser {
                         if note.duration == Duration::Quarter {
                             note.duration = tuplet_context.default_duration;
                         }
-                        note.tuplet = Some(tuplet_info);
+                        // Enclosing groups come first, so this group wraps any nested ones
+                        note.tuplets.insert(0, tuplet_info);
 
                         // Apply group octave offset
                         if group_octave_offset != 0 {
@@ -868,17 +965,18 @@ impl Parser {
 
                         Element::Note(note)
                     }
-                    Element::Rest { duration, dotted, .. } => {
+                    Element::Rest { duration, dotted, mut tuplets, .. } => {
                         // If rest doesn't have explicit duration, use tuplet's default
                         let final_duration = if duration == Duration::Quarter {
                             tuplet_context.default_duration
                         } else {
                             duration
                         };
+                        tuplets.insert(0, tuplet_info);
                         Element::Rest {
                             duration: final_duration,
                             dotted,
-                            tuplet: Some(tuplet_info),
+                            tuplets,
                             chord: None,
                         }
                     }
@@ -908,7 +1006,7 @@ impl Parser {
 
                         Element::Note(note)
                     }
-                    Element::Rest { duration, dotted, tuplet, .. } => {
+                    Element::Rest { duration, dotted, tuplets, .. } => {
                         // If rest doesn't have explicit duration, use group's rhythm
                         let final_duration = if duration == Duration::Quarter && group_duration != Duration::Quarter {
                             group_duration
@@ -923,7 +1021,7 @@ impl Parser {
                         Element::Rest {
                             duration: final_duration,
                             dotted: final_dotted,
-                            tuplet,
+                            tuplets,
                             chord: None,
                         }
                     }
@@ -935,10 +1033,52 @@ impl Parser {
         }
     }
 
+    /// Parse the tuplet suffix after a bracket group: a number (`3`) or an explicit ratio (`7:8`)
+    /// Returns None for a plain rhythm grouping
+    fn parse_tuplet_ratio(&mut self) -> Result<Option<TupletInfo>, GenError> {
+        let Some(t) = self.current() else { return Ok(None) };
+        let Token::Number(actual_notes) = t.token else { return Ok(None) };
+        let (line, column) = (t.line, t.column);
+        self.advance();
+
+        if !self.current().is_some_and(|t| t.token == Token::Colon) {
+            if actual_notes < 2 {
+                return Err(GenError::ParseError {
+                    line,
+                    column,
+                    message: format!("Invalid tuplet number {}. Tuplets need at least 2 notes", actual_notes),
+                });
+            }
+            return Ok(Some(TupletInfo::new(actual_notes)));
+        }
+
+        self.advance(); // :
+        let normal_notes = match self.current().map(|t| &t.token) {
+            Some(Token::Number(n)) => *n,
+            _ => {
+                return Err(GenError::ParseError {
+                    line,
+                    column,
+                    message: format!("Expected a number after '{}:' in tuplet ratio (e.g. [C D E]3:2)", actual_notes),
+                });
+            }
+        };
+        self.advance();
+
+        if actual_notes == 0 || normal_notes == 0 {
+            return Err(GenError::ParseError {
+                line,
+                column,
+                message: format!("Invalid tuplet ratio {}:{}. Both numbers must be positive", actual_notes, normal_notes),
+            });
+        }
+        Ok(Some(TupletInfo::with_ratio(actual_notes, normal_notes)))
+    }
+
     /// Parse a single element (note or rest with rhythm)
     /// New syntax order: [octave][note][accidental][rhythm]
     /// Examples: ^C#/ (eighth C# up), _Bbd (half Bb down), C (quarter C)
-    fn parse_element(&mut self, tuplet_info: Option<TupletInfo>) -> Result<Element, GenError> {
+    fn parse_element(&mut self) -> Result<Element, GenError> {
         let (line, column) = self
             .current()
             .map(|t| (t.line, t.column))
@@ -959,7 +1099,7 @@ impl Parser {
                 self.advance();
                 // Parse rhythm suffix
                 let (duration, dotted) = self.parse_rhythm()?;
-                Ok(Element::Rest { duration, dotted, tuplet: tuplet_info, chord: None })
+                Ok(Element::Rest { duration, dotted, tuplets: Vec::new(), chord: None })
             }
             Token::NoteA | Token::NoteB | Token::NoteC | Token::NoteD | Token::NoteE
             | Token::NoteF | Token::NoteG => {
@@ -975,7 +1115,7 @@ impl Parser {
                     octave,
                     duration,
                     dotted,
-                    tuplet: tuplet_info,
+                    tuplets: Vec::new(),
                     tie_start: false,
                     tie_stop: false,
                     slur_start: false,
@@ -1093,7 +1233,7 @@ impl Parser {
     }
 
     /// Parse accidental AFTER the note name
-    /// Examples: # (sharp), b (flat), % (force natural)
+    /// Examples: # (sharp), b (flat), ## (double sharp), bb (double flat), % (force natural)
     fn parse_accidental(&mut self) -> Accidental {
         if let Some(t) = self.current() {
             match &t.token {
@@ -1105,6 +1245,14 @@ impl Parser {
                     self.advance();
                     Accidental::Flat
                 }
+                Token::DoubleSharp => {
+                    self.advance();
+                    Accidental::DoubleSharp
+                }
+                Token::DoubleFlat => {
+                    self.advance();
+                    Accidental::DoubleFlat
+                }
                 Token::Natural => {
                     self.advance();
                     Accidental::ForceNatural
@@ -1359,6 +1507,15 @@ pub(crate) fn extract_chords(source: &str) -> ChordAnnotations {
                 }
             }
 
+            // Skip annotations (@key:F, @Eb:^) - their letters aren't notes
+            if ch == '@' {
+                i += 1;
+                while i < line.len() && !matches!(line_bytes[i] as char, ' ' | '\t' | '@') {
+                    i += 1;
+                }
+                continue;
+            }
+
             // Check for note or rest
             if matches!(ch, 'A' | 'B' | 'C' | 'D' | 'E' | 'F' | 'G' | '$') {
                 // Apply pending chord to this note
@@ -1618,6 +1775,113 @@ C D E"#;
         assert_eq!(score.metadata.time_signature.beat_type, 4);
     }
 
+    #[test]
+    fn test_comping_metadata() {
+        let source = r#"---
+comping: charleston
+---
+C D E F"#;
+        let score = parse(source).unwrap();
+        assert_eq!(score.metadata.comping, Some(CompingPattern::Charleston));
+
+        let invalid = parse("---\ncomping: polka\n---\nC D E F");
+        assert!(matches!(invalid, Err(GenError::MetadataError(_))));
+    }
+
+    #[test]
+    fn test_uncountable_time_signature() {
+        for time in ["4/0", "0/4", "4/6"] {
+            let result = parse(&format!("---\ntime-signature: {}\n---\nC D E F", time));
+            assert!(matches!(result, Err(GenError::MetadataError(_))), "{}", time);
+        }
+    }
+
+    #[test]
+    fn test_accidentals_metadata() {
+        let score = parse("---\naccidentals: measure\n---\nC D E F").unwrap();
+        assert_eq!(score.metadata.accidentals, AccidentalScope::Measure);
+
+        let default = parse("C D E F").unwrap();
+        assert_eq!(default.metadata.accidentals, AccidentalScope::Note);
+
+        let invalid = parse("---\naccidentals: always\n---\nC D E F");
+        assert!(matches!(invalid, Err(GenError::MetadataError(_))));
+    }
+
+    #[test]
+    fn test_modal_key_signatures() {
+        let key = |value: &str| KeySignature::from_str(value).map(|k| (k.fifths, k.mode));
+
+        assert_eq!(key("D dorian"), Some((0, Mode::Dorian)));
+        assert_eq!(key("Ddorian"), Some((0, Mode::Dorian)));
+        assert_eq!(key("E Phrygian"), Some((0, Mode::Phrygian)));
+        assert_eq!(key("Gmix"), Some((0, Mode::Mixolydian)));
+        assert_eq!(key("Bb lydian"), Some((-1, Mode::Lydian)));
+        assert_eq!(key("F# locrian"), Some((1, Mode::Locrian)));
+        assert_eq!(key("A aeolian"), Some((0, Mode::Minor)));
+        assert_eq!(key("Ebm"), Some((-6, Mode::Minor)));
+
+        // More than 7 sharps or flats
+        assert_eq!(key("C# lydian"), None);
+        assert_eq!(key("Fb locrian"), None);
+        assert_eq!(key("D bebop"), None);
+
+        let score = parse("---\nkey-signature: G mixolydian\n---\nF G A B").unwrap();
+        assert_eq!(score.metadata.key_signature.mode, Mode::Mixolydian);
+        assert_eq!(score.metadata.key_signature.fifths, 0);
+    }
+
+    #[test]
+    fn test_custom_key_signatures() {
+        let freygish = KeySignature::from_str("Bb Eb F#").unwrap();
+        assert!(freygish.is_custom());
+        assert_eq!(freygish.accidental_for_note(NoteName::B), Accidental::Flat);
+        assert_eq!(freygish.accidental_for_note(NoteName::F), Accidental::Sharp);
+        assert_eq!(freygish.accidental_for_note(NoteName::A), Accidental::Natural);
+
+        assert_eq!(KeySignature::from_str("Bb,Eb,F#"), Some(freygish));
+        assert_eq!(KeySignature::from_str("F#,").unwrap().alterations, vec![(NoteName::F, 1)]);
+
+        // Each step at most once, and only real accidentals
+        assert_eq!(KeySignature::from_str("Bb B#"), None);
+        assert_eq!(KeySignature::from_str("Bb E"), None);
+
+        // Inline key changes end at whitespace, so use commas
+        let score = parse("@key:Bb,Eb,F# D Eb F# G").unwrap();
+        assert_eq!(score.measures[0].key_change.as_ref().unwrap().alterations.len(), 3);
+    }
+
+    #[test]
+    fn test_key_signature_display_round_trips() {
+        for name in ["C", "F#", "Cb", "Bbm", "D#m", "D dorian", "B# locrian", "Bb Eb F#", "F#,"] {
+            let key = KeySignature::from_str(name).unwrap();
+            assert_eq!(key.to_string(), name);
+            assert_eq!(KeySignature::from_str(&key.to_string()), Some(key));
+        }
+        assert_eq!(KeySignature::from_str("##").unwrap().tonic(), Some((NoteName::D, 0)));
+        assert_eq!(KeySignature::from_str("Bb Eb F#").unwrap().tonic(), None);
+    }
+
+    #[test]
+    fn test_swing_metadata_ratio() {
+        let swing = |value: &str| parse(&format!("---\nswing: {}\n---\nC D E F", value)).map(|s| s.metadata.swing.unwrap());
+
+        let default = swing("/").unwrap();
+        assert_eq!(default.duration, Duration::Eighth);
+        assert_eq!(default.ratio, Swing::TRIPLET);
+
+        let sixteenths = swing("//60%").unwrap();
+        assert_eq!(sixteenths.duration, Duration::Sixteenth);
+        assert!((sixteenths.ratio - 0.6).abs() < 1e-9);
+
+        assert_eq!(swing("/dotted").unwrap().ratio, Swing::DOTTED);
+        assert_eq!(swing("/ 3:1").unwrap().ratio, 0.75);
+
+        assert!(matches!(swing("/40%"), Err(GenError::MetadataError(_))));
+        assert!(matches!(swing("/heavy"), Err(GenError::MetadataError(_))));
+        assert!(matches!(swing("///"), Err(GenError::MetadataError(_))));
+    }
+
     #[test]
     fn test_with_metadata_at_bottom() {
         let source = r#"C D E
@@ -1709,8 +1973,8 @@ time-signature: 6/8
         // Check that all notes have triplet info
         for (i, element) in elements.iter().enumerate() {
             if let Element::Note(n) = element {
-                assert!(n.tuplet.is_some(), "Note {} should have tuplet info", i);
-                let tuplet = n.tuplet.unwrap();
+                assert!(n.tuplets.len() == 1, "Note {} should have tuplet info", i);
+                let tuplet = n.tuplets[0];
                 assert_eq!(tuplet.actual_notes, 3);
                 assert_eq!(tuplet.normal_notes, 2);
 
@@ -1740,7 +2004,7 @@ time-signature: 6/8
         for element in elements.iter() {
             if let Element::Note(n) = element {
                 assert_eq!(n.duration, Duration::Eighth);
-                assert!(n.tuplet.is_some());
+                assert!(n.tuplets.len() == 1);
             }
         }
     }
@@ -1774,7 +2038,7 @@ time-signature: 6/8
         for element in elements.iter() {
             if let Element::Note(n) = element {
                 assert_eq!(n.duration, Duration::Sixteenth);
-                assert!(n.tuplet.is_none(), "Rhythm grouping should not have tuplet info");
+                assert!(n.tuplets.is_empty(), "Rhythm grouping should not have tuplet info");
             }
         }
     }
@@ -1790,7 +2054,7 @@ time-signature: 6/8
         for element in elements.iter() {
             if let Element::Note(n) = element {
                 assert_eq!(n.duration, Duration::Eighth);
-                assert!(n.tuplet.is_none());
+                assert!(n.tuplets.is_empty());
             }
         }
     }
@@ -1829,8 +2093,8 @@ time-signature: 6/8
 
         for (i, element) in elements.iter().enumerate() {
             if let Element::Note(n) = element {
-                assert!(n.tuplet.is_some());
-                let tuplet = n.tuplet.unwrap();
+                assert!(n.tuplets.len() == 1);
+                let tuplet = n.tuplets[0];
                 assert_eq!(tuplet.actual_notes, 5);
                 assert_eq!(tuplet.normal_notes, 4);
 
@@ -1853,14 +2117,70 @@ time-signature: 6/8
 
         for element in elements.iter() {
             if let Element::Note(n) = element {
-                assert!(n.tuplet.is_some());
-                let tuplet = n.tuplet.unwrap();
+                assert!(n.tuplets.len() == 1);
+                let tuplet = n.tuplets[0];
                 assert_eq!(tuplet.actual_notes, 6);
                 assert_eq!(tuplet.normal_notes, 4);
             }
         }
     }
 
+    #[test]
+    fn test_explicit_tuplet_ratio() {
+        // Seven eighths in the time of eight: [...]7:8/
+        let score = parse("[C D E F G A B]7:8/").unwrap();
+        let elements = &score.measures[0].elements;
+
+        assert_eq!(elements.len(), 7);
+        for element in elements {
+            if let Element::Note(n) = element {
+                assert_eq!(n.duration, Duration::Eighth);
+                assert_eq!((n.tuplets[0].actual_notes, n.tuplets[0].normal_notes), (7, 8));
+            }
+        }
+
+        // 6:2 instead of the default 6:4
+        let score = parse("[C D E F G A]6:2/ Cp*").unwrap();
+        if let Element::Note(n) = &score.measures[0].elements[0] {
+            assert_eq!((n.tuplets[0].actual_notes, n.tuplets[0].normal_notes), (6, 2));
+        }
+    }
+
+    #[test]
+    fn test_nested_tuplets() {
+        // A triplet inside a quintuplet, both in eighths
+        let score = parse("[C D [E F G]3 A]5/ Gp").unwrap();
+        let elements = &score.measures[0].elements;
+        assert_eq!(elements.len(), 7);
+
+        let ratios: Vec<Vec<(u8, u8)>> = elements[..6]
+            .iter()
+            .map(|e| e.tuplets().iter().map(|t| (t.actual_notes, t.normal_notes)).collect())
+            .collect();
+        assert_eq!(ratios[0], vec![(5, 4)]);
+        assert_eq!(ratios[2], vec![(5, 4), (3, 2)]);
+        assert_eq!(ratios[5], vec![(5, 4)]);
+
+        // Outer bracket spans C..A, inner bracket spans E..G
+        assert!(elements[0].tuplets()[0].is_start);
+        assert!(elements[5].tuplets()[0].is_stop);
+        assert!(elements[2].tuplets()[1].is_start && !elements[2].tuplets()[0].is_start);
+        assert!(elements[4].tuplets()[1].is_stop && !elements[4].tuplets()[0].is_stop);
+
+        // Inner notes inherit the outer group's rhythm
+        if let Element::Note(n) = &elements[3] {
+            assert_eq!(n.duration, Duration::Eighth);
+        }
+        assert_eq!(elements[3].total_fraction(), crate::Rational::new(1, 15));
+    }
+
+    #[test]
+    fn test_invalid_tuplet_ratio() {
+        assert!(parse("[C D]1").is_err());
+        assert!(parse("[C D E]3:0").is_err());
+        assert!(parse("[C D E]3:").is_err());
+    }
+
     #[test]
     fn test_simple_tie() {
         // C tied to D
@@ -2085,6 +2405,27 @@ time-signature: 6/8
         }
     }
 
+    #[test]
+    fn test_double_accidentals() {
+        let score = parse("F##/ Bbb/ ^C##").unwrap();
+        let elements = &score.measures[0].elements;
+
+        let key = KeySignature::default();
+        if let Element::Note(n) = &elements[0] {
+            assert_eq!(n.accidental, Accidental::DoubleSharp);
+            assert_eq!(n.duration, Duration::Eighth);
+            assert_eq!(n.to_midi_note(&key, 0), 67); // F double sharp sounds as G
+        }
+        if let Element::Note(n) = &elements[1] {
+            assert_eq!(n.accidental, Accidental::DoubleFlat);
+            assert_eq!(n.to_midi_note(&key, 0), 69); // B double flat sounds as A
+        }
+        if let Element::Note(n) = &elements[2] {
+            assert_eq!(n.octave, Octave::High);
+            assert_eq!(n.to_midi_note(&key, 0), 74);
+        }
+    }
+
     #[test]
     fn test_slur_followed_by_regular_note() {
         // (C D E) F - slur followed by regular note
@@ -2405,7 +2746,7 @@ time-signature: 6/8
         // All notes should have tuplet info
         for element in elements.iter() {
             if let Element::Note(n) = element {
-                assert!(n.tuplet.is_some());
+                assert!(n.tuplets.len() == 1);
                 assert_eq!(n.duration, Duration::Eighth);
             }
         }
@@ -2777,7 +3118,7 @@ time-signature: 6/8
 
         for (i, element) in elements.iter().enumerate() {
             if let Element::Note(n) = element {
-                assert!(n.tuplet.is_some(), "Note {} should have tuplet info", i);
+                assert!(n.tuplets.len() == 1, "Note {} should have tuplet info", i);
                 assert_eq!(n.octave, Octave::High, "Note {} should be in high octave", i);
             } else {
                 panic!("Expected note at position {}", i);
@@ -2795,7 +3136,7 @@ time-signature: 6/8
 
         for element in elements.iter() {
             if let Element::Note(n) = element {
-                assert!(n.tuplet.is_some());
+                assert!(n.tuplets.len() == 1);
                 assert_eq!(n.duration, Duration::Eighth);
                 assert_eq!(n.octave, Octave::High);
             }
@@ -3093,6 +3434,21 @@ time-signature: 6/8
         assert!(score.measures[0].key_change.is_none());
     }
 
+    #[test]
+    fn test_chord_after_annotation() {
+        // Letters inside @key: and mod points must not shift chords onto later notes
+        let score = parse("@key:F {C7}:C D @Eb:^ {F}:E F").unwrap();
+        let chords: Vec<Option<String>> = score.measures[0]
+            .elements
+            .iter()
+            .map(|e| match e {
+                Element::Note(n) => n.chord.as_ref().map(|c| c.symbol.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(chords, vec![Some("C7".to_string()), None, Some("F".to_string()), None]);
+    }
+
     #[test]
     fn test_chord_on_rest_in_bracket() {
         // This is the real-world case from the-wizard-and-i.gen
@@ -0,0 +1,235 @@
+//! Bass line generation from chord symbols
+//!
+//! Builds a bass track from the chord progression: a walking line, a two-feel, or a
+//! simple root-fifth pattern. Each chord sounds until the next chord symbol arrives
+//! (or its own duration runs out), and the line steps on the beat of the time signature.
+
+use crate::rational::Rational;
+use super::chord_parser::{parse_chord_structure, ChordStructure};
+use super::types::{BassStyle, PlaybackBassNote, PlaybackChord};
+
+/// Lowest MIDI note of the register chord roots are placed in (C2)
+const ROOT_REGISTER_LOW: u8 = 36;
+
+/// Generate a bass line from block chords
+///
+/// # Parameters
+/// - `chords`: Block chords (one per chord symbol) with times in time-signature beats
+/// - `beat`: Length of one bass step in time-signature beats (quarter note, or dotted
+///   quarter in compound meters)
+/// - `style`: Walking, two-feel, or root-fifth
+///
+/// Walking and two-feel lines end each chord with a chromatic approach tone into the
+/// next chord's root when the next chord follows directly.
+pub(crate) fn generate_bass_line(
+    chords: &[PlaybackChord<Rational>],
+    beat: Rational,
+    style: BassStyle,
+) -> Vec<PlaybackBassNote<Rational>> {
+    let step = match style {
+        BassStyle::Walking => beat,
+        BassStyle::TwoFeel | BassStyle::RootFifth => beat * Rational::from_integer(2),
+    };
+
+    let mut bass = Vec::new();
+    for (i, chord) in chords.iter().enumerate() {
+        let Some(structure) = parse_chord_structure(&chord.symbol) else {
+            continue;
+        };
+
+        // A chord lasts until the next symbol arrives or its own duration ends
+        let next = chords.get(i + 1);
+        let chord_end = chord.start_time + chord.duration;
+        let span_end = next.map_or(chord_end, |n| n.start_time.min(chord_end));
+        let span = span_end - chord.start_time;
+        if span <= Rational::ZERO {
+            continue;
+        }
+
+        // Only approach the next root if it starts right where this chord ends
+        let next_root = next
+            .filter(|n| n.start_time == span_end)
+            .and_then(|n| parse_chord_structure(&n.symbol))
+            .map(|n| in_root_register(n.bass.unwrap_or(n.root)));
+
+        let count = (span / step).floor().max(1) as usize;
+        let pitches = bass_pitches(&structure, count, next_root, style);
+
+        for (k, midi_note) in pitches.into_iter().enumerate() {
+            let start_time = chord.start_time + Rational::from_integer(k as i64) * step;
+            let duration = if k + 1 == count { span_end - start_time } else { step };
+            bass.push(PlaybackBassNote {
+                midi_note,
+                start_time,
+                duration,
+                osmd_timestamp: chord.osmd_timestamp,
+            });
+        }
+    }
+
+    bass
+}
+
+/// Pick `count` bass pitches for one chord
+fn bass_pitches(chord: &ChordStructure, count: usize, next_root: Option<u8>, style: BassStyle) -> Vec<u8> {
+    let root = in_root_register(chord.root);
+    let bass = chord.bass.map(in_root_register).unwrap_or(root);
+    let fifth = root + chord.fifth.unwrap_or(7);
+
+    let mut pitches: Vec<u8> = match style {
+        BassStyle::Walking => {
+            // Walk toward the next root: up through 3rd, 5th, 7th (or octave) when it's
+            // higher, down through 7th (or 6th), 5th, 3rd when it's lower
+            let third = root + chord.third.unwrap_or(4);
+            let pattern = match next_root {
+                Some(target) if target < root => {
+                    let below = root + chord.seventh.unwrap_or(9) - 12;
+                    [bass, below, fifth - 12, third - 12]
+                }
+                _ => [bass, third, fifth, root + chord.seventh.unwrap_or(12)],
+            };
+            (0..count).map(|k| pattern[k % pattern.len()]).collect()
+        }
+        BassStyle::TwoFeel => (0..count).map(|k| if k % 2 == 0 { bass } else { fifth }).collect(),
+        BassStyle::RootFifth => (0..count).map(|k| if k % 2 == 0 { bass } else { fifth - 12 }).collect(),
+    };
+
+    if style != BassStyle::RootFifth && count >= 2 {
+        if let Some(target) = next_root {
+            let previous = pitches[count - 2];
+            let approach = if previous < target { target - 1 } else { target + 1 };
+            // Don't repeat the previous note; come from the other side instead
+            pitches[count - 1] = if approach == previous { 2 * target - approach } else { approach };
+        }
+    }
+
+    pitches
+}
+
+/// Move a note into the root register (C2-B2), keeping its pitch class
+fn in_root_register(note: u8) -> u8 {
+    ROOT_REGISTER_LOW + note % 12
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn beats(n: i64) -> Rational {
+        Rational::from_integer(n)
+    }
+
+    fn block(symbol: &str, start_time: i64, duration: i64) -> PlaybackChord<Rational> {
+        PlaybackChord {
+            symbol: symbol.to_string(),
+            midi_notes: vec![],
+            start_time: beats(start_time),
+            duration: beats(duration),
+            osmd_timestamp: beats(start_time),
+        }
+    }
+
+    fn pitches(line: &[PlaybackBassNote<Rational>]) -> Vec<u8> {
+        line.iter().map(|n| n.midi_note).collect()
+    }
+
+    #[test]
+    fn test_walking_with_approach_tone() {
+        // Cmaj7 for a bar, then F
+        let chords = vec![block("Cmaj7", 0, 4), block("F", 4, 4)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::Walking);
+
+        // C E G then F# (approach from above into F), then F A C F (no chord follows)
+        assert_eq!(pitches(&line[..4]), vec![36, 40, 43, 42]);
+        assert_eq!(pitches(&line[4..]), vec![41, 45, 48, 53]);
+
+        let starts: Vec<Rational> = line.iter().map(|n| n.start_time).collect();
+        assert_eq!(starts, (0..8).map(beats).collect::<Vec<_>>());
+        assert!(line.iter().all(|n| n.duration == Rational::ONE));
+    }
+
+    #[test]
+    fn test_walking_approaches_from_below() {
+        // A7 over two beats: approaching Bb from below would repeat A, so use B
+        let chords = vec![block("A7", 0, 2), block("Bb", 2, 2)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::Walking);
+
+        assert_eq!(pitches(&line), vec![45, 47, 46, 50]);
+    }
+
+    #[test]
+    fn test_walking_down_to_lower_root() {
+        // G7 -> C: the next root is below, so walk down G F D and approach with C#
+        let chords = vec![block("G7", 0, 4), block("C", 4, 4)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::Walking);
+
+        assert_eq!(pitches(&line[..5]), vec![43, 41, 38, 37, 36]);
+    }
+
+    #[test]
+    fn test_walking_down_from_triad_never_repeats() {
+        // G -> C: without a 7th the walk steps down through the 6th, not the octave
+        let chords = vec![block("G", 0, 4), block("C", 4, 4)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::Walking);
+
+        assert_eq!(pitches(&line[..4]), vec![43, 40, 38, 37]);
+        assert!(line.windows(2).all(|pair| pair[0].midi_note != pair[1].midi_note));
+    }
+
+    #[test]
+    fn test_chord_ends_at_next_symbol() {
+        // Default whole-note chords overlap; each only walks until the next symbol
+        let chords = vec![block("Dm7", 0, 4), block("G7", 2, 4)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::Walking);
+
+        assert_eq!(line.len(), 6);
+        assert_eq!(pitches(&line[..2]), vec![38, 42]); // D, approach F# into G
+        assert_eq!(line[2].start_time, beats(2));
+        assert_eq!(line[2].midi_note, 43); // G
+        assert_eq!(line[2].osmd_timestamp, beats(2));
+    }
+
+    #[test]
+    fn test_two_feel_half_notes() {
+        let chords = vec![block("C", 0, 4), block("G", 4, 4)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::TwoFeel);
+
+        assert_eq!(pitches(&line), vec![36, 42, 43, 50]);
+        assert!(line.iter().all(|n| n.duration == beats(2)));
+    }
+
+    #[test]
+    fn test_root_fifth_without_approach() {
+        let chords = vec![block("C", 0, 4), block("G", 4, 4)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::RootFifth);
+
+        assert_eq!(pitches(&line), vec![36, 31, 43, 38]);
+    }
+
+    #[test]
+    fn test_slash_bass_starts_the_chord() {
+        let chords = vec![block("C/E", 0, 2)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::Walking);
+
+        assert_eq!(pitches(&line), vec![40, 40]);
+    }
+
+    #[test]
+    fn test_leftover_beat_extends_last_note() {
+        // Two-feel over a 3/4 bar: one half note plus a leftover beat
+        let chords = vec![block("C", 0, 3)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::TwoFeel);
+
+        assert_eq!(line.len(), 1);
+        assert_eq!(line[0].duration, beats(3));
+    }
+
+    #[test]
+    fn test_unparseable_symbols_are_skipped() {
+        let chords = vec![block("N.C.", 0, 4), block("C", 4, 4)];
+        let line = generate_bass_line(&chords, Rational::ONE, BassStyle::Walking);
+
+        assert_eq!(line.len(), 4);
+        assert_eq!(line[0].start_time, beats(4));
+    }
+}
@@ -53,6 +53,58 @@ use intervals::*;
 /// assert!(a7b9.contains(&70));  // Bb (b9)
 /// ```
 pub fn parse_chord_symbol(chord_symbol: &str) -> Vec<u8> {
+    parse_chord_structure(chord_symbol)
+        .map(|chord| chord.close_voicing())
+        .unwrap_or_default()
+}
+
+/// A parsed chord symbol broken down by chord function
+///
+/// Tones are stored as semitone offsets from `root` so that voicing strategies can
+/// rearrange them freely. `root` and `bass` are MIDI notes in the C3 octave (48-59).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ChordStructure {
+    pub root: u8,
+    pub third: Option<u8>,
+    pub fifth: Option<u8>,
+    pub seventh: Option<u8>,
+    pub extensions: Vec<u8>,
+    pub bass: Option<u8>, // Slash chord bass note (e.g., E in C/E)
+}
+
+impl ChordStructure {
+    /// Chord tones above the root as semitone offsets (3rd, 5th, 7th, then extensions)
+    pub fn upper_intervals(&self) -> Vec<u8> {
+        let mut intervals: Vec<u8> = [self.third, self.fifth, self.seventh]
+            .into_iter()
+            .flatten()
+            .collect();
+        intervals.extend(self.extensions.iter().copied());
+        intervals
+    }
+
+    /// Close-position voicing in the C3 octave, with any slash bass placed first
+    pub fn close_voicing(&self) -> Vec<u8> {
+        let mut chord_tones = vec![self.root];
+        chord_tones.extend(self.upper_intervals().iter().map(|i| self.root + i));
+
+        // Handle slash chord - add bass note
+        if let Some(bass_midi) = self.bass {
+            // Remove the bass note from chord tones if it's already there
+            chord_tones.retain(|&note| note != bass_midi);
+
+            // Insert bass at the beginning
+            chord_tones.insert(0, bass_midi);
+        }
+
+        chord_tones
+    }
+}
+
+/// Parse a chord symbol into its root, chord tones and optional slash bass
+///
+/// Returns `None` if the symbol doesn't start with a valid root note (A-G).
+pub(crate) fn parse_chord_structure(chord_symbol: &str) -> Option<ChordStructure> {
     // Check for slash chord (e.g., "C/E", "Am/G")
     let (main_chord, bass_note) = if let Some(slash_pos) = chord_symbol.find('/') {
         (&chord_symbol[..slash_pos], Some(&chord_symbol[slash_pos + 1..]))
@@ -63,7 +115,7 @@ pub fn parse_chord_symbol(chord_symbol: &str) -> Vec<u8> {
     // Extract root note and chord quality
     let chars: Vec<char> = main_chord.chars().collect();
     if chars.is_empty() {
-        return vec![];
+        return None;
     }
 
     // Parse root note
@@ -87,7 +139,7 @@ pub fn parse_chord_symbol(chord_symbol: &str) -> Vec<u8> {
         'G' => 55i8,
         'A' => 57i8,
         'B' => 59i8,
-        _ => return vec![],
+        _ => return None,
     };
     let root = (base_midi + accidental) as u8;
 
@@ -95,44 +147,49 @@ pub fn parse_chord_symbol(chord_symbol: &str) -> Vec<u8> {
     let quality = &main_chord[idx..];
 
     // Build chord using compositional parsing
-    let mut chord_tones = parse_quality(root, quality);
+    let (third, fifth, seventh, extensions) = parse_quality(quality);
 
-    // Handle slash chord - add bass note
-    if let Some(bass_str) = bass_note {
+    // Handle slash chord bass note
+    let bass = bass_note.and_then(|bass_str| {
         let bass_chars: Vec<char> = bass_str.chars().collect();
-        if !bass_chars.is_empty() {
-            let bass_name = bass_chars[0];
-            let bass_accidental = if bass_chars.len() > 1 && (bass_chars[1] == '#' || bass_chars[1] == 'b') {
-                if bass_chars[1] == '#' { 1i8 } else { -1i8 }
-            } else {
-                0i8
-            };
-
-            let bass_base = match bass_name {
-                'C' => 48i8,
-                'D' => 50i8,
-                'E' => 52i8,
-                'F' => 53i8,
-                'G' => 55i8,
-                'A' => 57i8,
-                'B' => 59i8,
-                _ => 48i8,
-            };
-            let bass_midi = (bass_base + bass_accidental) as u8;
-
-            // Remove the bass note from chord tones if it's already there
-            chord_tones.retain(|&note| note != bass_midi);
-
-            // Insert bass at the beginning
-            chord_tones.insert(0, bass_midi);
+        if bass_chars.is_empty() {
+            return None;
         }
-    }
-
-    chord_tones
+        let bass_name = bass_chars[0];
+        let bass_accidental = if bass_chars.len() > 1 && (bass_chars[1] == '#' || bass_chars[1] == 'b') {
+            if bass_chars[1] == '#' { 1i8 } else { -1i8 }
+        } else {
+            0i8
+        };
+
+        let bass_base = match bass_name {
+            'C' => 48i8,
+            'D' => 50i8,
+            'E' => 52i8,
+            'F' => 53i8,
+            'G' => 55i8,
+            'A' => 57i8,
+            'B' => 59i8,
+            _ => 48i8,
+        };
+        Some((bass_base + bass_accidental) as u8)
+    });
+
+    Some(ChordStructure {
+        root,
+        third,
+        fifth,
+        seventh,
+        extensions,
+        bass,
+    })
 }
 
-/// Parse chord quality string and return intervals to add to root
-fn parse_quality(root: u8, quality: &str) -> Vec<u8> {
+/// Chord tones parsed from a quality string: (third, fifth, seventh, extensions)
+type QualityTones = (Option<u8>, Option<u8>, Option<u8>, Vec<u8>);
+
+/// Parse chord quality string and return the intervals above the root
+fn parse_quality(quality: &str) -> QualityTones {
     let mut third = Some(MAJOR_3RD);  // Default major third
     let mut fifth = Some(PERFECT_5TH); // Default perfect fifth
     let mut seventh: Option<u8> = None;
@@ -163,23 +220,7 @@ fn parse_quality(root: u8, quality: &str) -> Vec<u8> {
         }
     }
 
-    // Build final chord
-    let mut tones = vec![root];
-
-    if let Some(t) = third {
-        tones.push(root + t);
-    }
-    if let Some(f) = fifth {
-        tones.push(root + f);
-    }
-    if let Some(s) = seventh {
-        tones.push(root + s);
-    }
-    for ext in extensions {
-        tones.push(root + ext);
-    }
-
-    tones
+    (third, fifth, seventh, extensions)
 }
 
 /// Parse a single token from the quality string
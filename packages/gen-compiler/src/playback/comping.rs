@@ -0,0 +1,298 @@
+//! Accompaniment rhythm patterns (comping)
+//!
+//! Expands each block chord into a rhythmic sequence of chord events. Patterns are laid
+//! on a grid anchored to the barline, so a chord arriving mid-measure picks up the groove
+//! where the measure is. Patterns longer than a bar (the two-bar bossa) run over groups of
+//! bars counted from the start of the playback, as the drum clave does. Every event keeps
+//! the `symbol` and `osmd_timestamp` of the chord it came from, so visual highlighting
+//! still points at the original chord symbol.
+
+use crate::ast::CompingPattern;
+use crate::rational::Rational;
+use super::drums::{measure_origin, MeasureSpan};
+use super::types::PlaybackChord;
+
+/// Highest MIDI note used for the bass hits of waltz and stride patterns (B2)
+const BASS_CEILING: u8 = 47;
+
+/// Which notes of the chord voicing a hit plays
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Voices {
+    /// The whole voicing
+    Full,
+    /// The lowest note, moved into the bass register
+    Bass,
+    /// Everything above the lowest note
+    Upper,
+    /// One note at a time, cycling upward through the voicing; each note rings to the end of the chord
+    Arpeggio,
+}
+
+/// A hit in a pattern: (offset, length, voices), in eighth notes from the start of the cycle
+type Hit = (i64, i64, Voices);
+
+const HALF_NOTES: &[Hit] = &[(0, 4, Voices::Full)];
+const CHARLESTON: &[Hit] = &[(0, 3, Voices::Full), (3, 5, Voices::Full)];
+const BOSSA: &[Hit] = &[
+    (0, 3, Voices::Full),
+    (3, 3, Voices::Full),
+    (6, 2, Voices::Full),
+    (9, 3, Voices::Full),
+    (12, 4, Voices::Full),
+];
+const WALTZ: &[Hit] = &[(0, 2, Voices::Bass), (2, 2, Voices::Upper), (4, 2, Voices::Upper)];
+const BALLAD_ARPEGGIO: &[Hit] = &[(0, 1, Voices::Arpeggio)];
+const STRIDE: &[Hit] = &[(0, 2, Voices::Bass), (2, 2, Voices::Upper)];
+
+/// Cycle length in eighth notes and the hits within one cycle
+///
+/// Returns `None` for patterns that keep the block chord unchanged.
+fn pattern_hits(pattern: CompingPattern) -> Option<(i64, &'static [Hit])> {
+    match pattern {
+        CompingPattern::Pads => None,
+        CompingPattern::HalfNotes => Some((4, HALF_NOTES)),
+        CompingPattern::Charleston => Some((8, CHARLESTON)),
+        CompingPattern::Bossa => Some((16, BOSSA)),
+        CompingPattern::Waltz => Some((6, WALTZ)),
+        CompingPattern::BalladArpeggio => Some((1, BALLAD_ARPEGGIO)),
+        CompingPattern::Stride => Some((4, STRIDE)),
+    }
+}
+
+/// Expand block chords into comping events
+///
+/// # Parameters
+/// - `chords`: Block chords with times in time-signature beats
+/// - `measures`: The measures as played
+/// - `bar`: Length of a full bar in time-signature beats (to align pickup measures)
+/// - `quarter_beats`: Length of a quarter note in time-signature beats
+/// - `pattern`: The comping pattern to apply
+///
+/// A chord always sounds when it arrives: if it starts between pattern hits, an extra
+/// hit is added at its start, lasting until the next hit. Hits are cut off where the
+/// chord ends.
+pub(crate) fn expand_comping(
+    chords: &[PlaybackChord<Rational>],
+    measures: &[MeasureSpan],
+    bar: Rational,
+    quarter_beats: Rational,
+    pattern: CompingPattern,
+) -> Vec<PlaybackChord<Rational>> {
+    let Some((cycle_eighths, hits)) = pattern_hits(pattern) else {
+        return chords.to_vec();
+    };
+    let eighth = quarter_beats / Rational::from_integer(2);
+    let cycle = Rational::from_integer(cycle_eighths) * eighth;
+    // Number of bars one cycle spans (1 for patterns up to a bar long)
+    let bars_per_cycle = (cycle / bar + Rational::new(1, 2)).floor().max(1) as usize;
+
+    let mut events = Vec::new();
+    for chord in chords {
+        let start = chord.start_time;
+        let end = start + chord.duration;
+
+        // The cycle restarts on the first bar of each group of `bars_per_cycle` bars
+        // (a chord on a barline belongs to the bar that starts there)
+        let measure = measures.partition_point(|&(measure_start, _, _)| measure_start <= start).saturating_sub(1);
+        let anchor = measures
+            .get(measure - measure % bars_per_cycle)
+            .map_or(Rational::ZERO, |&span| measure_origin(span, bar));
+
+        // Collect hit times on the barline-anchored grid that fall within the chord
+        let mut timed_hits: Vec<(Rational, Rational, Voices)> = Vec::new();
+        let mut cycle_start = anchor + Rational::from_integer(((start - anchor) / cycle).floor()) * cycle;
+        while cycle_start < end {
+            for &(offset, length, voices) in hits {
+                let at = cycle_start + Rational::from_integer(offset) * eighth;
+                if at >= start && at < end {
+                    timed_hits.push((at, Rational::from_integer(length) * eighth, voices));
+                }
+            }
+            cycle_start += cycle;
+        }
+
+        let (_, first_length, first_voices) = hits[0];
+        if timed_hits.first().is_none_or(|&(at, _, _)| at > start) {
+            let first_length = Rational::from_integer(first_length) * eighth;
+            let length = timed_hits.first().map_or(first_length, |&(next, _, _)| first_length.min(next - start));
+            timed_hits.insert(0, (start, length, first_voices));
+        }
+
+        let mut arpeggio_step = 0;
+        for (at, length, voices) in timed_hits {
+            let midi_notes = select_voices(&chord.midi_notes, voices, &mut arpeggio_step);
+            if midi_notes.is_empty() {
+                continue;
+            }
+            let duration = if voices == Voices::Arpeggio { end - at } else { length.min(end - at) };
+            events.push(PlaybackChord {
+                symbol: chord.symbol.clone(),
+                midi_notes,
+                start_time: at,
+                duration,
+                osmd_timestamp: chord.osmd_timestamp,
+            });
+        }
+    }
+
+    events
+}
+
+/// Pick the notes of a voicing played by a hit
+fn select_voices(notes: &[u8], voices: Voices, arpeggio_step: &mut usize) -> Vec<u8> {
+    let mut sorted = notes.to_vec();
+    sorted.sort_unstable();
+
+    match voices {
+        Voices::Full => notes.to_vec(),
+        Voices::Bass => sorted
+            .first()
+            .map(|&lowest| {
+                let mut bass = lowest;
+                while bass > BASS_CEILING {
+                    bass -= 12;
+                }
+                vec![bass]
+            })
+            .unwrap_or_default(),
+        Voices::Upper if sorted.len() > 1 => sorted[1..].to_vec(),
+        Voices::Upper => sorted,
+        Voices::Arpeggio => {
+            if sorted.is_empty() {
+                return sorted;
+            }
+            let note = sorted[*arpeggio_step % sorted.len()];
+            *arpeggio_step += 1;
+            vec![note]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` consecutive full bars of `length` beats
+    fn bars(count: i64, length: i64) -> Vec<MeasureSpan> {
+        (0..count)
+            .map(|i| (Rational::from_integer(i * length), Rational::from_integer(length), false))
+            .collect()
+    }
+
+    const fn beats(n: i64) -> Rational {
+        Rational::from_integer(n)
+    }
+
+    fn block(symbol: &str, notes: Vec<u8>, start_time: i64, duration: i64) -> PlaybackChord<Rational> {
+        PlaybackChord {
+            symbol: symbol.to_string(),
+            midi_notes: notes,
+            start_time: start_time.into(),
+            duration: duration.into(),
+            osmd_timestamp: start_time.into(),
+        }
+    }
+
+    fn times(events: &[PlaybackChord<Rational>]) -> Vec<(f64, f64)> {
+        events.iter().map(|e| (e.start_time.to_f64(), e.duration.to_f64())).collect()
+    }
+
+    #[test]
+    fn test_pads_keep_block_chords() {
+        let chords = vec![block("C", vec![48, 52, 55], 0, 4)];
+        let events = expand_comping(&chords, &bars(1, 4), beats(4), Rational::ONE, CompingPattern::Pads);
+        assert_eq!(times(&events), vec![(0.0, 4.0)]);
+    }
+
+    #[test]
+    fn test_half_note_pulse() {
+        let chords = vec![block("C", vec![48, 52, 55], 0, 4)];
+        let events = expand_comping(&chords, &bars(1, 4), beats(4), Rational::ONE, CompingPattern::HalfNotes);
+        assert_eq!(times(&events), vec![(0.0, 2.0), (2.0, 2.0)]);
+        assert!(events.iter().all(|e| e.midi_notes == vec![48, 52, 55]));
+    }
+
+    #[test]
+    fn test_charleston_over_two_bars() {
+        let chords = vec![block("C", vec![48, 52, 55], 0, 8)];
+        let events = expand_comping(&chords, &bars(2, 4), beats(4), Rational::ONE, CompingPattern::Charleston);
+        assert_eq!(times(&events), vec![(0.0, 1.5), (1.5, 2.5), (4.0, 1.5), (5.5, 2.5)]);
+    }
+
+    #[test]
+    fn test_chord_change_mid_measure_follows_bar_grid() {
+        // C for two beats, then G7 on beat 3 with the Charleston
+        let chords = vec![
+            block("C", vec![48, 52, 55], 0, 2),
+            block("G7", vec![55, 59, 62, 65], 2, 2),
+        ];
+        let events = expand_comping(&chords, &bars(1, 4), beats(4), Rational::ONE, CompingPattern::Charleston);
+
+        // C: beat 1 and the "and" of 2 (cut off at beat 3)
+        // G7: no grid hit until the next bar, so it sounds on arrival
+        assert_eq!(times(&events), vec![(0.0, 1.5), (1.5, 0.5), (2.0, 1.5)]);
+        assert_eq!(events[2].symbol, "G7");
+        assert_eq!(events[2].osmd_timestamp, beats(2));
+    }
+
+    #[test]
+    fn test_waltz_bass_and_upper() {
+        let chords = vec![block("C", vec![48, 52, 55], 0, 3)];
+        let events = expand_comping(&chords, &bars(1, 3), beats(3), Rational::ONE, CompingPattern::Waltz);
+
+        assert_eq!(times(&events), vec![(0.0, 1.0), (1.0, 1.0), (2.0, 1.0)]);
+        assert_eq!(events[0].midi_notes, vec![36]); // C2
+        assert_eq!(events[1].midi_notes, vec![52, 55]);
+        assert_eq!(events[2].midi_notes, vec![52, 55]);
+    }
+
+    #[test]
+    fn test_stride_alternates_bass_and_chord() {
+        let chords = vec![block("F", vec![53, 57, 60], 0, 4)];
+        let events = expand_comping(&chords, &bars(1, 4), beats(4), Rational::ONE, CompingPattern::Stride);
+
+        let notes: Vec<Vec<u8>> = events.iter().map(|e| e.midi_notes.clone()).collect();
+        assert_eq!(notes, vec![vec![41], vec![57, 60], vec![41], vec![57, 60]]);
+    }
+
+    #[test]
+    fn test_ballad_arpeggio_rolls_upward_and_rings() {
+        let chords = vec![block("Am", vec![57, 60, 64], 0, 2)];
+        let events = expand_comping(&chords, &bars(1, 4), beats(4), Rational::ONE, CompingPattern::BalladArpeggio);
+
+        let notes: Vec<u8> = events.iter().map(|e| e.midi_notes[0]).collect();
+        assert_eq!(notes, vec![57, 60, 64, 57]);
+        assert_eq!(times(&events), vec![(0.0, 2.0), (0.5, 1.5), (1.0, 1.0), (1.5, 0.5)]);
+    }
+
+    #[test]
+    fn test_six_eight_uses_quarter_grid() {
+        // In 6/8 a quarter note is 2 beats: half-note pulse hits every 4 eighths
+        let chords = vec![block("C", vec![48, 52, 55], 0, 6)];
+        let events = expand_comping(&chords, &bars(1, 6), beats(6), beats(2), CompingPattern::HalfNotes);
+        assert_eq!(times(&events), vec![(0.0, 4.0), (4.0, 2.0)]);
+    }
+
+    #[test]
+    fn test_bossa_continues_into_second_bar() {
+        // One chord per bar: the second bar plays the second half of the two-bar pattern
+        let chords = vec![
+            block("Dm7", vec![50, 53, 57, 60], 0, 4),
+            block("G7", vec![55, 59, 62, 65], 4, 4),
+        ];
+        let events = expand_comping(&chords, &bars(2, 4), beats(4), Rational::ONE, CompingPattern::Bossa);
+
+        // Dm7: 1, the "and" of 2, 4; G7 sounds on arrival until the "and" of 1, then 3
+        assert_eq!(times(&events), vec![(0.0, 1.5), (1.5, 1.5), (3.0, 1.0), (4.0, 0.5), (4.5, 1.5), (6.0, 2.0)]);
+        assert!(events[3..].iter().all(|e| e.symbol == "G7"));
+    }
+
+    #[test]
+    fn test_bossa_restarts_every_two_bars() {
+        let chords: Vec<_> = (0..3).map(|bar| block("C", vec![48, 52, 55], bar * 4, 4)).collect();
+        let events = expand_comping(&chords, &bars(3, 4), beats(4), Rational::ONE, CompingPattern::Bossa);
+
+        // The third bar starts the pattern again
+        assert_eq!(times(&events)[6..], [(8.0, 1.5), (9.5, 1.5), (11.0, 1.0)]);
+    }
+}
@@ -0,0 +1,294 @@
+//! Metronome, count-in and drum groove generation
+//!
+//! Produces General MIDI percussion hits (channel 10 note numbers) aligned to the
+//! measures of the playback sequence. Hits are laid on a grid anchored to each barline;
+//! pickup measures are aligned to the end of a full bar so their clicks land on the
+//! right beats.
+
+use crate::rational::Rational;
+use super::types::{GroovePattern, PlaybackDrumHit};
+
+/// General MIDI percussion note numbers
+mod gm {
+    pub const METRONOME_CLICK: u8 = 33;
+    pub const METRONOME_BELL: u8 = 34;
+    pub const BASS_DRUM: u8 = 36;
+    pub const SIDE_STICK: u8 = 37;
+    pub const SNARE: u8 = 38;
+    pub const CLOSED_HI_HAT: u8 = 42;
+    pub const PEDAL_HI_HAT: u8 = 44;
+    pub const RIDE: u8 = 51;
+}
+
+/// Velocity of the click on beat 1
+const ACCENT_VELOCITY: u8 = 127;
+/// Velocity of the clicks on the other beats
+const BEAT_VELOCITY: u8 = 96;
+/// Velocity of subdivision clicks between beats
+const SUBDIVISION_VELOCITY: u8 = 64;
+
+/// A measure as played: (start time, length, is pickup), in time-signature beats
+pub(crate) type MeasureSpan = (Rational, Rational, bool);
+
+/// Beat layout shared by the metronome, count-in and grooves, in time-signature beats
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DrumGrid {
+    /// Length of a full bar
+    pub bar: Rational,
+    /// Length of one metronome click (the tempo's beat unit, e.g. dotted quarter for "120*")
+    pub click: Rational,
+    /// Length of a quarter note (groove patterns are written in quarters)
+    pub quarter: Rational,
+}
+
+impl DrumGrid {
+    /// Length of a single drum hit
+    fn hit_length(&self) -> Rational {
+        self.quarter / Rational::from_integer(4)
+    }
+
+    /// Where the bar grid starts for a measure
+    fn origin(&self, span: MeasureSpan) -> Rational {
+        measure_origin(span, self.bar)
+    }
+}
+
+/// Where the bar grid starts for a measure of a `bar`-long time signature
+///
+/// Pickups are aligned to the end of a full bar, so a one-beat pickup in 4/4 starts on beat 4.
+pub(crate) fn measure_origin((start, length, is_pickup): MeasureSpan, bar: Rational) -> Rational {
+    if is_pickup {
+        start - (bar - length).max(Rational::ZERO)
+    } else {
+        start
+    }
+}
+
+/// Clicks for an N-bar count-in starting at time 0 (beats only, accent on each downbeat)
+pub(crate) fn count_in_hits(grid: &DrumGrid, bars: u8) -> Vec<PlaybackDrumHit<Rational>> {
+    let spans: Vec<MeasureSpan> = (0..bars)
+        .map(|bar| (Rational::from_integer(bar as i64) * grid.bar, grid.bar, false))
+        .collect();
+    metronome_hits(&spans, grid, 1)
+}
+
+/// Metronome clicks for each measure, with `subdivision` clicks per beat
+///
+/// Beat 1 of each bar is accented with the metronome bell; other beats use the click,
+/// and subdivisions are played softer.
+pub(crate) fn metronome_hits(measures: &[MeasureSpan], grid: &DrumGrid, subdivision: u8) -> Vec<PlaybackDrumHit<Rational>> {
+    let step = grid.click / Rational::from_integer(subdivision.max(1) as i64);
+    let mut hits = Vec::new();
+
+    for &span in measures {
+        let (start, length, _) = span;
+        let origin = grid.origin(span);
+        let end = start + length;
+
+        let mut k = 0;
+        loop {
+            let at = origin + Rational::from_integer(k) * step;
+            if at >= end {
+                break;
+            }
+            k += 1;
+            if at < start {
+                continue;
+            }
+
+            let position = at - origin;
+            let on_beat = (position / grid.click).is_integer();
+            let (midi_note, velocity) = if position == Rational::ZERO {
+                (gm::METRONOME_BELL, ACCENT_VELOCITY)
+            } else if on_beat {
+                (gm::METRONOME_CLICK, BEAT_VELOCITY)
+            } else {
+                (gm::METRONOME_CLICK, SUBDIVISION_VELOCITY)
+            };
+            hits.push(PlaybackDrumHit {
+                midi_note,
+                velocity,
+                start_time: at,
+                duration: grid.hit_length(),
+            });
+        }
+    }
+
+    hits
+}
+
+/// Drum groove hits for each measure
+///
+/// Patterns are written for a bar of 4/4 in quarter notes; shorter bars play the
+/// beginning of the pattern.
+pub(crate) fn groove_hits(measures: &[MeasureSpan], grid: &DrumGrid, groove: GroovePattern) -> Vec<PlaybackDrumHit<Rational>> {
+    let mut hits = Vec::new();
+
+    for (bar_index, &span) in measures.iter().enumerate() {
+        let (start, length, _) = span;
+        let origin = grid.origin(span);
+        let end = start + length;
+
+        for (offset, midi_note, velocity) in groove_bar(groove, bar_index) {
+            let at = origin + offset * grid.quarter;
+            if at >= start && at < end {
+                hits.push(PlaybackDrumHit {
+                    midi_note,
+                    velocity,
+                    start_time: at,
+                    duration: grid.hit_length(),
+                });
+            }
+        }
+    }
+
+    hits.sort_by_key(|hit| hit.start_time);
+    hits
+}
+
+/// One bar of a groove: (offset in quarter notes, GM note, velocity)
+fn groove_bar(groove: GroovePattern, bar_index: usize) -> Vec<(Rational, u8, u8)> {
+    let quarters = |n: i64, d: i64| Rational::new(n, d);
+    let eighths = |midi_note: u8, velocity: u8| (0..8).map(move |i| (quarters(i, 2), midi_note, velocity));
+
+    match groove {
+        GroovePattern::SwingRide => vec![
+            // "ding, ding-a ding, ding-a" with the hi-hat foot on 2 and 4
+            (quarters(0, 1), gm::RIDE, 96),
+            (quarters(1, 1), gm::RIDE, 110),
+            (quarters(1, 1), gm::PEDAL_HI_HAT, 90),
+            (quarters(5, 3), gm::RIDE, 70),
+            (quarters(2, 1), gm::RIDE, 96),
+            (quarters(3, 1), gm::RIDE, 110),
+            (quarters(3, 1), gm::PEDAL_HI_HAT, 90),
+            (quarters(11, 3), gm::RIDE, 70),
+        ],
+        GroovePattern::Rock => {
+            let mut bar: Vec<(Rational, u8, u8)> = eighths(gm::CLOSED_HI_HAT, 80).collect();
+            bar.extend([
+                (quarters(0, 1), gm::BASS_DRUM, 110),
+                (quarters(1, 1), gm::SNARE, 110),
+                (quarters(2, 1), gm::BASS_DRUM, 110),
+                (quarters(5, 2), gm::BASS_DRUM, 100),
+                (quarters(3, 1), gm::SNARE, 110),
+            ]);
+            bar
+        }
+        GroovePattern::Bossa => {
+            let mut bar: Vec<(Rational, u8, u8)> = eighths(gm::CLOSED_HI_HAT, 60).collect();
+            bar.extend([
+                (quarters(0, 1), gm::BASS_DRUM, 90),
+                (quarters(3, 2), gm::BASS_DRUM, 80),
+                (quarters(2, 1), gm::BASS_DRUM, 90),
+                (quarters(7, 2), gm::BASS_DRUM, 80),
+            ]);
+            // Two-bar bossa clave on the side stick
+            let clave = if bar_index.is_multiple_of(2) {
+                vec![quarters(0, 1), quarters(3, 2), quarters(3, 1)]
+            } else {
+                vec![quarters(1, 1), quarters(5, 2)]
+            };
+            bar.extend(clave.into_iter().map(|offset| (offset, gm::SIDE_STICK, 100)));
+            bar
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOUR_FOUR: DrumGrid = DrumGrid { bar: beats(4), click: beats(1), quarter: beats(1) };
+
+    const fn beats(n: i64) -> Rational {
+        Rational::from_integer(n)
+    }
+
+    fn bar(start: i64, length: i64, is_pickup: bool) -> MeasureSpan {
+        (beats(start), beats(length), is_pickup)
+    }
+
+    fn starts(hits: &[PlaybackDrumHit<Rational>]) -> Vec<f64> {
+        hits.iter().map(|h| h.start_time.to_f64()).collect()
+    }
+
+    #[test]
+    fn test_metronome_accents_downbeat() {
+        let hits = metronome_hits(&[bar(0, 4, false), bar(4, 4, false)], &FOUR_FOUR, 1);
+
+        assert_eq!(starts(&hits), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert_eq!(hits[0].midi_note, gm::METRONOME_BELL);
+        assert_eq!(hits[0].velocity, ACCENT_VELOCITY);
+        assert_eq!(hits[1].midi_note, gm::METRONOME_CLICK);
+        assert_eq!(hits[4].velocity, ACCENT_VELOCITY);
+    }
+
+    #[test]
+    fn test_metronome_subdivision() {
+        let hits = metronome_hits(&[bar(0, 2, false)], &DrumGrid { bar: beats(2), ..FOUR_FOUR }, 2);
+
+        assert_eq!(starts(&hits), vec![0.0, 0.5, 1.0, 1.5]);
+        let velocities: Vec<u8> = hits.iter().map(|h| h.velocity).collect();
+        assert_eq!(velocities, vec![ACCENT_VELOCITY, SUBDIVISION_VELOCITY, BEAT_VELOCITY, SUBDIVISION_VELOCITY]);
+    }
+
+    #[test]
+    fn test_dotted_beat_clicks() {
+        // 6/8 with a dotted-quarter beat: two clicks per bar
+        let grid = DrumGrid { bar: beats(6), click: beats(3), quarter: beats(2) };
+        let hits = metronome_hits(&[bar(0, 6, false)], &grid, 1);
+        assert_eq!(starts(&hits), vec![0.0, 3.0]);
+    }
+
+    #[test]
+    fn test_pickup_measure_clicks_on_last_beat() {
+        // One-beat pickup in 4/4: the click is beat 4, not an accented downbeat
+        let hits = metronome_hits(&[bar(0, 1, true), bar(1, 4, false)], &FOUR_FOUR, 1);
+
+        assert_eq!(starts(&hits), vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(hits[0].velocity, BEAT_VELOCITY);
+        assert_eq!(hits[1].velocity, ACCENT_VELOCITY);
+    }
+
+    #[test]
+    fn test_count_in() {
+        let hits = count_in_hits(&FOUR_FOUR, 2);
+        assert_eq!(hits.len(), 8);
+        assert_eq!(hits[4].start_time, beats(4));
+        assert_eq!(hits[4].midi_note, gm::METRONOME_BELL);
+    }
+
+    #[test]
+    fn test_rock_groove() {
+        let hits = groove_hits(&[bar(0, 4, false)], &FOUR_FOUR, GroovePattern::Rock);
+
+        let snares: Vec<Rational> = hits.iter().filter(|h| h.midi_note == gm::SNARE).map(|h| h.start_time).collect();
+        assert_eq!(snares, vec![beats(1), beats(3)]);
+        assert_eq!(hits.iter().filter(|h| h.midi_note == gm::CLOSED_HI_HAT).count(), 8);
+    }
+
+    #[test]
+    fn test_swing_ride_skip_notes() {
+        let hits = groove_hits(&[bar(0, 4, false)], &FOUR_FOUR, GroovePattern::SwingRide);
+
+        let ride: Vec<Rational> = hits.iter().filter(|h| h.midi_note == gm::RIDE).map(|h| h.start_time).collect();
+        assert_eq!(ride.len(), 6);
+        assert_eq!(ride[2], Rational::new(5, 3));
+    }
+
+    #[test]
+    fn test_bossa_clave_alternates_bars() {
+        let spans = [bar(0, 4, false), bar(4, 4, false)];
+        let hits = groove_hits(&spans, &FOUR_FOUR, GroovePattern::Bossa);
+
+        let clave: Vec<f64> = hits.iter().filter(|h| h.midi_note == gm::SIDE_STICK).map(|h| h.start_time.to_f64()).collect();
+        assert_eq!(clave, vec![0.0, 1.5, 3.0, 5.0, 6.5]);
+    }
+
+    #[test]
+    fn test_groove_truncated_in_three_four() {
+        let grid = DrumGrid { bar: beats(3), ..FOUR_FOUR };
+        let hits = groove_hits(&[bar(0, 3, false)], &grid, GroovePattern::Rock);
+        assert!(hits.iter().all(|h| h.start_time < beats(3)));
+    }
+}
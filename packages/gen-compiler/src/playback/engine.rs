@@ -4,10 +4,16 @@
 //! audio playback and visual note highlighting.
 
 use crate::ast::*;
+use crate::accidentals::AccidentalCarry;
 use crate::error::GenError;
 use crate::parser::parse;
-use super::chord_parser::parse_chord_symbol;
-use super::types::{PlaybackData, PlaybackNote, PlaybackChord, SwingType};
+use crate::rational::Rational;
+use super::bass::generate_bass_line;
+use super::comping::expand_comping;
+use super::drums::{count_in_hits, groove_hits, metronome_hits, DrumGrid, MeasureSpan};
+use super::swing::SwingGrid;
+use super::types::{element_id, PlaybackBassNote, PlaybackChord, PlaybackData, PlaybackDrumHit, PlaybackNote, PlaybackOptions, SwingType};
+use super::voicing::{apply_voice_leading, voice_chord_symbol};
 
 /// Build an expanded sequence of measure indices that respects repeats and volta endings.
 ///
@@ -160,7 +166,7 @@ fn build_playback_sequence(measures: &[Measure]) -> Vec<(usize, usize)> {
 /// - `source`: Gen source code string
 /// - `clef`: "treble" or "bass" - affects display MIDI notes
 /// - `octave_shift`: Shift playback pitch by N octaves (-2 to +2 typical)
-/// - `instrument_group`: Reserved for future mod point support (currently unused)
+/// - `instrument_group`: "eb" or "bb" - applies that group's mod points (`@Eb:^`) per line
 ///
 /// # Returns
 /// `PlaybackData` containing:
@@ -170,20 +176,21 @@ fn build_playback_sequence(measures: &[Measure]) -> Vec<(usize, usize)> {
 /// - Tempo in BPM
 ///
 /// # Timing System
-/// This function maintains **two separate timing tracks**:
+/// All times are accumulated as exact fractions ([`Rational`]) and only converted to
+/// `f64` when written into the output. This function maintains **two timing tracks**:
 ///
 /// ## 1. Playback Time (current_time)
 /// - Used for actual audio playback
-/// - Correctly calculates triplet durations
-/// - Example: Quarter note triplet = 0.667 beats per note
+/// - Follows repeats and endings
+/// - Example: Quarter note triplet = exactly 2/3 beat per note
 ///
 /// ## 2. OSMD Time (osmd_time)
 /// - Used for visual note matching with OpenSheetMusicDisplay
-/// - Uses MusicXML quantized durations
-/// - Example: Quarter note triplet = 0.5 beats per note (MusicXML quantization)
+/// - Linear through the score (ignores repeats), like the rendered sheet music
+/// - Uses the same exact durations as the MusicXML `<divisions>`
 ///
 /// This dual-timing system ensures:
-/// - Audio plays back at correct speed (using playback time)
+/// - Audio plays back in order (using playback time)
 /// - Visual highlighting matches the rendered sheet music (using OSMD time)
 ///
 /// # MIDI Note System
@@ -223,6 +230,45 @@ pub fn generate_playback_data(
     octave_shift: i8,
     instrument_group: Option<&str>,
     transpose_key: Option<&str>,
+) -> Result<PlaybackData, GenError> {
+    generate_playback_data_with_options(
+        source,
+        clef,
+        octave_shift,
+        instrument_group,
+        transpose_key,
+        &PlaybackOptions::default(),
+    )
+}
+
+/// Generate playback data with custom accompaniment options
+///
+/// Same as [`generate_playback_data()`], but chord accompaniment is voiced according
+/// to `options` (voicing style and optional voice leading between chords) and played
+/// with a comping pattern. A comping pattern in `options` overrides the score's
+/// `comping:` metadata. Setting `options.bass` also generates a bass line from the
+/// chord symbols into [`PlaybackData::bass`].
+///
+/// # Example
+/// ```rust
+/// use gen::playback::generate_playback_data_with_options;
+/// use gen::{PlaybackOptions, VoicingStyle};
+///
+/// let options = PlaybackOptions {
+///     voicing: VoicingStyle::Shell,
+///     ..Default::default()
+/// };
+/// let data = generate_playback_data_with_options("{G7} G A B C", "treble", 0, None, None, &options).unwrap();
+///
+/// assert_eq!(data.chords[0].midi_notes, vec![55, 59, 65]); // G, B, F
+/// ```
+pub fn generate_playback_data_with_options(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<&str>,
+    transpose_key: Option<&str>,
+    options: &PlaybackOptions,
 ) -> Result<PlaybackData, GenError> {
     let score = parse(source)?;
 
@@ -244,54 +290,33 @@ pub fn generate_playback_data(
         0
     };
 
-    let total_offset = clef_offset + octave_shift;
-    let _group = instrument_group.and_then(InstrumentGroup::from_str); // Reserved for future mod point support
+    let group = instrument_group.and_then(InstrumentGroup::from_str);
 
-    let mut current_time = 0.0;      // Playback time (triplet-adjusted)
+    let mut current_time = Rational::ZERO; // Playback time (exact, follows repeats)
     let mut notes = Vec::new();
     let mut chords = Vec::new();
+    let mut measure_spans: Vec<MeasureSpan> = Vec::new(); // Measures as played (for comping, swing and drums)
     let mut current_key = score.metadata.key_signature.clone();
-    let mut pending_tie: Option<(usize, f64)> = None; // (note index, accumulated duration)
+    let mut accidentals = AccidentalCarry::new(score.metadata.accidentals);
+    let mut pending_tie: Option<(usize, Rational)> = None; // (note index, accumulated duration)
     let mut note_index = 0usize;
 
     // Calculate conversion factor from time-signature beats to quarter-note beats for OSMD matching
-    // For 12/8 (beat_type=8): eighth note = 1 TS beat = 0.5 quarter notes, so multiply by 0.5
-    // For 4/4 (beat_type=4): quarter note = 1 TS beat = 1.0 quarter note, so multiply by 1.0
-    let osmd_to_quarter_multiplier = 4.0 / score.metadata.time_signature.beat_type as f64;
+    // For 12/8 (beat_type=8): eighth note = 1 TS beat = 1/2 quarter note
+    // For 4/4 (beat_type=4): quarter note = 1 TS beat = 1 quarter note
+    let osmd_to_quarter_multiplier = Rational::new(4, score.metadata.time_signature.beat_type as i64);
+
+    let octave_shifts = score.octave_shifts(octave_shift, group);
 
     // Pre-calculate OSMD timestamps for each measure (linear, ignoring repeats)
     // OSMD renders the sheet music linearly, so we need to use the original timestamps
     // when we repeat back to an earlier measure for highlighting to match.
-    let mut measure_osmd_times: Vec<f64> = Vec::with_capacity(score.measures.len());
-    let mut osmd_time = 0.0;
+    let mut measure_osmd_times: Vec<Rational> = Vec::with_capacity(score.measures.len());
+    let mut osmd_time = Rational::ZERO;
     for measure in &score.measures {
         measure_osmd_times.push(osmd_time);
         for element in &measure.elements {
-            let osmd_duration = match element {
-                Element::Note(note) => {
-                    let base = note.duration.as_beats(&score.metadata.time_signature);
-                    let with_dot = if note.dotted { base * 1.5 } else { base };
-                    if let Some(tuplet) = &note.tuplet {
-                        let divisions = 4.0;
-                        let musicxml_dur = ((tuplet.normal_notes as f64 * with_dot * divisions) / tuplet.actual_notes as f64).floor();
-                        musicxml_dur / divisions
-                    } else {
-                        with_dot
-                    }
-                },
-                Element::Rest { duration: rest_dur, dotted, tuplet, .. } => {
-                    let base = rest_dur.as_beats(&score.metadata.time_signature);
-                    let with_dot = if *dotted { base * 1.5 } else { base };
-                    if let Some(t) = tuplet {
-                        let divisions = 4.0;
-                        let musicxml_dur = ((t.normal_notes as f64 * with_dot * divisions) / t.actual_notes as f64).floor();
-                        musicxml_dur / divisions
-                    } else {
-                        with_dot
-                    }
-                },
-            };
-            osmd_time += osmd_duration;
+            osmd_time += element.total_beats(&score.metadata.time_signature);
         }
     }
 
@@ -306,59 +331,41 @@ pub fn generate_playback_data(
         // Get OSMD time for this measure from pre-calculated values
         // This ensures repeated measures use their original OSMD timestamps for highlighting
         let measure_osmd_start = measure_osmd_times[*measure_idx];
-        let mut element_osmd_offset = 0.0;
+        let mut element_osmd_offset = Rational::ZERO;
 
         // Check for key changes
         if let Some(new_key) = &measure.key_change {
             current_key = new_key.clone();
         }
+        accidentals.start_measure();
 
-        for element in &measure.elements {
-            let duration = element.total_beats(&score.metadata.time_signature);
+        // Apply the mod point on this measure's source line for the instrument group (as the MusicXML does)
+        let measure_octave_shift = octave_shifts[*measure_idx];
+        let total_offset = clef_offset + measure_octave_shift;
 
-            // Calculate OSMD duration for this element (for tracking offset within measure)
-            let osmd_duration = match element {
-                Element::Note(note) => {
-                    let base = note.duration.as_beats(&score.metadata.time_signature);
-                    let with_dot = if note.dotted { base * 1.5 } else { base };
-                    if let Some(tuplet) = &note.tuplet {
-                        let divisions = 4.0;
-                        let musicxml_dur = ((tuplet.normal_notes as f64 * with_dot * divisions) / tuplet.actual_notes as f64).floor();
-                        musicxml_dur / divisions
-                    } else {
-                        with_dot
-                    }
-                },
-                Element::Rest { duration: rest_dur, dotted, tuplet, .. } => {
-                    let base = rest_dur.as_beats(&score.metadata.time_signature);
-                    let with_dot = if *dotted { base * 1.5 } else { base };
-                    if let Some(t) = tuplet {
-                        let divisions = 4.0;
-                        let musicxml_dur = ((t.normal_notes as f64 * with_dot * divisions) / t.actual_notes as f64).floor();
-                        musicxml_dur / divisions
-                    } else {
-                        with_dot
-                    }
-                },
-            };
+        for (element_index, element) in measure.elements.iter().enumerate() {
+            let duration = element.total_beats(&score.metadata.time_signature);
 
             // Calculate the OSMD timestamp for this element using pre-calculated measure start
             let element_osmd_time = measure_osmd_start + element_osmd_offset;
 
             match element {
                 Element::Note(note) => {
+                    // Resolve the pitch as the MusicXML does (accidentals may carry through the measure)
+                    let alter = accidentals.resolve(note, &current_key);
+
                     // Handle chord symbol if present - uses its own duration (independent from melody)
                     if let Some(chord_ann) = &note.chord {
-                        let chord_notes = parse_chord_symbol(&chord_ann.symbol);
+                        let chord_notes = voice_chord_symbol(&chord_ann.symbol, options.voicing);
                         if !chord_notes.is_empty() {
                             // Use chord's own duration (defaults to whole note)
                             let chord_duration = chord_ann.duration_beats(&score.metadata.time_signature);
-                            let osmd_quarter_time = element_osmd_time * osmd_to_quarter_multiplier;
                             chords.push(PlaybackChord {
+                                symbol: chord_ann.symbol.clone(),
                                 midi_notes: chord_notes,
                                 start_time: current_time,
                                 duration: chord_duration,
-                                osmd_timestamp: osmd_quarter_time,
+                                osmd_timestamp: element_osmd_time * osmd_to_quarter_multiplier,
                             });
                         }
                     }
@@ -367,11 +374,11 @@ pub fn generate_playback_data(
                         // Start of a tied group - create note and track it
                         let note_idx = notes.len();
                         let beat_in_measure = current_time - measure_start_time;
-                        let display_midi_base = note.to_midi_note(&current_key, total_offset);
+                        let display_midi_base = note.to_midi_note_with_alter(alter, total_offset);
                         let display_midi = (display_midi_base as i16 + transposition_chromatic as i16).clamp(0, 127) as u8;
                         let osmd_quarter_time = element_osmd_time * osmd_to_quarter_multiplier;
                         notes.push(PlaybackNote {
-                            midi_note: note.to_midi_note(&current_key, octave_shift), // Playback pitch (with octave shift, no clef offset)
+                            midi_note: note.to_midi_note_with_alter(alter, measure_octave_shift), // Playback pitch (with octave shift, no clef offset)
                             display_midi_note: display_midi, // Display pitch (with full offset + transposition)
                             start_time: current_time,
                             duration,
@@ -379,7 +386,8 @@ pub fn generate_playback_data(
                             measure_number,
                             beat_in_measure,
                             osmd_timestamp: osmd_quarter_time,
-                            osmd_match_key: format!("{}_{:.3}", display_midi, osmd_quarter_time),
+                            osmd_match_key: format!("{}_{}", display_midi, osmd_quarter_time),
+                            element_id: element_id(*measure_idx, element_index),
                         });
                         note_index += 1;
                         pending_tie = Some((note_idx, duration));
@@ -398,11 +406,11 @@ pub fn generate_playback_data(
                     } else {
                         // Regular note (not tied)
                         let beat_in_measure = current_time - measure_start_time;
-                        let display_midi_base = note.to_midi_note(&current_key, total_offset);
+                        let display_midi_base = note.to_midi_note_with_alter(alter, total_offset);
                         let display_midi = (display_midi_base as i16 + transposition_chromatic as i16).clamp(0, 127) as u8;
                         let osmd_quarter_time = element_osmd_time * osmd_to_quarter_multiplier;
                         notes.push(PlaybackNote {
-                            midi_note: note.to_midi_note(&current_key, octave_shift), // Playback pitch (with octave shift, no clef offset)
+                            midi_note: note.to_midi_note_with_alter(alter, measure_octave_shift), // Playback pitch (with octave shift, no clef offset)
                             display_midi_note: display_midi, // Display pitch (with full offset + transposition)
                             start_time: current_time,
                             duration,
@@ -410,7 +418,8 @@ pub fn generate_playback_data(
                             measure_number,
                             beat_in_measure,
                             osmd_timestamp: osmd_quarter_time,
-                            osmd_match_key: format!("{}_{:.3}", display_midi, osmd_quarter_time),
+                            osmd_match_key: format!("{}_{}", display_midi, osmd_quarter_time),
+                            element_id: element_id(*measure_idx, element_index),
                         });
                         note_index += 1;
                         pending_tie = None;
@@ -419,16 +428,16 @@ pub fn generate_playback_data(
                 Element::Rest { chord, .. } => {
                     // Handle chord symbol on rest if present - uses its own duration
                     if let Some(chord_ann) = chord {
-                        let chord_notes = parse_chord_symbol(&chord_ann.symbol);
+                        let chord_notes = voice_chord_symbol(&chord_ann.symbol, options.voicing);
                         if !chord_notes.is_empty() {
                             // Use chord's own duration (defaults to whole note)
                             let chord_duration = chord_ann.duration_beats(&score.metadata.time_signature);
-                            let osmd_quarter_time = element_osmd_time * osmd_to_quarter_multiplier;
                             chords.push(PlaybackChord {
+                                symbol: chord_ann.symbol.clone(),
                                 midi_notes: chord_notes,
                                 start_time: current_time,
                                 duration: chord_duration,
-                                osmd_timestamp: osmd_quarter_time,
+                                osmd_timestamp: element_osmd_time * osmd_to_quarter_multiplier,
                             });
                         }
                     }
@@ -437,37 +446,81 @@ pub fn generate_playback_data(
                 }
             }
 
-            current_time += duration;            // Playback time (triplet-adjusted)
-            element_osmd_offset += osmd_duration; // Track position within measure for OSMD
+            current_time += duration;         // Playback time (follows repeats)
+            element_osmd_offset += duration;  // Track position within measure for OSMD
         }
+
+        measure_spans.push((measure_start_time, current_time - measure_start_time, measure.is_pickup));
+    }
+
+    if options.voice_leading {
+        apply_voice_leading(&mut chords, options.voicing);
+    }
+
+    // Bass line follows the chord symbols, so build it from the block chords before comping
+    let bass = match options.bass {
+        Some(style) => {
+            let time_sig = &score.metadata.time_signature;
+            let compound = time_sig.beat_type == 8 && time_sig.beats % 3 == 0 && time_sig.beats > 3;
+            let beat = if compound {
+                crate::ast::Duration::Quarter.modified_beats(time_sig, true, &[]) // Dotted quarter
+            } else {
+                crate::ast::Duration::Quarter.as_beats(time_sig)
+            };
+            generate_bass_line(&chords, beat, style)
+        }
+        None => Vec::new(),
+    };
+
+    // Expand block chords into the comping pattern (option takes precedence over metadata)
+    let bar = Rational::from_integer(score.metadata.time_signature.beats as i64);
+    if let Some(pattern) = options.comping.or(score.metadata.comping) {
+        let quarter_beats = crate::ast::Duration::Quarter.as_beats(&score.metadata.time_signature);
+        chords = expand_comping(&chords, &measure_spans, bar, quarter_beats, pattern);
     }
 
     // Get tempo and calculate beat conversion
     // If tempo specifies a rhythm (e.g., "*88" = dotted quarter), use that as the beat unit
     // Otherwise default to quarter note
-    let (_tempo_bpm, tempo_beat_duration) = if let Some(ref tempo) = score.metadata.tempo {
-        let beat_duration = tempo.duration.as_beats(&score.metadata.time_signature);
-        let with_dot = if tempo.dotted { beat_duration * 1.5 } else { beat_duration };
-        (tempo.bpm, with_dot)
+    let (tempo_bpm, tempo_beat) = if let Some(ref tempo) = score.metadata.tempo {
+        (tempo.bpm, tempo.duration.modified_beats(&score.metadata.time_signature, tempo.dotted, &[]))
     } else {
         // Default: 120 quarter-note BPM
         let quarter_duration = crate::ast::Duration::Quarter.as_beats(&score.metadata.time_signature);
         (120, quarter_duration)
     };
 
-    // Convert all startTime and duration from time-signature beats to tempo's beat unit
-    // For example: tempo "*88" in 12/8 has tempo_beat_duration = 3 (dotted quarter = 3 eighths)
-    // So we divide all times by 3 to convert from eighth-note beats to dotted-quarter beats
-    // Note: beat_in_measure is NOT converted - it stays in time-signature beats for reference
-    for note in &mut notes {
-        note.start_time /= tempo_beat_duration;
-        note.duration /= tempo_beat_duration;
+    // Metronome and groove follow the measures as played; the count-in comes before them
+    let grid = DrumGrid {
+        bar,
+        click: tempo_beat,
+        quarter: crate::ast::Duration::Quarter.as_beats(&score.metadata.time_signature),
+    };
+    let count_in = Rational::from_integer(options.count_in_bars as i64) * grid.bar;
+    let mut drums: Vec<PlaybackDrumHit<Rational>> = count_in_hits(&grid, options.count_in_bars)
+        .into_iter()
+        .map(|hit| PlaybackDrumHit { start_time: hit.start_time - count_in, ..hit })
+        .collect();
+    if options.metronome {
+        drums.extend(metronome_hits(&measure_spans, &grid, options.metronome_subdivision));
     }
-
-    for chord in &mut chords {
-        chord.start_time /= tempo_beat_duration;
-        chord.duration /= tempo_beat_duration;
+    if let Some(groove) = options.groove {
+        drums.extend(groove_hits(&measure_spans, &grid, groove));
     }
+    drums.sort_by_key(|hit| hit.start_time);
+
+    // Swing the melody and accompaniment (OSMD timestamps stay straight for highlighting).
+    // Drum grooves carry their own feel and the metronome stays on the beat, so they aren't swung.
+    let timeline = Timeline {
+        measures: &measure_spans,
+        swing: score.metadata.swing.map(|swing| SwingGrid {
+            pair: swing.duration.as_beats(&score.metadata.time_signature) * Rational::from_integer(2),
+            ratio: swing.ratio,
+            bar: grid.bar,
+        }),
+        count_in,
+        beat: tempo_beat,
+    };
 
     // Return quarter-note equivalent BPM for a unified playback API
     let quarter_note_bpm = if let Some(ref tempo) = score.metadata.tempo {
@@ -476,19 +529,97 @@ pub fn generate_playback_data(
         120 // Default quarter-note BPM
     };
 
-    // Convert swing metadata to playback swing type
-    // Note: Swing timing is applied by the frontend during playback, not here.
-    // This allows the frontend to apply swing in real-time without affecting
-    // the note scheduling or visual synchronization.
-    let swing = score.metadata.swing.map(|s| match s {
-        crate::ast::Swing::Eighth => SwingType::Eighth,
-        crate::ast::Swing::Sixteenth => SwingType::Sixteenth,
+    // Report the swing feel (timing above is already swung; this is for display)
+    let swing = score.metadata.swing.map(|s| match s.duration {
+        crate::ast::Duration::Sixteenth => SwingType::Sixteenth,
+        _ => SwingType::Eighth,
     });
+    let swing_ratio = score.metadata.swing.map(|s| s.ratio);
 
     Ok(PlaybackData {
         tempo: quarter_note_bpm,
-        notes,
-        chords,
+        beat_bpm: tempo_bpm,
+        notes: notes.into_iter().map(|note| timeline.note(note)).collect(),
+        chords: chords.into_iter().map(|chord| timeline.chord(chord)).collect(),
+        bass: bass.into_iter().map(|bass_note| timeline.bass_note(bass_note)).collect(),
+        drums: drums.into_iter().map(|hit| timeline.drum_hit(hit)).collect(),
+        count_in: (count_in / tempo_beat).to_f64(),
         swing,
+        swing_ratio,
     })
 }
+
+/// Converts exact times in time-signature beats into the times of [`PlaybackData`]
+///
+/// Melody and accompaniment are swung; every time is moved past the count-in and counted
+/// in the tempo's beat unit (`beat_in_measure` stays in time-signature beats).
+struct Timeline<'a> {
+    measures: &'a [MeasureSpan],
+    swing: Option<SwingGrid>,
+    count_in: Rational,
+    beat: Rational,
+}
+
+impl Timeline<'_> {
+    /// Start and duration of an event that isn't swung
+    fn straight(&self, start: Rational, duration: Rational) -> (f64, f64) {
+        (((start + self.count_in) / self.beat).to_f64(), (duration / self.beat).to_f64())
+    }
+
+    /// Start and duration of an event, swung if the score swings
+    fn swung(&self, start: Rational, duration: Rational) -> (f64, f64) {
+        let Some(grid) = &self.swing else {
+            return self.straight(start, duration);
+        };
+        let (start, duration) = grid.swing_event(self.measures, start, duration);
+        let beat = self.beat.to_f64();
+        ((start + self.count_in.to_f64()) / beat, duration / beat)
+    }
+
+    fn note(&self, note: PlaybackNote<Rational>) -> PlaybackNote {
+        let (start_time, duration) = self.swung(note.start_time, note.duration);
+        PlaybackNote {
+            midi_note: note.midi_note,
+            display_midi_note: note.display_midi_note,
+            start_time,
+            duration,
+            note_index: note.note_index,
+            measure_number: note.measure_number,
+            beat_in_measure: note.beat_in_measure.to_f64(),
+            osmd_timestamp: note.osmd_timestamp.to_f64(),
+            osmd_match_key: note.osmd_match_key,
+            element_id: note.element_id,
+        }
+    }
+
+    fn chord(&self, chord: PlaybackChord<Rational>) -> PlaybackChord {
+        let (start_time, duration) = self.swung(chord.start_time, chord.duration);
+        PlaybackChord {
+            symbol: chord.symbol,
+            midi_notes: chord.midi_notes,
+            start_time,
+            duration,
+            osmd_timestamp: chord.osmd_timestamp.to_f64(),
+        }
+    }
+
+    fn bass_note(&self, note: PlaybackBassNote<Rational>) -> PlaybackBassNote {
+        let (start_time, duration) = self.swung(note.start_time, note.duration);
+        PlaybackBassNote {
+            midi_note: note.midi_note,
+            start_time,
+            duration,
+            osmd_timestamp: note.osmd_timestamp.to_f64(),
+        }
+    }
+
+    fn drum_hit(&self, hit: PlaybackDrumHit<Rational>) -> PlaybackDrumHit {
+        let (start_time, duration) = self.straight(hit.start_time, hit.duration);
+        PlaybackDrumHit {
+            midi_note: hit.midi_note,
+            velocity: hit.velocity,
+            start_time,
+            duration,
+        }
+    }
+}
@@ -12,15 +12,25 @@
 //! - `types` - PlaybackData, PlaybackNote, PlaybackChord type definitions
 //! - `engine` - Main playback data generation logic
 //! - `chord_parser` - Chord symbol parsing (C, Am, G7, etc.)
+//! - `voicing` - Chord voicing styles (close, drop-2, shell, rootless, open) and voice leading
+//! - `comping` - Accompaniment rhythm patterns (Charleston, bossa, stride, etc.)
+//! - `bass` - Bass line generation (walking, two-feel, root-fifth) from chord symbols
+//! - `drums` - Metronome, count-in and drum grooves (swing ride, rock, bossa)
+//! - `swing` - Swing timing with a configurable ratio
+//! - `synth` - Offline rendering to WAV with a small software synthesizer
 //!
 //! ## Key Types
 //! - [`PlaybackData`] - Complete playback info (notes + chords + tempo)
 //! - [`PlaybackNote`] - Single note with MIDI pitch, timing, and OSMD matching info
 //! - [`PlaybackChord`] - Chord accompaniment (multiple notes simultaneously)
+//! - [`PlaybackBassNote`] - A note of the generated bass line
+//! - [`PlaybackDrumHit`] - A metronome click or drum hit
 //!
 //! ## Entry Point
 //! [`generate_playback_data()`] - Convert Gen source to playback data
 //!
+//! [`generate_playback_data_with_options()`] - Same, with accompaniment options ([`PlaybackOptions`])
+//!
 //! ## Example
 //! ```rust
 //! use gen::playback::generate_playback_data;
@@ -76,10 +86,22 @@
 mod types;
 mod engine;
 mod chord_parser;
+mod voicing;
+mod comping;
+mod bass;
+mod drums;
+mod swing;
+mod synth;
 
 #[cfg(test)]
 mod tests;
 
-pub use types::{PlaybackData, PlaybackNote, PlaybackChord, TieType};
-pub use engine::generate_playback_data;
+pub use types::{
+    element_id, BassStyle, GroovePattern, PlaybackBassNote, PlaybackChord, PlaybackData, PlaybackDrumHit, PlaybackNote,
+    PlaybackOptions, TieType, VoicingStyle,
+};
+pub use engine::{generate_playback_data, generate_playback_data_with_options};
+pub use synth::{render_wav, SynthOptions, Waveform, SAMPLE_RATE};
 pub use chord_parser::parse_chord_symbol;
+pub(crate) use chord_parser::{parse_chord_structure, ChordStructure};
+pub use voicing::{apply_voice_leading, voice_chord_symbol};
@@ -0,0 +1,117 @@
+//! Swing timing
+//!
+//! Warps straight playback times into swung ones. Swung notes are grouped in pairs counted
+//! from the barline (two eighths per quarter, or two sixteenths per eighth): the first half
+//! of each pair is stretched to `ratio` of the pair and the second half squeezed into the
+//! rest. Start and end times are warped separately, so notes on the beat are unchanged and
+//! a note tied across the beat keeps both its swung start and its swung end.
+//!
+//! Pairs are found on the exact timeline; only the offset within a pair is scaled in `f64`
+//! (the ratio can be any percentage), so swinging is done as the final times are written.
+
+use crate::rational::Rational;
+use super::drums::{measure_origin, MeasureSpan};
+
+/// Swing layout in time-signature beats
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SwingGrid {
+    /// Length of one swung pair (a quarter for eighth swing, an eighth for sixteenth swing)
+    pub pair: Rational,
+    /// Share of the pair given to the first note (0.5 = straight, 2/3 = triplet)
+    pub ratio: f64,
+    /// Length of a full bar (to align pickup measures)
+    pub bar: Rational,
+}
+
+impl SwingGrid {
+    /// Swung start and duration of an event given its straight start and duration
+    pub(crate) fn swing_event(&self, measures: &[MeasureSpan], start: Rational, duration: Rational) -> (f64, f64) {
+        let swung_start = self.swing_time(measures, start);
+        let swung_end = self.swing_time(measures, start + duration);
+        (swung_start, swung_end - swung_start)
+    }
+
+    /// Map a straight time onto the swung timeline
+    pub(crate) fn swing_time(&self, measures: &[MeasureSpan], time: Rational) -> f64 {
+        // The measure containing this time (a time on a barline belongs to the next measure)
+        let index = measures.partition_point(|&(start, _, _)| start <= time);
+        let Some(&span) = index.checked_sub(1).and_then(|i| measures.get(i)) else {
+            return time.to_f64();
+        };
+
+        let origin = measure_origin(span, self.bar);
+        let pair_start = origin + Rational::from_integer(((time - origin) / self.pair).floor()) * self.pair;
+        let through = (time - pair_start) / self.pair; // 0 at the start of the pair, 1/2 halfway
+        let half = Rational::new(1, 2);
+
+        let swung_through = if through <= half {
+            through.to_f64() * 2.0 * self.ratio
+        } else {
+            self.ratio + (through - half).to_f64() * 2.0 * (1.0 - self.ratio)
+        };
+
+        pair_start.to_f64() + swung_through * self.pair.to_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIPLET_EIGHTHS: SwingGrid = SwingGrid { pair: Rational::ONE, ratio: 2.0 / 3.0, bar: Rational::from_integer(4) };
+    const ONE_BAR: &[MeasureSpan] = &[(Rational::ZERO, Rational::from_integer(4), false)];
+
+    fn beats(n: i64, d: i64) -> Rational {
+        Rational::new(n, d)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_downbeats_unchanged() {
+        for beat in 0..=4 {
+            assert!(close(TRIPLET_EIGHTHS.swing_time(ONE_BAR, Rational::from_integer(beat)), beat as f64));
+        }
+    }
+
+    #[test]
+    fn test_upbeat_eighths_delayed() {
+        let (start, duration) = TRIPLET_EIGHTHS.swing_event(ONE_BAR, Rational::ZERO, beats(1, 2));
+        assert!(close(start, 0.0) && close(duration, 2.0 / 3.0));
+
+        let (start, duration) = TRIPLET_EIGHTHS.swing_event(ONE_BAR, beats(3, 2), beats(1, 2));
+        assert!(close(start, 1.0 + 2.0 / 3.0) && close(duration, 1.0 / 3.0));
+    }
+
+    #[test]
+    fn test_custom_ratio() {
+        let grid = SwingGrid { ratio: 0.6, ..TRIPLET_EIGHTHS };
+        assert!(close(grid.swing_time(ONE_BAR, beats(5, 2)), 2.6));
+    }
+
+    #[test]
+    fn test_note_tied_across_beat() {
+        // An upbeat eighth tied into the next beat's first eighth: 0.5 -> 1.5
+        let (start, duration) = TRIPLET_EIGHTHS.swing_event(ONE_BAR, beats(1, 2), Rational::ONE);
+        assert!(close(start, 2.0 / 3.0));
+        assert!(close(start + duration, 1.0 + 2.0 / 3.0));
+    }
+
+    #[test]
+    fn test_sixteenth_pairs() {
+        let grid = SwingGrid { pair: beats(1, 2), ratio: 0.75, bar: beats(4, 1) };
+        assert!(close(grid.swing_time(ONE_BAR, beats(1, 4)), 0.375));
+        assert!(close(grid.swing_time(ONE_BAR, beats(3, 4)), 0.875));
+    }
+
+    #[test]
+    fn test_pickup_upbeat_swings() {
+        // A single-eighth pickup in 4/4 is the "and" of beat 4
+        let measures = [(Rational::ZERO, beats(1, 2), true), (beats(1, 2), beats(4, 1), false)];
+        let (start, duration) = TRIPLET_EIGHTHS.swing_event(&measures, Rational::ZERO, beats(1, 2));
+        assert!(close(start, 1.0 / 6.0));
+        assert!(close(duration, 1.0 / 3.0));
+    }
+}
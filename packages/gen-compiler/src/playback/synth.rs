@@ -0,0 +1,316 @@
+//! Offline audio rendering
+//!
+//! A small software synthesizer that turns [`PlaybackData`] into a 16-bit, 44.1 kHz mono WAV
+//! file, for practice tracks that play without a browser. Melody notes hold for their
+//! length; chords and the bass line decay like plucked or struck strings; drum hits are
+//! synthesized from noise and pitch sweeps, scaled by their velocity.
+//!
+//! Times in the data are already swung and shifted by the count-in, so the renderer only
+//! converts beats to seconds. Melody and accompaniment (chords, bass and drums) have separate
+//! gains; the mix is scaled down if it would clip.
+
+use super::types::PlaybackData;
+
+/// Output sample rate in Hz
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Level of a melody note before gain
+const MELODY_LEVEL: f64 = 0.35;
+/// Level of each chord tone before gain
+const CHORD_LEVEL: f64 = 0.12;
+/// Level of a bass note before gain
+const BASS_LEVEL: f64 = 0.3;
+/// Level of a full-velocity drum hit before gain
+const DRUM_LEVEL: f64 = 0.4;
+/// Silence after the last note, so releases ring out
+const TAIL_SECONDS: f64 = 1.0;
+
+/// Oscillator used for melody and chord voices
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Waveform {
+    /// Pure sine
+    Sine,
+    /// Bright sawtooth (band-limited)
+    Saw,
+    /// Two-operator FM with a decaying brightness, like an electric piano
+    #[default]
+    Fm,
+}
+
+impl Waveform {
+    /// Parse from string (case-insensitive): "sine", "saw", "fm"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "sine" => Some(Waveform::Sine),
+            "saw" | "sawtooth" => Some(Waveform::Saw),
+            "fm" | "epiano" => Some(Waveform::Fm),
+            _ => None,
+        }
+    }
+}
+
+/// Options for [`render_wav()`]
+///
+/// # Fields
+/// - `melody_waveform`: Oscillator for the melody
+/// - `accompaniment_waveform`: Oscillator for chord symbols
+/// - `melody_gain`: Melody volume (1.0 = full)
+/// - `accompaniment_gain`: Volume of chords, bass and drums (0.6 by default, as in the web player)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthOptions {
+    pub melody_waveform: Waveform,
+    pub accompaniment_waveform: Waveform,
+    pub melody_gain: f64,
+    pub accompaniment_gain: f64,
+}
+
+impl Default for SynthOptions {
+    fn default() -> Self {
+        SynthOptions {
+            melody_waveform: Waveform::Fm,
+            accompaniment_waveform: Waveform::Fm,
+            melody_gain: 1.0,
+            accompaniment_gain: 0.6,
+        }
+    }
+}
+
+/// Render playback data to the bytes of a WAV file
+///
+/// Playback times count the tempo's beat unit, so `data.beat_bpm` converts them to seconds.
+///
+/// # Example
+/// ```rust
+/// use gen::playback::{generate_playback_data, render_wav, SynthOptions};
+///
+/// let data = generate_playback_data("{C}:C D E F", "treble", 0, None, None).unwrap();
+/// let wav = render_wav(&data, &SynthOptions::default());
+/// assert_eq!(&wav[0..4], b"RIFF");
+/// ```
+pub fn render_wav(data: &PlaybackData, options: &SynthOptions) -> Vec<u8> {
+    let beat_seconds = 60.0 / data.beat_bpm.max(1) as f64;
+    encode_wav(&render_samples(data, beat_seconds, options))
+}
+
+/// Mix every note, chord, bass note and drum hit into samples in -1.0..=1.0
+fn render_samples(data: &PlaybackData, beat_seconds: f64, options: &SynthOptions) -> Vec<f64> {
+    let mut mix = Mix::default();
+    let melody = Envelope::Held { attack: 0.01, decay: 0.15, sustain: 0.75, release: 0.12 };
+    for note in &data.notes {
+        let level = MELODY_LEVEL * options.melody_gain;
+        mix.tone(note.start_time * beat_seconds, note.duration * beat_seconds, note.midi_note, options.melody_waveform, melody, level);
+    }
+
+    let chord = Envelope::Struck { attack: 0.005, decay: 1.2, release: 0.15 };
+    for playback_chord in &data.chords {
+        for &midi_note in &playback_chord.midi_notes {
+            let level = CHORD_LEVEL * options.accompaniment_gain;
+            let (start, duration) = (playback_chord.start_time * beat_seconds, playback_chord.duration * beat_seconds);
+            mix.tone(start, duration, midi_note, options.accompaniment_waveform, chord, level);
+        }
+    }
+
+    let bass = Envelope::Struck { attack: 0.005, decay: 0.6, release: 0.06 };
+    for note in &data.bass {
+        let level = BASS_LEVEL * options.accompaniment_gain;
+        mix.tone(note.start_time * beat_seconds, note.duration * beat_seconds, note.midi_note, Waveform::Sine, bass, level);
+    }
+
+    for hit in &data.drums {
+        let level = DRUM_LEVEL * options.accompaniment_gain * hit.velocity as f64 / 127.0;
+        mix.drum(hit.start_time * beat_seconds, hit.midi_note, level);
+    }
+
+    let mut samples = mix.samples;
+    samples.resize(samples.len() + (TAIL_SECONDS * SAMPLE_RATE as f64) as usize, 0.0);
+    let peak = samples.iter().fold(0.0f64, |peak, s| peak.max(s.abs()));
+    if peak > 0.99 {
+        for sample in &mut samples {
+            *sample *= 0.99 / peak;
+        }
+    }
+    samples
+}
+
+/// Amplitude over the life of a voice
+#[derive(Debug, Clone, Copy)]
+enum Envelope {
+    /// Holds at `sustain` until the note ends (organ or wind)
+    Held { attack: f64, decay: f64, sustain: f64, release: f64 },
+    /// Dies away exponentially with time constant `decay`, even while held (piano or bass)
+    Struck { attack: f64, decay: f64, release: f64 },
+}
+
+impl Envelope {
+    fn release(&self) -> f64 {
+        match *self {
+            Envelope::Held { release, .. } | Envelope::Struck { release, .. } => release,
+        }
+    }
+
+    /// Amplitude `t` seconds into a note lasting `duration` seconds
+    fn amplitude(&self, t: f64, duration: f64) -> f64 {
+        let level = |t: f64| match *self {
+            Envelope::Held { attack, decay, sustain, .. } => {
+                if t < attack {
+                    t / attack
+                } else if t < attack + decay {
+                    1.0 - (1.0 - sustain) * (t - attack) / decay
+                } else {
+                    sustain
+                }
+            }
+            Envelope::Struck { attack, decay, .. } => {
+                if t < attack {
+                    t / attack
+                } else {
+                    (-(t - attack) / decay).exp()
+                }
+            }
+        };
+        if t < duration {
+            level(t)
+        } else {
+            level(duration) * (1.0 - (t - duration) / self.release()).max(0.0)
+        }
+    }
+}
+
+/// The growing mix buffer
+#[derive(Default)]
+struct Mix {
+    samples: Vec<f64>,
+    /// State of the noise generator (xorshift), so renders are repeatable
+    noise: u32,
+}
+
+impl Mix {
+    /// Add `length` seconds of a voice from `start`, computed by `voice(t)`
+    fn add(&mut self, start: f64, length: f64, mut voice: impl FnMut(f64) -> f64) {
+        let first = (start.max(0.0) * SAMPLE_RATE as f64).round() as usize;
+        let count = (length * SAMPLE_RATE as f64).ceil() as usize;
+        if self.samples.len() < first + count {
+            self.samples.resize(first + count, 0.0);
+        }
+        for (i, sample) in self.samples[first..first + count].iter_mut().enumerate() {
+            *sample += voice(i as f64 / SAMPLE_RATE as f64);
+        }
+    }
+
+    /// A pitched note
+    fn tone(&mut self, start: f64, duration: f64, midi_note: u8, waveform: Waveform, envelope: Envelope, level: f64) {
+        if level <= 0.0 || duration <= 0.0 {
+            return;
+        }
+        let frequency = 440.0 * 2f64.powf((midi_note as f64 - 69.0) / 12.0);
+        let step = frequency / SAMPLE_RATE as f64;
+        self.add(start, duration + envelope.release(), |t| {
+            let phase = (frequency * t).fract();
+            let wave = match waveform {
+                Waveform::Sine => (std::f64::consts::TAU * phase).sin(),
+                Waveform::Saw => 0.6 * (2.0 * phase - 1.0 - poly_blep(phase, step)),
+                Waveform::Fm => {
+                    // Modulator at the same frequency; brightness fades after the attack
+                    let index = 0.4 + 1.8 * (-t / 0.3).exp();
+                    (std::f64::consts::TAU * phase + index * (std::f64::consts::TAU * phase).sin()).sin()
+                }
+            };
+            wave * envelope.amplitude(t, duration) * level
+        });
+    }
+
+    /// A General MIDI percussion hit
+    fn drum(&mut self, start: f64, midi_note: u8, level: f64) {
+        if level <= 0.0 {
+            return;
+        }
+        let decaying = |t: f64, decay: f64| (-t / decay).exp();
+        match midi_note {
+            // Metronome click and bell
+            33 | 34 => {
+                let frequency = if midi_note == 34 { 2500.0 } else { 1800.0 };
+                self.add(start, 0.05, |t| (std::f64::consts::TAU * frequency * t).sin() * decaying(t, 0.008) * level);
+            }
+            // Bass drum: a sine sweeping down
+            35 | 36 => self.add(start, 0.4, |t| {
+                let phase = 45.0 * t + 90.0 * 0.03 * (1.0 - (-t / 0.03).exp());
+                (std::f64::consts::TAU * phase).sin() * decaying(t, 0.12) * level * 1.5
+            }),
+            // Snare and side stick: noise over a short tone
+            37..=40 => {
+                let (decay, tone) = if midi_note == 37 { (0.025, 900.0) } else { (0.1, 190.0) };
+                let mut noise = self.noise_source();
+                self.add(start, decay * 6.0, |t| {
+                    (0.6 * noise() + 0.5 * (std::f64::consts::TAU * tone * t).sin()) * decaying(t, decay) * level
+                });
+            }
+            // Cymbals: high-passed noise, long for the ride
+            _ => {
+                let decay: f64 = match midi_note {
+                    42 => 0.03,
+                    44 => 0.05,
+                    46 => 0.25,
+                    49 | 51 | 52 | 55 | 57 | 59 => 0.4,
+                    _ => 0.05,
+                };
+                let mut noise = self.noise_source();
+                let mut previous = 0.0;
+                self.add(start, (decay * 6.0).min(1.5), |t| {
+                    let sample = noise();
+                    let high = sample - previous;
+                    previous = sample;
+                    0.5 * high * decaying(t, decay) * level
+                });
+            }
+        }
+    }
+
+    /// White noise in -1.0..1.0, continuing the mix's sequence
+    fn noise_source(&mut self) -> impl FnMut() -> f64 {
+        self.noise = self.noise.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+        let mut state = self.noise | 1;
+        move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f64 / u32::MAX as f64 * 2.0 - 1.0
+        }
+    }
+}
+
+/// Correction that removes most of the aliasing from a sawtooth's jump
+fn poly_blep(phase: f64, step: f64) -> f64 {
+    if phase < step {
+        let t = phase / step;
+        t + t - t * t - 1.0
+    } else if phase > 1.0 - step {
+        let t = (phase - 1.0) / step;
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// 16-bit PCM mono WAV file
+fn encode_wav(samples: &[f64]) -> Vec<u8> {
+    let data_size = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_size as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // Mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // Bytes per second
+    wav.extend_from_slice(&2u16.to_le_bytes()); // Block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f64).round() as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    wav
+}
@@ -64,6 +64,18 @@ C- C $p
     assert_eq!(data.notes[0].duration, 2.0); // Two quarter notes tied
 }
 
+#[test]
+fn test_playback_element_ids_match_mei() {
+    let source = "||: $ C D- D\n1. E F G A :||\n2. Co";
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+
+    // Repeated notes keep the id of the written note; a tied group uses its first note
+    let ids: Vec<&str> = data.notes.iter().map(|n| n.element_id.as_str()).collect();
+    assert_eq!(ids, ["m1e2", "m1e3", "m2e1", "m2e2", "m2e3", "m2e4", "m1e2", "m1e3", "m3e1"]);
+    let mei = crate::to_mei(&parse(source).unwrap());
+    assert!(ids.iter().all(|id| mei.contains(&format!("<note xml:id=\"{}\"", id))));
+}
+
 #[test]
 fn test_playback_different_rhythms() {
     let source = r#"---
@@ -466,6 +478,39 @@ C [D E F]3/ G
     assert!((data.notes[4].start_time - 2.0).abs() < 0.0001);
 }
 
+#[test]
+fn test_playback_nested_tuplets() {
+    // Quintuplet eighths (0.4 beats each) with a triplet taking two of its slots
+    let source = r#"---
+tempo: 120
+---
+[C D [E F G]3 A]5/ Gp
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+    assert_eq!(data.notes.len(), 7);
+
+    let starts: Vec<f64> = data.notes.iter().map(|n| n.start_time).collect();
+    let expected = [0.0, 0.4, 0.8, 0.8 + 4.0 / 15.0, 0.8 + 8.0 / 15.0, 1.6, 2.0];
+    for (start, expected) in starts.iter().zip(expected) {
+        assert!((start - expected).abs() < 1e-9, "expected {} got {}", expected, start);
+    }
+    assert!((data.notes[3].duration - 4.0 / 15.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_playback_explicit_tuplet_ratio() {
+    // Seven eighths in the time of eight fill the whole bar
+    let source = r#"---
+tempo: 120
+---
+[C D E F G A B]7:8/
+Co
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+    assert!((data.notes[1].start_time - 4.0 / 7.0).abs() < 1e-9);
+    assert_eq!(data.notes[7].start_time, 4.0);
+}
+
 #[test]
 fn test_osmd_match_keys() {
     let source = r#"---
@@ -478,13 +523,13 @@ C D E
     let data = result.unwrap();
 
     // C4 = MIDI 60, display 60 (OSMD uses display MIDI directly)
-    assert_eq!(data.notes[0].osmd_match_key, "60_0.000");
+    assert_eq!(data.notes[0].osmd_match_key, "60_0");
 
     // D4 = MIDI 62, display 62
-    assert_eq!(data.notes[1].osmd_match_key, "62_1.000");
+    assert_eq!(data.notes[1].osmd_match_key, "62_1");
 
     // E4 = MIDI 64, display 64
-    assert_eq!(data.notes[2].osmd_match_key, "64_2.000");
+    assert_eq!(data.notes[2].osmd_match_key, "64_2");
 }
 
 #[test]
@@ -509,27 +554,27 @@ C [D E F]3 G
     // C at beat 0 (both playback and OSMD) - C4 = MIDI 60
     assert_eq!(data.notes[0].start_time, 0.0);
     assert_eq!(data.notes[0].osmd_timestamp, 0.0);
-    assert_eq!(data.notes[0].osmd_match_key, "60_0.000");
+    assert_eq!(data.notes[0].osmd_match_key, "60_0");
 
     // D: playback 1.0, OSMD 1.0 (start of triplet) - D4 = MIDI 62
     assert_eq!(data.notes[1].start_time, 1.0);
     assert_eq!(data.notes[1].osmd_timestamp, 1.0);
-    assert_eq!(data.notes[1].osmd_match_key, "62_1.000");
-
-    // E: playback 1.667 (triplet math), OSMD 1.5 (MusicXML quantized) - E4 = MIDI 64
-    assert!((data.notes[2].start_time - 1.6666666666666667).abs() < 0.0001);
-    assert_eq!(data.notes[2].osmd_timestamp, 1.5);
-    assert_eq!(data.notes[2].osmd_match_key, "64_1.500");
-
-    // F: playback 2.333 (triplet math), OSMD 2.0 (MusicXML quantized) - F4 = MIDI 65
-    assert!((data.notes[3].start_time - 2.3333333333333335).abs() < 0.0001);
-    assert_eq!(data.notes[3].osmd_timestamp, 2.0);
-    assert_eq!(data.notes[3].osmd_match_key, "65_2.000");
-
-    // G: playback ~3.0 (floating point), OSMD 2.5 (MusicXML quantized) - G4 = MIDI 67
-    assert!((data.notes[4].start_time - 3.0).abs() < 0.0001);
-    assert_eq!(data.notes[4].osmd_timestamp, 2.5);
-    assert_eq!(data.notes[4].osmd_match_key, "67_2.500");
+    assert_eq!(data.notes[1].osmd_match_key, "62_1");
+
+    // E: playback and OSMD both 5/3 (MusicXML divisions are exact for triplets) - E4 = MIDI 64
+    assert!((data.notes[2].start_time - 5.0 / 3.0).abs() < 1e-9);
+    assert!((data.notes[2].osmd_timestamp - 5.0 / 3.0).abs() < 1e-9);
+    assert_eq!(data.notes[2].osmd_match_key, "64_5/3");
+
+    // F: 7/3 - F4 = MIDI 65
+    assert!((data.notes[3].start_time - 7.0 / 3.0).abs() < 1e-9);
+    assert!((data.notes[3].osmd_timestamp - 7.0 / 3.0).abs() < 1e-9);
+    assert_eq!(data.notes[3].osmd_match_key, "65_7/3");
+
+    // G: exactly 3 after the triplet, no floating point drift - G4 = MIDI 67
+    assert_eq!(data.notes[4].start_time, 3.0);
+    assert_eq!(data.notes[4].osmd_timestamp, 3.0);
+    assert_eq!(data.notes[4].osmd_match_key, "67_3");
 }
 
 #[test]
@@ -548,18 +593,17 @@ C D E
     // C4 shifted up 1 octave = C5 = MIDI 72 (both playback and display)
     assert_eq!(data.notes[0].midi_note, 72); // Playback includes octave shift
     assert_eq!(data.notes[0].display_midi_note, 72); // Display shifted up
-    assert_eq!(data.notes[0].osmd_match_key, "72_0.000");
+    assert_eq!(data.notes[0].osmd_match_key, "72_0");
 
     // D4 shifted up 1 octave = D5 = MIDI 74
     assert_eq!(data.notes[1].midi_note, 74);
     assert_eq!(data.notes[1].display_midi_note, 74);
-    assert_eq!(data.notes[1].osmd_match_key, "74_1.000");
+    assert_eq!(data.notes[1].osmd_match_key, "74_1");
 }
 
 #[test]
 fn test_swing_eighth_notes() {
-    // Test swing metadata is correctly parsed and passed to PlaybackData
-    // NOTE: Swing timing adjustment is now handled by the frontend during playback
+    // Swing is applied by the engine: upbeat eighths land on the last triplet of the beat
     let source = r#"---
 swing: /
 ---
@@ -571,12 +615,19 @@ swing: /
 
     // Swing type should be set
     assert_eq!(data.swing, Some(crate::playback::types::SwingType::Eighth));
+    assert!((data.swing_ratio.unwrap() - 2.0 / 3.0).abs() < 1e-9);
 
-    // Note timings should be straight (swing applied by frontend)
+    // Long-short pairs at a 2:1 ratio
     assert!((data.notes[0].start_time - 0.0).abs() < 0.01);
-    assert!((data.notes[1].start_time - 0.5).abs() < 0.01);
+    assert!((data.notes[0].duration - 2.0 / 3.0).abs() < 0.01);
+    assert!((data.notes[1].start_time - 2.0 / 3.0).abs() < 0.01);
+    assert!((data.notes[1].duration - 1.0 / 3.0).abs() < 0.01);
     assert!((data.notes[2].start_time - 1.0).abs() < 0.01);
-    assert!((data.notes[3].start_time - 1.5).abs() < 0.01);
+    assert!((data.notes[3].start_time - 5.0 / 3.0).abs() < 0.01);
+
+    // OSMD timestamps stay straight for highlighting
+    assert!((data.notes[1].osmd_timestamp - 0.5).abs() < 0.01);
+    assert!((data.notes[3].osmd_timestamp - 1.5).abs() < 0.01);
 }
 
 #[test]
@@ -594,11 +645,11 @@ swing: //
     // Swing type should be set to sixteenth
     assert_eq!(data.swing, Some(crate::playback::types::SwingType::Sixteenth));
 
-    // Note timings should be straight (swing applied by frontend)
+    // Sixteenth pairs swing within each eighth
     assert!((data.notes[0].start_time - 0.0).abs() < 0.01);
-    assert!((data.notes[1].start_time - 0.25).abs() < 0.01);
+    assert!((data.notes[1].start_time - 1.0 / 3.0).abs() < 0.01);
     assert!((data.notes[2].start_time - 0.5).abs() < 0.01);
-    assert!((data.notes[3].start_time - 0.75).abs() < 0.01);
+    assert!((data.notes[3].start_time - 5.0 / 6.0).abs() < 0.01);
 }
 
 #[test]
@@ -615,6 +666,7 @@ tempo: 120
 
     // No swing should be set
     assert_eq!(data.swing, None);
+    assert_eq!(data.swing_ratio, None);
 
     // Notes should be at straight timing
     assert!((data.notes[0].start_time - 0.0).abs() < 0.01);
@@ -625,8 +677,7 @@ tempo: 120
 
 #[test]
 fn test_swing_with_complex_pattern() {
-    // Test that swing is correctly set with complex patterns
-    // (swing timing applied by frontend, not here)
+    // Half notes are unaffected, eighths after them swing
     let source = r#"---
 title: Test Pattern
 time-signature: 4/4
@@ -641,11 +692,70 @@ Cp [B F]/
     // Verify swing is set
     assert_eq!(data.swing, Some(crate::playback::types::SwingType::Eighth));
 
-    // Verify notes are at straight timing (swing applied by frontend)
     assert_eq!(data.notes.len(), 3);
     assert!((data.notes[0].start_time - 0.0).abs() < 0.01); // C half
+    assert!((data.notes[0].duration - 2.0).abs() < 0.01);
     assert!((data.notes[1].start_time - 2.0).abs() < 0.01); // B eighth
-    assert!((data.notes[2].start_time - 2.5).abs() < 0.01); // F eighth
+    assert!((data.notes[2].start_time - 8.0 / 3.0).abs() < 0.01); // F eighth, delayed
+}
+
+#[test]
+fn test_swing_custom_ratio() {
+    let source = r#"---
+swing: /60%
+---
+[C D E F]/
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+
+    assert!((data.notes[1].start_time - 0.6).abs() < 1e-9);
+    assert!((data.notes[1].duration - 0.4).abs() < 1e-9);
+    assert!((data.notes[3].start_time - 1.6).abs() < 1e-9);
+}
+
+#[test]
+fn test_swing_dotted_preset() {
+    let source = r#"---
+swing: /dotted
+---
+[C D E F]/
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+    assert!((data.notes[1].start_time - 0.75).abs() < 1e-9);
+}
+
+#[test]
+fn test_swing_tied_across_beat() {
+    // D on the "and" of 1, tied over beat 2's first eighth: starts late, ends late
+    let source = r#"---
+swing: /
+---
+C/ D/- D/ E/ Fp
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+
+    assert_eq!(data.notes.len(), 4);
+    let tied = &data.notes[1];
+    assert!((tied.start_time - 2.0 / 3.0).abs() < 1e-9);
+    assert!((tied.start_time + tied.duration - 5.0 / 3.0).abs() < 1e-9);
+    assert!((data.notes[2].start_time - 5.0 / 3.0).abs() < 1e-9);
+    assert!((tied.osmd_timestamp - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_swing_applies_to_comping() {
+    // The Charleston's "and" of 2 swings with the melody
+    let source = r#"---
+swing: /
+comping: charleston
+---
+{C} C D E F
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+
+    assert_eq!(data.chords.len(), 2);
+    assert!((data.chords[1].start_time - 5.0 / 3.0).abs() < 1e-9);
+    assert!((data.chords[1].start_time + data.chords[1].duration - 4.0).abs() < 1e-9);
 }
 
 // ==================== REPEAT TESTS ====================
@@ -816,3 +926,471 @@ C D E F
     assert_eq!(data.notes[0].midi_note, 60);
     assert_eq!(data.notes[3].midi_note, 65);
 }
+
+// ==================== VOICING TESTS ====================
+
+#[test]
+fn test_playback_default_options_match_plain_playback() {
+    let source = r#"---
+tempo: 120
+---
+{Dm7} D E F G
+{G7} G A B ^C
+{Cmaj7} ^Co
+"#;
+    let plain = generate_playback_data(source, "treble", 0, None, None).unwrap();
+    let with_defaults =
+        generate_playback_data_with_options(source, "treble", 0, None, None, &PlaybackOptions::default()).unwrap();
+
+    assert_eq!(plain.chords.len(), 3);
+    for (a, b) in plain.chords.iter().zip(&with_defaults.chords) {
+        assert_eq!(a.midi_notes, b.midi_notes);
+        assert_eq!(a.start_time, b.start_time);
+    }
+    assert_eq!(plain.chords[0].symbol, "Dm7");
+}
+
+#[test]
+fn test_playback_voicing_style_option() {
+    let source = r#"---
+tempo: 120
+---
+{Dm7} D E F G
+{G7} G A B ^C
+"#;
+    let options = PlaybackOptions {
+        voicing: VoicingStyle::RootlessA,
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    assert_eq!(data.chords[0].midi_notes, vec![53, 57, 60, 64]); // F A C E
+    assert_eq!(data.chords[1].midi_notes, vec![59, 62, 65, 69]); // B D F A
+}
+
+#[test]
+fn test_playback_voice_leading_option() {
+    let source = r#"---
+tempo: 120
+---
+{Dm7} D E F G
+{G7} G A B ^C
+{Cmaj7} ^Co
+"#;
+    let options = PlaybackOptions {
+        voicing: VoicingStyle::RootlessA,
+        voice_leading: true,
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    // ii-V-I alternates A and B forms: F A C E -> F A B D -> E G B D
+    assert_eq!(data.chords[0].midi_notes, vec![53, 57, 60, 64]);
+    assert_eq!(data.chords[1].midi_notes, vec![53, 57, 59, 62]);
+    assert_eq!(data.chords[2].midi_notes, vec![52, 55, 59, 62]);
+
+    // Timing is unaffected by voicing
+    assert_eq!(data.chords[1].start_time, 4.0);
+    assert_eq!(data.chords[2].start_time, 8.0);
+}
+
+// ==================== COMPING TESTS ====================
+
+#[test]
+fn test_playback_comping_from_metadata() {
+    let source = r#"---
+tempo: 120
+comping: half-notes
+---
+{C} C D E F
+{G} G A B ^C
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+
+    // Each whole-note chord becomes two half-note hits
+    assert_eq!(data.chords.len(), 4);
+    let starts: Vec<f64> = data.chords.iter().map(|c| c.start_time).collect();
+    assert_eq!(starts, vec![0.0, 2.0, 4.0, 6.0]);
+    assert!(data.chords.iter().all(|c| c.duration == 2.0));
+
+    // Hits keep the OSMD timestamp of the chord symbol they came from
+    assert_eq!(data.chords[1].symbol, "C");
+    assert_eq!(data.chords[1].osmd_timestamp, 0.0);
+    assert_eq!(data.chords[3].osmd_timestamp, 4.0);
+}
+
+#[test]
+fn test_playback_comping_option_overrides_metadata() {
+    let source = r#"---
+tempo: 120
+comping: half-notes
+---
+{C} C D E F
+"#;
+    let options = PlaybackOptions {
+        comping: Some(crate::ast::CompingPattern::Charleston),
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    let times: Vec<(f64, f64)> = data.chords.iter().map(|c| (c.start_time, c.duration)).collect();
+    assert_eq!(times, vec![(0.0, 1.5), (1.5, 2.5)]);
+}
+
+#[test]
+fn test_playback_comping_scales_with_tempo_beat() {
+    // Half-note tempo: comping hits are converted to the tempo's beat unit like notes
+    let source = r#"---
+tempo: 60p
+comping: half-notes
+---
+{C} C D E F
+"#;
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+
+    let times: Vec<(f64, f64)> = data.chords.iter().map(|c| (c.start_time, c.duration)).collect();
+    assert_eq!(times, vec![(0.0, 1.0), (1.0, 1.0)]);
+}
+
+// ==================== BASS LINE TESTS ====================
+
+#[test]
+fn test_playback_no_bass_by_default() {
+    let source = "{C} C D E F";
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+    assert!(data.bass.is_empty());
+}
+
+#[test]
+fn test_playback_walking_bass() {
+    let source = r#"---
+tempo: 120
+---
+{Dm7} D E F G
+{G7} G A B ^C
+{Cmaj7} ^Co
+"#;
+    let options = PlaybackOptions {
+        bass: Some(BassStyle::Walking),
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    // One note per beat for three bars
+    assert_eq!(data.bass.len(), 12);
+    let pitches: Vec<u8> = data.bass.iter().map(|n| n.midi_note).collect();
+    assert_eq!(&pitches[..4], &[38, 41, 45, 44]); // D F A, G# into G
+    assert_eq!(&pitches[4..8], &[43, 41, 38, 37]); // G F D walking down, C# into C
+    assert_eq!(pitches[8], 36); // C
+
+    // Bass notes point at their chord symbol for highlighting
+    assert_eq!(data.bass[5].osmd_timestamp, 4.0);
+    assert_eq!(data.bass[5].start_time, 5.0);
+}
+
+#[test]
+fn test_playback_bass_in_three_four() {
+    let source = r#"---
+time-signature: 3/4
+---
+{C}p* C D E
+{G}p* G A B
+"#;
+    let options = PlaybackOptions {
+        bass: Some(BassStyle::Walking),
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    let starts: Vec<f64> = data.bass.iter().map(|n| n.start_time).collect();
+    assert_eq!(starts, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+}
+
+// ==================== METRONOME & DRUM TESTS ====================
+
+#[test]
+fn test_playback_no_drums_by_default() {
+    let data = generate_playback_data("C D E F\n", "treble", 0, None, None).unwrap();
+    assert!(data.drums.is_empty());
+    assert_eq!(data.count_in, 0.0);
+    assert_eq!(data.notes[0].start_time, 0.0);
+}
+
+#[test]
+fn test_playback_metronome_clicks_every_beat() {
+    let source = "C D E F\nG A B ^C\n";
+    let options = PlaybackOptions {
+        metronome: true,
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    let starts: Vec<f64> = data.drums.iter().map(|h| h.start_time).collect();
+    assert_eq!(starts, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+    assert!(data.drums[0].velocity > data.drums[1].velocity);
+    assert_eq!(data.drums[4].velocity, data.drums[0].velocity);
+}
+
+#[test]
+fn test_playback_count_in_shifts_everything() {
+    let source = "{C} C D E F\n";
+    let options = PlaybackOptions {
+        count_in_bars: 2,
+        bass: Some(BassStyle::RootFifth),
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    assert_eq!(data.count_in, 8.0);
+    assert_eq!(data.drums.len(), 8); // Count-in only, no metronome requested
+    assert_eq!(data.notes[0].start_time, 8.0);
+    assert_eq!(data.chords[0].start_time, 8.0);
+    assert_eq!(data.bass[0].start_time, 8.0);
+
+    // Visual timestamps are unaffected
+    assert_eq!(data.notes[0].osmd_timestamp, 0.0);
+    assert_eq!(data.notes[0].beat_in_measure, 0.0);
+}
+
+#[test]
+fn test_playback_count_in_with_dotted_tempo() {
+    // 6/8 at dotted quarter = 120: two clicks per bar
+    let source = r#"---
+time-signature: 6/8
+tempo: 120*
+---
+C/ D/ E/ F/ G/ A/
+"#;
+    let options = PlaybackOptions {
+        metronome: true,
+        count_in_bars: 1,
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    // Times are in dotted-quarter beats
+    assert_eq!(data.count_in, 2.0);
+    let starts: Vec<f64> = data.drums.iter().map(|h| h.start_time).collect();
+    assert_eq!(starts, vec![0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(data.notes[0].start_time, 2.0);
+}
+
+#[test]
+fn test_playback_metronome_subdivision() {
+    let options = PlaybackOptions {
+        metronome: true,
+        metronome_subdivision: 2,
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options("C D E F\n", "treble", 0, None, None, &options).unwrap();
+    assert_eq!(data.drums.len(), 8);
+    assert_eq!(data.drums[1].start_time, 0.5);
+}
+
+#[test]
+fn test_playback_groove_follows_repeats() {
+    let source = "||: C D E F :||\n";
+    let options = PlaybackOptions {
+        groove: Some(crate::GroovePattern::Rock),
+        ..Default::default()
+    };
+    let data = generate_playback_data_with_options(source, "treble", 0, None, None, &options).unwrap();
+
+    // Two bars of rock: snare on 2 and 4 of each
+    let snares: Vec<f64> = data.drums.iter().filter(|h| h.midi_note == 38).map(|h| h.start_time).collect();
+    assert_eq!(snares, vec![1.0, 3.0, 5.0, 7.0]);
+}
+
+
+// ==================== MOD POINT TESTS ====================
+
+/// MIDI numbers of the `<pitch>` elements in MusicXML output, in document order
+fn musicxml_midi_pitches(xml: &str) -> Vec<u8> {
+    let text = |pitch: &str, tag: &str| {
+        pitch
+            .split(&format!("<{}>", tag))
+            .nth(1)
+            .and_then(|rest| rest.split(&format!("</{}>", tag)).next())
+            .map(str::to_string)
+    };
+
+    xml.split("<pitch>")
+        .skip(1)
+        .map(|rest| {
+            let pitch = rest.split("</pitch>").next().unwrap();
+            let step = match text(pitch, "step").unwrap().as_str() {
+                "C" => 0,
+                "D" => 2,
+                "E" => 4,
+                "F" => 5,
+                "G" => 7,
+                "A" => 9,
+                _ => 11,
+            };
+            let alter: i16 = text(pitch, "alter").map_or(0, |a| a.parse().unwrap());
+            let octave: i16 = text(pitch, "octave").unwrap().parse().unwrap();
+            ((octave + 1) * 12 + step + alter) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_playback_mod_points_shift_line() {
+    let source = "C D E F @Eb:^\nC D E F\n";
+
+    // Eb group: the first line is an octave up, in sound and on the page
+    let eb = generate_playback_data(source, "treble", 0, Some("eb"), None).unwrap();
+    let midi: Vec<u8> = eb.notes.iter().map(|n| n.midi_note).collect();
+    assert_eq!(midi, vec![72, 74, 76, 77, 60, 62, 64, 65]);
+    assert_eq!(eb.notes[0].display_midi_note, 72);
+    assert_eq!(eb.notes[0].osmd_match_key, "72_0");
+
+    // Other groups and no group are unaffected
+    for group in [Some("bb"), None] {
+        let data = generate_playback_data(source, "treble", 0, group, None).unwrap();
+        assert_eq!(data.notes[0].midi_note, 60);
+        assert_eq!(data.notes[0].display_midi_note, 60);
+    }
+}
+
+#[test]
+fn test_playback_mod_points_match_musicxml() {
+    let source = "C D E F @Eb:^\nG A B ^C\nC D E F @Eb:_ @Bb:^\n";
+    let score = parse(source).unwrap();
+
+    for (name, group) in [("eb", crate::InstrumentGroup::Eb), ("bb", crate::InstrumentGroup::Bb)] {
+        for octave_shift in [0, 1] {
+            let xml = crate::to_musicxml_with_mod_points(
+                &score,
+                None,
+                crate::Clef::Treble,
+                octave_shift,
+                Some(group),
+            );
+            let data = generate_playback_data(source, "treble", octave_shift, Some(name), None).unwrap();
+
+            let display: Vec<u8> = data.notes.iter().map(|n| n.display_midi_note).collect();
+            assert_eq!(display, musicxml_midi_pitches(&xml), "group {} shift {}", name, octave_shift);
+        }
+    }
+}
+
+#[test]
+fn test_playback_mod_points_follow_repeats() {
+    // The mod point belongs to the source line, so it applies on every pass
+    let source = "||: C D E F @Eb:_ :||\n";
+    let data = generate_playback_data(source, "treble", 0, Some("eb"), None).unwrap();
+
+    assert_eq!(data.notes.len(), 8);
+    assert!(data.notes.iter().all(|n| n.midi_note < 60));
+}
+
+// ==================== ACCIDENTAL CARRY TESTS ====================
+
+#[test]
+fn test_playback_accidentals_carry_through_measure() {
+    let source = "---\naccidentals: measure\n---\nF# G F ^F\nF Bb/- B/ B A\n";
+    let data = generate_playback_data(source, "treble", 0, None, None).unwrap();
+
+    let pitches: Vec<u8> = data.notes.iter().map(|n| n.midi_note).collect();
+    // F# carries to the second F but not to ^F or the next bar; Bb carries through its tie
+    // (not a new note) to the later B
+    assert_eq!(pitches, vec![66, 67, 66, 77, 65, 70, 70, 69]);
+}
+
+#[test]
+fn test_playback_accidentals_default_to_note_scope() {
+    let data = generate_playback_data("F# G F ^F\n", "treble", 0, None, None).unwrap();
+    let pitches: Vec<u8> = data.notes.iter().map(|n| n.midi_note).collect();
+    assert_eq!(pitches, vec![66, 67, 65, 77]);
+}
+
+#[test]
+fn test_playback_accidentals_match_musicxml() {
+    for scope in ["note", "measure"] {
+        let source = format!("---\naccidentals: {}\n---\nF# G F F%\nBb/- B/ B A B\n", scope);
+        let score = parse(&source).unwrap();
+        let xml = crate::to_musicxml(&score);
+        let data = generate_playback_data(&source, "treble", 0, None, None).unwrap();
+
+        // Tied continuations are written notes but not separate playback notes
+        let mut written = musicxml_midi_pitches(&xml);
+        written.remove(5);
+        let played: Vec<u8> = data.notes.iter().map(|n| n.display_midi_note).collect();
+        assert_eq!(played, written, "accidentals: {}", scope);
+    }
+}
+
+/// Samples of a rendered WAV, checking the header on the way
+fn wav_samples(wav: &[u8]) -> Vec<i16> {
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u16::from_le_bytes([wav[20], wav[21]]), 1, "PCM");
+    assert_eq!(u16::from_le_bytes([wav[22], wav[23]]), 1, "mono");
+    assert_eq!(u32::from_le_bytes([wav[24], wav[25], wav[26], wav[27]]), SAMPLE_RATE);
+    assert_eq!(u16::from_le_bytes([wav[34], wav[35]]), 16, "16-bit");
+    assert_eq!(&wav[36..40], b"data");
+    let size = u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]) as usize;
+    assert_eq!(wav.len(), 44 + size);
+    wav[44..].chunks(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect()
+}
+
+fn peak(samples: &[i16]) -> i16 {
+    samples.iter().map(|s| s.saturating_abs()).max().unwrap_or(0)
+}
+
+#[test]
+fn test_render_wav_length_follows_tempo() {
+    let rate = SAMPLE_RATE as usize;
+    // A dotted quarter at 120 is a quarter at 180
+    for (tempo, seconds) in [("120", 2.0), ("60", 4.0), ("\"120*\"", 4.0 / 3.0)] {
+        let source = format!("---\ntempo: {}\ntime-signature: 4/4\n---\nC D E F\n", tempo);
+        let data = generate_playback_data(&source, "treble", 0, None, None).unwrap();
+        let samples = wav_samples(&render_wav(&data, &SynthOptions::default()));
+        // Four beats, the last note's release and a second of tail
+        let expected = ((seconds + 0.12 + 1.0) * rate as f64) as usize;
+        assert!(samples.len().abs_diff(expected) < rate / 100, "tempo {}: {} samples", tempo, samples.len());
+    }
+}
+
+#[test]
+fn test_render_wav_note_onsets() {
+    let data = generate_playback_data("---\ntempo: 60\n---\nC $ Cp", "treble", 0, None, None).unwrap();
+    let samples = wav_samples(&render_wav(&data, &SynthOptions::default()));
+    let rate = SAMPLE_RATE as usize;
+    // Sound for the first beat, silence through the rest, sound again on beat 3
+    assert!(peak(&samples[rate / 2..rate * 3 / 4]) > 3000);
+    assert_eq!(peak(&samples[rate * 3 / 2..rate * 2]), 0);
+    assert!(peak(&samples[rate * 2 + rate / 10..rate * 5 / 2]) > 3000);
+}
+
+#[test]
+fn test_render_wav_gains_and_waveforms() {
+    let data = generate_playback_data("{C}:C D E F", "treble", 0, None, None).unwrap();
+    let render = |options: &SynthOptions| wav_samples(&render_wav(&data, options));
+
+    let full = render(&SynthOptions::default());
+    let melody_only = render(&SynthOptions { accompaniment_gain: 0.0, ..SynthOptions::default() });
+    let silent = render(&SynthOptions { melody_gain: 0.0, accompaniment_gain: 0.0, ..SynthOptions::default() });
+    assert_eq!(peak(&silent), 0);
+    assert!(peak(&melody_only) > 0 && full != melody_only);
+
+    for waveform in ["sine", "saw", "fm"] {
+        let waveform = Waveform::from_name(waveform).unwrap();
+        let samples = render(&SynthOptions { melody_waveform: waveform, accompaniment_waveform: waveform, ..SynthOptions::default() });
+        // Loud but never clipped
+        assert!(peak(&samples) > 3000 && peak(&samples) < i16::MAX);
+    }
+}
+
+#[test]
+fn test_render_wav_drum_velocities() {
+    let options = PlaybackOptions { metronome: true, ..PlaybackOptions::default() };
+    let data = generate_playback_data_with_options("---\ntempo: 60\n---\n$ $ $ $", "treble", 0, None, None, &options).unwrap();
+    let samples = wav_samples(&render_wav(&data, &SynthOptions::default()));
+    let rate = SAMPLE_RATE as usize;
+    // The accented click on beat 1 is louder than the click on beat 2
+    let downbeat = peak(&samples[..rate / 10]);
+    let beat_two = peak(&samples[rate..rate + rate / 10]);
+    assert!(downbeat > beat_two && beat_two > 0, "{} {}", downbeat, beat_two);
+}
@@ -2,7 +2,9 @@
 //!
 //! This module defines the types used for MIDI playback and visual note highlighting.
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::ast::CompingPattern;
 
 /// Tie type for notes
 ///
@@ -34,7 +36,10 @@ pub enum TieType {
 /// - `measure_number`: Which measure this note is in (1-indexed)
 /// - `beat_in_measure`: Beat position within the measure (for OSMD timestamp matching)
 /// - `osmd_timestamp`: OSMD's display timestamp (accumulated note lengths, not triplet-adjusted)
-/// - `osmd_match_key`: Pre-computed key for matching with OSMD GraphicalNotes: "{midi}_{timestamp}"
+/// - `osmd_match_key`: Pre-computed key for matching with OSMD GraphicalNotes: "{midi}_{timestamp}",
+///   with the exact timestamp in quarter notes ("64_5/3")
+/// - `element_id`: `xml:id` of the note in MEI output (see [`element_id()`]); for a
+///   tied group, the first note
 ///
 /// # MIDI Note vs Display MIDI Note
 /// - **Concert Pitch (midi_note)**: Used for audio playback, unaffected by clef
@@ -46,43 +51,59 @@ pub enum TieType {
 /// - `start_time` and `duration` use actual triplet math (e.g., 0.667 beats per note in triplet)
 /// - `osmd_timestamp` uses MusicXML quantized durations (e.g., 0.5 beats per triplet note)
 /// - This dual-timing system enables correct audio playback AND visual note matching
+///
+/// The engine builds notes with exact [`Rational`](crate::rational::Rational) times (`T`)
+/// and converts them to `f64` when filling [`PlaybackData`].
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct PlaybackNote {
+pub struct PlaybackNote<T = f64> {
     pub midi_note: u8,
     pub display_midi_note: u8,
-    pub start_time: f64,
-    pub duration: f64,
+    pub start_time: T,
+    pub duration: T,
     pub note_index: usize,
     pub measure_number: usize,
-    pub beat_in_measure: f64,
-    pub osmd_timestamp: f64,
+    pub beat_in_measure: T,
+    pub osmd_timestamp: T,
     pub osmd_match_key: String,
+    pub element_id: String,
+}
+
+/// `xml:id` of the element at `element_index` in the measure at `measure_index` (both 0-based)
+///
+/// `m3e2` is the second element of the third measure; MEI output uses the same ids.
+pub fn element_id(measure_index: usize, element_index: usize) -> String {
+    format!("m{}e{}", measure_index + 1, element_index + 1)
 }
 
 /// Playback data for a chord (multiple notes played simultaneously)
 ///
-/// Used for chord accompaniment in lead sheet style.
+/// Used for chord accompaniment in lead sheet style. With a comping pattern, one chord
+/// symbol produces several `PlaybackChord`s that share its `symbol` and `osmd_timestamp`.
 ///
 /// # Fields
+/// - `symbol`: The chord symbol as written (e.g., "Dm7")
 /// - `midi_notes`: MIDI note numbers for all notes in the chord
 /// - `start_time`: Time in beats from start of the score (for audio playback)
 /// - `duration`: Duration in beats
 /// - `osmd_timestamp`: OSMD's display timestamp (for visual highlighting)
+///
+/// Comping and bass generation work on chords with exact [`Rational`](crate::rational::Rational) times (`T`).
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct PlaybackChord {
+pub struct PlaybackChord<T = f64> {
+    pub symbol: String,
     pub midi_notes: Vec<u8>,
-    pub start_time: f64,
-    pub duration: f64,
-    pub osmd_timestamp: f64,
+    pub start_time: T,
+    pub duration: T,
+    pub osmd_timestamp: T,
 }
 
 /// Swing feel for playback
 ///
-/// Specifies which note duration should be played with swing feel.
-/// Standard jazz swing uses a 2:1 ratio (triplet-based), where the first note
-/// of a pair gets 2/3 of the beat and the second gets 1/3.
+/// Specifies which note duration is played with swing feel. Standard jazz swing uses
+/// a 2:1 ratio (triplet-based), where the first note of a pair gets 2/3 of the beat
+/// and the second gets 1/3. Playback times are already swung by the engine.
 #[derive(Debug, Clone, Copy, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum SwingType {
@@ -92,21 +113,177 @@ pub enum SwingType {
     Sixteenth,
 }
 
+/// A single note of the generated bass line
+///
+/// # Fields
+/// - `midi_note`: MIDI note number (bass register, roughly E1-G3)
+/// - `start_time`: Time in beats from start of the score
+/// - `duration`: Duration in beats
+/// - `osmd_timestamp`: OSMD timestamp of the chord symbol this note belongs to
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackBassNote<T = f64> {
+    pub midi_note: u8,
+    pub start_time: T,
+    pub duration: T,
+    pub osmd_timestamp: T,
+}
+
+/// Bass line style generated from the chord progression
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BassStyle {
+    /// One note per beat: chord tones with a chromatic approach into the next root
+    Walking,
+    /// Half notes on root and fifth, approaching the next root chromatically
+    TwoFeel,
+    /// Half notes alternating root and the fifth below, no approach tones
+    RootFifth,
+}
+
+impl BassStyle {
+    /// Parse from string (case-insensitive): "walking", "two-feel", "root-fifth"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "walking" => Some(BassStyle::Walking),
+            "two-feel" | "2-feel" => Some(BassStyle::TwoFeel),
+            "root-fifth" => Some(BassStyle::RootFifth),
+            _ => None,
+        }
+    }
+}
+
+/// A single hit of the metronome or drum track
+///
+/// # Fields
+/// - `midi_note`: General MIDI percussion note number (channel 10)
+/// - `velocity`: MIDI velocity (accented beats are louder)
+/// - `start_time`: Time in beats from start of the score
+/// - `duration`: Duration in beats
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackDrumHit<T = f64> {
+    pub midi_note: u8,
+    pub velocity: u8,
+    pub start_time: T,
+    pub duration: T,
+}
+
+/// Drum groove played along with the score
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroovePattern {
+    /// Jazz ride pattern with the hi-hat foot on 2 and 4
+    SwingRide,
+    /// Eighth-note hi-hat, kick on 1 and 3, snare on 2 and 4
+    Rock,
+    /// Bossa nova kick with a two-bar side-stick clave
+    Bossa,
+}
+
+impl GroovePattern {
+    /// Parse from string (case-insensitive): "swing-ride", "rock", "bossa"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "swing-ride" | "swing" => Some(GroovePattern::SwingRide),
+            "rock" => Some(GroovePattern::Rock),
+            "bossa" | "bossa-nova" => Some(GroovePattern::Bossa),
+            _ => None,
+        }
+    }
+}
+
+/// Chord voicing style for accompaniment
+///
+/// Controls how the tones of a chord symbol are arranged on the keyboard.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VoicingStyle {
+    /// Close position stacked from the root (C3 octave)
+    #[default]
+    Close,
+    /// Close position with the second voice from the top dropped an octave
+    Drop2,
+    /// Root with 3rd and 7th only
+    Shell,
+    /// Rootless 3-5-7-9 (Bill Evans "A" form)
+    RootlessA,
+    /// Rootless 7-9-3-5 (Bill Evans "B" form)
+    RootlessB,
+    /// Root in the low register with the remaining tones spread above
+    Open,
+}
+
+impl VoicingStyle {
+    /// Parse from string (case-insensitive): "close", "drop2", "shell", "rootless-a", "rootless-b", "open"
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "close" => Some(VoicingStyle::Close),
+            "drop2" | "drop-2" => Some(VoicingStyle::Drop2),
+            "shell" => Some(VoicingStyle::Shell),
+            "rootless-a" | "rootless" => Some(VoicingStyle::RootlessA),
+            "rootless-b" => Some(VoicingStyle::RootlessB),
+            "open" | "spread" => Some(VoicingStyle::Open),
+            _ => None,
+        }
+    }
+}
+
+/// Options controlling playback data generation
+///
+/// Defaults reproduce the plain output of [`generate_playback_data()`](super::generate_playback_data).
+///
+/// # Fields
+/// - `voicing`: How chord symbols are voiced for accompaniment
+/// - `voice_leading`: Re-voice each chord to minimize movement from the previous one
+/// - `comping`: Rhythm pattern for chords (overrides the score's `comping:` metadata)
+/// - `bass`: Generate a bass line from the chord symbols in this style
+/// - `metronome`: Click on every beat of the tempo, accenting beat 1
+/// - `metronome_subdivision`: Clicks per beat (0 or 1 clicks the beat only)
+/// - `count_in_bars`: Bars of clicks before the score starts; all other times shift by this length
+/// - `groove`: Drum pattern to play along with the score
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlaybackOptions {
+    pub voicing: VoicingStyle,
+    pub voice_leading: bool,
+    pub comping: Option<CompingPattern>,
+    pub bass: Option<BassStyle>,
+    pub metronome: bool,
+    pub metronome_subdivision: u8,
+    pub count_in_bars: u8,
+    pub groove: Option<GroovePattern>,
+}
+
 /// Playback data for an entire score
 ///
 /// Contains all information needed to play back a score with audio and visual highlighting.
 ///
 /// # Fields
 /// - `tempo`: Tempo in BPM (beats per minute, where beat = quarter note)
+/// - `beat_bpm`: Tempo in the score's own beat unit (a dotted quarter for `"120*"`); every time
+///   and duration below counts these beats
 /// - `notes`: All melody notes with timing and OSMD matching info
 /// - `chords`: Chord accompaniment (always piano, from {chord} annotations)
-/// - `swing`: Optional swing feel (eighth or sixteenth notes)
+/// - `bass`: Generated bass line (empty unless requested in [`PlaybackOptions`])
+/// - `drums`: Count-in, metronome and groove hits (empty unless requested in [`PlaybackOptions`])
+/// - `count_in`: Length of the count-in in beats (the score starts at this time)
+/// - `swing`: Optional swing feel (eighth or sixteenth notes); note, chord and bass times are already swung
+/// - `swing_ratio`: Share of each swung pair given to the first note (e.g. 0.667 for triplet swing)
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaybackData {
     pub tempo: u16,
+    pub beat_bpm: u16,
     pub notes: Vec<PlaybackNote>,
     pub chords: Vec<PlaybackChord>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bass: Vec<PlaybackBassNote>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub drums: Vec<PlaybackDrumHit>,
+    pub count_in: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub swing: Option<SwingType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swing_ratio: Option<f64>,
 }
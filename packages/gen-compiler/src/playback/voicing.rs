@@ -0,0 +1,359 @@
+//! Chord voicing strategies and voice leading for accompaniment
+//!
+//! Arranges the tones of a chord symbol according to a [`VoicingStyle`] and smooths a
+//! progression by choosing, for each chord, the inversion closest to the previous chord.
+
+use super::chord_parser::{parse_chord_structure, ChordStructure};
+use super::types::{PlaybackChord, VoicingStyle};
+
+/// Lowest MIDI note a voice-led chord may use (C2)
+const VOICE_LEADING_FLOOR: i16 = 36;
+
+/// Highest MIDI note a voice-led chord may use (C6)
+const VOICE_LEADING_CEILING: i16 = 84;
+
+/// Semitone offsets for the 9th and its alterations
+const NINTHS: [u8; 3] = [13, 14, 15];
+
+/// Layout of a voicing before it is realized at a particular inversion
+///
+/// `upper` holds the voiced tones in close position (ascending). `bass`, when present,
+/// is kept underneath the upper tones. `drop2` lowers the second voice from the top
+/// by an octave after inverting.
+struct VoicingPlan {
+    bass: Option<u8>,
+    upper: Vec<u8>,
+    drop2: bool,
+    default_inversion: usize,
+}
+
+/// Voice a chord symbol in the given style
+///
+/// Returns an empty Vec if the symbol can't be parsed.
+///
+/// # Example
+/// ```
+/// use gen::playback::voice_chord_symbol;
+/// use gen::VoicingStyle;
+///
+/// assert_eq!(voice_chord_symbol("Cmaj7", VoicingStyle::Close), vec![48, 52, 55, 59]);
+/// assert_eq!(voice_chord_symbol("Cmaj7", VoicingStyle::Drop2), vec![43, 48, 52, 59]);
+/// assert_eq!(voice_chord_symbol("Cmaj7", VoicingStyle::Shell), vec![48, 52, 59]);
+/// ```
+pub fn voice_chord_symbol(symbol: &str, style: VoicingStyle) -> Vec<u8> {
+    match parse_chord_structure(symbol) {
+        // Close position keeps the historical voicing, including slash bass placement
+        Some(chord) if style == VoicingStyle::Close => chord.close_voicing(),
+        Some(chord) => {
+            let plan = plan_voicing(&chord, style);
+            realize(&plan, plan.default_inversion, 0)
+                .into_iter()
+                .map(|note| note.clamp(0, 127) as u8)
+                .collect()
+        }
+        None => vec![],
+    }
+}
+
+/// Re-voice a chord progression to minimize movement between consecutive chords
+///
+/// The first chord keeps its voicing. Every later chord is replaced by the inversion
+/// (and octave placement) of its `style` voicing whose notes are closest to the chord
+/// before it, staying within C2-C6. Chords whose symbol can't be parsed are left as is.
+pub fn apply_voice_leading<T>(chords: &mut [PlaybackChord<T>], style: VoicingStyle) {
+    // Upper voices of the previous chord; bass notes are placed by the plan, not led
+    let mut previous_upper: Option<Vec<u8>> = None;
+
+    for chord in chords.iter_mut() {
+        let Some(structure) = parse_chord_structure(&chord.symbol) else {
+            previous_upper = Some(chord.midi_notes.clone());
+            continue;
+        };
+        let plan = plan_voicing(&structure, style);
+
+        if let Some(best) = previous_upper.as_deref().and_then(|prev| closest_voicing(&plan, prev)) {
+            chord.midi_notes = best;
+        }
+        previous_upper = Some(upper_voices(&plan, &chord.midi_notes).to_vec());
+    }
+}
+
+/// Build the voicing plan for a chord in the given style
+fn plan_voicing(chord: &ChordStructure, style: VoicingStyle) -> VoicingPlan {
+    let root = chord.root;
+    let third = chord.third.map(|t| root + t);
+    let fifth = chord.fifth.map(|f| root + f);
+    let seventh = chord.seventh.map(|s| root + s);
+
+    match style {
+        VoicingStyle::Close => {
+            let bass = chord.bass.unwrap_or(root);
+            let mut upper: Vec<u8> = chord
+                .close_voicing()
+                .into_iter()
+                .filter(|&note| note != bass)
+                .collect();
+            upper.sort_unstable();
+            VoicingPlan { bass: Some(bass), upper, drop2: false, default_inversion: 0 }
+        }
+        VoicingStyle::Drop2 => {
+            // Four voices: a 9th (if any) replaces the root on seventh chords
+            let top = match (chord.seventh, chord.extensions.first()) {
+                (Some(_), Some(&ext)) => root + ext % 12,
+                _ => root,
+            };
+            let mut upper: Vec<u8> = [Some(top), third, fifth, seventh].into_iter().flatten().collect();
+            upper.sort_unstable();
+            VoicingPlan { bass: chord.bass, upper, drop2: true, default_inversion: 0 }
+        }
+        VoicingStyle::Shell => {
+            let guide = seventh.or(sixth(chord).map(|s| root + s)).or(fifth);
+            let upper = [third, guide].into_iter().flatten().collect();
+            VoicingPlan { bass: Some(chord.bass.unwrap_or(root)), upper, drop2: false, default_inversion: 0 }
+        }
+        VoicingStyle::RootlessA | VoicingStyle::RootlessB => {
+            // 3-5-7-9; the B form is the second inversion (7-9-3-5)
+            let seventh = seventh.unwrap_or(root + sixth(chord).unwrap_or(9));
+            let ninth = chord
+                .extensions
+                .iter()
+                .copied()
+                .find(|ext| NINTHS.contains(ext))
+                .unwrap_or(14);
+            let upper = [third, fifth, Some(seventh), Some(root + ninth)].into_iter().flatten().collect();
+            let default_inversion = if style == VoicingStyle::RootlessB { 2 } else { 0 };
+            VoicingPlan { bass: chord.bass, upper, drop2: false, default_inversion }
+        }
+        VoicingStyle::Open => {
+            // Triads double the root on top so there are enough voices to spread
+            let top = seventh.unwrap_or(root + 12);
+            let mut upper: Vec<u8> = [third, fifth, Some(top)].into_iter().flatten().collect();
+            upper.sort_unstable();
+            let bass = chord.bass.unwrap_or(root).saturating_sub(12);
+            VoicingPlan { bass: Some(bass), upper, drop2: true, default_inversion: 0 }
+        }
+    }
+}
+
+/// The added 6th of a chord (e.g., C6, Cm6), if it has one
+fn sixth(chord: &ChordStructure) -> Option<u8> {
+    chord.extensions.iter().copied().find(|&ext| ext == 9)
+}
+
+/// Realize a plan at the given inversion, shifted by `shift` semitones
+///
+/// Each inversion moves the lowest upper tone up an octave. The bass is lowered by
+/// octaves until it sits below the upper tones.
+fn realize(plan: &VoicingPlan, inversion: usize, shift: i16) -> Vec<i16> {
+    let mut upper: Vec<i16> = plan.upper.iter().map(|&note| note as i16).collect();
+    for _ in 0..inversion {
+        if !upper.is_empty() {
+            let lowest = upper.remove(0);
+            upper.push(lowest + 12);
+        }
+    }
+
+    if plan.drop2 && upper.len() >= 3 {
+        let second_from_top = upper.len() - 2;
+        upper[second_from_top] -= 12;
+        upper.sort_unstable();
+    }
+
+    for note in upper.iter_mut() {
+        *note += shift;
+    }
+
+    match plan.bass {
+        Some(bass) => {
+            let mut bass = bass as i16;
+            while upper.first().is_some_and(|&lowest| bass >= lowest) {
+                bass -= 12;
+            }
+            let mut notes = vec![bass];
+            notes.extend(upper);
+            notes
+        }
+        None => upper,
+    }
+}
+
+/// The notes of a realized voicing above its bass (all notes if the plan has no bass)
+fn upper_voices<'a, T>(plan: &VoicingPlan, notes: &'a [T]) -> &'a [T] {
+    if plan.bass.is_some() && notes.len() > 1 {
+        &notes[1..]
+    } else {
+        notes
+    }
+}
+
+/// Find the realization of `plan` whose upper voices move least from `previous`
+fn closest_voicing(plan: &VoicingPlan, previous: &[u8]) -> Option<Vec<u8>> {
+    let inversions = plan.upper.len().max(1);
+    let mut best: Option<(i16, Vec<i16>)> = None;
+
+    // Try the style's default inversion first so it wins ties
+    for step in 0..inversions {
+        let inversion = (plan.default_inversion + step) % inversions;
+        for shift in [0, -12, 12] {
+            let candidate = realize(plan, inversion, shift);
+            let in_range = candidate
+                .iter()
+                .all(|&note| (VOICE_LEADING_FLOOR..=VOICE_LEADING_CEILING).contains(&note));
+            if !in_range {
+                continue;
+            }
+            let cost = movement(upper_voices(plan, &candidate), previous);
+            if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                best = Some((cost, candidate));
+            }
+        }
+    }
+
+    best.map(|(_, notes)| notes.into_iter().map(|note| note as u8).collect())
+}
+
+/// Total distance between two voicings, measured from each note to the nearest note of the other
+fn movement(candidate: &[i16], previous: &[u8]) -> i16 {
+    let previous: Vec<i16> = previous.iter().map(|&note| note as i16).collect();
+    let nearest = |note: i16, others: &[i16]| {
+        others.iter().map(|&other| (note - other).abs()).min().unwrap_or(0)
+    };
+
+    let forward: i16 = candidate.iter().map(|&note| nearest(note, &previous)).sum();
+    let backward: i16 = previous.iter().map(|&note| nearest(note, candidate)).sum();
+    forward + backward
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(symbol: &str, style: VoicingStyle) -> PlaybackChord {
+        PlaybackChord {
+            symbol: symbol.to_string(),
+            midi_notes: voice_chord_symbol(symbol, style),
+            start_time: 0.0,
+            duration: 4.0,
+            osmd_timestamp: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_close_matches_chord_parser() {
+        for symbol in ["C", "Am7", "G7b9", "C/E", "F#m7b5"] {
+            assert_eq!(
+                voice_chord_symbol(symbol, VoicingStyle::Close),
+                super::super::parse_chord_symbol(symbol)
+            );
+        }
+    }
+
+    #[test]
+    fn test_drop2_voicings() {
+        // Cmaj7 close C E G B -> G dropped an octave: G2 C3 E3 B3
+        assert_eq!(voice_chord_symbol("Cmaj7", VoicingStyle::Drop2), vec![43, 48, 52, 59]);
+
+        // C9: the 9th replaces the root -> D E G Bb, drop G
+        assert_eq!(voice_chord_symbol("C9", VoicingStyle::Drop2), vec![43, 50, 52, 58]);
+    }
+
+    #[test]
+    fn test_shell_voicings() {
+        // G7: G, B, F
+        assert_eq!(voice_chord_symbol("G7", VoicingStyle::Shell), vec![55, 59, 65]);
+
+        // C6 uses the 6th as the guide tone
+        assert_eq!(voice_chord_symbol("C6", VoicingStyle::Shell), vec![48, 52, 57]);
+
+        // Triad falls back to the 5th
+        assert_eq!(voice_chord_symbol("Dm", VoicingStyle::Shell), vec![50, 53, 57]);
+    }
+
+    #[test]
+    fn test_rootless_voicings() {
+        // Dm7 A form: F A C E
+        assert_eq!(voice_chord_symbol("Dm7", VoicingStyle::RootlessA), vec![53, 57, 60, 64]);
+
+        // Dm7 B form: C E F A
+        assert_eq!(voice_chord_symbol("Dm7", VoicingStyle::RootlessB), vec![60, 64, 65, 69]);
+
+        // Altered 9th is kept instead of the natural 9th
+        assert_eq!(voice_chord_symbol("G7b9", VoicingStyle::RootlessA), vec![59, 62, 65, 68]);
+    }
+
+    #[test]
+    fn test_open_voicings() {
+        // Cmaj7: C2 G2 E3 B3
+        assert_eq!(voice_chord_symbol("Cmaj7", VoicingStyle::Open), vec![36, 43, 52, 59]);
+
+        // C triad doubles the root on top: C2 G2 E3 C4
+        assert_eq!(voice_chord_symbol("C", VoicingStyle::Open), vec![36, 43, 52, 60]);
+    }
+
+    #[test]
+    fn test_slash_bass_stays_below_rootless_voicing() {
+        let notes = voice_chord_symbol("Dm7/G", VoicingStyle::RootlessA);
+        assert_eq!(notes[0], 43); // G dropped below F A C E
+        assert_eq!(&notes[1..], &[53, 57, 60, 64]);
+    }
+
+    #[test]
+    fn test_unknown_symbol_is_empty() {
+        assert!(voice_chord_symbol("X7", VoicingStyle::Drop2).is_empty());
+    }
+
+    #[test]
+    fn test_voice_leading_ii_v_i() {
+        let mut chords = vec![
+            chord("Dm7", VoicingStyle::Close),
+            chord("G7", VoicingStyle::Close),
+            chord("Cmaj7", VoicingStyle::Close),
+        ];
+        apply_voice_leading(&mut chords, VoicingStyle::Close);
+
+        // First chord is untouched
+        assert_eq!(chords[0].midi_notes, vec![50, 53, 57, 60]);
+
+        // G7 keeps its root but inverts the upper voices toward Dm7: G2 + F3 B3 D4
+        assert_eq!(chords[1].midi_notes, vec![43, 53, 59, 62]);
+
+        // Cmaj7 resolves down by step: C3 + E3 G3 B3
+        assert_eq!(chords[2].midi_notes, vec![48, 52, 55, 59]);
+    }
+
+    #[test]
+    fn test_voice_leading_moves_less_than_block_chords() {
+        let symbols = ["C", "F", "G7", "Am", "Dm7", "G7", "C"];
+        let mut led: Vec<PlaybackChord> = symbols.iter().map(|s| chord(s, VoicingStyle::Close)).collect();
+        let block = led.clone();
+        apply_voice_leading(&mut led, VoicingStyle::Close);
+
+        let total = |chords: &[PlaybackChord]| -> i16 {
+            chords
+                .windows(2)
+                .map(|pair| {
+                    let notes: Vec<i16> = pair[1].midi_notes.iter().map(|&n| n as i16).collect();
+                    movement(&notes, &pair[0].midi_notes)
+                })
+                .sum()
+        };
+        assert!(total(&led) < total(&block));
+
+        // Every chord stays in the accompaniment range
+        for chord in &led {
+            assert!(chord.midi_notes.iter().all(|&n| (36..=84).contains(&n)));
+        }
+    }
+
+    #[test]
+    fn test_voice_leading_rootless_alternates_forms() {
+        let mut chords = vec![
+            chord("Dm7", VoicingStyle::RootlessA),
+            chord("G7", VoicingStyle::RootlessA),
+        ];
+        apply_voice_leading(&mut chords, VoicingStyle::RootlessA);
+
+        // Dm7 A form (F A C E) -> G7 B form (F A B D): two common tones, two half/whole steps
+        assert_eq!(chords[1].midi_notes, vec![53, 57, 59, 62]);
+    }
+}
@@ -0,0 +1,354 @@
+//! # Gen Source Printer
+//!
+//! Turns a [`Score`] back into Gen source: the YAML frontmatter from the metadata, then one
+//! measure per line. Scores built without a source (imported from ABC, say) can be saved
+//! as editable `.gen` files this way.
+//!
+//! ## Layout
+//! - Frontmatter fields are written only when they differ from the defaults (4/4, C major,
+//!   no tempo, ...)
+//! - Each line starts with its ending (`1.`/`2.`), repeat start (`||:`), `@key:` change and
+//!   `@pickup` marker, and ends with mod points (`@Eb:^`) and the repeat end (`:||`)
+//! - Notes carry their own octave (`^`, `_`) rather than measure octave modifiers
+//! - Tuplets are bracketed (`[C/ D/ E/]3`), with the ratio when it isn't the standard one
+//! - Chord symbols are attached (`{G7}:B`) when they last as long as their note, and
+//!   standalone with a rhythm otherwise (`{G7}p B/`)
+//!
+//! Scores read from JSON or built in code can hold things Gen source can't say (an empty
+//! measure, a `}` in a chord symbol); [`check_printable()`] finds them before printing.
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, printer::to_gen_source};
+//!
+//! let source = "---\ntitle: Scale\n---\n{C}:C D E F\n[G/ A/ B/]3 ^Cp*\n";
+//! let score = parse(source).unwrap();
+//! assert_eq!(to_gen_source(&score), source);
+//! ```
+
+use crate::ast::*;
+use crate::error::GenError;
+use crate::lexer::check_chord_symbol;
+
+/// Print a score as Gen source
+pub fn to_gen_source(score: &Score) -> String {
+    let mut source = frontmatter(&score.metadata);
+    let mod_point_shifts = [
+        ("Eb", score.mod_point_shifts(InstrumentGroup::Eb)),
+        ("Bb", score.mod_point_shifts(InstrumentGroup::Bb)),
+    ];
+
+    for (i, measure) in score.measures.iter().enumerate() {
+        let mut parts: Vec<String> = Vec::new();
+        match measure.ending {
+            Some(Ending::First) => parts.push("1.".to_string()),
+            Some(Ending::Second) => parts.push("2.".to_string()),
+            None => {}
+        }
+        if measure.repeat_start {
+            parts.push("||:".to_string());
+        }
+        if let Some(key) = &measure.key_change {
+            parts.push(format!("@key:{}", key_annotation(key)));
+        }
+        if measure.is_pickup {
+            parts.push("@pickup".to_string());
+        }
+
+        parts.extend(measure_elements(&measure.elements));
+
+        // Mod points must follow the notes so the line still counts as a measure
+        for (name, shifts) in &mod_point_shifts {
+            let shift = shifts[i];
+            if shift != 0 {
+                parts.push(format!("@{}:{}", name, octave_marks(shift)));
+            }
+        }
+        if measure.repeat_end {
+            parts.push(":||".to_string());
+        }
+
+        source.push_str(&parts.join(" "));
+        source.push('\n');
+    }
+
+    source
+}
+
+/// Check that [`to_gen_source()`] prints the score as Gen source that parses back to it
+///
+/// # Errors
+/// - [`GenError::MetadataError`] for a tempo of 0 BPM, or swing on a note value other than
+///   eighths and sixteenths or with a ratio outside 50% to 100%
+/// - [`GenError::SemanticError`] for an empty measure (it would print as a blank line) or a
+///   chord symbol that can't go between braces
+pub fn check_printable(score: &Score) -> Result<(), GenError> {
+    if let Some(tempo) = &score.metadata.tempo {
+        if tempo.bpm == 0 {
+            return Err(GenError::MetadataError("Tempo BPM must be greater than 0".to_string()));
+        }
+    }
+    if let Some(swing) = &score.metadata.swing {
+        if !matches!(swing.duration, Duration::Eighth | Duration::Sixteenth) {
+            return Err(GenError::MetadataError(format!(
+                "Swing on {} notes can't be written; use eighths or sixteenths",
+                swing.duration.musicxml_type()
+            )));
+        }
+        if !(0.5..1.0).contains(&swing.ratio) {
+            return Err(GenError::MetadataError(format!(
+                "Swing ratio must be at least 50% and below 100%: {}",
+                swing.ratio
+            )));
+        }
+    }
+
+    for (i, measure) in score.measures.iter().enumerate() {
+        if measure.elements.is_empty() {
+            return Err(GenError::SemanticError {
+                measure: i + 1,
+                message: "Empty measure".to_string(),
+            });
+        }
+        for chord in measure.elements.iter().filter_map(Element::chord) {
+            check_chord_symbol(&chord.symbol).map_err(|message| GenError::SemanticError { measure: i + 1, message })?;
+        }
+    }
+    Ok(())
+}
+
+/// YAML frontmatter for the non-default metadata fields (empty if there are none)
+fn frontmatter(metadata: &Metadata) -> String {
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    if let Some(title) = &metadata.title {
+        fields.push(("title", title.clone()));
+    }
+    if let Some(composer) = &metadata.composer {
+        fields.push(("composer", composer.clone()));
+    }
+    if metadata.time_signature != TimeSignature::default() {
+        let time = &metadata.time_signature;
+        fields.push(("time-signature", format!("{}/{}", time.beats, time.beat_type)));
+    }
+    if metadata.key_signature != KeySignature::default() {
+        fields.push(("key-signature", metadata.key_signature.to_string()));
+    }
+    if metadata.written_pitch != Pitch::default() {
+        let pitch = &metadata.written_pitch;
+        fields.push(("written-pitch", format!("{}{}", pitch.note.letter(), octave_marks(pitch.octave_offset))));
+    }
+    if let Some(tempo) = &metadata.tempo {
+        fields.push(("tempo", format!("{}{}", tempo.bpm, rhythm_suffix(tempo.duration, tempo.dotted))));
+    }
+    if let Some(swing) = &metadata.swing {
+        fields.push(("swing", swing_value(swing)));
+    }
+    if let Some(comping) = &metadata.comping {
+        let name = serde_yaml::to_string(comping).unwrap_or_default();
+        fields.push(("comping", name.trim().to_string()));
+    }
+    if metadata.accidentals == AccidentalScope::Measure {
+        fields.push(("accidentals", "measure".to_string()));
+    }
+
+    if fields.is_empty() {
+        return String::new();
+    }
+    let mut yaml = String::from("---\n");
+    for (name, value) in fields {
+        yaml.push_str(&format!("{}: {}\n", name, yaml_value(&value)));
+    }
+    yaml.push_str("---\n");
+    yaml
+}
+
+/// A YAML scalar, quoted when it would otherwise read as something other than this string
+fn yaml_value(value: &str) -> String {
+    match serde_yaml::to_string(value) {
+        Ok(yaml) => yaml.trim_end().to_string(),
+        Err(_) => format!("{:?}", value),
+    }
+}
+
+/// Swing value: note value slashes plus the ratio ("/", "//dotted", "/60%")
+fn swing_value(swing: &Swing) -> String {
+    let slashes = rhythm_suffix(swing.duration, false);
+    let ratio = if swing.ratio == Swing::TRIPLET {
+        String::new()
+    } else if swing.ratio == Swing::DOTTED {
+        "dotted".to_string()
+    } else {
+        format!("{}%", (swing.ratio * 10000.0).round() / 100.0)
+    };
+    format!("{}{}", slashes, ratio)
+}
+
+/// Key for an `@key:` annotation, which can't contain spaces ("Ddorian", "F#,Bb")
+fn key_annotation(key: &KeySignature) -> String {
+    let name = key.to_string();
+    if key.is_custom() {
+        name.replace(' ', ",")
+    } else {
+        name.replace(' ', "")
+    }
+}
+
+/// Elements of one measure, with tuplet brackets around their groups
+fn measure_elements(elements: &[Element]) -> Vec<String> {
+    let mut tokens = Vec::with_capacity(elements.len());
+    for element in elements {
+        let mut token = String::new();
+        let tuplets = element.tuplets();
+
+        // Chords inside a bracket aren't read back, so a group's chord goes before it
+        if let Some(chord) = element.chord() {
+            token.push_str(&chord_annotation(chord, element));
+        }
+        for tuplet in tuplets {
+            if tuplet.is_start {
+                token.push('[');
+            }
+        }
+
+        match element {
+            Element::Note(note) => {
+                if note.slur_start {
+                    token.push('(');
+                }
+                token.push_str(&note_text(note));
+                if note.tie_start {
+                    token.push('-');
+                }
+                if note.slur_stop {
+                    token.push(')');
+                }
+            }
+            Element::Rest { duration, dotted, .. } => {
+                token.push('$');
+                token.push_str(&rhythm_suffix(*duration, *dotted));
+            }
+        }
+
+        for tuplet in tuplets.iter().rev() {
+            if tuplet.is_stop {
+                token.push(']');
+                token.push_str(&tuplet.actual_notes.to_string());
+                if !tuplet.is_standard() {
+                    token.push_str(&format!(":{}", tuplet.normal_notes));
+                }
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Chord annotation before an element: attached when it lasts as long as the element
+fn chord_annotation(chord: &ChordAnnotation, element: &Element) -> String {
+    let (duration, dotted) = match element {
+        Element::Note(note) => (note.duration, note.dotted),
+        Element::Rest { duration, dotted, .. } => (*duration, *dotted),
+    };
+    // A standalone chord without a rhythm is a whole note, so a quarter can only be attached
+    let attached = (chord.duration == duration && chord.dotted == dotted)
+        || (chord.duration == Duration::Quarter && !chord.dotted);
+    if attached {
+        format!("{{{}}}:", chord.symbol)
+    } else if chord.duration == Duration::Whole && !chord.dotted {
+        format!("{{{}}} ", chord.symbol)
+    } else {
+        format!("{{{}}}{} ", chord.symbol, rhythm_suffix(chord.duration, chord.dotted))
+    }
+}
+
+/// Note as `[octave][letter][accidental][rhythm]`
+fn note_text(note: &Note) -> String {
+    let octave = match note.octave {
+        Octave::DoubleLow => "__",
+        Octave::Low => "_",
+        Octave::Middle => "",
+        Octave::High => "^",
+        Octave::DoubleHigh => "^^",
+    };
+    format!(
+        "{}{}{}{}",
+        octave,
+        note.name.letter(),
+        accidental_text(note.accidental),
+        rhythm_suffix(note.duration, note.dotted)
+    )
+}
+
+/// Source text for an explicit accidental
+pub(crate) fn accidental_text(accidental: Accidental) -> &'static str {
+    match accidental {
+        Accidental::DoubleSharp => "##",
+        Accidental::Sharp => "#",
+        Accidental::Flat => "b",
+        Accidental::DoubleFlat => "bb",
+        Accidental::ForceNatural => "%",
+        Accidental::Natural => "",
+    }
+}
+
+/// Rhythm suffix for a duration ("" for a quarter, "/" for an eighth, "p*" for a dotted half)
+fn rhythm_suffix(duration: Duration, dotted: bool) -> String {
+    let rhythm = match duration {
+        Duration::Whole => "o",
+        Duration::Half => "p",
+        Duration::Quarter => "",
+        Duration::Eighth => "/",
+        Duration::Sixteenth => "//",
+        Duration::ThirtySecond => "///",
+    };
+    format!("{}{}", rhythm, if dotted { "*" } else { "" })
+}
+
+/// Octave marks for a shift in octaves (`^^`, `_`, ...)
+fn octave_marks(shift: i8) -> String {
+    if shift >= 0 {
+        "^".repeat(shift as usize)
+    } else {
+        "_".repeat(shift.unsigned_abs() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    /// Printing a parsed score and parsing it again gives the same score
+    fn assert_round_trip(source: &str) -> String {
+        let score = parse(source).unwrap();
+        let printed = to_gen_source(&score);
+        let reparsed = parse(&printed).unwrap_or_else(|e| panic!("{}\n{}", e, printed));
+        assert_eq!(
+            reparsed.measures.iter().map(|m| &m.elements).collect::<Vec<_>>(),
+            score.measures.iter().map(|m| &m.elements).collect::<Vec<_>>(),
+            "{}",
+            printed
+        );
+        printed
+    }
+
+    #[test]
+    fn test_metadata_fields() {
+        let source = "---\ntitle: \"Tune: No. 2\"\ntime-signature: 6/8\nkey-signature: D dorian\ntempo: \"116*\"\nswing: //dotted\ncomping: bossa\n---\nD/ E/ F/ G/ A/ B/\n";
+        let printed = assert_round_trip(source);
+        assert!(printed.starts_with("---\ntitle: 'Tune: No. 2'\ntime-signature: 6/8\nkey-signature: D dorian\ntempo: 116*\nswing: //dotted\ncomping: bossa\n---\n"), "{}", printed);
+    }
+
+    #[test]
+    fn test_notes_tuplets_ties_and_slurs() {
+        let printed = assert_round_trip("(_G/ A/) ^C#// $// Bb/ [C D E]3/ F-\nF [C D E F G]5:8/ Gp");
+        assert_eq!(printed, "(_G/ A/) ^C#// $// Bb/ [C/ D/ E/]3 F-\nF [C/ D/ E/ F/ G/]5:8 Gp\n");
+    }
+
+    #[test]
+    fn test_repeats_endings_and_annotations() {
+        let source = "@pickup G\n||: {C}:C D {G7}p E F\n1. @key:Bb G A B ^C :||\n2. ^Co\nC D E @Eb:^ F";
+        let printed = assert_round_trip(source);
+        assert_eq!(printed, "@pickup G\n||: {C}:C D {G7}p E F\n1. @key:Bb G A B ^C :||\n2. ^Co\nC D E F @Eb:^\n");
+    }
+}
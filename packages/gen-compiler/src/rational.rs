@@ -0,0 +1,228 @@
+//! Exact rational numbers for musical durations
+//!
+//! Durations and positions are kept as exact fractions (e.g. a quintuplet sixteenth is
+//! 1/20 of a whole note) so that measure sums, tuplet timing and MusicXML divisions never
+//! accumulate rounding drift. Convert with [`Rational::to_f64()`] only at the final
+//! beats/seconds conversion.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+
+/// An exact fraction, always stored in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i64,
+    denom: i64,
+}
+
+impl Rational {
+    /// Zero
+    pub const ZERO: Rational = Rational { numer: 0, denom: 1 };
+    /// One
+    pub const ONE: Rational = Rational { numer: 1, denom: 1 };
+
+    /// Create a fraction `numer / denom`, reduced to lowest terms
+    ///
+    /// # Panics
+    /// Panics if `denom` is zero.
+    pub fn new(numer: i64, denom: i64) -> Self {
+        assert!(denom != 0, "Rational with zero denominator");
+        let sign = if denom < 0 { -1 } else { 1 };
+        let divisor = gcd(numer, denom).max(1);
+        Rational {
+            numer: sign * numer / divisor,
+            denom: sign * denom / divisor,
+        }
+    }
+
+    /// Create a whole number
+    pub const fn from_integer(n: i64) -> Self {
+        Rational { numer: n, denom: 1 }
+    }
+
+    /// Numerator (in lowest terms)
+    pub fn numer(&self) -> i64 {
+        self.numer
+    }
+
+    /// Denominator (in lowest terms, always positive)
+    pub fn denom(&self) -> i64 {
+        self.denom
+    }
+
+    /// Whether this is a whole number
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    /// Largest whole number not greater than this value
+    pub fn floor(&self) -> i64 {
+        self.numer.div_euclid(self.denom)
+    }
+
+    /// Reciprocal (`1 / self`)
+    ///
+    /// # Panics
+    /// Panics if this value is zero.
+    pub fn recip(&self) -> Rational {
+        Rational::new(self.denom, self.numer)
+    }
+
+    /// Convert to floating point (for the final beats/seconds conversion only)
+    pub fn to_f64(&self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+}
+
+impl Default for Rational {
+    fn default() -> Self {
+        Rational::ZERO
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Rational::from_integer(n)
+    }
+}
+
+impl fmt::Display for Rational {
+    /// Whole numbers print as "3", fractions as "11/3"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denom == 1 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, other: Rational) -> Rational {
+        let denom = lcm(self.denom, other.denom);
+        Rational::new(
+            self.numer * (denom / self.denom) + other.numer * (denom / other.denom),
+            denom,
+        )
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, other: Rational) -> Rational {
+        self + Rational { numer: -other.numer, denom: other.denom }
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Rational) -> Rational {
+        // Cross-reduce first to keep intermediate values small
+        let a = gcd(self.numer, other.denom).max(1);
+        let b = gcd(other.numer, self.denom).max(1);
+        Rational::new(
+            (self.numer / a) * (other.numer / b),
+            (self.denom / b) * (other.denom / a),
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    /// # Panics
+    /// Panics when dividing by zero.
+    fn div(self, other: Rational) -> Rational {
+        self.mul(other.recip())
+    }
+}
+
+impl AddAssign for Rational {
+    fn add_assign(&mut self, other: Rational) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Rational {
+    fn sub_assign(&mut self, other: Rational) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Rational {
+    fn sum<I: Iterator<Item = Rational>>(iter: I) -> Rational {
+        iter.fold(Rational::ZERO, |acc, r| acc + r)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        // Denominators are positive, so cross-multiplying preserves order
+        (self.numer as i128 * other.denom as i128).cmp(&(other.numer as i128 * self.denom as i128))
+    }
+}
+
+/// Greatest common divisor (always non-negative)
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Least common multiple of two positive numbers
+pub(crate) fn lcm(a: i64, b: i64) -> i64 {
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduces_to_lowest_terms() {
+        let r = Rational::new(6, -8);
+        assert_eq!((r.numer(), r.denom()), (-3, 4));
+        assert_eq!(Rational::new(0, 5), Rational::ZERO);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let third = Rational::new(1, 3);
+        let half = Rational::new(1, 2);
+        assert_eq!(third + half, Rational::new(5, 6));
+        assert_eq!(half - third, Rational::new(1, 6));
+        assert_eq!(third * half, Rational::new(1, 6));
+        assert_eq!(third / half, Rational::new(2, 3));
+    }
+
+    #[test]
+    fn test_quintuplet_sixteenths_sum_exactly() {
+        // Five quintuplet sixteenths fill exactly one quarter note
+        let note = Rational::new(1, 16) * Rational::new(4, 5);
+        let total: Rational = std::iter::repeat_n(note, 5).sum();
+        assert_eq!(total, Rational::new(1, 4));
+    }
+
+    #[test]
+    fn test_ordering_and_display() {
+        assert!(Rational::new(2, 3) > Rational::new(3, 5));
+        assert_eq!(Rational::new(11, 3).to_string(), "11/3");
+        assert_eq!(Rational::from_integer(4).to_string(), "4");
+        assert_eq!(Rational::new(7, 2).floor(), 3);
+        assert_eq!(Rational::new(-1, 2).floor(), -1);
+    }
+}
@@ -44,6 +44,7 @@
 
 use crate::ast::*;
 use crate::error::GenError;
+use crate::rational::Rational;
 
 /// Validate a score for semantic correctness
 ///
@@ -157,7 +158,7 @@ fn validate_endings(score: &Score) -> Result<(), GenError> {
 }
 
 /// Validate a single measure
-fn validate_measure(
+pub(crate) fn validate_measure(
     measure: &Measure,
     time_signature: &TimeSignature,
     measure_number: usize,
@@ -165,15 +166,15 @@ fn validate_measure(
     let total_duration = calculate_measure_duration(measure);
     let expected_duration = time_signature_duration(time_signature);
 
-    // Allow some floating point tolerance
-    let tolerance = 0.001;
-    if (total_duration - expected_duration).abs() > tolerance {
+    // Durations are exact fractions, so no tolerance is needed
+    if total_duration != expected_duration {
+        let beat_type = Rational::from_integer(time_signature.beat_type as i64);
         return Err(GenError::SemanticError {
             measure: measure_number,
             message: format!(
                 "Measure duration mismatch: expected {} beats, got {} beats",
-                expected_duration * (time_signature.beat_type as f64),
-                total_duration * (time_signature.beat_type as f64)
+                expected_duration * beat_type,
+                total_duration * beat_type
             ),
         });
     }
@@ -182,43 +183,22 @@ fn validate_measure(
 }
 
 /// Calculate the total duration of a measure as a fraction of a whole note
-fn calculate_measure_duration(measure: &Measure) -> f64 {
+fn calculate_measure_duration(measure: &Measure) -> Rational {
     measure
         .elements
         .iter()
-        .map(|e| element_duration(e))
+        .map(Element::total_fraction)
         .sum()
 }
 
-/// Get the duration of an element as a fraction of a whole note
-fn element_duration(element: &Element) -> f64 {
-    let (duration, dotted, tuplet) = match element {
-        Element::Note(note) => (note.duration, note.dotted, note.tuplet),
-        Element::Rest { duration, dotted, tuplet, .. } => (*duration, *dotted, *tuplet),
-    };
-
-    let mut base = duration.as_fraction();
-    if dotted {
-        base *= 1.5;
-    }
-
-    // Apply tuplet ratio: actual duration = base * (normal_notes / actual_notes)
-    // e.g., triplet eighth = eighth * (2/3)
-    if let Some(tuplet_info) = tuplet {
-        base *= tuplet_info.normal_notes as f64 / tuplet_info.actual_notes as f64;
-    }
-
-    base
-}
-
 /// Get the expected duration of a measure based on time signature
 /// Returns duration as a fraction of a whole note
-fn time_signature_duration(ts: &TimeSignature) -> f64 {
+fn time_signature_duration(ts: &TimeSignature) -> Rational {
     // beats / beat_type gives us the fraction of a whole note
-    // e.g., 4/4 = 4/4 = 1.0 whole note
-    // e.g., 3/4 = 3/4 = 0.75 whole note
-    // e.g., 6/8 = 6/8 = 0.75 whole note
-    (ts.beats as f64) / (ts.beat_type as f64)
+    // e.g., 4/4 = 1 whole note
+    // e.g., 3/4 = 3/4 whole note
+    // e.g., 6/8 = 3/4 whole note
+    Rational::new(ts.beats as i64, ts.beat_type as i64)
 }
 
 #[cfg(test)]
@@ -365,4 +345,22 @@ mod tests {
         let score = parse("[C C D E C]5").unwrap();
         assert!(validate(&score).is_ok());
     }
+
+    #[test]
+    fn test_sixteenth_quintuplets_are_exact() {
+        // Quintuplet sixteenths fill each beat exactly, with no tolerance needed
+        let score = parse("[C D E F G]5// [C D E F G]5// [C D E F G]5// [C D E F G]5//").unwrap();
+        assert!(validate(&score).is_ok());
+    }
+
+    #[test]
+    fn test_mismatch_reports_fractional_beats() {
+        let score = parse("C/ C C C").unwrap();
+        match validate(&score) {
+            Err(GenError::SemanticError { message, .. }) => {
+                assert!(message.contains("expected 4 beats, got 7/2 beats"), "{}", message)
+            }
+            other => panic!("expected a semantic error, got {:?}", other),
+        }
+    }
 }
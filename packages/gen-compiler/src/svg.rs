@@ -0,0 +1,867 @@
+//! # SVG Engraving
+//!
+//! A small engraver for thumbnails and quick previews when no notation renderer is at hand
+//! (the CLI, servers, tests). It lays a score out on a single staff and writes plain SVG:
+//! one image per page, or one per system.
+//!
+//! ## What is drawn
+//! - Clef, key signature (at the start of every system and at `@key:` changes) and the time
+//!   signature on the first system
+//! - Noteheads, stems, flags and dots, with ledger lines; beams follow the MusicXML grouping
+//!   and get secondary beams for sixteenths and thirty-seconds
+//! - Accidentals as the MusicXML writer shows them, courtesy accidentals in parentheses
+//! - Rests, ties (across barlines and system breaks), and tuplet numbers
+//! - Chord symbols above the staff, repeat barlines and first/second ending brackets
+//! - Title, composer and tempo on the first page
+//!
+//! Measures are packed greedily into systems and spaced by duration, then stretched to fill
+//! the line (a short last system keeps its natural width). Slurs are not drawn.
+//!
+//! Glyphs that need a music font (clefs, accidentals) are Unicode characters with a font
+//! fallback list, so they look best where Bravura or Noto Music is installed; everything
+//! else is drawn with SVG shapes.
+//!
+//! Transposition, clef, octave shift and mod points work as in the MusicXML writer.
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, to_svg};
+//!
+//! let score = parse("---\ntitle: Scale\n---\n{C}:C D E F\n{G7}:G A B ^C").unwrap();
+//! let pages = to_svg(&score);
+//! assert_eq!(pages.len(), 1);
+//! assert!(pages[0].starts_with("<svg"));
+//! assert!(pages[0].contains(">Scale</text>"));
+//! ```
+
+use crate::accidentals::{AccidentalDisplay, MeasureAccidentals};
+use crate::ast::*;
+use crate::musicxml::{calculate_beam_states, transpose_chord_root, BeamState, Clef, Transposition, WrittenMeasure};
+use std::fmt::Write;
+
+/// How the engraved score is split into SVG images
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SvgLayout {
+    /// One image per page, with the title block on the first
+    #[default]
+    Pages,
+    /// One image per system (line of music)
+    Systems,
+}
+
+/// Staff space: the distance between two staff lines
+const SPACE: f64 = 10.0;
+const PAGE_WIDTH: f64 = 800.0;
+const PAGE_HEIGHT: f64 = 1130.0;
+const MARGIN: f64 = 40.0;
+/// Height of one system, with room above the staff for chord symbols and endings
+const SYSTEM_HEIGHT: f64 = 110.0;
+/// Top staff line, relative to the top of its system
+const STAFF_OFFSET: f64 = 50.0;
+const TITLE_HEIGHT: f64 = 70.0;
+const STEM_LENGTH: f64 = 3.5 * SPACE;
+const BEAM_THICKNESS: f64 = 5.0;
+const MUSIC_FONT: &str = "Bravura, 'Noto Music', 'Segoe UI Symbol', serif";
+const TEXT_FONT: &str = "Helvetica, Arial, sans-serif";
+
+/// Staff positions (steps above the bottom line of a treble staff) of the key signature
+/// accidentals, in the order they are added
+const SHARP_POSITIONS: [(NoteName, i32); 7] =
+    [(NoteName::F, 8), (NoteName::C, 5), (NoteName::G, 9), (NoteName::D, 6), (NoteName::A, 3), (NoteName::E, 7), (NoteName::B, 4)];
+const FLAT_POSITIONS: [(NoteName, i32); 7] =
+    [(NoteName::B, 4), (NoteName::E, 7), (NoteName::A, 3), (NoteName::D, 6), (NoteName::G, 2), (NoteName::C, 5), (NoteName::F, 1)];
+
+/// Engrave a Score as one SVG per page, at concert pitch in treble clef
+pub fn to_svg(score: &Score) -> Vec<String> {
+    to_svg_with_options(score, None, Clef::Treble, 0, None, SvgLayout::Pages)
+}
+
+/// Engrave a Score with transposition, clef, octave shift and mod points
+/// (the same options as [`crate::to_musicxml_with_mod_points()`]), as pages or systems
+pub fn to_svg_with_options(
+    score: &Score,
+    transposition: Option<Transposition>,
+    clef: Clef,
+    octave_shift: i8,
+    instrument_group: Option<InstrumentGroup>,
+    layout: SvgLayout,
+) -> Vec<String> {
+    let bars = prepare_bars(score, transposition.as_ref(), clef, octave_shift, instrument_group);
+    let systems = break_systems(&bars);
+    let metadata = &score.metadata;
+    let mut engraver = Engraver { clef, time: metadata.time_signature.clone(), tie: None, last_bar: bars.len().saturating_sub(1) };
+
+    let mut images = Vec::new();
+    match layout {
+        SvgLayout::Systems => {
+            for system in &systems {
+                let mut body = String::new();
+                engraver.system(&mut body, &bars, system, 0.0);
+                images.push(svg_document(SYSTEM_HEIGHT, &body));
+            }
+        }
+        SvgLayout::Pages => {
+            let mut body = String::new();
+            let mut y = MARGIN / 2.0;
+            if metadata.title.is_some() || metadata.composer.is_some() {
+                if let Some(title) = &metadata.title {
+                    text(&mut body, PAGE_WIDTH / 2.0, y + 24.0, "middle", &format!("font-family=\"{}\" font-size=\"22\"", TEXT_FONT), title);
+                }
+                if let Some(composer) = &metadata.composer {
+                    text(&mut body, PAGE_WIDTH - MARGIN, y + 50.0, "end", &format!("font-family=\"{}\" font-size=\"13\"", TEXT_FONT), composer);
+                }
+                y += TITLE_HEIGHT;
+            }
+            if let Some(tempo) = &metadata.tempo {
+                let label = format!("\u{2669} = {}", tempo.to_quarter_note_bpm().round());
+                text(&mut body, MARGIN, y + STAFF_OFFSET - 36.0, "start", &format!("font-family=\"{}\" font-size=\"13\"", MUSIC_FONT), &label);
+            }
+            for system in &systems {
+                if y + SYSTEM_HEIGHT > PAGE_HEIGHT - MARGIN / 2.0 && !body.is_empty() {
+                    images.push(svg_document(PAGE_HEIGHT, &body));
+                    body.clear();
+                    y = MARGIN / 2.0;
+                }
+                engraver.system(&mut body, &bars, system, y);
+                y += SYSTEM_HEIGHT;
+            }
+            if !body.is_empty() || images.is_empty() {
+                images.push(svg_document(PAGE_HEIGHT, &body));
+            }
+        }
+    }
+    images
+}
+
+/// A measure ready to lay out: written pitches, accidentals and spacing are decided
+struct Bar {
+    /// Written key signature in effect
+    key: KeySignature,
+    /// Whether the key changes at this measure
+    key_change: bool,
+    events: Vec<Event>,
+    repeat_start: bool,
+    repeat_end: bool,
+    ending: Option<Ending>,
+}
+
+impl Bar {
+    /// Room before the first note: repeat sign and key change
+    fn lead(&self) -> f64 {
+        let mut lead = SPACE;
+        if self.repeat_start {
+            lead += 1.2 * SPACE;
+        }
+        if self.key_change {
+            lead += key_width(&self.key);
+        }
+        lead
+    }
+
+    /// Natural width of the notes
+    fn content(&self) -> f64 {
+        self.events.iter().map(|event| event.space).sum()
+    }
+
+    /// Natural width of the whole measure
+    fn width(&self) -> f64 {
+        self.lead() + self.content() + if self.repeat_end { 1.2 * SPACE } else { 0.4 * SPACE }
+    }
+}
+
+/// A note or rest
+struct Event {
+    /// Horizontal room before stretching
+    space: f64,
+    /// Staff position and accidental of a note, `None` for a rest
+    head: Option<(i32, Option<AccidentalDisplay>)>,
+    duration: Duration,
+    dotted: bool,
+    beam: BeamState,
+    tie_start: bool,
+    tie_stop: bool,
+    /// Tuplets starting here, and the numbers of those ending here (innermost first)
+    tuplet_starts: usize,
+    tuplet_stops: Vec<u8>,
+    chord: Option<String>,
+}
+
+/// Written pitches, accidentals and spacing for every measure
+fn prepare_bars(
+    score: &Score,
+    transposition: Option<&Transposition>,
+    clef: Clef,
+    octave_shift: i8,
+    instrument_group: Option<InstrumentGroup>,
+) -> Vec<Bar> {
+    let metadata = &score.metadata;
+    let bottom_line = match clef {
+        Clef::Treble => 4 * 7 + 2, // E4
+        Clef::Bass => 2 * 7 + 4,   // G2
+    };
+    let mut key_signature = metadata.key_signature.clone();
+    let octave_shifts = score.octave_shifts(octave_shift, instrument_group);
+    let mut accidentals = MeasureAccidentals::new(metadata.accidentals);
+    let mut bars = Vec::new();
+    for (i, measure) in score.measures.iter().enumerate() {
+        let mut key_change = false;
+        if let Some(new_key) = measure.key_change.as_ref().filter(|key| **key != key_signature) {
+            key_signature = new_key.clone();
+            key_change = i > 0;
+        }
+        let written = WrittenMeasure::start(&key_signature, transposition, octave_shifts[i], &mut accidentals);
+
+        let beams = calculate_beam_states(&measure.elements, &metadata.time_signature);
+        let mut events = Vec::new();
+        for (element, beam) in measure.elements.iter().zip(beams) {
+            let (head, duration, dotted, tie_start, tie_stop) = match element {
+                Element::Note(note) => {
+                    let (step, alter, octave) = written.pitch(note, &mut accidentals);
+                    let explicit = note.accidental != Accidental::Natural;
+                    let display = accidentals.note(step, octave, alter, explicit).filter(|_| explicit || !note.tie_stop);
+                    let position = octave as i32 * 7 + step_index(step) - bottom_line;
+                    (Some((position, display)), note.duration, note.dotted, note.tie_start, note.tie_stop)
+                }
+                Element::Rest { duration, dotted, .. } => (None, *duration, *dotted, false, false),
+            };
+
+            let mut space = 14.0 + 26.0 * (element.total_fraction().to_f64() * 4.0).sqrt();
+            if let Some((_, Some(display))) = head {
+                space += if display.cautionary { 2.0 * SPACE } else { 1.2 * SPACE };
+            }
+            if dotted {
+                space += 0.6 * SPACE;
+            }
+            let chord = element.chord().map(|chord| match transposition {
+                Some(trans) => transpose_chord_root(&chord.symbol, trans),
+                None => chord.symbol.clone(),
+            });
+            if let Some(chord) = &chord {
+                // Keep neighbouring chord symbols apart
+                space = space.max(chord.chars().count() as f64 * 7.5 + 6.0);
+            }
+            events.push(Event {
+                space,
+                head,
+                duration,
+                dotted,
+                beam,
+                tie_start,
+                tie_stop,
+                tuplet_starts: element.tuplets().iter().filter(|t| t.is_start).count(),
+                tuplet_stops: element.tuplets().iter().rev().filter(|t| t.is_stop).map(|t| t.actual_notes).collect(),
+                chord,
+            });
+        }
+        bars.push(Bar {
+            key: written.written_key,
+            key_change,
+            events,
+            repeat_start: measure.repeat_start,
+            repeat_end: measure.repeat_end,
+            ending: measure.ending,
+        });
+    }
+    bars
+}
+
+/// A line of music: a range of bars and how much their notes are stretched
+struct System {
+    first: usize,
+    end: usize,
+    stretch: f64,
+}
+
+/// Pack bars greedily into systems, each stretched to the full line width
+fn break_systems(bars: &[Bar]) -> Vec<System> {
+    let mut systems = Vec::new();
+    let mut first = 0;
+    while first < bars.len() {
+        let available = PAGE_WIDTH - 2.0 * MARGIN - header_width(&bars[first].key, first == 0);
+        let mut end = first;
+        let mut width = 0.0;
+        while end < bars.len() && (end == first || width + bars[end].width() <= available) {
+            width += bars[end].width();
+            end += 1;
+        }
+        let content: f64 = bars[first..end].iter().map(Bar::content).sum();
+        let short_last_line = end == bars.len() && width < 0.7 * available;
+        let stretch = if content > 0.0 && !short_last_line { ((available - width) / content + 1.0).max(1.0) } else { 1.0 };
+        systems.push(System { first, end, stretch });
+        first = end;
+    }
+    systems
+}
+
+/// Width of the clef, key signature and (on the first system) time signature
+fn header_width(key: &KeySignature, first_system: bool) -> f64 {
+    3.6 * SPACE + key_width(key) + if first_system { 2.8 * SPACE } else { 0.0 }
+}
+
+/// Width of a key signature
+fn key_width(key: &KeySignature) -> f64 {
+    let count = key_accidentals(key).len();
+    if count == 0 { 0.0 } else { count as f64 * 0.9 * SPACE + 0.8 * SPACE }
+}
+
+/// Key signature accidentals in written order, with treble staff positions
+fn key_accidentals(key: &KeySignature) -> Vec<(i8, i32)> {
+    let position = |step: NoteName, alter: i8| {
+        let table = if alter > 0 { &SHARP_POSITIONS } else { &FLAT_POSITIONS };
+        table.iter().find(|(s, _)| *s == step).map_or(4, |(_, p)| *p)
+    };
+    if key.is_custom() {
+        key.alterations.iter().map(|&(step, alter)| (alter, position(step, alter))).collect()
+    } else if key.fifths >= 0 {
+        SHARP_POSITIONS.iter().take(key.fifths as usize).map(|&(_, p)| (1, p)).collect()
+    } else {
+        FLAT_POSITIONS.iter().take(key.fifths.unsigned_abs() as usize).map(|&(_, p)| (-1, p)).collect()
+    }
+}
+
+/// Index of a step within the octave, C = 0
+fn step_index(step: NoteName) -> i32 {
+    NoteName::ALL.iter().position(|&s| s == step).unwrap_or(0) as i32
+}
+
+/// A tie waiting for the note it ends on
+enum OpenTie {
+    /// Starts at a notehead, curving above or below it
+    At { x: f64, y: f64, above: bool },
+    /// Carried over a system break
+    Continued { y: f64, above: bool },
+}
+
+/// Where a note ended up, for stems, beams and ties
+struct Placed {
+    x: f64,
+    y: f64,
+    levels: usize,
+    beam: BeamState,
+}
+
+/// Draws systems, carrying ties from one to the next
+struct Engraver {
+    clef: Clef,
+    time: TimeSignature,
+    tie: Option<OpenTie>,
+    last_bar: usize,
+}
+
+impl Engraver {
+    /// Draw one system with its top at `y`
+    fn system(&mut self, svg: &mut String, bars: &[Bar], system: &System, y: f64) {
+        let top = y + STAFF_OFFSET;
+        let right = PAGE_WIDTH - MARGIN;
+        for line in 0..5 {
+            let line_y = top + line as f64 * SPACE;
+            let _ = write!(svg, "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\" stroke-width=\"1\"/>", MARGIN, line_y, right, line_y);
+        }
+        line(svg, MARGIN, top, MARGIN, top + 4.0 * SPACE, 1.0);
+
+        // Clef, key and time
+        let mut x = MARGIN + 0.6 * SPACE;
+        match self.clef {
+            Clef::Treble => music_text(svg, x, top + 3.1 * SPACE, 38.0, "\u{1D11E}"),
+            Clef::Bass => music_text(svg, x, top + 2.6 * SPACE, 34.0, "\u{1D122}"),
+        }
+        x += 3.0 * SPACE;
+        x = self.key_signature(svg, x, top, &bars[system.first].key);
+        if system.first == 0 {
+            let style = format!("font-family=\"{}\" font-size=\"22\" font-weight=\"bold\"", TEXT_FONT);
+            text(svg, x + SPACE, top + 1.9 * SPACE, "middle", &style, &self.time.beats.to_string());
+            text(svg, x + SPACE, top + 3.9 * SPACE, "middle", &style, &self.time.beat_type.to_string());
+            x += 2.8 * SPACE;
+        }
+        let content_start = x;
+
+        for index in system.first..system.end {
+            let bar = &bars[index];
+            let start = x;
+            if bar.repeat_start {
+                repeat_sign(svg, x + 0.2 * SPACE, top, false);
+            }
+            x += if bar.repeat_start { 1.4 * SPACE } else { SPACE };
+            if bar.key_change && index != system.first {
+                x = self.key_signature(svg, x - 0.4 * SPACE, top, &bar.key);
+            }
+            let placed = self.events(svg, bar, x, top, system.stretch, content_start);
+            x += bar.content() * system.stretch;
+            self.beams(svg, &placed, top);
+
+            // Barline
+            x += if bar.repeat_end { 1.2 * SPACE } else { 0.4 * SPACE };
+            if index == system.end - 1 {
+                x = right;
+            }
+            if bar.repeat_end {
+                repeat_sign(svg, x, top, true);
+            } else if index == self.last_bar {
+                line(svg, x - 0.6 * SPACE, top, x - 0.6 * SPACE, top + 4.0 * SPACE, 1.0);
+                line(svg, x - 0.2 * SPACE, top, x - 0.2 * SPACE, top + 4.0 * SPACE, 4.0);
+            } else {
+                line(svg, x, top, x, top + 4.0 * SPACE, 1.0);
+            }
+
+            if let Some(ending) = bar.ending {
+                let opens = index == system.first || bars[index - 1].ending != Some(ending);
+                let closes = ending == Ending::First && (index + 1 == bars.len() || bars[index + 1].ending != Some(ending));
+                ending_bracket(svg, start, x, top, ending, opens, closes);
+            }
+        }
+
+        // A tie running over the system break
+        if let Some(OpenTie::At { x: tie_x, y: tie_y, above }) = self.tie.take() {
+            tie(svg, tie_x, right, tie_y, above);
+            self.tie = Some(OpenTie::Continued { y: tie_y - top, above });
+        }
+    }
+
+    /// Draw a key signature starting at `x`; returns the x after it
+    fn key_signature(&self, svg: &mut String, x: f64, top: f64, key: &KeySignature) -> f64 {
+        let accidentals = key_accidentals(key);
+        if accidentals.is_empty() {
+            return x;
+        }
+        let mut x = x + 0.4 * SPACE;
+        for (alter, position) in accidentals {
+            let y = staff_y(top, position - self.clef_shift());
+            music_text(svg, x, y + 0.5 * SPACE, 20.0, accidental_glyph(alter));
+            x += 0.9 * SPACE;
+        }
+        x + 0.4 * SPACE
+    }
+
+    /// Key signature positions are drawn a third lower on the bass staff
+    fn clef_shift(&self) -> i32 {
+        match self.clef {
+            Clef::Treble => 0,
+            Clef::Bass => 2,
+        }
+    }
+
+    /// Draw the notes and rests of a bar from `x`; returns the notes for beaming
+    fn events(&mut self, svg: &mut String, bar: &Bar, mut x: f64, top: f64, stretch: f64, content_start: f64) -> Vec<Option<Placed>> {
+        let mut placed = Vec::new();
+        let mut tuplets: Vec<f64> = Vec::new();
+        for event in &bar.events {
+            let accidental_room = match event.head {
+                Some((_, Some(display))) if display.cautionary => 2.0 * SPACE,
+                Some((_, Some(_))) => 1.2 * SPACE,
+                _ => 0.0,
+            };
+            let head_x = x + accidental_room + 0.6 * SPACE;
+            for _ in 0..event.tuplet_starts {
+                tuplets.push(head_x);
+            }
+
+            if let Some(chord) = &event.chord {
+                let style = format!("font-family=\"{}\" font-size=\"14\"", TEXT_FONT);
+                text(svg, head_x - 0.6 * SPACE, top - 1.6 * SPACE, "start", &style, chord);
+            }
+
+            match event.head {
+                Some((position, display)) => {
+                    let y = staff_y(top, position);
+                    ledger_lines(svg, head_x, top, position);
+                    if let Some(display) = display {
+                        let glyph = accidental_glyph(display.alter);
+                        let glyph = if display.cautionary { format!("({})", glyph) } else { glyph.to_string() };
+                        music_text(svg, head_x - 1.5 * SPACE - if display.cautionary { 0.6 * SPACE } else { 0.0 }, y + 0.5 * SPACE, 20.0, &glyph);
+                    }
+                    notehead(svg, head_x, y, event.duration);
+                    if event.dotted {
+                        let dot_y = if position % 2 == 0 { y - SPACE / 2.0 } else { y };
+                        circle(svg, head_x + 1.1 * SPACE, dot_y, 1.8);
+                    }
+                    if event.tie_stop {
+                        match self.tie.take() {
+                            Some(OpenTie::At { x: tie_x, y: tie_y, above }) => tie(svg, tie_x, head_x, tie_y, above),
+                            Some(OpenTie::Continued { y: tie_y, above }) => tie(svg, content_start, head_x, top + tie_y, above),
+                            None => {}
+                        }
+                    }
+                    if event.tie_start {
+                        let above = position >= 4;
+                        self.tie = Some(OpenTie::At { x: head_x, y, above });
+                    }
+                    let levels = flag_count(event.duration);
+                    if event.beam == BeamState::None && event.duration != Duration::Whole {
+                        stem_and_flags(svg, head_x, y, top, levels);
+                    }
+                    placed.push(Some(Placed { x: head_x, y, levels, beam: event.beam }));
+                }
+                None => {
+                    rest(svg, head_x, top, event.duration);
+                    if event.dotted {
+                        circle(svg, head_x + 1.2 * SPACE, top + 1.5 * SPACE, 1.8);
+                    }
+                    placed.push(None);
+                }
+            }
+
+            for number in &event.tuplet_stops {
+                let start = tuplets.pop().unwrap_or(head_x);
+                let style = format!("font-family=\"{}\" font-size=\"11\" font-style=\"italic\"", TEXT_FONT);
+                text(svg, (start + head_x) / 2.0, top - 0.3 * SPACE, "middle", &style, &number.to_string());
+            }
+            x += event.space * stretch;
+        }
+        placed
+    }
+
+    /// Beam each Begin..End group of a bar, drawing its stems to the beam
+    fn beams(&self, svg: &mut String, placed: &[Option<Placed>], top: f64) {
+        let middle = top + 2.0 * SPACE;
+        let mut group: Vec<&Placed> = Vec::new();
+        for note in placed.iter().flatten() {
+            match note.beam {
+                BeamState::None => continue,
+                BeamState::Begin => group = vec![note],
+                BeamState::Continue => group.push(note),
+                BeamState::End => group.push(note),
+            }
+            if note.beam != BeamState::End || group.len() < 2 {
+                continue;
+            }
+
+            // Stems go down when the notes sit mostly above the middle line
+            let up = group.iter().map(|n| n.y - middle).sum::<f64>() > 0.0;
+            let direction = if up { -1.0 } else { 1.0 };
+            let stem_x = |n: &Placed| if up { n.x + 5.4 } else { n.x - 5.4 };
+            let (first, last) = (group[0], group[group.len() - 1]);
+            let (x0, x1) = (stem_x(first), stem_x(last));
+            let rise = (last.y - first.y).clamp(-SPACE, SPACE);
+            let slope = if x1 > x0 { rise / (x1 - x0) } else { 0.0 };
+            let mut y0 = first.y + direction * STEM_LENGTH;
+            // Every stem keeps at least 2.5 spaces between its note and the beam
+            for n in &group {
+                let beam_y = y0 + slope * (stem_x(n) - x0);
+                let shortfall = if up { beam_y - (n.y - 2.5 * SPACE) } else { (n.y + 2.5 * SPACE) - beam_y };
+                if shortfall > 0.0 {
+                    y0 += direction * shortfall;
+                }
+            }
+            let beam_at = |x: f64| y0 + slope * (x - x0);
+
+            for n in &group {
+                line(svg, stem_x(n), n.y, stem_x(n), beam_at(stem_x(n)), 1.2);
+            }
+            beam(svg, x0, beam_at(x0), x1, beam_at(x1), up);
+            // Secondary beams between neighbours that share them, stubs for the rest
+            for level in 2..=3 {
+                let offset = -direction * (level - 1) as f64 * 0.8 * SPACE;
+                for (k, n) in group.iter().enumerate() {
+                    if n.levels < level {
+                        continue;
+                    }
+                    let next = group.get(k + 1).filter(|m| m.levels >= level);
+                    let previous = k.checked_sub(1).and_then(|p| group.get(p)).filter(|m| m.levels >= level);
+                    let (from, to) = match (previous, next) {
+                        (_, Some(next)) => (stem_x(n), stem_x(next)),
+                        (Some(_), None) => continue,
+                        (None, None) if k + 1 == group.len() => (stem_x(n) - SPACE, stem_x(n)),
+                        (None, None) => (stem_x(n), stem_x(n) + SPACE),
+                    };
+                    beam(svg, from, beam_at(from) + offset, to, beam_at(to) + offset, up);
+                }
+            }
+            group.clear();
+        }
+    }
+}
+
+/// Vertical position of a staff position (steps above the bottom line)
+fn staff_y(top: f64, position: i32) -> f64 {
+    top + 4.0 * SPACE - position as f64 * SPACE / 2.0
+}
+
+/// Number of flags or beams for a duration
+fn flag_count(duration: Duration) -> usize {
+    match duration {
+        Duration::Eighth => 1,
+        Duration::Sixteenth => 2,
+        Duration::ThirtySecond => 3,
+        _ => 0,
+    }
+}
+
+/// Text glyph for an alteration
+fn accidental_glyph(alter: i8) -> &'static str {
+    match alter {
+        2 => "\u{1D12A}",
+        1 => "\u{266F}",
+        -1 => "\u{266D}",
+        -2 => "\u{1D12B}",
+        _ => "\u{266E}",
+    }
+}
+
+fn notehead(svg: &mut String, x: f64, y: f64, duration: Duration) {
+    match duration {
+        Duration::Whole => {
+            let _ = write!(svg, "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"7\" ry=\"4.4\" fill=\"white\" stroke=\"black\" stroke-width=\"2.2\"/>", x, y);
+        }
+        Duration::Half => {
+            let _ = write!(
+                svg,
+                "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"5.6\" ry=\"3.9\" transform=\"rotate(-20 {:.1} {:.1})\" fill=\"white\" stroke=\"black\" stroke-width=\"1.6\"/>",
+                x, y, x, y
+            );
+        }
+        _ => {
+            let _ = write!(svg, "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"5.9\" ry=\"4.2\" transform=\"rotate(-20 {:.1} {:.1})\"/>", x, y, x, y);
+        }
+    }
+}
+
+/// Ledger lines for a note above or below the staff
+fn ledger_lines(svg: &mut String, x: f64, top: f64, position: i32) {
+    let ledgers = if position <= -2 { (position..=-2).collect::<Vec<_>>() } else { (10..=position).collect() };
+    for p in ledgers.into_iter().filter(|p| p % 2 == 0) {
+        let y = staff_y(top, p);
+        line(svg, x - 1.0 * SPACE, y, x + 1.0 * SPACE, y, 1.0);
+    }
+}
+
+/// Stem for a note on its own, reaching at least the middle line, with its flags
+fn stem_and_flags(svg: &mut String, x: f64, y: f64, top: f64, flags: usize) {
+    let middle = top + 2.0 * SPACE;
+    if y > middle {
+        // Stem up, flags hang down from the top
+        let end = (y - STEM_LENGTH).min(middle);
+        line(svg, x + 5.4, y, x + 5.4, end, 1.2);
+        for k in 0..flags {
+            let _ = write!(svg, "<path d=\"M{:.1} {:.1}c1 6 10 9 8 20c0-6-4-11-8-13z\"/>", x + 5.4, end + k as f64 * 0.8 * SPACE);
+        }
+    } else {
+        let end = (y + STEM_LENGTH).max(middle);
+        line(svg, x - 5.4, y, x - 5.4, end, 1.2);
+        for k in 0..flags {
+            let _ = write!(svg, "<path d=\"M{:.1} {:.1}c1-6 10-9 8-20c0 6-4 11-8 13z\"/>", x - 5.4, end - k as f64 * 0.8 * SPACE);
+        }
+    }
+}
+
+fn rest(svg: &mut String, x: f64, top: f64, duration: Duration) {
+    match duration {
+        Duration::Whole => rect(svg, x - 0.6 * SPACE, top + SPACE, 1.2 * SPACE, 0.5 * SPACE),
+        Duration::Half => rect(svg, x - 0.6 * SPACE, top + 1.5 * SPACE, 1.2 * SPACE, 0.5 * SPACE),
+        Duration::Quarter => {
+            let _ = write!(
+                svg,
+                "<path d=\"M{:.1} {:.1}l6 8l-5 6l5 8c-5-2-8 1-4 6\" fill=\"none\" stroke=\"black\" stroke-width=\"2.2\"/>",
+                x - 2.0,
+                top + 0.6 * SPACE
+            );
+        }
+        _ => {
+            let flags = flag_count(duration);
+            let stem_top = top + 1.2 * SPACE;
+            let stem_bottom = stem_top + (flags as f64 + 1.2) * SPACE;
+            line(svg, x + 3.0, stem_top, x - 1.0 - flags as f64, stem_bottom, 1.2);
+            for k in 0..flags {
+                let hook_y = stem_top + k as f64 * SPACE;
+                let hook_x = x + 3.0 - k as f64 * 1.2;
+                circle(svg, hook_x - 6.0, hook_y + 2.0, 2.4);
+                let _ = write!(svg, "<path d=\"M{:.1} {:.1}q3 3 6 -2\" fill=\"none\" stroke=\"black\" stroke-width=\"1.2\"/>", hook_x - 6.0, hook_y + 2.0);
+            }
+        }
+    }
+}
+
+/// Curve between two noteheads, above or below them
+fn tie(svg: &mut String, from: f64, to: f64, y: f64, above: bool) {
+    let (start, end) = (from + 0.5 * SPACE, to - 0.5 * SPACE);
+    let direction = if above { -1.0 } else { 1.0 };
+    let y = y + direction * 0.6 * SPACE;
+    let bulge = direction * (0.8 * SPACE).min((end - start) / 5.0 + 2.0);
+    let _ = write!(
+        svg,
+        "<path d=\"M{:.1} {:.1}C{:.1} {:.1} {:.1} {:.1} {:.1} {:.1}C{:.1} {:.1} {:.1} {:.1} {:.1} {:.1}z\"/>",
+        start,
+        y,
+        start + (end - start) / 4.0,
+        y + bulge * 1.4,
+        end - (end - start) / 4.0,
+        y + bulge * 1.4,
+        end,
+        y,
+        end - (end - start) / 4.0,
+        y + bulge,
+        start + (end - start) / 4.0,
+        y + bulge,
+        start,
+        y
+    );
+}
+
+/// Beam from one stem end to another, thickened toward the notes
+fn beam(svg: &mut String, x0: f64, y0: f64, x1: f64, y1: f64, up: bool) {
+    let thickness = if up { BEAM_THICKNESS } else { -BEAM_THICKNESS };
+    let _ = write!(
+        svg,
+        "<polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\"/>",
+        x0,
+        y0,
+        x1,
+        y1,
+        x1,
+        y1 + thickness,
+        x0,
+        y0 + thickness
+    );
+}
+
+/// Repeat barline: thick, thin and dots, mirrored for an end repeat at `x`
+fn repeat_sign(svg: &mut String, x: f64, top: f64, end: bool) {
+    let bottom = top + 4.0 * SPACE;
+    let (thick, thin, dots) = if end { (x - 0.2 * SPACE, x - 0.6 * SPACE, x - 1.0 * SPACE) } else { (x + 0.2 * SPACE, x + 0.6 * SPACE, x + 1.0 * SPACE) };
+    line(svg, thick, top, thick, bottom, 4.0);
+    line(svg, thin, top, thin, bottom, 1.0);
+    circle(svg, dots, top + 1.5 * SPACE, 1.8);
+    circle(svg, dots, top + 2.5 * SPACE, 1.8);
+}
+
+/// Volta bracket over a bar: the label and left hook where the ending opens, a right hook
+/// where a first ending closes
+fn ending_bracket(svg: &mut String, from: f64, to: f64, top: f64, ending: Ending, opens: bool, closes: bool) {
+    let y = top - 3.4 * SPACE;
+    let (start, end) = (from + 0.2 * SPACE, to - 0.2 * SPACE);
+    line(svg, start, y, end, y, 1.0);
+    if opens {
+        line(svg, start, y, start, y + 1.2 * SPACE, 1.0);
+        let label = match ending {
+            Ending::First => "1.",
+            Ending::Second => "2.",
+        };
+        text(svg, start + 0.4 * SPACE, y + 1.2 * SPACE, "start", &format!("font-family=\"{}\" font-size=\"11\"", TEXT_FONT), label);
+    }
+    if closes {
+        line(svg, end, y, end, y + 1.2 * SPACE, 1.0);
+    }
+}
+
+fn line(svg: &mut String, x1: f64, y1: f64, x2: f64, y2: f64, width: f64) {
+    let _ = write!(svg, "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\" stroke-width=\"{}\"/>", x1, y1, x2, y2, width);
+}
+
+fn rect(svg: &mut String, x: f64, y: f64, width: f64, height: f64) {
+    let _ = write!(svg, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"/>", x, y, width, height);
+}
+
+fn circle(svg: &mut String, x: f64, y: f64, r: f64) {
+    let _ = write!(svg, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\"/>", x, y, r);
+}
+
+fn text(svg: &mut String, x: f64, y: f64, anchor: &str, style: &str, content: &str) {
+    let _ = write!(svg, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\" {}>{}</text>", x, y, anchor, style, escape(content));
+}
+
+fn music_text(svg: &mut String, x: f64, y: f64, size: f64, glyph: &str) {
+    text(svg, x, y, "start", &format!("font-family=\"{}\" font-size=\"{}\"", MUSIC_FONT, size), glyph);
+}
+
+fn escape(content: &str) -> String {
+    content.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Wrap drawn elements in an SVG document with a white background
+fn svg_document(height: f64, body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n<rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n{}\n</svg>\n",
+        body,
+        w = PAGE_WIDTH,
+        h = height
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn systems(source: &str) -> Vec<String> {
+        to_svg_with_options(&parse(source).unwrap(), None, Clef::Treble, 0, None, SvgLayout::Systems)
+    }
+
+    #[test]
+    fn test_page_header() {
+        let source = "---\ntitle: Blues & More\ncomposer: Trad\ntempo: 96\nkey-signature: Eb\ntime-signature: 3/4\n---\nC D E\n";
+        let pages = to_svg(&parse(source).unwrap());
+        assert_eq!(pages.len(), 1);
+        let page = &pages[0];
+        assert!(page.contains(">Blues &amp; More</text>"), "{}", page);
+        assert!(page.contains(">Trad</text>"));
+        assert!(page.contains(">\u{2669} = 96</text>"));
+        // Three flats, and 3 over 4
+        assert_eq!(page.matches(">\u{266D}</text>").count(), 3);
+        assert!(page.contains(">3</text>") && page.contains(">4</text>"));
+    }
+
+    #[test]
+    fn test_noteheads_stems_beams_and_ledger_lines() {
+        let svg = &systems("C/ D/ E/ F/ Gp\n^^Co")[0];
+        // Four filled heads, a half and a whole
+        assert_eq!(svg.matches("<ellipse").count(), 6);
+        assert_eq!(svg.matches("fill=\"white\" stroke=\"black\" stroke-width=\"1.6\"").count(), 1);
+        assert_eq!(svg.matches("stroke-width=\"2.2\"/>").count(), 1);
+        // A beam over each beat of eighths, no flags
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(!svg.contains("<path d=\"M"), "{}", svg);
+        // Middle C needs a ledger line, C two octaves up needs two: eleven thin lines with the
+        // staff, its left edge and two barlines
+        assert_eq!(svg.matches("stroke-width=\"1\"/>").count(), 11, "{}", svg);
+    }
+
+    #[test]
+    fn test_sixteenth_beams_and_flags() {
+        let svg = &systems("C// D// E/ F/ $/ G A")[0];
+        // A primary beam and a secondary beam over the sixteenths
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        // The eighth before the rest stands alone with a flag
+        assert_eq!(svg.matches("c1 6 10 9 8 20").count() + svg.matches("c1-6 10-9 8-20").count(), 1);
+    }
+
+    #[test]
+    fn test_accidentals_ties_and_tuplets() {
+        let svg = &systems("F# F% Fb-Fb/ Fb/\n[C D E]3/ Fp G")[0];
+        // Written accidentals show on every note (note scope), with a courtesy natural after
+        // the barline
+        assert_eq!(svg.matches(">\u{266F}</text>").count(), 1);
+        assert_eq!(svg.matches(">\u{266E}</text>").count(), 1);
+        assert_eq!(svg.matches(">\u{266D}</text>").count(), 3);
+        assert!(svg.contains(">(\u{266E})</text>"));
+        assert!(svg.contains(">3</text>"));
+        let ties = svg.matches("z\"/>").count();
+        assert_eq!(ties, 1, "{}", svg);
+    }
+
+    #[test]
+    fn test_chords_repeats_and_endings() {
+        let source = "||: {Cmaj7}:C D E F\n1. {G7}:G A B ^C :||\n2. {C}:^Co";
+        let svg = &systems(source)[0];
+        assert!(svg.contains(">Cmaj7</text>") && svg.contains(">G7</text>") && svg.contains(">C</text>"));
+        assert!(svg.contains(">1.</text>") && svg.contains(">2.</text>"));
+        // Dots of the start and end repeat
+        assert_eq!(svg.matches("r=\"1.8\"").count(), 4);
+        // Thick lines: two repeats and the final barline
+        assert_eq!(svg.matches("stroke-width=\"4\"").count(), 3);
+    }
+
+    #[test]
+    fn test_systems_and_transposition() {
+        let line = "C D E F\n".repeat(24);
+        let score = parse(&line).unwrap();
+        let systems = to_svg_with_options(&score, None, Clef::Treble, 0, None, SvgLayout::Systems);
+        assert!(systems.len() > 2, "{}", systems.len());
+        assert!(systems.iter().all(|svg| svg.contains("height=\"110\"")));
+        let pages = to_svg_with_options(&score, None, Clef::Treble, 0, None, SvgLayout::Pages);
+        assert_eq!(pages.len(), 1);
+
+        let score = parse("{C}:C D E F").unwrap();
+        let svg = &to_svg_with_options(&score, Transposition::for_key("Bb"), Clef::Treble, 0, None, SvgLayout::Systems)[0];
+        assert!(svg.contains(">D</text>"));
+        assert_eq!(svg.matches(">\u{266F}</text>").count(), 2);
+    }
+}
@@ -0,0 +1,248 @@
+//! # Text Chord Chart
+//!
+//! Plain-text chord charts for rehearsal sheets and text messages, built from the score's
+//! measures and chord symbols:
+//!
+//! ```text
+//! Autumn Leaves
+//! Composer: Joseph Kosma
+//! Key: Gm  Time: 4/4  Tempo: 120
+//!
+//! ||: Cm7 . . . | F7 . . . | Bbmaj7 . . . | Ebmaj7 . . . |
+//! |1. Am7b5 . . . | D7 . . . :||
+//! |2. Am7b5 D7 | Gm . . . |
+//! ```
+//!
+//! ## Layout
+//! - Four measures per line; a repeat sign, an ending or a key change also starts a new line
+//! - One slot per beat: the chord on the beat it starts, `.` while it continues
+//! - A measure split evenly between two or more chords lists just the chords (`| Dm7 G7 |`)
+//! - A measure without chord symbols is `%` after a measure holding one chord, `N.C.` before
+//!   the first chord, and otherwise `.` slots
+//! - Key changes get their own `Key: G` line
+//!
+//! Chord symbols and keys are transposed for transposing instruments, as in the MusicXML output.
+//!
+//! ## Example
+//! ```rust
+//! use gen::{parse, to_text_chart};
+//!
+//! let score = parse("{Cmaj7}:C D E F\n{Dm7}:D E {G7}:F G").unwrap();
+//! let chart = to_text_chart(&score, None);
+//! assert!(chart.contains("| Cmaj7 . . . | Dm7 G7 |"));
+//! ```
+
+use crate::ast::*;
+use crate::musicxml::{beat_length, transpose_chord_root, written_key_signature, Transposition};
+use crate::rational::Rational;
+
+/// Measures per line of the chart
+const MEASURES_PER_LINE: usize = 4;
+
+/// Render a score as a plain-text chord chart, optionally transposed
+pub fn to_text_chart(score: &Score, transposition: Option<Transposition>) -> String {
+    let transposition = transposition.as_ref();
+    let time_signature = &score.metadata.time_signature;
+    let mut chart = String::new();
+
+    // Header: title, composer, then key/time/tempo
+    if let Some(title) = &score.metadata.title {
+        chart.push_str(title);
+        chart.push('\n');
+    }
+    if let Some(composer) = &score.metadata.composer {
+        chart.push_str(&format!("Composer: {}\n", composer));
+    }
+    let mut key_signature = score.metadata.key_signature.clone();
+    chart.push_str(&format!(
+        "Key: {}  Time: {}/{}",
+        written_key_signature(&key_signature, transposition),
+        time_signature.beats,
+        time_signature.beat_type
+    ));
+    if let Some(tempo) = &score.metadata.tempo {
+        chart.push_str(&format!("  Tempo: {}", tempo_label(tempo)));
+    }
+    chart.push_str("\n\n");
+
+    let mut line = String::new();
+    let mut line_measures = 0;
+    let mut previous = BarChords::None;
+    for (i, measure) in score.measures.iter().enumerate() {
+        let ending_start = measure.ending.is_some() && (i == 0 || score.measures[i - 1].ending != measure.ending);
+        let key_change = measure.key_change.as_ref().filter(|key| **key != key_signature);
+
+        // Finish the line, closing it with the previous measure's barline
+        let new_line = measure.repeat_start || ending_start || key_change.is_some();
+        if line_measures == MEASURES_PER_LINE || (line_measures > 0 && new_line) {
+            line.push_str(barline(score.measures[i - 1].repeat_end, false));
+            chart.push_str(&line);
+            chart.push('\n');
+            line.clear();
+            line_measures = 0;
+        }
+
+        if let Some(key) = key_change {
+            key_signature = key.clone();
+            chart.push_str(&format!("Key: {}\n", written_key_signature(&key_signature, transposition)));
+        }
+
+        let repeat_end_before = line_measures > 0 && score.measures[i - 1].repeat_end;
+        line.push_str(barline(repeat_end_before, measure.repeat_start));
+        match measure.ending {
+            Some(Ending::First) if ending_start => line.push_str("1."),
+            Some(Ending::Second) if ending_start => line.push_str("2."),
+            _ => {}
+        }
+        let (text, chords) = bar_text(measure, time_signature, transposition, previous);
+        line.push(' ');
+        line.push_str(&text);
+        line.push(' ');
+        line_measures += 1;
+        previous = chords;
+    }
+    if let Some(last) = score.measures.last() {
+        line.push_str(barline(last.repeat_end, false));
+        chart.push_str(&line);
+        chart.push('\n');
+    }
+
+    chart
+}
+
+/// What the previous measure held, for deciding how to show a measure without chord symbols
+#[derive(Clone, Copy, PartialEq)]
+enum BarChords {
+    None,   // No chord yet in the score
+    Single, // One chord for the whole measure
+    Other,  // Several chords, or one that starts after the downbeat
+}
+
+/// Barline between two measures (either side may carry a repeat sign)
+fn barline(repeat_end: bool, repeat_start: bool) -> &'static str {
+    match (repeat_end, repeat_start) {
+        (false, false) => "|",
+        (true, false) => ":||",
+        (false, true) => "||:",
+        (true, true) => ":||:",
+    }
+}
+
+/// Chord text for one measure, and what it holds
+fn bar_text(
+    measure: &Measure,
+    time_signature: &TimeSignature,
+    transposition: Option<&Transposition>,
+    previous: BarChords,
+) -> (String, BarChords) {
+    let beat = beat_length(time_signature);
+    let length: Rational = measure.elements.iter().map(Element::total_fraction).sum();
+    let slots = slot_count(length, beat);
+
+    // Chord symbols with the beat they start on
+    let mut position = Rational::ZERO;
+    let mut chords: Vec<(usize, String)> = Vec::new();
+    for element in &measure.elements {
+        if let Some(chord) = element.chord() {
+            let symbol = match transposition {
+                Some(trans) => transpose_chord_root(&chord.symbol, trans),
+                None => chord.symbol.clone(),
+            };
+            let slot = ((position / beat).floor() as usize).min(slots - 1);
+            chords.push((slot, symbol));
+        }
+        position += element.total_fraction();
+    }
+
+    if chords.is_empty() {
+        let text = match previous {
+            BarChords::None => "N.C.".to_string(),
+            BarChords::Single => "%".to_string(),
+            BarChords::Other => vec!["."; slots].join(" "),
+        };
+        return (text, previous);
+    }
+
+    let held = if chords.len() == 1 && chords[0].0 == 0 { BarChords::Single } else { BarChords::Other };
+
+    // Evenly split measures list just the chords
+    let count = chords.len();
+    let even = count > 1
+        && slots.is_multiple_of(count)
+        && chords.iter().enumerate().all(|(k, (slot, _))| *slot == k * slots / count);
+    if even {
+        let names: Vec<&str> = chords.iter().map(|(_, symbol)| symbol.as_str()).collect();
+        return (names.join(" "), held);
+    }
+
+    // One slot per beat; chords sharing a beat share its slot
+    let mut beats = vec![String::from("."); slots];
+    for (slot, symbol) in chords {
+        if beats[slot] == "." {
+            beats[slot] = symbol;
+        } else {
+            beats[slot] = format!("{} {}", beats[slot], symbol);
+        }
+    }
+    (beats.join(" "), held)
+}
+
+/// Number of beat slots in a measure of the given length (a partial beat gets a slot)
+fn slot_count(length: Rational, beat: Rational) -> usize {
+    let beats = length / beat;
+    let whole = beats.floor() as usize;
+    let slots = if beats.is_integer() { whole } else { whole + 1 };
+    slots.max(1)
+}
+
+/// Tempo as "120", or "60 (dotted quarter)" when the beat isn't a quarter note
+fn tempo_label(tempo: &Tempo) -> String {
+    if tempo.duration == Duration::Quarter && !tempo.dotted {
+        return tempo.bpm.to_string();
+    }
+    let dotted = if tempo.dotted { "dotted " } else { "" };
+    format!("{} ({}{})", tempo.bpm, dotted, tempo.duration.musicxml_type())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_header_and_bars() {
+        let source = "---\ntitle: Blues\ncomposer: Me\nkey-signature: F\ntempo: 96\n---\n{F7}:F A ^C A\nF G A B\n{Bb7}:B ^D {F7}:^C A\nF G A B\n";
+        let chart = to_text_chart(&parse(source).unwrap(), None);
+        assert_eq!(chart, "Blues\nComposer: Me\nKey: F  Time: 4/4  Tempo: 96\n\n| F7 . . . | % | Bb7 F7 | . . . . |\n");
+    }
+
+    #[test]
+    fn test_repeats_and_endings() {
+        let source = "||: {C}:C D E F\n1. {G7}:G A B ^C :||\n2. {C}:^Co";
+        let chart = to_text_chart(&parse(source).unwrap(), None);
+        assert!(chart.ends_with("||: C . . . |\n|1. G7 . . . :||\n|2. C . . . |\n"), "{}", chart);
+    }
+
+    #[test]
+    fn test_uneven_chords_and_line_breaks() {
+        let source = "{C}:C D E {C7}:F\n{F}:F G A B\n{Fm}:A G F E\n{C}:C D E F\n{G7}:G A B ^C";
+        let chart = to_text_chart(&parse(source).unwrap(), None);
+        assert!(chart.contains("| C . . C7 | F . . . | Fm . . . | C . . . |\n| G7 . . . |\n"), "{}", chart);
+    }
+
+    #[test]
+    fn test_transposed_chart_with_key_change() {
+        let source = "{C}:C D E F\n@key:G {D7}:D E F# G";
+        let transposition = Transposition::for_key("Bb");
+        let chart = to_text_chart(&parse(source).unwrap(), transposition);
+        assert!(chart.starts_with("Key: D  Time: 4/4"), "{}", chart);
+        assert!(chart.ends_with("| D . . . |\nKey: A\n| E7 . . . |\n"), "{}", chart);
+    }
+
+    #[test]
+    fn test_compound_meter_and_pickup() {
+        let source = "---\ntime-signature: 6/8\n---\n@pickup G/\n{C}:C/ D/ E/ {G}:G/ A/ B/\n";
+        let chart = to_text_chart(&parse(source).unwrap(), None);
+        assert!(chart.ends_with("| N.C. | C G |\n"), "{}", chart);
+    }
+}
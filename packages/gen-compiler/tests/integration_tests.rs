@@ -2,7 +2,7 @@
 //!
 //! Tests full compilation pipeline from Gen source to MusicXML output.
 
-use gen::compile;
+use gen::{compile, json_to_source, parse, parse_to_json, GenError};
 
 #[test]
 fn test_compile_with_rhythm_groupings() {
@@ -138,3 +138,41 @@ title: Rhythm Grouping with Ties
     assert!(xml.contains("<tied type=\"start\"/>")); // Tie start
     assert!(xml.contains("<tied type=\"stop\"/>")); // Tie stop
 }
+
+#[test]
+fn test_json_round_trip_to_source() {
+    let source = "---\ntitle: Round Trip\nswing: //60%\n---\n||: {Dm7}:D/ E/ F/ G/ [A B ^C]3 :||\n{G7}:Bp Bp\n";
+    let printed = json_to_source(&parse_to_json(source).unwrap()).unwrap();
+    assert_eq!(printed, source);
+    assert_eq!(parse(&printed).unwrap().measures.len(), 2);
+}
+
+#[test]
+fn test_json_to_source_rejects_unprintable_chord_symbols() {
+    let json = parse_to_json("{C}:C D E F").unwrap();
+    for symbol in ["C} D {", ""] {
+        let edited = json.replace("\"symbol\": \"C\"", &format!("\"symbol\": \"{}\"", symbol));
+        assert_ne!(edited, json);
+        let result = json_to_source(&edited);
+        assert!(matches!(result, Err(GenError::SemanticError { measure: 1, .. })), "{:?}", result);
+    }
+}
+
+#[test]
+fn test_json_to_source_rejects_empty_measures() {
+    let json = r#"{"schemaVersion": 1, "score": {"measures": [
+        {"elements": [{"type": "note", "name": "C", "duration": "whole"}]},
+        {"elements": []}
+    ]}}"#;
+    let result = json_to_source(json);
+    assert!(matches!(result, Err(GenError::SemanticError { measure: 2, .. })), "{:?}", result);
+}
+
+#[test]
+fn test_json_to_source_rejects_unwritable_swing() {
+    let json = parse_to_json("---\nswing: /\n---\nC D E F").unwrap();
+    let edited = json.replacen("\"eighth\"", "\"quarter\"", 1);
+    assert_ne!(edited, json);
+    let result = json_to_source(&edited);
+    assert!(matches!(result, Err(GenError::MetadataError(_))), "{:?}", result);
+}
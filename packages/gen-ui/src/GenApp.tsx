@@ -794,7 +794,7 @@ export function GenApp({ compiler, files, playback, url, scores }: GenAppProps)
             <span className='text-xs text-gray-500 min-w-12 text-right'>
               {(() => {
                 if (!playbackData) return '0:00';
-                const seconds = (currentBeat / (playbackData.tempo / 60));
+                const seconds = (currentBeat / (playbackData.beatBpm / 60));
                 const mins = Math.floor(seconds / 60);
                 const secs = Math.floor(seconds % 60);
                 return `${mins}:${secs.toString().padStart(2, '0')}`;
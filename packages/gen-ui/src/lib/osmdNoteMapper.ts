@@ -1,3 +1,4 @@
+import { Fraction } from 'opensheetmusicdisplay';
 import type { OpenSheetMusicDisplay, GraphicalNote } from 'opensheetmusicdisplay';
 import type { PlaybackNote } from '../types';
 
@@ -12,16 +13,34 @@ function extractMidiNote(graphicalNote: GraphicalNote): number {
 }
 
 /**
- * Extracts timestamp (in beats) from OSMD's GraphicalStaffEntry.
- * OSMD uses divisions as the unit (where divisions per quarter note varies),
- * but we need quarter notes as beats.
- * In 4/4 time with divisions=1, OSMD beat 0.25 = our beat 1.0 (multiply by 4).
+ * Extracts the exact timestamp from OSMD's GraphicalStaffEntry.
+ * OSMD timestamps are fractions of a whole note ("measures" of 4/4).
  */
-function extractTimestamp(staffEntry: any): number {
-  const fraction = staffEntry.getAbsoluteTimestamp();
-  // OSMD timestamp is in "measures", convert to quarter note beats
-  // In 4/4 time: 1 measure = 4 quarter notes
-  return fraction.RealValue * 4;
+function extractTimestamp(staffEntry: any): Fraction {
+  return staffEntry.getAbsoluteTimestamp();
+}
+
+function gcd(a: number, b: number): number {
+  return b === 0 ? Math.abs(a) : gcd(b, a % b);
+}
+
+/**
+ * Formats a timestamp in whole notes as quarter notes, written like the compiler's
+ * osmdMatchKey: "3" for whole numbers, "5/3" for fractions (1 whole note = 4 quarters).
+ */
+function formatQuarterNotes(timestamp: Fraction): string {
+  const numerator = timestamp.GetExpandedNumerator() * 4;
+  const divisor = gcd(numerator, timestamp.Denominator);
+  const denominator = timestamp.Denominator / divisor;
+  return denominator === 1 ? `${numerator / divisor}` : `${numerator / divisor}/${denominator}`;
+}
+
+/**
+ * Parses a quarter-note timestamp written by formatQuarterNotes ("3" or "5/3").
+ */
+function parseQuarterNotes(text: string): number {
+  const [numerator, denominator = '1'] = text.split('/');
+  return parseInt(numerator, 10) / parseInt(denominator, 10);
 }
 
 /**
@@ -62,8 +81,8 @@ export class OSMDNoteMapper {
         if (!measure) continue;
 
         for (const staffEntry of measure.staffEntries) {
-          const baseTimestamp = extractTimestamp(staffEntry);
-          let accumulatedDuration = 0;
+          // Notes in later voice entries start after the earlier ones: base + accumulated lengths
+          let timestamp = extractTimestamp(staffEntry).clone();
 
           for (const voiceEntry of staffEntry.graphicalVoiceEntries) {
             for (const note of voiceEntry.notes) {
@@ -71,16 +90,14 @@ export class OSMDNoteMapper {
               if (!note.sourceNote || note.sourceNote.isRest()) {
                 // Still accumulate duration for rests
                 if (note.sourceNote && note.sourceNote.Length) {
-                  accumulatedDuration += note.sourceNote.Length.RealValue * 4;
+                  timestamp = Fraction.plus(timestamp, note.sourceNote.Length);
                 }
                 continue;
               }
 
               try {
                 const midiNote = extractMidiNote(note);
-                // Calculate actual timestamp: base + accumulated duration
-                const timestamp = baseTimestamp + accumulatedDuration;
-                const key = this.makeKey(midiNote, timestamp);
+                const key = this.makeKey(midiNote, formatQuarterNotes(timestamp));
 
                 // Store in map (handle potential collisions with array)
                 if (!this.noteMap.has(key)) {
@@ -90,7 +107,7 @@ export class OSMDNoteMapper {
 
                 // Accumulate this note's duration for the next note
                 if (note.sourceNote.Length) {
-                  accumulatedDuration += note.sourceNote.Length.RealValue * 4;
+                  timestamp = Fraction.plus(timestamp, note.sourceNote.Length);
                 }
 
               } catch (err) {
@@ -127,7 +144,7 @@ export class OSMDNoteMapper {
     const displayMidi = playbackNote.midiNote;
 
     // Try exact match first
-    const exactKey = this.makeKey(displayMidi, playbackNote.startTime);
+    const exactKey = this.makeKey(displayMidi, String(playbackNote.startTime));
     const exactMatches = this.noteMap.get(exactKey);
     if (exactMatches && exactMatches.length > 0) {
       return exactMatches[0]; // Return first match
@@ -137,7 +154,7 @@ export class OSMDNoteMapper {
     for (const [key, graphicalNotes] of this.noteMap.entries()) {
       const [midiStr, timestampStr] = key.split('_');
       const midi = parseInt(midiStr, 10);
-      const timestamp = parseFloat(timestampStr);
+      const timestamp = parseQuarterNotes(timestampStr);
 
       if (
         midi === displayMidi &&
@@ -168,10 +185,10 @@ export class OSMDNoteMapper {
   }
 
   /**
-   * Creates a composite key for the note map.
+   * Creates a composite key for the note map: "{midi}_{quarter notes}", e.g. "64_5/3".
    */
-  private makeKey(midiNote: number, timestamp: number): string {
-    return `${midiNote}_${timestamp.toFixed(3)}`;
+  private makeKey(midiNote: number, timestamp: string): string {
+    return `${midiNote}_${timestamp}`;
   }
 
   /**
@@ -131,6 +131,8 @@ describe('Playback Highlighting Integration', () => {
   it('should highlight first note, then second note sequentially', async () => {
     const playbackData: PlaybackData = {
       tempo: 120,
+      beatBpm: 120,
+      countIn: 0,
       notes: [
         { midiNote: 64, displayMidiNote: 64, startTime: 0.0, duration: 1.0 },
         { midiNote: 64, displayMidiNote: 64, startTime: 1.0, duration: 1.0 },
@@ -185,6 +187,8 @@ describe('Playback Highlighting Integration', () => {
   it('should find all 4 notes in Ode to Joy', () => {
     const playbackData: PlaybackData = {
       tempo: 120,
+      beatBpm: 120,
+      countIn: 0,
       notes: [
         { midiNote: 64, displayMidiNote: 64, startTime: 0.0, duration: 1.0 }, // E
         { midiNote: 64, displayMidiNote: 64, startTime: 1.0, duration: 1.0 }, // E
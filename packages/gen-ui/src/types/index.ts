@@ -51,23 +51,44 @@ export interface PlaybackNote {
   measureNumber: number;    // Which measure this note is in (1-indexed)
   beatInMeasure: number;    // Beat position within the measure (for OSMD timestamp matching)
   osmdTimestamp: number;    // OSMD's display timestamp (different from startTime for triplets)
-  osmdMatchKey: string;     // Pre-computed key for matching with OSMD: "{osmd_midi}_{osmdTimestamp}"
+  osmdMatchKey: string;     // Pre-computed key for matching with OSMD: "{osmd_midi}_{osmdTimestamp}", exact ("64_5/3")
+  elementId: string;        // xml:id of the note in MEI output (first note of a tied group)
 }
 
 export interface PlaybackChord {
+  symbol: string;       // chord symbol as written, e.g. "Cmaj7"
   midiNotes: number[];  // multiple MIDI notes played simultaneously
   startTime: number;    // in beats (for audio playback)
   duration: number;     // in beats
   osmdTimestamp: number; // OSMD's display timestamp (for visual highlighting)
 }
 
+export interface PlaybackBassNote {
+  midiNote: number;      // bass register, roughly E1-G3
+  startTime: number;     // in beats
+  duration: number;      // in beats
+  osmdTimestamp: number; // OSMD timestamp of the chord symbol this note belongs to
+}
+
+export interface PlaybackDrumHit {
+  midiNote: number;  // General MIDI percussion note (channel 10)
+  velocity: number;  // accented beats are louder
+  startTime: number; // in beats
+  duration: number;  // in beats
+}
+
 export type SwingType = 'eighth' | 'sixteenth';
 
 export interface PlaybackData {
-  tempo: number;      // BPM
+  tempo: number;      // BPM (quarter-note beats)
+  beatBpm: number;    // BPM in the tempo's own beat unit; note and chord times count these beats
   notes: PlaybackNote[];
   chords: PlaybackChord[];  // chord accompaniment (always piano)
-  swing?: SwingType;  // optional swing feel
+  bass?: PlaybackBassNote[]; // generated bass line (only when requested)
+  drums?: PlaybackDrumHit[]; // count-in, metronome and groove hits (only when requested)
+  countIn: number;    // length of the count-in in beats; the score starts at this time
+  swing?: SwingType;  // optional swing feel (note and chord times are already swung)
+  swingRatio?: number; // share of each swung pair given to the first note
 }
 
 export interface PlaybackResult {
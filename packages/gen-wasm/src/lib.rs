@@ -9,6 +9,12 @@ struct CompileError {
     column: Option<usize>,
 }
 
+#[derive(Serialize)]
+struct ImportResult {
+    source: String,
+    warnings: Vec<String>,
+}
+
 #[derive(Serialize)]
 struct Diagnostic {
     message: String,
@@ -131,6 +137,188 @@ pub fn generate_playback_data(
         .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
 }
 
+/// Generate playback data with accompaniment options
+/// `options` is a JSON object matching `gen::PlaybackOptions` (e.g. `{"voicing":"drop2","voiceLeading":true}`)
+#[wasm_bindgen]
+pub fn generate_playback_data_with_options(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<String>,
+    transpose_key: Option<String>,
+    options: &str,
+) -> Result<String, JsValue> {
+    let options: gen::PlaybackOptions = serde_json::from_str(options).map_err(|e| {
+        let error = CompileError { message: format!("Invalid playback options: {}", e), line: None, column: None };
+        JsValue::from_str(&serde_json::to_string(&error).unwrap())
+    })?;
+    gen::generate_playback_data_with_options(source, clef, octave_shift, instrument_group.as_deref(), transpose_key.as_deref(), &options)
+        .map(|data| serde_json::to_string(&data).unwrap())
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to MusicXML with chord symbols as Roman numerals or Nashville numbers
+/// `system` is "roman" or "nashville"; `above_symbols` keeps the chord symbols under the numerals
+#[wasm_bindgen]
+pub fn compile_with_numerals(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<String>,
+    transpose_key: Option<String>,
+    system: &str,
+    above_symbols: bool,
+) -> Result<String, JsValue> {
+    let system = gen::NumeralSystem::from_name(system).ok_or_else(|| {
+        let error = CompileError { message: format!("Unknown numeral system: {}", system), line: None, column: None };
+        JsValue::from_str(&serde_json::to_string(&error).unwrap())
+    })?;
+    let numerals = gen::HarmonyNumerals { system, above_symbols };
+    gen::compile_with_numerals(source, clef, octave_shift, instrument_group.as_deref(), transpose_key.as_deref(), numerals)
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to a comping chart with slashes in place of the melody
+/// `style` is "beat" or "rhythm"; `numeral_system` ("roman" or "nashville") labels the chords with numerals
+#[wasm_bindgen]
+pub fn compile_chart(
+    source: &str,
+    clef: &str,
+    transpose_key: Option<String>,
+    style: &str,
+    numeral_system: Option<String>,
+    numerals_above_symbols: bool,
+) -> Result<String, JsValue> {
+    let invalid = |message: String| {
+        let error = CompileError { message, line: None, column: None };
+        JsValue::from_str(&serde_json::to_string(&error).unwrap())
+    };
+    let style = gen::SlashStyle::from_name(style).ok_or_else(|| invalid(format!("Unknown slash style: {}", style)))?;
+    let numerals = match numeral_system.as_deref() {
+        Some(name) => {
+            let system = gen::NumeralSystem::from_name(name).ok_or_else(|| invalid(format!("Unknown numeral system: {}", name)))?;
+            Some(gen::HarmonyNumerals { system, above_symbols: numerals_above_symbols })
+        }
+        None => None,
+    };
+    gen::compile_chart(source, clef, transpose_key.as_deref(), style, numerals)
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to ABC notation, with the same options as MusicXML output
+#[wasm_bindgen]
+pub fn compile_abc(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<String>,
+    transpose_key: Option<String>,
+) -> Result<String, JsValue> {
+    gen::compile_abc(source, clef, octave_shift, instrument_group.as_deref(), transpose_key.as_deref())
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to a LilyPond file, with the same options as MusicXML output
+#[wasm_bindgen]
+pub fn compile_lilypond(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<String>,
+    transpose_key: Option<String>,
+) -> Result<String, JsValue> {
+    gen::compile_lilypond(source, clef, octave_shift, instrument_group.as_deref(), transpose_key.as_deref())
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to MEI 5, with the same options as MusicXML output
+/// Note ids match the `elementId` of the playback notes
+#[wasm_bindgen]
+pub fn compile_mei(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<String>,
+    transpose_key: Option<String>,
+) -> Result<String, JsValue> {
+    gen::compile_mei(source, clef, octave_shift, instrument_group.as_deref(), transpose_key.as_deref())
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to Humdrum **kern (concert pitch, chord symbols in a **mxhm spine)
+#[wasm_bindgen]
+pub fn compile_kern(source: &str) -> Result<String, JsValue> {
+    gen::compile_kern(source)
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to compressed MusicXML (the bytes of an `.mxl` file, as a Uint8Array)
+#[wasm_bindgen]
+pub fn compile_mxl(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<String>,
+    transpose_key: Option<String>,
+    include_source: bool,
+) -> Result<Vec<u8>, JsValue> {
+    gen::compile_mxl(source, clef, octave_shift, instrument_group.as_deref(), transpose_key.as_deref(), include_source)
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Engrave Gen source as SVG: one image per page, or one per system with `per_system`
+#[wasm_bindgen]
+pub fn compile_svg(
+    source: &str,
+    clef: &str,
+    octave_shift: i8,
+    instrument_group: Option<String>,
+    transpose_key: Option<String>,
+    per_system: bool,
+) -> Result<Vec<String>, JsValue> {
+    gen::compile_svg(source, clef, octave_shift, instrument_group.as_deref(), transpose_key.as_deref(), per_system)
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Compile Gen source to a plain-text chord chart
+#[wasm_bindgen]
+pub fn compile_text_chart(source: &str, transpose_key: Option<String>) -> Result<String, JsValue> {
+    gen::compile_text_chart(source, transpose_key.as_deref())
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Analyze the chord symbols of a score and return the harmony report as a JSON array
+/// Each entry has the measure, beat, symbol, active key, Roman numeral and Nashville number
+#[wasm_bindgen]
+pub fn analyze_harmony(source: &str) -> Result<String, JsValue> {
+    gen::parse(source)
+        .map(|score| serde_json::to_string(&gen::analysis::analyze_harmony(&score)).unwrap())
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Import an ABC tune as Gen source
+/// Returns JSON `{"source": "...", "warnings": [...]}`, one warning per kind of dropped content
+#[wasm_bindgen]
+pub fn import_abc(abc: &str) -> Result<String, JsValue> {
+    gen::import_abc(abc)
+        .map(|(source, warnings)| serde_json::to_string(&ImportResult { source, warnings }).unwrap())
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Parse Gen source into the versioned AST JSON document (`{"schemaVersion": 1, "score": {...}}`)
+#[wasm_bindgen]
+pub fn parse_to_json(source: &str) -> Result<String, JsValue> {
+    gen::parse_to_json(source)
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
+/// Print an AST JSON document as Gen source
+#[wasm_bindgen]
+pub fn json_to_source(json: &str) -> Result<String, JsValue> {
+    gen::json_to_source(json)
+        .map_err(|e| JsValue::from_str(&serde_json::to_string(&error_to_compile_error(e)).unwrap()))
+}
+
 #[wasm_bindgen]
 pub fn lint(source: &str) -> String {
     let mut diagnostics: Vec<Diagnostic> = Vec::new();